@@ -35,6 +35,7 @@ use turbopack_dev_server::{
 use turbopack_ecmascript::{
     chunk::EcmascriptChunkPlaceablesVc, magic_identifier, utils::stringify_str,
     EcmascriptInputTransformsVc, EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+    EcmascriptOptionsVc,
 };
 use turbopack_env::ProcessEnvAssetVc;
 use turbopack_node::{
@@ -576,8 +577,11 @@ import BOOTSTRAP from {};
                 Value::new(EcmascriptModuleAssetType::Typescript),
                 EcmascriptInputTransformsVc::cell(vec![
                     EcmascriptInputTransform::React { refresh: false },
-                    EcmascriptInputTransform::TypeScript,
+                    EcmascriptInputTransform::TypeScript {
+                        isolated_modules: false,
+                    },
                 ]),
+                EcmascriptOptionsVc::default(),
                 context.environment(),
             ),
             chunking_context,
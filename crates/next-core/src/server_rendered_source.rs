@@ -26,7 +26,7 @@ use turbopack_dev_server::{
 };
 use turbopack_ecmascript::{
     chunk::EcmascriptChunkPlaceablesVc, EcmascriptInputTransform, EcmascriptInputTransformsVc,
-    EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+    EcmascriptModuleAssetType, EcmascriptModuleAssetVc, EcmascriptOptionsVc,
 };
 use turbopack_env::ProcessEnvAssetVc;
 use turbopack_node::{
@@ -349,9 +349,12 @@ impl NodeEntry for SsrEntry {
                 self.context,
                 Value::new(EcmascriptModuleAssetType::Typescript),
                 EcmascriptInputTransformsVc::cell(vec![
-                    EcmascriptInputTransform::TypeScript,
+                    EcmascriptInputTransform::TypeScript {
+                        isolated_modules: false,
+                    },
                     EcmascriptInputTransform::React { refresh: false },
                 ]),
+                EcmascriptOptionsVc::default(),
                 self.context.environment(),
             ),
             chunking_context: self.chunking_context,
@@ -6,9 +6,13 @@ use turbo_tasks::{
 use turbo_tasks_fs::FileSystemPathVc;
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 
-use super::{ChunkingContext, ChunkingContextVc};
+use super::{ChunkSizeBudgetVc, ChunkingContext, ChunkingContextVc, SourcesContentLimitVc};
 use crate::asset::AssetVc;
 
+/// The estimated size in bytes above which the chunks of a
+/// [DevChunkingContext] are split, unless configured otherwise.
+pub const DEFAULT_CHUNK_SIZE_BUDGET: usize = 512 * 1024;
+
 pub struct DevChunkingContextBuilder {
     context: DevChunkingContext,
 }
@@ -19,6 +23,48 @@ impl DevChunkingContextBuilder {
         self
     }
 
+    pub fn scope_hoisting(mut self) -> Self {
+        self.context.enable_scope_hoisting = true;
+        self
+    }
+
+    pub fn tree_shaking(mut self) -> Self {
+        self.context.enable_tree_shaking = true;
+        self
+    }
+
+    /// Hashes the sources of the modules of chunks into their paths, see
+    /// [ChunkingContext::is_content_hashing_enabled].
+    pub fn content_hashing(mut self) -> Self {
+        self.context.enable_content_hashing = true;
+        self
+    }
+
+    pub fn minify(mut self) -> Self {
+        self.context.enable_minification = true;
+        self
+    }
+
+    pub fn ascii_only(mut self) -> Self {
+        self.context.ascii_only_output = true;
+        self
+    }
+
+    /// Splits chunks whose estimated size exceeds `budget` bytes, or disables
+    /// splitting by size with `None`. Defaults to
+    /// [DEFAULT_CHUNK_SIZE_BUDGET], see [ChunkingContext::chunk_size_budget].
+    pub fn chunk_size_budget(mut self, budget: Option<usize>) -> Self {
+        self.context.chunk_size_budget = budget;
+        self
+    }
+
+    /// Sets the size in bytes up to which sources are inlined into source
+    /// maps, see [ChunkingContext::sources_content_limit].
+    pub fn sources_content_limit(mut self, limit: Option<usize>) -> Self {
+        self.context.sources_content_limit = limit;
+        self
+    }
+
     pub fn layer(mut self, layer: &str) -> Self {
         self.context.layer = (!layer.is_empty()).then(|| layer.to_string());
         self
@@ -56,6 +102,20 @@ pub struct DevChunkingContext {
     layer: Option<String>,
     /// Enable HMR for this chunking
     enable_hot_module_replacement: bool,
+    /// Enable concatenation of eligible ESM modules
+    enable_scope_hoisting: bool,
+    /// Enable removal of unused exports that have no side effects
+    enable_tree_shaking: bool,
+    /// Hash the sources of the modules of chunks into their paths
+    enable_content_hashing: bool,
+    /// Enable minification of the generated code
+    enable_minification: bool,
+    /// Escape non-ASCII characters in the generated code
+    ascii_only_output: bool,
+    /// Split chunks whose estimated size exceeds this number of bytes
+    chunk_size_budget: Option<usize>,
+    /// Inline sources up to this number of bytes into source maps
+    sources_content_limit: Option<usize>,
 }
 
 impl DevChunkingContextVc {
@@ -74,6 +134,13 @@ impl DevChunkingContextVc {
                 asset_root_path,
                 layer: None,
                 enable_hot_module_replacement: false,
+                enable_scope_hoisting: false,
+                enable_tree_shaking: false,
+                enable_content_hashing: false,
+                enable_minification: false,
+                ascii_only_output: false,
+                chunk_size_budget: Some(DEFAULT_CHUNK_SIZE_BUDGET),
+                sources_content_limit: None,
             },
         }
     }
@@ -87,6 +154,27 @@ impl DevChunkingContextVc {
     }
 }
 
+impl DevChunkingContext {
+    /// The directory that chunks with the given extension are placed in.
+    fn root_path(&self, extension: &str) -> FileSystemPathVc {
+        let mut root_path = self.chunk_root_path;
+        #[allow(clippy::single_match, reason = "future extensions")]
+        match extension {
+            ".css" => {
+                if let Some(path) = self.css_chunk_root_path {
+                    root_path = path;
+                }
+            }
+            _ => {}
+        }
+        if let Some(layer) = self.layer.as_deref() {
+            root_path.join(layer)
+        } else {
+            root_path
+        }
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl ChunkingContext for DevChunkingContext {
     #[turbo_tasks::function]
@@ -139,22 +227,14 @@ impl ChunkingContext for DevChunkingContext {
         if !name.ends_with(extension) {
             name += extension;
         }
-        let mut root_path = self.chunk_root_path;
-        #[allow(clippy::single_match, reason = "future extensions")]
-        match extension {
-            ".css" => {
-                if let Some(path) = self.css_chunk_root_path {
-                    root_path = path;
-                }
-            }
-            _ => {}
-        }
-        let root_path = if let Some(layer) = self.layer.as_deref() {
-            root_path.join(layer)
-        } else {
-            root_path
-        };
-        Ok(root_path.join(&name))
+        Ok(self.root_path(extension).join(&name))
+    }
+
+    #[turbo_tasks::function]
+    fn named_chunk_path(&self, name: &str, extension: &str) -> FileSystemPathVc {
+        let name = name.replace('/', "_");
+        self.root_path(extension)
+            .join(&format!("{name}{extension}"))
     }
 
     #[turbo_tasks::function]
@@ -182,6 +262,41 @@ impl ChunkingContext for DevChunkingContext {
         BoolVc::cell(self.enable_hot_module_replacement)
     }
 
+    #[turbo_tasks::function]
+    fn is_scope_hoisting_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_scope_hoisting)
+    }
+
+    #[turbo_tasks::function]
+    fn is_tree_shaking_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_tree_shaking)
+    }
+
+    #[turbo_tasks::function]
+    fn is_content_hashing_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_content_hashing)
+    }
+
+    #[turbo_tasks::function]
+    fn is_minification_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_minification)
+    }
+
+    #[turbo_tasks::function]
+    fn is_ascii_only_output_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.ascii_only_output)
+    }
+
+    #[turbo_tasks::function]
+    fn chunk_size_budget(&self) -> ChunkSizeBudgetVc {
+        ChunkSizeBudgetVc::cell(self.chunk_size_budget)
+    }
+
+    #[turbo_tasks::function]
+    fn sources_content_limit(&self) -> SourcesContentLimitVc {
+        SourcesContentLimitVc::cell(self.sources_content_limit)
+    }
+
     #[turbo_tasks::function]
     fn layer(&self) -> StringVc {
         StringVc::cell(self.layer.clone().unwrap_or_default())
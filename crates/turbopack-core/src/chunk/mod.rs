@@ -8,23 +8,23 @@ use indexmap::IndexSet;
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{
     debug::ValueDebugFormat,
-    primitives::{BoolVc, StringVc},
+    primitives::{BoolVc, OptionStringVc, StringVc},
     trace::TraceRawVcs,
     ValueToString, ValueToStringVc,
 };
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
 use turbo_tasks_hash::{encode_hex, DeterministicHash, Xxh3Hash64Hasher};
 
 use self::optimize::optimize;
 use crate::{
-    asset::{Asset, AssetVc, AssetsVc},
+    asset::{Asset, AssetContent, AssetVc, AssetsVc},
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
     resolve::{ResolveResult, ResolveResultVc},
 };
 
 /// A module id, which can be a number or string
 #[turbo_tasks::value(shared)]
-#[derive(Debug, Clone, Hash, DeterministicHash)]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord, DeterministicHash)]
 #[serde(untagged)]
 pub enum ModuleId {
     Number(u32),
@@ -42,10 +42,44 @@ impl ModuleId {
     }
 }
 
+/// Computes a deterministic id of a chunk from the ids of its entry modules
+/// and the ids and content hashes of all modules in the chunk.
+///
+/// Only inputs that are stable across machines are used: module ids are
+/// derived from the module path relative to the root of its file system and
+/// content hashes from the module sources. The order of `modules` doesn't
+/// matter, so the same input graph always yields the same id.
+pub fn content_chunk_id(entries: &[&ModuleId], modules: &[(&ModuleId, u64)]) -> String {
+    let mut modules = modules.to_vec();
+    modules.sort();
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_value(entries.len() as u64);
+    for entry in entries {
+        hasher.write_ref(*entry);
+    }
+    for (id, hash) in modules {
+        hasher.write_ref(id);
+        hasher.write_value(hash);
+    }
+    encode_hex(hasher.finish())
+}
+
 /// A list of module ids.
 #[turbo_tasks::value(transparent, shared)]
 pub struct ModuleIds(Vec<ModuleIdVc>);
 
+/// The estimated size in bytes above which a chunk is split into multiple
+/// chunks. `None` disables splitting by size.
+#[turbo_tasks::value(transparent)]
+pub struct ChunkSizeBudget(Option<usize>);
+
+/// The size in bytes up to which the content of a source is inlined into the
+/// `sourcesContent` of source maps. Larger sources are only referenced by
+/// their path, which keeps the size of the source maps down. `None` inlines
+/// all sources, `Some(0)` none.
+#[turbo_tasks::value(transparent)]
+pub struct SourcesContentLimit(Option<usize>);
+
 /// A context for the chunking that influences the way chunks are created
 #[turbo_tasks::value_trait]
 pub trait ChunkingContext {
@@ -53,6 +87,10 @@ pub trait ChunkingContext {
 
     fn chunk_path(&self, path: FileSystemPathVc, extension: &str) -> FileSystemPathVc;
 
+    /// The path of a chunk that was explicitly named, e.g. with a
+    /// `webpackChunkName` comment.
+    fn named_chunk_path(&self, name: &str, extension: &str) -> FileSystemPathVc;
+
     fn can_be_in_same_chunk(&self, asset_a: AssetVc, asset_b: AssetVc) -> BoolVc;
 
     fn asset_path(&self, content_hash: &str, extension: &str) -> FileSystemPathVc;
@@ -61,6 +99,50 @@ pub trait ChunkingContext {
         BoolVc::cell(false)
     }
 
+    /// Whether eligible ESM modules may be concatenated into a single scope
+    /// instead of being emitted as separate modules.
+    fn is_scope_hoisting_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether unused exports of ESM modules that have no side effects may be
+    /// removed. Exports count as unused when no module reachable from the
+    /// entries of a chunk uses them.
+    fn is_tree_shaking_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether the paths of chunks contain a hash of the sources of their
+    /// modules, see [content_chunk_id]. Paths then change with every edit, so
+    /// it's meant for production output and not for HMR.
+    fn is_content_hashing_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether the generated code should be minified.
+    fn is_minification_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether non-ASCII characters should be escaped in the generated code.
+    fn is_ascii_only_output_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// The estimated size in bytes above which a chunk is split. Modules are
+    /// never split, so a single module can exceed the budget. Estimating the
+    /// size reads the content of every asset, so it's disabled unless the
+    /// chunking context opts in, like the [dev::DevChunkingContext] does.
+    fn chunk_size_budget(&self) -> ChunkSizeBudgetVc {
+        ChunkSizeBudgetVc::cell(None)
+    }
+
+    /// Which sources are inlined into the `sourcesContent` of the source maps
+    /// of chunks.
+    fn sources_content_limit(&self) -> SourcesContentLimitVc {
+        SourcesContentLimitVc::cell(None)
+    }
+
     fn layer(&self) -> StringVc {
         StringVc::cell("".to_string())
     }
@@ -186,6 +268,30 @@ impl Default for ChunkingType {
 #[turbo_tasks::value(transparent)]
 pub struct ChunkingTypeOption(Option<ChunkingType>);
 
+/// A hint to load a separate chunk group before it's requested, so the
+/// runtime or the HTML can emit `<link rel="preload">` or
+/// `<link rel="prefetch">` for its chunks.
+#[derive(
+    Copy, Clone, Debug, TraceRawVcs, Serialize, Deserialize, Eq, PartialEq, ValueDebugFormat,
+)]
+pub enum ChunkLoadingHint {
+    /// The chunk group is loaded in parallel to the referencing chunk, since
+    /// it's likely needed soon.
+    Preload {
+        /// Hints with a higher order are emitted first.
+        order: i32,
+    },
+    /// The chunk group is loaded when the browser is idle, since it's likely
+    /// needed for a future navigation.
+    Prefetch {
+        /// Hints with a higher order are emitted first.
+        order: i32,
+    },
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ChunkLoadingHintOption(Option<ChunkLoadingHint>);
+
 /// An [AssetReference] implementing this trait and returning true for
 /// [ChunkableAssetReference::is_chunkable] are considered as potentially
 /// chunkable references. When all [Asset]s of such a reference implement
@@ -197,6 +303,17 @@ pub trait ChunkableAssetReference: AssetReference + ValueToString {
     fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
         ChunkingTypeOptionVc::cell(Some(ChunkingType::default()))
     }
+
+    /// The name of the chunk that is created for a separate chunk group.
+    fn chunk_name(&self) -> OptionStringVc {
+        OptionStringVc::cell(None)
+    }
+
+    /// How the separate chunk group should be loaded ahead of time, e.g. from
+    /// a `webpackPrefetch` comment.
+    fn loading_hint(&self) -> ChunkLoadingHintOptionVc {
+        ChunkLoadingHintOptionVc::cell(None)
+    }
 }
 
 /// A reference to a [Chunk]. Can be loaded in parallel, see [Chunk].
@@ -256,13 +373,32 @@ impl ParallelChunkReference for ChunkReference {
 #[turbo_tasks::value]
 pub struct ChunkGroupReference {
     chunk_group: ChunkGroupVc,
+    loading_hint: ChunkLoadingHintOptionVc,
 }
 
 #[turbo_tasks::value_impl]
 impl ChunkGroupReferenceVc {
     #[turbo_tasks::function]
     pub fn new(chunk_group: ChunkGroupVc) -> Self {
-        Self::cell(ChunkGroupReference { chunk_group })
+        Self::new_with_loading_hint(chunk_group, ChunkLoadingHintOptionVc::cell(None))
+    }
+
+    /// A reference to a chunk group that should be loaded ahead of time, e.g.
+    /// with `<link rel="prefetch">` for its chunks.
+    #[turbo_tasks::function]
+    pub fn new_with_loading_hint(
+        chunk_group: ChunkGroupVc,
+        loading_hint: ChunkLoadingHintOptionVc,
+    ) -> Self {
+        Self::cell(ChunkGroupReference {
+            chunk_group,
+            loading_hint,
+        })
+    }
+
+    #[turbo_tasks::function]
+    pub async fn loading_hint(self) -> Result<ChunkLoadingHintOptionVc> {
+        Ok(self.await?.loading_hint)
     }
 }
 
@@ -296,6 +432,8 @@ pub struct ChunkContentResult<I> {
     pub chunk_items: Vec<I>,
     pub chunks: Vec<ChunkVc>,
     pub async_chunk_groups: Vec<ChunkGroupVc>,
+    /// How some of the `async_chunk_groups` should be loaded ahead of time.
+    pub loading_hints: Vec<(ChunkGroupVc, ChunkLoadingHint)>,
     pub external_asset_references: Vec<AssetReferenceVc>,
 }
 
@@ -305,6 +443,7 @@ pub trait FromChunkableAsset: ChunkItem + Sized + Debug {
     async fn from_async_asset(
         context: ChunkingContextVc,
         asset: ChunkableAssetVc,
+        chunk_name: OptionStringVc,
     ) -> Result<Option<(Self, ChunkableAssetVc)>>;
 }
 
@@ -332,19 +471,40 @@ enum ChunkContentWorkItem {
         assets: AssetsVc,
         reference: AssetReferenceVc,
         chunking_type: ChunkingType,
+        chunk_name: OptionStringVc,
+        loading_hint: ChunkLoadingHintOptionVc,
     },
 }
 
+/// The size of the source of an asset, which is used as estimate for the size
+/// of its chunk item.
+async fn estimated_size(asset: AssetVc) -> Result<usize> {
+    Ok(match &*asset.content().await? {
+        AssetContent::File(file) => match &*file.await? {
+            FileContent::Content(file) => file.content().len(),
+            FileContent::NotFound => 0,
+        },
+        AssetContent::Redirect { .. } => 0,
+    })
+}
+
 async fn chunk_content_internal<I: FromChunkableAsset>(
     context: ChunkingContextVc,
     entry: AssetVc,
     additional_entries: Option<AssetsVc>,
     split: bool,
 ) -> Result<Option<ChunkContentResult<I>>> {
+    let size_budget = if split {
+        None
+    } else {
+        *context.chunk_size_budget().await?
+    };
+    let mut chunk_size = 0;
     let mut chunk_items = Vec::new();
     let mut processed_assets = IndexSet::new();
     let mut chunks = Vec::new();
     let mut async_chunk_groups = Vec::new();
+    let mut loading_hints = Vec::new();
     let mut external_asset_references = Vec::new();
     let mut queue = VecDeque::new();
 
@@ -354,6 +514,9 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
     ));
     chunk_items.push(chunk_item);
     processed_assets.insert(entry);
+    if size_budget.is_some() {
+        chunk_size += estimated_size(entry).await?;
+    }
 
     if let Some(additional_entries) = additional_entries {
         for entry in &*additional_entries.await? {
@@ -363,6 +526,9 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
             ));
             chunk_items.push(chunk_item);
             processed_assets.insert(*entry);
+            if size_budget.is_some() {
+                chunk_size += estimated_size(*entry).await?;
+            }
         }
     }
 
@@ -376,6 +542,8 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                                 assets: r.resolve_reference().primary_assets(),
                                 reference: *r,
                                 chunking_type,
+                                chunk_name: pc.chunk_name(),
+                                loading_hint: pc.loading_hint(),
                             });
                             continue;
                         }
@@ -387,6 +555,8 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 assets,
                 reference,
                 chunking_type,
+                chunk_name,
+                loading_hint,
             } => {
                 // It's important to temporary store these results in these variables
                 // so that we can cancel to complete list of assets by that references together
@@ -396,6 +566,9 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 // Chunk items that are placed into the current chunk
                 let mut inner_chunk_items = Vec::new();
 
+                // Assets of the chunk items that are placed into the current chunk
+                let mut inner_assets = Vec::new();
+
                 // Chunks that are loaded in parallel to the current chunk
                 let mut inner_chunks = Vec::new();
 
@@ -403,6 +576,9 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 // not loaded in parallel
                 let mut inner_chunk_groups = Vec::new();
 
+                // How some of these chunk groups should be loaded ahead of time
+                let mut inner_loading_hints = Vec::new();
+
                 for asset in assets
                     .await?
                     .iter()
@@ -422,6 +598,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                         ChunkingType::Placed => {
                             if let Some(chunk_item) = I::from_asset(context, *asset).await? {
                                 inner_chunk_items.push(chunk_item);
+                                inner_assets.push(*asset);
                             } else {
                                 return Err(anyhow!(
                                     "Asset {} was requested to be placed into the same chunk, but \
@@ -440,6 +617,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                                 // chunk item, chunk or other asset?
                                 if let Some(chunk_item) = I::from_asset(context, *asset).await? {
                                     inner_chunk_items.push(chunk_item);
+                                    inner_assets.push(*asset);
                                     continue;
                                 }
                             }
@@ -448,16 +626,22 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                             inner_chunks.push(chunk);
                         }
                         ChunkingType::Separate => {
-                            inner_chunk_groups
-                                .push(ChunkGroupVc::from_asset(chunkable_asset, context));
+                            let chunk_group = ChunkGroupVc::from_asset(chunkable_asset, context);
+                            if let Some(hint) = *loading_hint.await? {
+                                inner_loading_hints.push((chunk_group, hint));
+                            }
+                            inner_chunk_groups.push(chunk_group);
                         }
                         ChunkingType::SeparateAsync => {
                             if let Some((manifest_loader_item, manifest_chunk)) =
-                                I::from_async_asset(context, chunkable_asset).await?
+                                I::from_async_asset(context, chunkable_asset, chunk_name).await?
                             {
                                 inner_chunk_items.push(manifest_loader_item);
-                                inner_chunk_groups
-                                    .push(ChunkGroupVc::from_asset(manifest_chunk, context));
+                                let chunk_group = ChunkGroupVc::from_asset(manifest_chunk, context);
+                                if let Some(hint) = *loading_hint.await? {
+                                    inner_loading_hints.push((chunk_group, hint));
+                                }
+                                inner_chunk_groups.push(chunk_group);
                             } else {
                                 external_asset_references.push(reference);
                                 continue 'outer;
@@ -476,14 +660,22 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 }
                 chunks.extend(inner_chunks);
                 async_chunk_groups.extend(inner_chunk_groups);
+                loading_hints.extend(inner_loading_hints);
+                if size_budget.is_some() {
+                    for asset in inner_assets {
+                        chunk_size += estimated_size(asset).await?;
+                    }
+                }
 
                 // Make sure the chunk doesn't become too large.
                 // This will hurt performance in many aspects.
-                let chunk_items_count = chunk_items.len();
                 if !split
-                    && prev_chunk_items != chunk_items_count
-                    && chunk_items_count > 5000
-                    && prev_chunk_items > 1
+                    && is_chunk_too_large(
+                        prev_chunk_items,
+                        chunk_items.len(),
+                        chunk_size,
+                        size_budget,
+                    )
                 {
                     // Chunk is too large, cancel this algorithm and
                     // restart with splitting from the start
@@ -497,10 +689,25 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
         chunk_items,
         chunks,
         async_chunk_groups,
+        loading_hints,
         external_asset_references,
     }))
 }
 
+/// Whether a chunk grew too large by adding the chunk items after
+/// `prev_chunk_items`. A chunk with a single chunk item is never too large, as
+/// it can't be split any further.
+fn is_chunk_too_large(
+    prev_chunk_items: usize,
+    chunk_items: usize,
+    chunk_size: usize,
+    size_budget: Option<usize>,
+) -> bool {
+    prev_chunk_items != chunk_items
+        && (chunk_items > 5000 || size_budget.map_or(false, |budget| chunk_size > budget))
+        && prev_chunk_items > 1
+}
+
 #[turbo_tasks::value_trait]
 pub trait ChunkItem {
     /// A [ChunkItem] can describe different `references` than its original
@@ -512,3 +719,61 @@ pub trait ChunkItem {
 
 #[turbo_tasks::value(transparent)]
 pub struct ChunkItems(Vec<ChunkItemVc>);
+
+#[cfg(test)]
+mod tests {
+    use super::{content_chunk_id, is_chunk_too_large, ModuleId};
+
+    fn graph() -> Vec<(ModuleId, u64)> {
+        vec![
+            (ModuleId::String("[project]/src/index.js".to_string()), 1),
+            (ModuleId::String("[project]/src/a.js".to_string()), 2),
+            (ModuleId::Number(3), 3),
+        ]
+    }
+
+    #[test]
+    fn deterministic_chunk_id() {
+        let entry = ModuleId::String("[project]/src/index.js".to_string());
+        let first = graph();
+        let second = graph();
+        let first_id = content_chunk_id(
+            &[&entry],
+            &first
+                .iter()
+                .map(|(id, hash)| (id, *hash))
+                .collect::<Vec<_>>(),
+        );
+        // The order in which modules are visited doesn't matter
+        let second_id = content_chunk_id(
+            &[&entry],
+            &second
+                .iter()
+                .rev()
+                .map(|(id, hash)| (id, *hash))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(first_id, second_id);
+
+        let changed = [(&first[0].0, 1), (&first[1].0, 5), (&first[2].0, 3)];
+        assert_ne!(content_chunk_id(&[&entry], &changed), first_id);
+        assert_ne!(
+            content_chunk_id(&[&first[1].0], &changed[..0]),
+            content_chunk_id(&[&first[0].0], &changed[..0])
+        );
+    }
+
+    #[test]
+    fn chunk_too_large() {
+        // Without a budget only the number of chunk items is considered
+        assert!(!is_chunk_too_large(2, 3, usize::MAX, None));
+        assert!(is_chunk_too_large(4000, 5001, 0, None));
+
+        assert!(!is_chunk_too_large(2, 3, 100, Some(100)));
+        assert!(is_chunk_too_large(2, 3, 101, Some(100)));
+        // Nothing was added
+        assert!(!is_chunk_too_large(3, 3, 101, Some(100)));
+        // The entry alone exceeds the budget
+        assert!(!is_chunk_too_large(1, 2, 101, Some(100)));
+    }
+}
@@ -10,6 +10,7 @@ use turbo_tasks_fs::rope::{Rope, RopeBuilder};
 
 use crate::{
     source_map::{GenerateSourceMap, GenerateSourceMapVc, SourceMapSection, SourceMapVc},
+    source_map_concat::SourceMapConcat,
     source_pos::SourcePos,
 };
 
@@ -41,6 +42,56 @@ impl Code {
     pub fn has_source_map(&self) -> bool {
         !self.mappings.is_empty()
     }
+
+    /// Writes a regular source map of the code to `out`. Unlike the sectioned
+    /// map of [GenerateSourceMap::generate_source_map], the maps of the
+    /// sections are appended one after another by [SourceMapConcat], so the
+    /// complete map is never buffered.
+    pub async fn write_source_map<W: Write + Send>(&self, out: W) -> Result<W> {
+        let mut starts = Vec::with_capacity(self.mappings.len());
+        let end = {
+            let mut pos = SourcePos::new();
+            let mut last_byte_pos = 0;
+            let mut read = self.code.read();
+            for (byte_pos, _) in &self.mappings {
+                advance(&mut read, &mut pos, byte_pos - last_byte_pos)?;
+                last_byte_pos = *byte_pos;
+                starts.push(pos);
+            }
+            advance(&mut read, &mut pos, self.code.len() - last_byte_pos)?;
+            pos
+        };
+
+        let mut concat = SourceMapConcat::new(out)?;
+        for (i, (_, map)) in self.mappings.iter().enumerate() {
+            let start = starts[i];
+            match map {
+                None => concat.push_unmapped(start)?,
+                Some(map) => {
+                    let section_end = starts.get(i + 1).copied().unwrap_or(end);
+                    map.generate_source_map()
+                        .write_into(&mut concat, start, section_end)
+                        .await?
+                }
+            }
+        }
+        concat.finish()
+    }
+}
+
+/// Consumes `len` bytes of `read` and updates `pos` accordingly.
+fn advance(read: &mut impl BufRead, pos: &mut SourcePos, mut len: usize) -> IoResult<()> {
+    while len > 0 {
+        let buf = read.fill_buf()?;
+        debug_assert!(!buf.is_empty());
+
+        let end = min(len, buf.len());
+        pos.update(&buf[0..end]);
+
+        read.consume(end);
+        len -= end;
+    }
+    Ok(())
 }
 
 impl CodeBuilder {
@@ -151,17 +202,7 @@ impl GenerateSourceMap for Code {
         let mut sections = Vec::with_capacity(self.mappings.len());
         let mut read = self.code.read();
         for (byte_pos, map) in &self.mappings {
-            let mut want = byte_pos - last_byte_pos;
-            while want > 0 {
-                let buf = read.fill_buf()?;
-                debug_assert!(!buf.is_empty());
-
-                let end = min(want, buf.len());
-                pos.update(&buf[0..end]);
-
-                read.consume(end);
-                want -= end;
-            }
+            advance(&mut read, &mut pos, byte_pos - last_byte_pos)?;
             last_byte_pos = *byte_pos;
 
             let encoded = match map {
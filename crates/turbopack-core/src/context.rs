@@ -23,4 +23,8 @@ pub trait AssetContext {
     fn process(&self, asset: AssetVc) -> AssetVc;
     fn process_resolve_result(&self, result: ResolveResultVc) -> ResolveResultVc;
     fn with_transition(&self, transition: &str) -> AssetContextVc;
+    /// Returns a context that processes assets as the given module type, e.g.
+    /// `json` for an import with a `type: "json"` attribute. Unknown module
+    /// types are ignored.
+    fn with_module_type(&self, module_type: &str) -> AssetContextVc;
 }
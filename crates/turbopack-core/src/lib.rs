@@ -16,6 +16,7 @@ pub mod reference;
 pub mod resolve;
 pub mod source_asset;
 pub mod source_map;
+pub mod source_map_concat;
 pub mod source_pos;
 pub mod target;
 mod utils;
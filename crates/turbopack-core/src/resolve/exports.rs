@@ -1,11 +1,14 @@
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+};
 
 use anyhow::{anyhow, bail, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::{
-    alias_map::{AliasMap, AliasMapLookupIterator, AliasPattern, AliasTemplate},
+    alias_map::{AliasMap, AliasMapLookupIterator, AliasMatch, AliasPattern, AliasTemplate},
     options::ConditionValue,
 };
 
@@ -317,4 +320,38 @@ impl ExportsField {
     pub fn lookup<'a>(&'a self, request: &'a str) -> AliasMapLookupIterator<'a, ExportsValue> {
         self.0.lookup(request)
     }
+
+    /// Returns the paths that `request` resolves to with the given conditions,
+    /// in order of preference and without duplicates. Conditions that are
+    /// neither in `conditions` nor `default` have the value of
+    /// `unspecified_conditions`.
+    pub fn results(
+        &self,
+        request: &str,
+        conditions: &BTreeMap<String, ConditionValue>,
+        unspecified_conditions: &ConditionValue,
+    ) -> Result<Vec<String>> {
+        let mut results = Vec::new();
+        let mut conditions_state = HashMap::new();
+        let values = self
+            .lookup(request)
+            .map(AliasMatch::try_into_self)
+            .collect::<Result<Vec<Cow<'_, ExportsValue>>>>()?;
+        for value in values.iter() {
+            if value.add_results(
+                conditions,
+                unspecified_conditions,
+                &mut conditions_state,
+                &mut results,
+            ) {
+                break;
+            }
+        }
+        let mut duplicates_set = HashSet::new();
+        results.retain(|item| duplicates_set.insert(*item));
+        Ok(results
+            .into_iter()
+            .map(|result| result.to_string())
+            .collect())
+    }
 }
@@ -1,6 +1,5 @@
 use std::{
-    borrow::Cow,
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, HashMap},
     future::Future,
     mem::take,
     pin::Pin,
@@ -20,7 +19,7 @@ use turbo_tasks_fs::{
 };
 
 use self::{
-    exports::{ExportsField, ExportsValue},
+    exports::ExportsField,
     options::{
         resolve_modules_options, ImportMapResult, ResolveInPackage, ResolveIntoPackage,
         ResolveModules, ResolveModulesOptionsVc, ResolveOptionsVc,
@@ -43,7 +42,7 @@ use crate::{
 };
 
 mod alias_map;
-mod exports;
+pub mod exports;
 pub mod options;
 pub mod origin;
 pub mod parse;
@@ -1052,29 +1051,10 @@ fn handle_exports_field(
     conditions: &BTreeMap<String, ConditionValue>,
     unspecified_conditions: &ConditionValue,
 ) -> Result<ResolveResultVc> {
-    let mut results = Vec::new();
-    let mut conditions_state = HashMap::new();
-    let values = exports_field
-        .lookup(path)
-        .map(AliasMatch::try_into_self)
-        .collect::<Result<Vec<Cow<'_, ExportsValue>>>>()?;
-    for value in values.iter() {
-        if value.add_results(
-            conditions,
-            unspecified_conditions,
-            &mut conditions_state,
-            &mut results,
-        ) {
-            break;
-        }
-    }
-    {
-        let mut duplicates_set = HashSet::new();
-        results.retain(|item| duplicates_set.insert(*item));
-    }
+    let results = exports_field.results(path, conditions, unspecified_conditions)?;
     let mut resolved_results = Vec::new();
     for path in results {
-        if let Some(path) = normalize_path(path) {
+        if let Some(path) = normalize_path(&path) {
             let request = RequestVc::parse(Value::new(format!("./{}", path).into()));
             resolved_results.push(resolve(package_path, request, options));
         }
@@ -48,6 +48,18 @@ impl ResolveOriginVc {
         .cell()
         .into()
     }
+
+    /// Processes resolved assets as the given module type, e.g. from a
+    /// `type: "json"` import attribute.
+    #[turbo_tasks::function]
+    pub fn with_module_type(self, module_type: &str) -> Self {
+        ResolveOriginWithModuleType {
+            previous: self,
+            module_type: module_type.to_string(),
+        }
+        .cell()
+        .into()
+    }
 }
 
 /// A resolve origin for some path and context without additional modifications.
@@ -101,3 +113,23 @@ impl ResolveOrigin for ResolveOriginWithTransition {
         self.previous.context().with_transition(&self.transition)
     }
 }
+
+/// Wraps a ResolveOrigin to process resolved assets as a specific module type.
+#[turbo_tasks::value]
+struct ResolveOriginWithModuleType {
+    previous: ResolveOriginVc,
+    module_type: String,
+}
+
+#[turbo_tasks::value_impl]
+impl ResolveOrigin for ResolveOriginWithModuleType {
+    #[turbo_tasks::function]
+    fn origin_path(&self) -> FileSystemPathVc {
+        self.previous.origin_path()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> AssetContextVc {
+        self.previous.context().with_module_type(&self.module_type)
+    }
+}
@@ -1,12 +1,13 @@
 use std::{io::Write, ops::Deref, sync::Arc};
 
 use anyhow::Result;
+use futures::future::BoxFuture;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sourcemap::SourceMap as CrateMap;
 use turbo_tasks::TryJoinIterExt;
 use turbo_tasks_fs::rope::{Rope, RopeBuilder, RopeVc};
 
-use crate::source_pos::SourcePos;
+use crate::{source_map_concat::SourceMapConcat, source_pos::SourcePos};
 
 /// Generates a usable source map, capable of both tracing and stringifying.
 #[turbo_tasks::value_trait]
@@ -94,6 +95,35 @@ impl SourceMapVc {
     pub fn new_sectioned(sections: Vec<SourceMapSection>) -> Self {
         SourceMap::Sectioned(SectionedSourceMap::new(sections)).cell()
     }
+
+    /// Appends the mappings of this map, which covers the region from `start`
+    /// to `end` of the generated file, to `concat`. Sectioned maps are
+    /// flattened.
+    pub(crate) fn write_into<'a, W: Write + Send>(
+        self,
+        concat: &'a mut SourceMapConcat<W>,
+        start: SourcePos,
+        end: SourcePos,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            match &*self.await? {
+                SourceMap::Regular(map) => concat.push_map(start, end, map)?,
+                SourceMap::Sectioned(map) => {
+                    for (i, section) in map.sections.iter().enumerate() {
+                        let section_end = map
+                            .sections
+                            .get(i + 1)
+                            .map_or(end, |next| start.offset(next.offset));
+                        section
+                            .map
+                            .write_into(concat, start.offset(section.offset), section_end)
+                            .await?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
 }
 
 #[turbo_tasks::value_impl]
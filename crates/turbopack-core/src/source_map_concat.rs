@@ -0,0 +1,248 @@
+use std::io::Write;
+
+use anyhow::Result;
+use indexmap::IndexSet;
+use sourcemap::SourceMap as CrateMap;
+
+use crate::source_pos::SourcePos;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Concatenates the source maps of consecutive regions of a generated file,
+/// e.g. the modules of a chunk, into a single regular source map.
+///
+/// The mappings of each region are shifted by the running line/column offset
+/// and written to `out` as soon as the region is pushed, so the concatenated
+/// map never exists in memory. Only the `sources`, `sourcesContent` and
+/// `names` tables are kept until [SourceMapConcat::finish] writes them.
+pub struct SourceMapConcat<W: Write> {
+    out: W,
+    /// The start of the next region in the generated file.
+    pos: SourcePos,
+    sources: IndexSet<String>,
+    sources_content: Vec<Option<String>>,
+    names: IndexSet<String>,
+    /// The generated line that's currently written to `mappings`.
+    line: usize,
+    /// Whether a segment was written on the current generated line.
+    line_has_segment: bool,
+    /// Whether the previous region is mapped to an original source.
+    mapped: bool,
+    /// The previous values of the segment fields, which are delta encoded.
+    /// The generated column is reset on each line.
+    prev_column: i64,
+    prev_source: i64,
+    prev_original_line: i64,
+    prev_original_column: i64,
+    prev_name: i64,
+}
+
+impl<W: Write> SourceMapConcat<W> {
+    pub fn new(mut out: W) -> Result<Self> {
+        write!(out, r#"{{"version":3,"mappings":""#)?;
+        Ok(Self {
+            out,
+            pos: SourcePos::new(),
+            sources: IndexSet::new(),
+            sources_content: Vec::new(),
+            names: IndexSet::new(),
+            line: 0,
+            line_has_segment: false,
+            mapped: false,
+            prev_column: 0,
+            prev_source: 0,
+            prev_original_line: 0,
+            prev_original_column: 0,
+            prev_name: 0,
+        })
+    }
+
+    /// Appends the generated `code` of the next region, which is mapped by
+    /// `map` if it has a source map. Mappings of `map` that don't point into
+    /// `code` are dropped, since they would overlap the next region.
+    pub fn push(&mut self, code: &[u8], map: Option<&CrateMap>) -> Result<()> {
+        let start = self.pos;
+        self.pos.update(code);
+        match map {
+            Some(map) => self.push_map(start, self.pos, map),
+            None => self.push_unmapped(start),
+        }
+    }
+
+    /// Ends the mappings of the previous region at `start`. Like in
+    /// [crate::code_builder::CodeBuilder], synthetic code would be treated as
+    /// part of the previous original code otherwise.
+    pub(crate) fn push_unmapped(&mut self, start: SourcePos) -> Result<()> {
+        if self.mapped {
+            self.mapped = false;
+            self.write_segment(start, None, None)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the mappings of `map` that fall into the region from `start`
+    /// (inclusive) to `end` (exclusive) of the generated file. `map` is
+    /// relative to `start`. Regions have to be written in order.
+    pub(crate) fn push_map(
+        &mut self,
+        start: SourcePos,
+        end: SourcePos,
+        map: &CrateMap,
+    ) -> Result<()> {
+        for token in map.tokens() {
+            let pos = start.offset(SourcePos {
+                line: token.get_dst_line() as usize,
+                column: token.get_dst_col() as usize,
+            });
+            if pos >= end {
+                break;
+            }
+
+            self.mapped = token.has_source();
+            let original = token.get_source().map(|source| {
+                (
+                    source,
+                    map.get_source_contents(token.get_src_id()),
+                    token.get_src_line(),
+                    token.get_src_col(),
+                )
+            });
+            self.write_segment(pos, original, token.get_name())?;
+        }
+        Ok(())
+    }
+
+    /// Writes the segment of a mapping at the generated position `pos` to an
+    /// `original` source, its content and line/column.
+    fn write_segment(
+        &mut self,
+        pos: SourcePos,
+        original: Option<(&str, Option<&str>, u32, u32)>,
+        name: Option<&str>,
+    ) -> Result<()> {
+        if self.line < pos.line {
+            for _ in self.line..pos.line {
+                self.out.write_all(b";")?;
+            }
+            self.line = pos.line;
+            self.line_has_segment = false;
+            self.prev_column = 0;
+        }
+        if self.line_has_segment {
+            self.out.write_all(b",")?;
+        }
+        self.line_has_segment = true;
+
+        let mut segment = Vec::with_capacity(16);
+        encode_vlq(&mut segment, pos.column as i64 - self.prev_column);
+        self.prev_column = pos.column as i64;
+        if let Some((source, content, line, column)) = original {
+            let (source, inserted) = self.sources.insert_full(source.to_string());
+            if inserted {
+                self.sources_content
+                    .push(content.map(|content| content.to_string()));
+            }
+            let (source, line, column) = (source as i64, line as i64, column as i64);
+            encode_vlq(&mut segment, source - self.prev_source);
+            encode_vlq(&mut segment, line - self.prev_original_line);
+            encode_vlq(&mut segment, column - self.prev_original_column);
+            self.prev_source = source;
+            self.prev_original_line = line;
+            self.prev_original_column = column;
+
+            if let Some(name) = name {
+                let name = self.names.insert_full(name.to_string()).0 as i64;
+                encode_vlq(&mut segment, name - self.prev_name);
+                self.prev_name = name;
+            }
+        }
+        self.out.write_all(&segment)?;
+        Ok(())
+    }
+
+    /// Writes the remaining fields of the source map and returns the writer.
+    pub fn finish(mut self) -> Result<W> {
+        write!(self.out, r#"","sources":"#)?;
+        serde_json::to_writer(&mut self.out, &self.sources)?;
+        write!(self.out, r#","sourcesContent":"#)?;
+        serde_json::to_writer(&mut self.out, &self.sources_content)?;
+        write!(self.out, r#","names":"#)?;
+        serde_json::to_writer(&mut self.out, &self.names)?;
+        write!(self.out, "}}")?;
+        Ok(self.out)
+    }
+}
+
+/// Appends the base64 VLQ encoding of `value` to `out`.
+fn encode_vlq(out: &mut Vec<u8>, value: i64) {
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = vlq & 0b11111;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64[digit as usize]);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sourcemap::{SourceMap as CrateMap, SourceMapBuilder};
+
+    use super::SourceMapConcat;
+
+    /// A map of a module that maps the start of each of its lines to the same
+    /// line of `file`.
+    fn module_map(file: &str, lines: u32) -> CrateMap {
+        let mut builder = SourceMapBuilder::new(None);
+        let source = builder.add_source(file);
+        builder.set_source_contents(source, Some(file));
+        for line in 0..lines {
+            builder.add(line, 0, line, 0, Some(file), Some("name"));
+        }
+        builder.into_sourcemap()
+    }
+
+    #[test]
+    fn concat_modules() {
+        let mut concat = SourceMapConcat::new(Vec::new()).unwrap();
+        concat.push(b"/* header */ ", None).unwrap();
+        // Ends with a newline, the next module starts at column 0
+        concat
+            .push(b"a();\nb();\n", Some(&module_map("a.js", 2)))
+            .unwrap();
+        // Doesn't end with a newline, the next module starts on the same line
+        concat.push(b"c();", Some(&module_map("b.js", 1))).unwrap();
+        // Ends the mappings of b.js
+        concat.push(b" ", None).unwrap();
+        // The map has a mapping past the end of the code, which is dropped
+        concat
+            .push(b"d();\r\ne();", Some(&module_map("c.js", 3)))
+            .unwrap();
+        let bytes = concat.finish().unwrap();
+
+        let map = CrateMap::from_slice(&bytes).unwrap();
+        let lookup = |line, column| {
+            let token = map.lookup_token(line, column).unwrap();
+            assert_eq!((token.get_dst_line(), token.get_dst_col()), (line, column));
+            (token.get_source().unwrap(), token.get_src_line())
+        };
+        assert_eq!(lookup(0, 13), ("a.js", 0));
+        assert_eq!(lookup(1, 0), ("a.js", 1));
+        assert_eq!(lookup(2, 0), ("b.js", 0));
+        assert_eq!(map.lookup_token(2, 4).unwrap().get_source(), None);
+        assert_eq!(lookup(2, 5), ("c.js", 0));
+        assert_eq!(lookup(3, 0), ("c.js", 1));
+        assert_eq!(map.get_token_count(), 6);
+        assert_eq!(map.get_source_contents(1), Some("b.js"));
+        assert_eq!(map.get_name(0), Some("name"));
+    }
+}
@@ -38,6 +38,19 @@ impl SourcePos {
         }
     }
 
+    /// The position of `relative`, a position relative to this one. Only the
+    /// first line is shifted by the column of this position.
+    pub fn offset(self, relative: SourcePos) -> Self {
+        Self {
+            line: self.line + relative.line,
+            column: if relative.line == 0 {
+                self.column + relative.column
+            } else {
+                relative.column
+            },
+        }
+    }
+
     /// Increments the line/column position to account for new source code.
     /// Line terminators are the classic "\n", "\r", "\r\n" (which counts as
     /// a single terminator), and JSON LINE/PARAGRAPH SEPARATORs.
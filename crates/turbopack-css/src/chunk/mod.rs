@@ -5,7 +5,10 @@ use std::fmt::Write;
 
 use anyhow::{anyhow, Result};
 use indexmap::IndexSet;
-use turbo_tasks::{primitives::StringVc, TryJoinIterExt, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringVc},
+    TryJoinIterExt, ValueToString, ValueToStringVc,
+};
 use turbo_tasks_fs::{File, FileSystemPathOptionVc, FileSystemPathVc};
 use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
 use turbopack_core::{
@@ -307,6 +310,7 @@ impl FromChunkableAsset for CssChunkItemVc {
     async fn from_async_asset(
         _context: ChunkingContextVc,
         _asset: ChunkableAssetVc,
+        _chunk_name: OptionStringVc,
     ) -> Result<Option<(Self, ChunkableAssetVc)>> {
         Ok(None)
     }
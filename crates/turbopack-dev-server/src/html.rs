@@ -1,3 +1,5 @@
+use std::collections::{HashSet, VecDeque};
+
 use anyhow::{anyhow, Result};
 use mime_guess::mime::TEXT_HTML_UTF_8;
 use turbo_tasks::{debug::ValueDebug, primitives::StringVc};
@@ -5,14 +7,15 @@ use turbo_tasks_fs::{File, FileSystemPathVc};
 use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
-    chunk::{ChunkGroupVc, ChunkReferenceVc},
+    chunk::{ChunkGroupReferenceVc, ChunkGroupVc, ChunkLoadingHint, ChunkReferenceVc, ChunkVc},
     reference::AssetReferencesVc,
     version::{Update, UpdateVc, Version, VersionVc, VersionedContent, VersionedContentVc},
 };
 
 /// The HTML entry point of the dev server.
 ///
-/// Generates an HTML page that includes the ES and CSS chunks.
+/// Generates an HTML page that includes the ES and CSS chunks, and preloads or
+/// prefetches the chunks of dynamic imports with a loading hint.
 #[turbo_tasks::value(shared)]
 #[derive(Clone)]
 pub struct DevHtmlAsset {
@@ -103,28 +106,128 @@ impl DevHtmlAssetVc {
         let context_path = this.path.parent().await?;
 
         let mut chunk_paths = vec![];
+        let mut loading_hints = vec![];
         for chunk_group in &this.chunk_groups {
             for chunk in chunk_group.chunks().await?.iter() {
                 let chunk_path = &*chunk.path().await?;
                 if let Some(relative_path) = context_path.get_path_to(chunk_path) {
                     chunk_paths.push(format!("/{relative_path}"));
                 }
+                for (hinted_chunk, hint) in hinted_chunks(*chunk).await? {
+                    let chunk_path = &*hinted_chunk.path().await?;
+                    if let Some(relative_path) = context_path.get_path_to(chunk_path) {
+                        loading_hints.push((format!("/{relative_path}"), hint));
+                    }
+                }
             }
         }
 
-        Ok(DevHtmlAssetContentVc::new(chunk_paths, this.body.clone()))
+        Ok(DevHtmlAssetContentVc::new(
+            chunk_paths,
+            loading_hints,
+            this.body.clone(),
+        ))
+    }
+}
+
+/// The chunks of the chunk groups that `chunk` references with a loading hint.
+/// The chunk group of a dynamic import consists of a manifest chunk, which
+/// references the chunks that it loads, so these are followed too.
+async fn hinted_chunks(chunk: ChunkVc) -> Result<Vec<(ChunkVc, ChunkLoadingHint)>> {
+    let mut hinted_chunks = Vec::new();
+    for reference in chunk.references().await?.iter() {
+        let Some(group_reference) = ChunkGroupReferenceVc::resolve_from(reference).await? else {
+            continue;
+        };
+        let Some(hint) = *group_reference.loading_hint().await? else {
+            continue;
+        };
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.extend(
+            reference
+                .resolve_reference()
+                .primary_assets()
+                .await?
+                .iter()
+                .copied(),
+        );
+        while let Some(asset) = queue.pop_front() {
+            if !visited.insert(asset) {
+                continue;
+            }
+            let Some(chunk) = ChunkVc::resolve_from(asset).await? else {
+                continue;
+            };
+            hinted_chunks.push((chunk, hint));
+            for reference in chunk.references().await?.iter() {
+                if ChunkReferenceVc::resolve_from(reference).await?.is_some() {
+                    queue.extend(
+                        reference
+                            .resolve_reference()
+                            .primary_assets()
+                            .await?
+                            .iter()
+                            .copied(),
+                    );
+                }
+            }
+        }
     }
+    Ok(hinted_chunks)
+}
+
+/// The `<link>` tags for the `loading_hints` of chunks that aren't loaded by
+/// the page anyway. Preloads are emitted before prefetches, and hints with a
+/// higher order first.
+fn hint_links(chunk_paths: &[String], loading_hints: &[(String, ChunkLoadingHint)]) -> Vec<String> {
+    let mut loading_hints = loading_hints.iter().collect::<Vec<_>>();
+    loading_hints.sort_by_key(|(_, hint)| match *hint {
+        ChunkLoadingHint::Preload { order } => (0, -(order as i64)),
+        ChunkLoadingHint::Prefetch { order } => (1, -(order as i64)),
+    });
+    let mut seen = chunk_paths.iter().collect::<HashSet<_>>();
+    let mut links = Vec::new();
+    for (relative_path, hint) in loading_hints {
+        if !seen.insert(relative_path) {
+            continue;
+        }
+        links.push(match hint {
+            ChunkLoadingHint::Preload { .. } => {
+                let ty = if relative_path.ends_with(".css") {
+                    "style"
+                } else {
+                    "script"
+                };
+                format!("<link rel=\"preload\" href=\"{relative_path}\" as=\"{ty}\">")
+            }
+            ChunkLoadingHint::Prefetch { .. } => {
+                format!("<link rel=\"prefetch\" href=\"{relative_path}\">")
+            }
+        });
+    }
+    links
 }
 
 #[turbo_tasks::value]
 struct DevHtmlAssetContent {
     chunk_paths: Vec<String>,
+    loading_hints: Vec<(String, ChunkLoadingHint)>,
     body: Option<String>,
 }
 
 impl DevHtmlAssetContentVc {
-    pub fn new(chunk_paths: Vec<String>, body: Option<String>) -> Self {
-        DevHtmlAssetContent { chunk_paths, body }.cell()
+    pub fn new(
+        chunk_paths: Vec<String>,
+        loading_hints: Vec<(String, ChunkLoadingHint)>,
+        body: Option<String>,
+    ) -> Self {
+        DevHtmlAssetContent {
+            chunk_paths,
+            loading_hints,
+            body,
+        }
+        .cell()
     }
 }
 
@@ -155,6 +258,8 @@ impl DevHtmlAssetContentVc {
             None => "",
         };
 
+        stylesheets.extend(hint_links(&this.chunk_paths, &this.loading_hints));
+
         let html = format!(
             "<!DOCTYPE html>\n<html>\n<head>\n{}\n</head>\n<body>\n{}\n{}\n</body>\n</html>",
             stylesheets.join("\n"),
@@ -194,7 +299,9 @@ impl VersionedContent for DevHtmlAssetContent {
         let to = to_version.await?;
         let from = from_version.await?;
 
-        if to.content.chunk_paths == from.content.chunk_paths {
+        if to.content.chunk_paths == from.content.chunk_paths
+            && to.content.loading_hints == from.content.loading_hints
+        {
             return Ok(Update::None.into());
         }
 
@@ -220,6 +327,9 @@ impl Version for DevHtmlAssetVersion {
         for relative_path in &*self.content.chunk_paths {
             hasher.write_ref(relative_path);
         }
+        for link in hint_links(&self.content.chunk_paths, &self.content.loading_hints) {
+            hasher.write_ref(&link);
+        }
         if let Some(body) = &self.content.body {
             hasher.write_ref(body);
         }
@@ -228,3 +338,36 @@ impl Version for DevHtmlAssetVersion {
         Ok(StringVc::cell(hex_hash))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use turbopack_core::chunk::ChunkLoadingHint;
+
+    use super::hint_links;
+
+    #[test]
+    fn loading_hint_links() {
+        let chunk_paths = vec!["/index.js".to_string()];
+        let loading_hints = vec![
+            ("/a.js".to_string(), ChunkLoadingHint::Prefetch { order: 0 }),
+            ("/b.js".to_string(), ChunkLoadingHint::Preload { order: 0 }),
+            ("/b.css".to_string(), ChunkLoadingHint::Preload { order: 2 }),
+            ("/c.js".to_string(), ChunkLoadingHint::Prefetch { order: 1 }),
+            // Already loaded by the page, or by a preload
+            (
+                "/index.js".to_string(),
+                ChunkLoadingHint::Preload { order: 0 },
+            ),
+            ("/b.js".to_string(), ChunkLoadingHint::Prefetch { order: 5 }),
+        ];
+        assert_eq!(
+            hint_links(&chunk_paths, &loading_hints),
+            vec![
+                r#"<link rel="preload" href="/b.css" as="style">"#,
+                r#"<link rel="preload" href="/b.js" as="script">"#,
+                r#"<link rel="prefetch" href="/c.js">"#,
+                r#"<link rel="prefetch" href="/a.js">"#,
+            ]
+        );
+    }
+}
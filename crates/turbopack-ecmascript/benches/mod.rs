@@ -1,6 +1,8 @@
 use criterion::{criterion_group, criterion_main};
 
 mod analyzer;
+mod path_visitor;
 
 criterion_group!(analyzer_benches, analyzer::benchmark);
-criterion_main!(analyzer_benches);
+criterion_group!(path_visitor_benches, path_visitor::benchmark);
+criterion_main!(analyzer_benches, path_visitor_benches);
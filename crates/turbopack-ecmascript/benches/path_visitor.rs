@@ -0,0 +1,177 @@
+use std::{sync::Arc, time::Duration};
+
+use criterion::{BatchSize, BenchmarkId, Criterion};
+use swc_core::{
+    common::{FileName, FilePathMapping, SourceMap},
+    ecma::{
+        ast::{Decl, EsVersion, FnDecl, Module, Stmt, Str},
+        parser::parse_file_as_module,
+        visit::{fields::*, AstParentKind, VisitMut, VisitMutWithPath},
+    },
+};
+use turbopack_ecmascript::{
+    code_gen::VisitorFactory,
+    path_visitor::{find_paths, ApplyVisitors, AstPath, Node},
+};
+
+const STATEMENTS: usize = 5000;
+
+pub fn benchmark(c: &mut Criterion) {
+    let cm = Arc::new(SourceMap::new(FilePathMapping::empty()));
+    let source = (0..STATEMENTS)
+        .map(|i| format!("var a{i} = 'value';\n"))
+        .collect::<String>();
+    let fm = cm.new_source_file(FileName::Anon, source);
+    let module = parse_file_as_module(
+        &fm,
+        Default::default(),
+        EsVersion::latest(),
+        None,
+        &mut vec![],
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("path_visitor");
+    group.warm_up_time(Duration::from_secs(1));
+    group.measurement_time(Duration::from_secs(3));
+
+    let paths = (0..STATEMENTS).map(str_path).collect::<Vec<_>>();
+    let factories = [StrVisitorFactory, StrVisitorFactory, StrVisitorFactory];
+    let visitor_sets = || {
+        factories
+            .iter()
+            .enumerate()
+            .map(|(set, factory)| {
+                paths
+                    .iter()
+                    .skip(set)
+                    .step_by(factories.len())
+                    .map(|path| (path, factory as &dyn VisitorFactory))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    group.bench_function("sequential_visitor_sets", |b| {
+        b.iter_batched(
+            || module.clone(),
+            |mut module: Module| {
+                for visitors in visitor_sets() {
+                    module.visit_mut_with_path(
+                        &mut ApplyVisitors::new(visitors),
+                        &mut Default::default(),
+                    );
+                }
+                module
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("merged_visitor_sets", |b| {
+        b.iter_batched(
+            || module.clone(),
+            |mut module: Module| {
+                module.visit_mut_with_path(
+                    &mut ApplyVisitors::merged(visitor_sets()),
+                    &mut Default::default(),
+                );
+                module
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    for step in [1, 10, 100] {
+        let paths = (0..STATEMENTS)
+            .step_by(step)
+            .map(str_path)
+            .collect::<Vec<_>>();
+        let factory = StrVisitorFactory;
+        group.bench_with_input(
+            BenchmarkId::new("apply_visitors", paths.len()),
+            &paths,
+            |b, paths| {
+                b.iter_batched(
+                    || module.clone(),
+                    |mut module: Module| {
+                        let visitors = paths
+                            .iter()
+                            .map(|path| (path, &factory as &dyn VisitorFactory))
+                            .collect();
+                        module.visit_mut_with_path(
+                            &mut ApplyVisitors::new(visitors),
+                            &mut Default::default(),
+                        );
+                        module
+                    },
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+
+    // Half of the functions are private, their bodies are skipped when pruning
+    let source = (0..STATEMENTS)
+        .map(|i| {
+            let prefix = if i % 2 == 0 { "_" } else { "" };
+            format!("function {prefix}f{i}() {{ g('value', ['value']); }}\n")
+        })
+        .collect::<String>();
+    let fm = cm.new_source_file(FileName::Anon, source);
+    let module = parse_file_as_module(
+        &fm,
+        Default::default(),
+        EsVersion::latest(),
+        None,
+        &mut vec![],
+    )
+    .unwrap();
+    let is_str = |_: &AstPath, node: &Node| matches!(node, Node::Str(_));
+    group.bench_function("find_paths", |b| {
+        b.iter(|| find_paths(&module, |_, _| true, is_str));
+    });
+    group.bench_function("find_paths_pruned", |b| {
+        b.iter(|| {
+            find_paths(
+                &module,
+                |_, node| {
+                    !matches!(
+                        node,
+                        Node::Stmt(Stmt::Decl(Decl::Fn(FnDecl { ident, .. })))
+                            if ident.sym.starts_with('_')
+                    )
+                },
+                is_str,
+            )
+        });
+    });
+}
+
+/// Path to the string literal in the `index`th `var aN = '...';` statement.
+fn str_path(index: usize) -> AstPath {
+    vec![
+        AstParentKind::Module(ModuleField::Body(index)),
+        AstParentKind::ModuleItem(ModuleItemField::Stmt),
+        AstParentKind::Stmt(StmtField::Decl),
+        AstParentKind::Decl(DeclField::Var),
+        AstParentKind::VarDecl(VarDeclField::Decls(0)),
+        AstParentKind::VarDeclarator(VarDeclaratorField::Init),
+        AstParentKind::Expr(ExprField::Lit),
+        AstParentKind::Lit(LitField::Str),
+    ]
+}
+
+struct StrVisitorFactory;
+
+impl VisitorFactory for StrVisitorFactory {
+    fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+        Box::new(StrVisitor)
+    }
+}
+
+struct StrVisitor;
+
+impl VisitMut for StrVisitor {
+    fn visit_mut_str(&mut self, s: &mut Str) {
+        s.raw = None;
+    }
+}
@@ -132,6 +132,18 @@
     module.exports = value;
   }
 
+  /**
+   * Runs the body of a module with top-level await. Its exports are complete
+   * once `module.promise` resolves.
+   *
+   * @param {Module} module
+   * @param {() => Promise<void>} body
+   */
+  function asyncModule(module, body) {
+    module.async = true;
+    module.promise = body();
+  }
+
   /**
    * @param {Record<string, any>} obj
    * @param {string} key
@@ -158,18 +170,25 @@
   }
 
   /**
+   * `isCjs` is set when the imported module is a CommonJS module. Like in
+   * Node.js, its `module.exports` is the default export, unless the module is
+   * flagged with `__esModule` (e.g. compiled by Babel or TypeScript) and sets
+   * `exports.default` itself.
+   *
    * @param {Module} sourceModule
    * @param {ModuleId} id
-   * @param {boolean} allowExportDefault
+   * @param {boolean} [isCjs]
    * @returns {EsmInteropNamespace}
    */
-  function esmImport(sourceModule, id, allowExportDefault) {
+  function esmImport(sourceModule, id, isCjs) {
     const module = getOrInstantiateModuleFromParent(id, sourceModule);
     const raw = module.exports;
-    if (raw.__esModule) return raw;
+    if (raw != null && raw.__esModule && (!isCjs || "default" in raw)) {
+      return raw;
+    }
     if (module.interopNamespace) return module.interopNamespace;
     const ns = (module.interopNamespace = {});
-    interopEsm(raw, ns, allowExportDefault);
+    interopEsm(raw, ns);
     return ns;
   }
 
@@ -266,12 +285,18 @@
       };
       document.body.appendChild(link);
     } else if (chunkPath.endsWith(".js")) {
-      const script = document.createElement("script");
-      script.src = `/${chunkPath}`;
-      // We'll only mark the chunk as loaded once the script has been executed,
-      // which happens in `registerChunk`.
-      script.onerror = onError;
-      document.body.appendChild(script);
+      if (self.TURBOPACK_CHUNK_FORMAT === "esm") {
+        // Chunks are ES modules, which are marked as loaded in `registerChunk`
+        // too.
+        import(`/${chunkPath}`).catch(onError);
+      } else {
+        const script = document.createElement("script");
+        script.src = `/${chunkPath}`;
+        // We'll only mark the chunk as loaded once the script has been executed,
+        // which happens in `registerChunk`.
+        script.onerror = onError;
+        document.body.appendChild(script);
+      }
     } else {
       throw new Error(`can't infer type of chunk from path ${chunkPath}`);
     }
@@ -362,6 +387,7 @@
         i: esmImport.bind(null, module),
         s: esm.bind(null, module.exports),
         v: exportValue.bind(null, module),
+        a: asyncModule.bind(null, module),
         m: module,
         c: moduleCache,
         l: loadChunk,
@@ -35,6 +35,8 @@ interface Module {
   children: ModuleId[];
   parents: ModuleId[];
   interopNamespace?: EsmInteropNamespace;
+  async?: boolean;
+  promise?: Promise<void>;
 }
 
 type ModuleCache = Record<ModuleId, Module>;
@@ -44,10 +46,11 @@ type CommonJsRequire = (moduleId: ModuleId) => Exports;
 export type EsmInteropNamespace = Record<string, any>;
 type EsmImport = (
   moduleId: ModuleId,
-  allowExportDefault: boolean
+  isCjs?: boolean
 ) => EsmInteropNamespace;
 type EsmExport = (exportGetters: Record<string, () => any>) => void;
 type ExportValue = (value: any) => void;
+type AsyncModule = (body: () => Promise<void>) => void;
 
 type LoadChunk = (chunkPath: ChunkPath) => Promise<any> | undefined;
 
@@ -57,6 +60,7 @@ interface TurbopackContext {
   i: EsmImport;
   s: EsmExport;
   v: ExportValue;
+  a: AsyncModule;
   m: Module;
   c: ModuleCache;
   l: LoadChunk;
@@ -89,6 +93,7 @@ export interface TurbopackGlobals {
   TURBOPACK_CHUNK_UPDATE_LISTENERS?:
     | ChunkUpdateProvider
     | [ChunkPath, UpdateCallback][];
+  TURBOPACK_CHUNK_FORMAT?: "esm";
 }
 
 declare global {
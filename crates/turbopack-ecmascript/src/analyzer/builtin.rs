@@ -1,15 +1,61 @@
 use std::{mem::take, sync::Arc};
 
-use super::{ConstantNumber, ConstantValue, JsValue, ObjectPart};
+use swc_core::ecma::atoms::JsWord;
+
+use super::{ConstantNumber, ConstantValue, JsValue, ObjectPart, WellKnownFunctionKind};
 use crate::analyzer::FreeVarKind;
 
-const ARRAY_METHODS: [&str; 2] = ["concat", "map"];
+const ARRAY_METHODS: [&str; 3] = ["concat", "map", "filter"];
+
+/// `String.prototype` methods that are evaluated on constant strings, see
+/// [super::well_known::string_method].
+const STRING_METHODS: [&str; 9] = [
+    "concat",
+    "replace",
+    "replaceAll",
+    "slice",
+    "toLowerCase",
+    "toUpperCase",
+    "trim",
+    "trimEnd",
+    "trimStart",
+];
+
+/// The index of the array element for the property `key`, which is only
+/// the case for the canonical string of an integer, e.g. `"1"` but not `"01"`.
+fn array_index(key: &str) -> Option<usize> {
+    key.parse::<usize>()
+        .ok()
+        .filter(|index| index.to_string() == key)
+}
+
+/// Compares property keys like JavaScript does, which converts constant keys
+/// to strings, e.g. `obj[1]` is `obj["1"]`.
+fn is_same_property(key: &JsValue, prop: &JsValue) -> bool {
+    match (key, prop) {
+        (JsValue::Constant(key), JsValue::Constant(prop)) => {
+            key.to_js_string() == prop.to_js_string()
+        }
+        _ => key == prop,
+    }
+}
 
 pub fn replace_builtin(value: &mut JsValue) -> bool {
     match value {
         JsValue::Member(_, box ref mut obj, ref mut prop) => {
             match obj {
-                JsValue::Constant(_) => {
+                JsValue::Constant(c) => {
+                    let method = prop
+                        .as_str()
+                        .filter(|method| c.as_str().is_some() && STRING_METHODS.contains(method));
+                    if let Some(method) = method {
+                        let method: JsWord = method.into();
+                        *value = JsValue::WellKnownFunction(WellKnownFunctionKind::StringMethod(
+                            box take(obj),
+                            method,
+                        ));
+                        return true;
+                    }
                     value.make_unknown("property on constant");
                     true
                 }
@@ -25,6 +71,10 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                     value.make_unknown("property on number or string");
                     true
                 }
+                JsValue::Binary(..) => {
+                    value.make_unknown("property on boolean");
+                    true
+                }
                 JsValue::Unknown(..) => {
                     value.make_unknown("property on unknown");
                     true
@@ -79,6 +129,13 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                                 if ARRAY_METHODS.iter().any(|method| *method == s) {
                                     return false;
                                 }
+                                // `array["0"]` is `array[0]`
+                                if let Some(index) = array_index(s) {
+                                    if index < array.len() {
+                                        *value = array.swap_remove(index);
+                                        return true;
+                                    }
+                                }
                             }
                             value.make_unknown("non-num constant property on array");
                             true
@@ -108,7 +165,7 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                             );
                             true
                         }
-                        JsValue::Concat(..) | JsValue::Add(..) => {
+                        JsValue::Concat(..) | JsValue::Add(..) | JsValue::Binary(..) => {
                             if prop.has_placeholder() {
                                 // keep the member infact since it might be handled later
                                 false
@@ -172,7 +229,7 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                             for part in parts.iter_mut().rev() {
                                 match part {
                                     ObjectPart::KeyValue(key, val) => {
-                                        if key == &**prop {
+                                        if is_same_property(key, prop) {
                                             *value = take(val);
                                             return true;
                                         }
@@ -211,7 +268,7 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                             );
                             true
                         }
-                        JsValue::Concat(..) | JsValue::Add(..) => {
+                        JsValue::Concat(..) | JsValue::Add(..) | JsValue::Binary(..) => {
                             if prop.has_placeholder() {
                                 // keep the member intact since it might be handled later
                                 false
@@ -256,23 +313,29 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                     if let Some(str) = prop.as_str() {
                         match str {
                             "concat" => {
-                                if args.iter().all(|arg| {
-                                    matches!(
-                                        arg,
-                                        JsValue::Array(..)
-                                            | JsValue::Constant(_)
-                                            | JsValue::Url(_)
-                                            | JsValue::Concat(..)
-                                            | JsValue::Add(..)
-                                            | JsValue::WellKnownObject(_)
-                                            | JsValue::WellKnownFunction(_)
-                                            | JsValue::Function(..)
-                                    )
-                                }) {
-                                    for arg in args {
+                                // Known arguments are appended up to the first unknown one,
+                                // the remaining ones are kept for when they are known
+                                let known = args
+                                    .iter()
+                                    .take_while(|arg| {
+                                        matches!(
+                                            arg,
+                                            JsValue::Array(..)
+                                                | JsValue::Constant(_)
+                                                | JsValue::Url(_)
+                                                | JsValue::Concat(..)
+                                                | JsValue::Add(..)
+                                                | JsValue::WellKnownObject(_)
+                                                | JsValue::WellKnownFunction(_)
+                                                | JsValue::Function(..)
+                                        )
+                                    })
+                                    .count();
+                                if known > 0 || args.is_empty() {
+                                    for arg in args.drain(..known) {
                                         match arg {
                                             JsValue::Array(_, inner) => {
-                                                items.extend(take(inner));
+                                                items.extend(inner);
                                             }
                                             JsValue::Constant(_)
                                             | JsValue::Url(_)
@@ -281,7 +344,7 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                                             | JsValue::WellKnownObject(_)
                                             | JsValue::WellKnownFunction(_)
                                             | JsValue::Function(..) => {
-                                                items.push(take(arg));
+                                                items.push(arg);
                                             }
                                             _ => {
                                                 unreachable!();
@@ -289,7 +352,11 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                                         }
                                     }
                                     obj.update_total_nodes();
-                                    *value = take(obj);
+                                    if args.is_empty() {
+                                        *value = take(obj);
+                                    } else {
+                                        value.update_total_nodes();
+                                    }
                                     return true;
                                 }
                             }
@@ -341,7 +408,15 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                                             *value = JsValue::array(
                                                 items
                                                     .iter()
-                                                    .map(|_| return_value.clone())
+                                                    .enumerate()
+                                                    .map(|(index, item)| {
+                                                        apply_array_callback(
+                                                            return_value,
+                                                            item,
+                                                            index,
+                                                            items,
+                                                        )
+                                                    })
                                                     .collect(),
                                             );
                                         }
@@ -351,6 +426,33 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                                     return false;
                                 }
                             }
+                            "filter" => {
+                                if let Some(JsValue::Function(_, box return_value)) = args.first() {
+                                    let kept: Option<Vec<_>> = items
+                                        .iter()
+                                        .enumerate()
+                                        .filter_map(|(index, item)| {
+                                            let result = apply_array_callback(
+                                                return_value,
+                                                item,
+                                                index,
+                                                items,
+                                            );
+                                            match is_truthy(&result) {
+                                                Some(true) => Some(Some(item.clone())),
+                                                Some(false) => None,
+                                                None => Some(None),
+                                            }
+                                        })
+                                        .collect();
+                                    match kept {
+                                        Some(kept) => *value = JsValue::array(kept),
+                                        None => value
+                                            .make_unknown("filter callback with unknown result"),
+                                    }
+                                    return true;
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -404,6 +506,10 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                     value.make_unknown("call of number or string");
                     true
                 }
+                JsValue::Binary(..) => {
+                    value.make_unknown("call of boolean");
+                    true
+                }
                 JsValue::Function(_, box ref mut return_value) => {
                     let mut return_value = take(return_value);
                     return_value.visit_mut_conditional(
@@ -471,3 +577,48 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
         _ => false,
     }
 }
+
+/// Evaluates an array method callback, represented by its `return_value`, for
+/// the element `item` at `index` of `items`.
+fn apply_array_callback(
+    return_value: &JsValue,
+    item: &JsValue,
+    index: usize,
+    items: &[JsValue],
+) -> JsValue {
+    let mut value = return_value.clone();
+    value.visit_mut_conditional(
+        |value| !matches!(value, JsValue::Function(..)),
+        &mut |value| match value {
+            JsValue::Argument(0) => {
+                *value = item.clone();
+                true
+            }
+            JsValue::Argument(1) => {
+                *value = (index as f64).into();
+                true
+            }
+            JsValue::Argument(2) => {
+                *value = JsValue::array(items.to_vec());
+                true
+            }
+            _ => false,
+        },
+    );
+    value.normalize();
+    value
+}
+
+/// Returns whether the value is truthy, or `None` if that is not known.
+fn is_truthy(value: &JsValue) -> Option<bool> {
+    match value {
+        JsValue::Constant(c) => Some(c.is_truthy()),
+        JsValue::Array(..)
+        | JsValue::Object(..)
+        | JsValue::Url(..)
+        | JsValue::Function(..)
+        | JsValue::WellKnownObject(..)
+        | JsValue::WellKnownFunction(..) => Some(true),
+        _ => None,
+    }
+}
@@ -0,0 +1,249 @@
+//! Detection of code that is evaluated from strings at runtime. The evaluated
+//! code can't be analyzed. Code that is evaluated by a direct `eval` might
+//! access the bindings of the module by name, so modules that use it must not
+//! be scope hoisted or have their bindings renamed.
+
+use std::fmt::Display;
+
+use swc_core::{
+    common::{Mark, Span},
+    ecma::{
+        ast::*,
+        visit::{Visit, VisitWith},
+    },
+};
+
+use crate::utils::unparen;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynamicCodeKind {
+    /// `eval(code)`, which evaluates the code in the scope of the call, with
+    /// access to all bindings in scope.
+    DirectEval,
+    /// Any other use of the global `eval`, e.g. `(0, eval)(code)` or
+    /// `globalThis.eval(code)`, which evaluates the code in the global scope.
+    IndirectEval,
+    /// `new Function(code)` or `Function(code)`, which evaluates the code in
+    /// the global scope.
+    FunctionConstructor,
+}
+
+impl Display for DynamicCodeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DynamicCodeKind::DirectEval => "eval()",
+            DynamicCodeKind::IndirectEval => "Indirect eval()",
+            DynamicCodeKind::FunctionConstructor => "new Function()",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicCode {
+    pub kind: DynamicCodeKind,
+    pub span: Span,
+}
+
+/// Finds the uses of `eval` and the `Function` constructor in `node`. Local
+/// bindings that shadow `eval` or `Function` are not considered.
+///
+/// Note: This requires running `resolver` with `unresolved_mark` **before**
+/// running this.
+pub fn find_dynamic_code<N>(node: &N, unresolved_mark: Mark) -> Vec<DynamicCode>
+where
+    N: VisitWith<DynamicCodeFinder>,
+{
+    let mut finder = DynamicCodeFinder {
+        unresolved_mark,
+        found: Vec::new(),
+    };
+    node.visit_with(&mut finder);
+    finder.found
+}
+
+/// Whether `node` uses a direct `eval`, see [find_dynamic_code]. Indirect
+/// `eval` and the `Function` constructor evaluate code in the global scope,
+/// which can't access the bindings of the module.
+pub fn uses_dynamic_scope<N>(node: &N, unresolved_mark: Mark) -> bool
+where
+    N: VisitWith<DynamicCodeFinder>,
+{
+    find_dynamic_code(node, unresolved_mark)
+        .iter()
+        .any(|code| code.kind == DynamicCodeKind::DirectEval)
+}
+
+pub struct DynamicCodeFinder {
+    unresolved_mark: Mark,
+    found: Vec<DynamicCode>,
+}
+
+impl DynamicCodeFinder {
+    fn is_global(&self, expr: &Expr, name: &str) -> bool {
+        matches!(
+            unparen(expr),
+            Expr::Ident(ident)
+                if &*ident.sym == name && ident.span.ctxt.outer() == self.unresolved_mark
+        )
+    }
+
+    fn push(&mut self, kind: DynamicCodeKind, span: Span) {
+        self.found.push(DynamicCode { kind, span });
+    }
+}
+
+impl Visit for DynamicCodeFinder {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Expr(callee) = &call.callee {
+            // `(eval)(code)` is a direct eval too, unlike `(0, eval)(code)`
+            let kind = if self.is_global(callee, "eval") {
+                Some(DynamicCodeKind::DirectEval)
+            } else if self.is_global(callee, "Function") {
+                Some(DynamicCodeKind::FunctionConstructor)
+            } else {
+                None
+            };
+            if let Some(kind) = kind {
+                self.push(kind, call.span);
+                call.args.visit_with(self);
+                return;
+            }
+        }
+        call.visit_children_with(self);
+    }
+
+    fn visit_new_expr(&mut self, new: &NewExpr) {
+        if self.is_global(&new.callee, "Function") {
+            self.push(DynamicCodeKind::FunctionConstructor, new.span);
+            new.args.visit_with(self);
+            return;
+        }
+        new.visit_children_with(self);
+    }
+
+    fn visit_member_expr(&mut self, member: &MemberExpr) {
+        let is_global_object = ["globalThis", "window", "self", "global"]
+            .iter()
+            .any(|name| self.is_global(&member.obj, name));
+        match &member.prop {
+            MemberProp::Ident(prop) if is_global_object && &*prop.sym == "eval" => {
+                self.push(DynamicCodeKind::IndirectEval, member.span);
+            }
+            _ => member.visit_children_with(self),
+        }
+    }
+
+    fn visit_ident(&mut self, ident: &Ident) {
+        if &*ident.sym == "eval" && ident.span.ctxt.outer() == self.unresolved_mark {
+            self.push(DynamicCodeKind::IndirectEval, ident.span);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, Mark},
+        ecma::{
+            ast::EsVersion, parser::parse_file_as_program, transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::{find_dynamic_code, uses_dynamic_scope, DynamicCodeKind};
+
+    fn dynamic_scope(src: &str) -> bool {
+        run_test(false, |cm, handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let mut program = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| err.into_diagnostic(handler).emit())?;
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+            Ok(uses_dynamic_scope(&program, unresolved_mark))
+        })
+        .unwrap()
+    }
+
+    fn dynamic_code(src: &str) -> Vec<DynamicCodeKind> {
+        run_test(false, |cm, handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let mut program = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| err.into_diagnostic(handler).emit())?;
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+            Ok(find_dynamic_code(&program, unresolved_mark)
+                .into_iter()
+                .map(|code| code.kind)
+                .collect())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn direct_eval() {
+        assert_eq!(
+            dynamic_code("eval(\"a + 1\");\n(eval)(\"a\");"),
+            vec![DynamicCodeKind::DirectEval, DynamicCodeKind::DirectEval]
+        );
+        // Shadowed
+        assert_eq!(
+            dynamic_code("function f(eval) {\n  eval(\"a\");\n}"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn indirect_eval() {
+        assert_eq!(
+            dynamic_code("(0, eval)(\"a\");\nconst e = eval;\nglobalThis.eval(\"a\");"),
+            vec![
+                DynamicCodeKind::IndirectEval,
+                DynamicCodeKind::IndirectEval,
+                DynamicCodeKind::IndirectEval
+            ]
+        );
+        assert_eq!(dynamic_code("obj.eval(\"a\");"), vec![]);
+    }
+
+    #[test]
+    fn function_constructor() {
+        assert_eq!(
+            dynamic_code("new Function(\"a\", \"return a\");\nFunction(\"return this\")();"),
+            vec![
+                DynamicCodeKind::FunctionConstructor,
+                DynamicCodeKind::FunctionConstructor
+            ]
+        );
+        assert_eq!(
+            dynamic_code("const Function = class {};\nnew Function();"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn only_direct_eval_uses_dynamic_scope() {
+        assert!(dynamic_scope("eval(\"a\");"));
+        assert!(!dynamic_scope("(0, eval)(\"a\");"));
+        assert!(!dynamic_scope("globalThis.eval(\"a\");"));
+        assert!(!dynamic_scope("new Function(\"return 1\");"));
+    }
+}
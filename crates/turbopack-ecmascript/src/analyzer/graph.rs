@@ -1,15 +1,24 @@
-use std::{collections::HashMap, iter, mem::replace, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    iter,
+    mem::{replace, take},
+    sync::Arc,
+};
 
 use swc_core::{
     common::{pass::AstNodePath, Mark, Span, Spanned, SyntaxContext},
     ecma::{
         ast::*,
-        atoms::js_word,
+        atoms::{js_word, JsWord},
+        utils::find_pat_ids,
         visit::{fields::*, VisitAstPath, VisitWithPath, *},
     },
 };
 
-use super::{ConstantNumber, ConstantValue, ImportMap, JsValue, ObjectPart, WellKnownFunctionKind};
+use super::{
+    BinaryOperator, ConstantNumber, ConstantValue, ImportMap, JsValue, ObjectPart,
+    WellKnownFunctionKind,
+};
 use crate::{
     analyzer::{is_unresolved, FreeVarKind},
     utils::unparen,
@@ -46,6 +55,25 @@ pub enum Effect {
         span: Span,
         ast_path: Vec<AstParentKind>,
     },
+    /// `new Worker(new URL(input, import.meta.url), options)`. `func` is the
+    /// constructor, which might be an alias of `Worker` or `SharedWorker`.
+    /// `options` is `undefined` when there is no second argument.
+    /// `url_span` is the span of the `new URL()` expression.
+    Worker {
+        func: JsValue,
+        input: JsValue,
+        options: JsValue,
+        ast_path: Vec<AstParentKind>,
+        span: Span,
+        url_span: Span,
+    },
+    /// An `if` statement or a conditional expression. The branch that isn't
+    /// taken can be removed when the `condition` is a constant.
+    Conditional {
+        condition: JsValue,
+        ast_path: Vec<AstParentKind>,
+        span: Span,
+    },
 }
 
 impl Effect {
@@ -94,6 +122,25 @@ impl Effect {
                 span: _,
                 ast_path: _,
             } => {}
+            Effect::Worker {
+                func,
+                input,
+                options,
+                ast_path: _,
+                span: _,
+                url_span: _,
+            } => {
+                func.normalize();
+                input.normalize();
+                options.normalize();
+            }
+            Effect::Conditional {
+                condition,
+                ast_path: _,
+                span: _,
+            } => {
+                condition.normalize();
+            }
         }
     }
 }
@@ -103,9 +150,26 @@ pub struct VarGraph {
     pub values: HashMap<Id, JsValue>,
 
     pub effects: Vec<Effect>,
+
+    /// Bindings that are assigned or updated after their declaration, e.g.
+    /// with `x = 1`, `x += 1` or `x++`. Not all of these updates are part of
+    /// their [VarGraph::values].
+    pub reassigned: HashSet<Id>,
 }
 
 impl VarGraph {
+    /// Whether `value` depends on a binding that is reassigned, so its linked
+    /// value might not be the value at runtime.
+    pub fn depends_on_reassigned(&self, value: &JsValue) -> bool {
+        let mut found = false;
+        value.visit(&mut |value| {
+            if let JsValue::Variable(id) = value {
+                found |= self.reassigned.contains(id);
+            }
+        });
+        found
+    }
+
     pub fn normalize(&mut self) {
         for value in self.values.values_mut() {
             value.normalize();
@@ -122,6 +186,7 @@ pub fn create_graph(m: &Program, eval_context: &EvalContext) -> VarGraph {
     let mut graph = VarGraph {
         values: Default::default(),
         effects: Default::default(),
+        reassigned: Default::default(),
     };
 
     m.visit_with_path(
@@ -135,11 +200,85 @@ pub fn create_graph(m: &Program, eval_context: &EvalContext) -> VarGraph {
         &mut Default::default(),
     );
 
+    let mut amd_require_params = AmdRequireParamFinder {
+        unresolved_mark: eval_context.unresolved_mark,
+        params: Vec::new(),
+    };
+    m.visit_with(&mut amd_require_params);
+    for id in amd_require_params.params {
+        graph
+            .values
+            .insert(id, JsValue::FreeVar(FreeVarKind::Require));
+    }
+
     graph.normalize();
 
     graph
 }
 
+/// Finds the `require` parameters of AMD factories, like in
+/// `define(function (require, exports, module) { ... })` or
+/// `define(["require", "./a"], function (require, a) { ... })`. AMD passes
+/// the module's `require` to them, so calls of them are analyzed like calls
+/// of the free `require`.
+struct AmdRequireParamFinder {
+    unresolved_mark: Mark,
+    params: Vec<Id>,
+}
+
+impl Visit for AmdRequireParamFinder {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+
+        let Callee::Expr(callee) = &call.callee else {
+            return;
+        };
+        let Expr::Ident(callee) = unparen(callee) else {
+            return;
+        };
+        if !is_unresolved(callee, self.unresolved_mark) {
+            return;
+        }
+        let is_define = match &*callee.sym {
+            "define" => true,
+            "require" => false,
+            _ => return,
+        };
+        if call.args.iter().any(|arg| arg.spread.is_some()) {
+            return;
+        }
+
+        let mut args = call.args.iter().map(|arg| unparen(&arg.expr)).peekable();
+        if is_define {
+            // `define("name", deps, factory)`
+            args.next_if(|arg| matches!(arg, Expr::Lit(Lit::Str(_))));
+        }
+        let index = match args.next_if(|arg| matches!(arg, Expr::Array(_))) {
+            Some(Expr::Array(deps)) => deps.elems.iter().position(|dep| {
+                matches!(
+                    dep,
+                    Some(ExprOrSpread { spread: None, expr })
+                        if matches!(&**expr, Expr::Lit(Lit::Str(str)) if &*str.value == "require")
+                )
+            }),
+            // The CommonJS wrapper `define(function (require, exports, module) { ... })`
+            _ if is_define => Some(0),
+            _ => None,
+        };
+        let (Some(index), Some(factory)) = (index, args.next()) else {
+            return;
+        };
+        let param = match factory {
+            Expr::Fn(FnExpr { function, .. }) => function.params.get(index).map(|param| &param.pat),
+            Expr::Arrow(arrow) => arrow.params.get(index),
+            _ => None,
+        };
+        if let Some(Pat::Ident(ident)) = param {
+            self.params.push(ident.id.to_id());
+        }
+    }
+}
+
 pub struct EvalContext {
     pub(crate) unresolved_mark: Mark,
     pub(crate) imports: ImportMap,
@@ -167,6 +306,9 @@ impl EvalContext {
         }
     }
 
+    /// Evaluates a template literal into a [JsValue::Concat] of its quasis and
+    /// the evaluated expressions, like `"./locales/" + lang + ".js"` would
+    /// be.
     fn eval_tpl(&self, e: &Tpl, raw: bool) -> JsValue {
         debug_assert!(e.quasis.len() == e.exprs.len() + 1);
 
@@ -175,18 +317,21 @@ impl EvalContext {
         for idx in 0..(e.quasis.len() + e.exprs.len()) {
             if idx % 2 == 0 {
                 let idx = idx / 2;
-                let e = &e.quasis[idx];
+                let quasi = &e.quasis[idx];
 
-                if raw {
-                    values.push(JsValue::from(e.raw.clone()));
+                let value = if raw {
+                    JsValue::from(quasi.raw.clone())
                 } else {
-                    match &e.cooked {
-                        Some(v) => {
-                            values.push(JsValue::from(v.clone()));
-                        }
+                    match &quasi.cooked {
+                        Some(v) => JsValue::from(v.clone()),
                         // This is actually unreachable
                         None => return JsValue::Unknown(None, ""),
                     }
+                };
+                // Empty quasis, e.g. before and after `${lang}` in `${lang}`,
+                // don't contribute to the string.
+                if e.exprs.is_empty() || value.as_str() != Some("") {
+                    values.push(value);
                 }
             } else {
                 let idx = idx / 2;
@@ -196,13 +341,32 @@ impl EvalContext {
             }
         }
 
-        if values.len() == 1 {
+        // A template without interpolations is a plain string. A single
+        // interpolation like `${x}` is still converted to a string, so it's
+        // kept as concatenation.
+        if values.len() == 1 && values[0].is_string() {
             return values.into_iter().next().unwrap();
         }
 
         JsValue::concat(values)
     }
 
+    /// Evaluates the property of a member expression. A computed property
+    /// with a constant key, like `obj["a"]` or `` obj[`a`] ``, evaluates to
+    /// the same value as the property of `obj.a`. Returns `None` for private
+    /// names.
+    pub fn eval_member_prop(&self, prop: &MemberProp) -> Option<JsValue> {
+        match prop {
+            // TODO avoid clone
+            MemberProp::Ident(ident) => Some(ident.sym.clone().into()),
+            MemberProp::Computed(ComputedPropName { expr, .. }) => Some(match self.eval(expr) {
+                JsValue::Constant(ConstantValue::StrAtom(str)) => JsWord::from(&*str).into(),
+                value => value,
+            }),
+            MemberProp::PrivateName(_) => None,
+        }
+    }
+
     pub fn eval(&self, e: &Expr) -> JsValue {
         match e {
             Expr::Lit(e) => JsValue::Constant(e.clone().into()),
@@ -219,6 +383,9 @@ impl EvalContext {
                         "__filename" => JsValue::FreeVar(FreeVarKind::Filename),
                         "process" => JsValue::FreeVar(FreeVarKind::NodeProcess),
                         "Object" => JsValue::FreeVar(FreeVarKind::Object),
+                        "Worker" => JsValue::FreeVar(FreeVarKind::Worker),
+                        "SharedWorker" => JsValue::FreeVar(FreeVarKind::SharedWorker),
+                        "URL" => JsValue::FreeVar(FreeVarKind::Url),
                         _ => JsValue::FreeVar(FreeVarKind::Other(i.sym.clone())),
                     }
                 } else {
@@ -244,6 +411,22 @@ impl EvalContext {
                 }
             }
 
+            Expr::Bin(BinExpr {
+                op: op @ (op!("==") | op!("!=") | op!("===") | op!("!==")),
+                left,
+                right,
+                ..
+            }) => JsValue::binary(
+                box self.eval(left),
+                match op {
+                    op!("==") => BinaryOperator::Equal,
+                    op!("!=") => BinaryOperator::NotEqual,
+                    op!("===") => BinaryOperator::StrictEqual,
+                    _ => BinaryOperator::StrictNotEqual,
+                },
+                box self.eval(right),
+            ),
+
             Expr::Bin(BinExpr {
                 op: op!("||") | op!("??"),
                 left,
@@ -293,8 +476,25 @@ impl EvalContext {
                 format!("*arrow function {}*", expr.span.lo.0).into(),
                 SyntaxContext::empty(),
             )),
+            Expr::New(NewExpr {
+                callee: box Expr::Ident(callee),
+                args: Some(args),
+                ..
+            }) if &*callee.sym == "URL" && is_unresolved(callee, self.unresolved_mark) => {
+                // We currently do not handle spreads.
+                if args.iter().any(|arg| arg.spread.is_some()) {
+                    return JsValue::Unknown(None, "spread in new URL() is not supported");
+                }
+                let args = args.iter().map(|arg| self.eval(&arg.expr)).collect();
+                JsValue::call(box JsValue::FreeVar(FreeVarKind::Url), args)
+            }
             Expr::New(..) => JsValue::Unknown(None, "new expression are not supported"),
 
+            Expr::MetaProp(MetaPropExpr {
+                kind: MetaPropKind::ImportMeta,
+                ..
+            }) => JsValue::FreeVar(FreeVarKind::ImportMeta),
+
             Expr::Seq(e) => {
                 if let Some(e) = e.exprs.last() {
                     self.eval(e)
@@ -305,26 +505,35 @@ impl EvalContext {
 
             Expr::Member(MemberExpr {
                 obj,
-                prop: MemberProp::Ident(prop),
+                prop: prop @ (MemberProp::Ident(_) | MemberProp::Computed(_)),
                 ..
             }) => {
-                let obj = self.eval(obj);
-                JsValue::member(box obj, box prop.sym.clone().into())
+                let obj_value = self.eval(obj);
+                if is_short_circuited(obj, &obj_value) {
+                    return obj_value;
+                }
+                let prop = self.eval_member_prop(prop).unwrap();
+                JsValue::member(box obj_value, box prop)
             }
 
-            Expr::Member(MemberExpr {
-                obj,
-                prop: MemberProp::Computed(computed),
+            // `a?.b` and `a?.()` are evaluated like `a.b` and `a()`, but fold to
+            // `undefined` when `a` is known to be nullish.
+            Expr::OptChain(OptChainExpr {
+                base: OptChainBase::Member(MemberExpr { obj, prop, .. }),
                 ..
             }) => {
                 let obj = self.eval(obj);
-                let prop = self.eval(&computed.expr);
-                JsValue::member(box obj, box prop)
+                if obj.is_nullish() {
+                    return JsValue::Constant(ConstantValue::Undefined);
+                }
+                match self.eval_member_prop(prop) {
+                    Some(prop) => JsValue::member(box obj, box prop),
+                    None => JsValue::Unknown(None, "private names are not supported"),
+                }
             }
 
-            Expr::Call(CallExpr {
-                callee: Callee::Expr(box callee),
-                args,
+            Expr::OptChain(OptChainExpr {
+                base: OptChainBase::Call(OptCall { callee, args, .. }),
                 ..
             }) => {
                 // We currently do not handle spreads.
@@ -333,19 +542,62 @@ impl EvalContext {
                 }
 
                 let args = args.iter().map(|arg| self.eval(&arg.expr)).collect();
+                if let Expr::Member(MemberExpr { obj, prop, .. })
+                | Expr::OptChain(OptChainExpr {
+                    base: OptChainBase::Member(MemberExpr { obj, prop, .. }),
+                    ..
+                }) = unparen(callee)
+                {
+                    let obj = self.eval(obj);
+                    if obj.is_nullish() {
+                        return JsValue::Constant(ConstantValue::Undefined);
+                    }
+                    let Some(prop) = self.eval_member_prop(prop) else {
+                        return JsValue::Unknown(
+                            None,
+                            "private names in function calls is not supported",
+                        );
+                    };
+                    let prop = box prop;
+                    JsValue::member_call(box obj, prop, args)
+                } else {
+                    let callee = self.eval(callee);
+                    if callee.is_nullish() {
+                        return JsValue::Constant(ConstantValue::Undefined);
+                    }
+                    JsValue::call(box callee, args)
+                }
+            }
+
+            Expr::Call(CallExpr {
+                callee: Callee::Expr(box callee),
+                args: callee_args,
+                ..
+            }) => {
+                // We currently do not handle spreads.
+                if callee_args.iter().any(|arg| arg.spread.is_some()) {
+                    return JsValue::Unknown(None, "spread in function calls is not supported");
+                }
+
+                let mut args: Vec<_> = callee_args.iter().map(|arg| self.eval(&arg.expr)).collect();
                 if let Expr::Member(MemberExpr { obj, prop, .. }) = unparen(callee) {
                     let obj = box self.eval(obj);
-                    let prop = box match prop {
-                        // TODO avoid clone
-                        MemberProp::Ident(i) => i.sym.clone().into(),
-                        MemberProp::PrivateName(_) => {
-                            return JsValue::Unknown(
-                                None,
-                                "private names in function calls is not supported",
-                            );
-                        }
-                        MemberProp::Computed(ComputedPropName { expr, .. }) => self.eval(expr),
+                    let Some(prop) = self.eval_member_prop(prop) else {
+                        return JsValue::Unknown(
+                            None,
+                            "private names in function calls is not supported",
+                        );
                     };
+                    let prop = box prop;
+                    // Array callbacks are applied to the known elements, which is only
+                    // valid when the callback has no side effects.
+                    if matches!(prop.as_str(), Some("map" | "filter"))
+                        && callee_args
+                            .first()
+                            .map_or(false, |arg| callback_has_side_effects(&arg.expr))
+                    {
+                        args[0] = JsValue::Unknown(None, "array callback with side effects");
+                    }
                     JsValue::member_call(obj, prop, args)
                 } else {
                     let callee = box self.eval(callee);
@@ -371,19 +623,41 @@ impl EvalContext {
             }
 
             Expr::Array(arr) => {
-                if arr.elems.iter().flatten().any(|v| v.spread.is_some()) {
-                    return JsValue::Unknown(None, "spread is not supported");
-                }
-
-                let arr = arr
-                    .elems
-                    .iter()
-                    .map(|e| match e {
+                // `[a, ...b, c]` is evaluated as `[a].concat(Array.from(b), [c])`.
+                // `Array.from` only resolves when `b` is an array, e.g. a spread string
+                // would be split into characters. The elements before the first spread
+                // stay known when a spread operand is unknown.
+                let mut items = Vec::new();
+                let mut spreads = Vec::new();
+                let mut pending = Vec::new();
+                for elem in arr.elems.iter() {
+                    let value = match elem {
                         Some(e) => self.eval(&e.expr),
                         _ => JsValue::FreeVar(FreeVarKind::Other(js_word!("undefined"))),
-                    })
-                    .collect();
-                JsValue::array(arr)
+                    };
+                    match elem {
+                        Some(ExprOrSpread {
+                            spread: Some(_), ..
+                        }) => {
+                            if !pending.is_empty() {
+                                spreads.push(JsValue::array(take(&mut pending)));
+                            }
+                            spreads.push(JsValue::call(
+                                box JsValue::WellKnownFunction(WellKnownFunctionKind::ArrayFrom),
+                                vec![value],
+                            ));
+                        }
+                        _ if spreads.is_empty() => items.push(value),
+                        _ => pending.push(value),
+                    }
+                }
+                if spreads.is_empty() {
+                    return JsValue::array(items);
+                }
+                if !pending.is_empty() {
+                    spreads.push(JsValue::array(pending));
+                }
+                JsValue::member_call(box JsValue::array(items), box "concat".into(), spreads)
             }
 
             Expr::Object(obj) => {
@@ -682,15 +956,8 @@ impl Analyzer<'_> {
             Callee::Expr(box expr) => {
                 if let Expr::Member(MemberExpr { obj, prop, .. }) = unparen(expr) {
                     let obj_value = self.eval_context.eval(obj);
-                    let prop_value = match prop {
-                        // TODO avoid clone
-                        MemberProp::Ident(i) => i.sym.clone().into(),
-                        MemberProp::PrivateName(_) => {
-                            return;
-                        }
-                        MemberProp::Computed(ComputedPropName { expr, .. }) => {
-                            self.eval_context.eval(expr)
-                        }
+                    let Some(prop_value) = self.eval_context.eval_member_prop(prop) else {
+                        return;
                     };
                     self.data.effects.push(Effect::MemberCall {
                         obj: obj_value,
@@ -713,19 +980,74 @@ impl Analyzer<'_> {
         }
     }
 
+    /// Checks for `new Worker(new URL(input, import.meta.url), options)`.
+    /// Whether the constructor is actually a worker is decided after linking.
+    fn check_new_expr_for_effects<'ast: 'r, 'r>(
+        &mut self,
+        n: &'ast NewExpr,
+        ast_path: &AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        let Some(args) = &n.args else {
+            return;
+        };
+        let Some((input, url_span)) = args
+            .first()
+            .filter(|arg| arg.spread.is_none())
+            .and_then(|arg| {
+                let input = self.new_url_with_import_meta_url(&arg.expr)?;
+                Some((input, unparen(&arg.expr).span()))
+            })
+        else {
+            return;
+        };
+        let options = match args.get(1) {
+            Some(ExprOrSpread { spread: None, expr }) => self.eval_context.eval(expr),
+            Some(_) => JsValue::Unknown(None, "spread in new Worker() is not supported"),
+            None => JsValue::Constant(ConstantValue::Undefined),
+        };
+        self.data.effects.push(Effect::Worker {
+            func: self.eval_context.eval(&n.callee),
+            input,
+            options,
+            ast_path: as_parent_path(ast_path),
+            span: n.span(),
+            url_span,
+        });
+    }
+
+    /// Evaluates the input of `new URL(input, import.meta.url)`.
+    fn new_url_with_import_meta_url(&self, expr: &Expr) -> Option<JsValue> {
+        let Expr::New(NewExpr {
+            callee: box Expr::Ident(callee),
+            args: Some(args),
+            ..
+        }) = unparen(expr)
+        else {
+            return None;
+        };
+        if &*callee.sym != "URL" || !is_unresolved(callee, self.eval_context.unresolved_mark) {
+            return None;
+        }
+        match &args[..] {
+            [ExprOrSpread {
+                spread: None,
+                expr: input,
+            }, ExprOrSpread {
+                spread: None,
+                expr: base,
+            }] if is_import_meta_url(base) => Some(self.eval_context.eval(input)),
+            _ => None,
+        }
+    }
+
     fn check_member_expr_for_effects<'ast: 'r, 'r>(
         &mut self,
         member_expr: &'ast MemberExpr,
         ast_path: &AstNodePath<AstParentNodeRef<'r>>,
     ) {
         let obj_value = self.eval_context.eval(&member_expr.obj);
-        let prop_value = match &member_expr.prop {
-            // TODO avoid clone
-            MemberProp::Ident(i) => i.sym.clone().into(),
-            MemberProp::PrivateName(_) => {
-                return;
-            }
-            MemberProp::Computed(ComputedPropName { expr, .. }) => self.eval_context.eval(expr),
+        let Some(prop_value) = self.eval_context.eval_member_prop(&member_expr.prop) else {
+            return;
         };
         self.data.effects.push(Effect::Member {
             obj: obj_value,
@@ -756,6 +1078,9 @@ impl VisitAstPath for Analyzer<'_> {
             AstParentNodeRef::AssignExpr(n, AssignExprField::Left),
             |ast_path| match &n.left {
                 PatOrExpr::Expr(expr) => {
+                    if let Expr::Ident(ident) = unparen(expr) {
+                        self.data.reassigned.insert(ident.to_id());
+                    }
                     ast_path.with(
                         AstParentNodeRef::PatOrExpr(&n.left, PatOrExprField::Expr),
                         |ast_path| {
@@ -767,6 +1092,7 @@ impl VisitAstPath for Analyzer<'_> {
                     ast_path.with(
                         AstParentNodeRef::PatOrExpr(&n.left, PatOrExprField::Pat),
                         |ast_path| {
+                            self.data.reassigned.extend(find_pat_ids::<_, Id>(&**pat));
                             self.current_value = Some(self.eval_context.eval(&n.right));
                             self.visit_pat(pat, ast_path);
                             self.current_value = None;
@@ -783,6 +1109,17 @@ impl VisitAstPath for Analyzer<'_> {
         );
     }
 
+    fn visit_update_expr<'ast: 'r, 'r>(
+        &mut self,
+        n: &'ast UpdateExpr,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        if let Expr::Ident(ident) = unparen(&n.arg) {
+            self.data.reassigned.insert(ident.to_id());
+        }
+        n.visit_children_with_path(self, ast_path);
+    }
+
     fn visit_call_expr<'ast: 'r, 'r>(
         &mut self,
         n: &'ast CallExpr,
@@ -807,6 +1144,41 @@ impl VisitAstPath for Analyzer<'_> {
         }
     }
 
+    fn visit_new_expr<'ast: 'r, 'r>(
+        &mut self,
+        n: &'ast NewExpr,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        self.check_new_expr_for_effects(n, ast_path);
+        n.visit_children_with_path(self, ast_path);
+    }
+
+    fn visit_if_stmt<'ast: 'r, 'r>(
+        &mut self,
+        n: &'ast IfStmt,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        self.data.effects.push(Effect::Conditional {
+            condition: self.eval_context.eval(&n.test),
+            ast_path: as_parent_path(ast_path),
+            span: n.span,
+        });
+        n.visit_children_with_path(self, ast_path);
+    }
+
+    fn visit_cond_expr<'ast: 'r, 'r>(
+        &mut self,
+        n: &'ast CondExpr,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        self.data.effects.push(Effect::Conditional {
+            condition: self.eval_context.eval(&n.test),
+            ast_path: as_parent_path(ast_path),
+            span: n.span,
+        });
+        n.visit_children_with_path(self, ast_path);
+    }
+
     fn visit_member_expr<'ast: 'r, 'r>(
         &mut self,
         member_expr: &'ast MemberExpr,
@@ -1262,3 +1634,109 @@ fn extract_var_from_umd_factory(callee: &Expr, args: &[ExprOrSpread]) -> Option<
 
     None
 }
+
+/// Returns true when calling the inline function `expr` may have side effects,
+/// i.e. it contains assignments, calls or statements besides a single
+/// `return`. Only `new URL(...)` and `require.resolve(...)` are assumed to be
+/// free of side effects, they are used to build lists of assets.
+fn callback_has_side_effects(expr: &Expr) -> bool {
+    struct SideEffects(bool);
+
+    impl Visit for SideEffects {
+        fn visit_call_expr(&mut self, expr: &CallExpr) {
+            let is_require_resolve = matches!(
+                &expr.callee,
+                Callee::Expr(box Expr::Member(MemberExpr {
+                    obj: box Expr::Ident(obj),
+                    prop: MemberProp::Ident(prop),
+                    ..
+                })) if &*obj.sym == "require" && &*prop.sym == "resolve"
+            );
+            if is_require_resolve {
+                expr.visit_children_with(self);
+            } else {
+                self.0 = true;
+            }
+        }
+
+        fn visit_tagged_tpl(&mut self, _: &TaggedTpl) {
+            self.0 = true;
+        }
+
+        fn visit_new_expr(&mut self, expr: &NewExpr) {
+            if matches!(&*expr.callee, Expr::Ident(ident) if &*ident.sym == "URL") {
+                expr.visit_children_with(self);
+            } else {
+                self.0 = true;
+            }
+        }
+
+        fn visit_assign_expr(&mut self, _: &AssignExpr) {
+            self.0 = true;
+        }
+
+        fn visit_update_expr(&mut self, _: &UpdateExpr) {
+            self.0 = true;
+        }
+
+        fn visit_unary_expr(&mut self, expr: &UnaryExpr) {
+            if expr.op == op!("delete") {
+                self.0 = true;
+            } else {
+                expr.visit_children_with(self);
+            }
+        }
+
+        // Nested functions are not called by the callback itself
+        fn visit_function(&mut self, _: &Function) {}
+
+        fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+    }
+
+    fn expr_has_side_effects(expr: &Expr) -> bool {
+        let mut visitor = SideEffects(false);
+        expr.visit_with(&mut visitor);
+        visitor.0
+    }
+
+    fn body_has_side_effects(body: &BlockStmt) -> bool {
+        match &body.stmts[..] {
+            [Stmt::Return(ReturnStmt { arg, .. })] => {
+                arg.as_deref().map_or(false, expr_has_side_effects)
+            }
+            _ => true,
+        }
+    }
+
+    match unparen(expr) {
+        Expr::Arrow(ArrowExpr { body, .. }) => match body {
+            BlockStmtOrExpr::Expr(expr) => expr_has_side_effects(expr),
+            BlockStmtOrExpr::BlockStmt(body) => body_has_side_effects(body),
+        },
+        Expr::Fn(FnExpr { function, .. }) => {
+            function.body.as_ref().map_or(false, body_has_side_effects)
+        }
+        _ => false,
+    }
+}
+
+fn is_import_meta_url(expr: &Expr) -> bool {
+    matches!(
+        unparen(expr),
+        Expr::Member(MemberExpr {
+            obj: box Expr::MetaProp(MetaPropExpr {
+                kind: MetaPropKind::ImportMeta,
+                ..
+            }),
+            prop: MemberProp::Ident(prop),
+            ..
+        }) if &*prop.sym == "url"
+    )
+}
+
+/// `a?.b.c` short-circuits to `undefined` when `a` is nullish, so the
+/// property access on the result of the optional chain is skipped.
+fn is_short_circuited(obj: &Expr, value: &JsValue) -> bool {
+    matches!(unparen(obj), Expr::OptChain(..))
+        && matches!(value, JsValue::Constant(ConstantValue::Undefined))
+}
@@ -8,7 +8,7 @@ use swc_core::ecma::{
     visit::{Visit, VisitWith},
 };
 
-use super::{JsValue, ModuleValue};
+use super::{JsValue, ModuleValue, ObjectPart};
 use crate::utils::unparen;
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -17,6 +17,10 @@ pub struct ImportAnnotations {
     // TODO store this in more structured way
     #[turbo_tasks(trace_ignore)]
     map: BTreeMap<JsWord, Option<JsWord>>,
+    /// Import attributes, e.g. `type: "json"` from `import data from
+    /// "./data.json" assert { type: "json" }`
+    #[turbo_tasks(trace_ignore)]
+    attributes: BTreeMap<JsWord, JsWord>,
 }
 
 /// Enables a specified transtion for the annotated import
@@ -32,6 +36,81 @@ impl ImportAnnotations {
 
     fn clear(&mut self) {
         self.map.clear();
+        self.attributes.clear();
+    }
+
+    /// Adds the attributes of an `assert { ... }` clause. The parser doesn't
+    /// support the newer `with { ... }` form of static imports yet.
+    /// Attributes with non-string values are ignored.
+    pub(crate) fn insert_attributes(&mut self, attributes: &ObjectLit) {
+        for prop in attributes.props.iter() {
+            if let PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp {
+                key,
+                value: box Expr::Lit(Lit::Str(value)),
+            })) = prop
+            {
+                let key = match key {
+                    PropName::Ident(ident) => ident.sym.clone(),
+                    PropName::Str(str) => str.value.clone(),
+                    _ => continue,
+                };
+                self.attributes.insert(key, value.value.clone());
+            }
+        }
+    }
+
+    /// Reads the import attributes from the options argument of a dynamic
+    /// `import()`, e.g. `{ with: { type: "json" } }`. The legacy `assert` key
+    /// is supported as well.
+    pub(crate) fn from_dynamic_import_options(options: &JsValue) -> Self {
+        let mut annotations = ImportAnnotations::default();
+        if let JsValue::Object(_, parts) = options {
+            for part in parts {
+                let ObjectPart::KeyValue(key, JsValue::Object(_, attributes)) = part else {
+                    continue;
+                };
+                if !matches!(key.as_str(), Some("with" | "assert")) {
+                    continue;
+                }
+                for attribute in attributes {
+                    if let ObjectPart::KeyValue(key, value) = attribute {
+                        if let (Some(key), Some(value)) = (key.as_str(), value.as_str()) {
+                            annotations.attributes.insert(key.into(), value.into());
+                        }
+                    }
+                }
+            }
+        }
+        annotations
+    }
+
+    /// Returns true when there are neither annotations nor attributes
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty() && self.attributes.is_empty()
+    }
+
+    /// Returns the value of the import attribute with the given key
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.get(&JsWord::from(key)).map(|w| &**w)
+    }
+
+    /// Returns the module type requested by the `type` import attribute
+    pub fn module_type(&self) -> Option<&str> {
+        self.attribute("type")
+    }
+
+    fn fmt_attributes(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.attributes.is_empty() {
+            return Ok(());
+        }
+        f.write_str(" with { ")?;
+        for (i, (k, v)) in self.attributes.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{k}: {v}")?;
+        }
+        f.write_str(" }")
     }
 
     /// Returns the content on the transition annotation
@@ -59,7 +138,8 @@ impl Display for ImportAnnotations {
                 write!(f, "{{ {k}")?
             }
         } else {
-            return f.write_str("{}");
+            f.write_str("{}")?;
+            return self.fmt_attributes(f);
         };
         for (k, v) in it {
             if let Some(v) = v {
@@ -68,7 +148,8 @@ impl Display for ImportAnnotations {
                 write!(f, "; {k}")?
             }
         }
-        f.write_str(" }")
+        f.write_str(" }")?;
+        self.fmt_attributes(f)
     }
 }
 
@@ -146,6 +227,15 @@ impl ImportMap {
         self.reexports.iter().map(|(i, r)| (*i, r))
     }
 
+    /// Whether the reference with the `index` declares bindings or
+    /// re-exports. A reference without bindings, e.g. `import "./a"`, is only
+    /// evaluated for its side effects.
+    pub fn has_bindings(&self, index: usize) -> bool {
+        self.imports.values().any(|(i, _)| *i == index)
+            || self.namespace_imports.values().any(|i| *i == index)
+            || self.reexports.iter().any(|(i, _)| *i == index)
+    }
+
     /// Analyze ES import
     pub(super) fn analyze(m: &Program) -> Self {
         let mut data = ImportMap::default();
@@ -220,6 +310,10 @@ impl Visit for Analyzer<'_> {
     }
 
     fn visit_import_decl(&mut self, import: &ImportDecl) {
+        // Only the `assert` keyword is parsed, `with` is a syntax error so far
+        if let Some(asserts) = &import.asserts {
+            self.current_annotations.insert_attributes(asserts);
+        }
         let i = self.ensure_reference(import.src.value.clone());
         for s in &import.specifiers {
             let (local, orig_sym) = match s {
@@ -242,6 +336,9 @@ impl Visit for Analyzer<'_> {
 
     fn visit_export_all(&mut self, export: &ExportAll) {
         self.data.has_exports = true;
+        if let Some(asserts) = &export.asserts {
+            self.current_annotations.insert_attributes(asserts);
+        }
         let i = self.ensure_reference(export.src.value.clone());
         self.data.reexports.push((i, Reexport::Star));
     }
@@ -249,6 +346,9 @@ impl Visit for Analyzer<'_> {
     fn visit_named_export(&mut self, export: &NamedExport) {
         self.data.has_exports = true;
         if let Some(ref src) = export.src {
+            if let Some(asserts) = &export.asserts {
+                self.current_annotations.insert_attributes(asserts);
+            }
             let i = self.ensure_reference(src.value.clone());
             for spec in export.specifiers.iter() {
                 match spec {
@@ -303,3 +403,117 @@ fn orig_name(n: &ModuleExportName) -> JsWord {
         ModuleExportName::Str(v) => v.value.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, SyntaxContext},
+        ecma::{
+            ast::EsVersion,
+            parser::{parse_file_as_program, EsConfig, Syntax},
+        },
+        testing::run_test,
+    };
+
+    use super::{ImportAnnotations, ImportMap};
+    use crate::analyzer::{JsValue, ObjectPart};
+
+    fn analyze(src: &str) -> ImportMap {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let program = parse_file_as_program(
+                &fm,
+                Syntax::Es(EsConfig {
+                    import_assertions: true,
+                    ..Default::default()
+                }),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            Ok(ImportMap::analyze(&program))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn import_attributes() {
+        let map = analyze(
+            r#"
+import data from "./data.json" assert { type: "json" };
+import other from "./other.js";
+export * from "./reexport.json" assert { type: "json" };
+"#,
+        );
+        let references = map.references().collect::<Vec<_>>();
+        assert_eq!(references.len(), 3);
+        assert_eq!(&**references[0].0, "./data.json");
+        assert_eq!(references[0].1.module_type(), Some("json"));
+        assert_eq!(references[0].1.to_string(), "{} with { type: json }");
+        assert_eq!(references[1].1.module_type(), None);
+        assert_eq!(references[2].1.module_type(), Some("json"));
+    }
+
+    #[test]
+    fn json_default_import() {
+        let map = analyze(
+            r#"
+import data from "./data.json" assert { type: "json" };
+console.log(data.name);
+"#,
+        );
+        // The JSON value is bound as the default export
+        let (index, export) = map
+            .get_binding(&("data".into(), SyntaxContext::empty()))
+            .unwrap();
+        assert_eq!(export.as_deref(), Some("default"));
+        let references = map.references().collect::<Vec<_>>();
+        assert_eq!(references[index].1.module_type(), Some("json"));
+        assert!(map.reexports().next().is_none());
+    }
+
+    #[test]
+    fn side_effect_imports() {
+        let map = analyze(
+            r#"
+import "./a.js";
+import "./b.js";
+import { b } from "./b.js";
+import * as c from "./c.js";
+export { d } from "./d.js";
+"#,
+        );
+        let has_bindings = (0..map.references().count())
+            .map(|index| map.has_bindings(index))
+            .collect::<Vec<_>>();
+        assert_eq!(has_bindings, vec![false, true, true, true]);
+    }
+
+    #[test]
+    fn same_module_with_different_attributes() {
+        let map = analyze(
+            r#"
+import a from "./data.json";
+import b from "./data.json" assert { type: "json" };
+"#,
+        );
+        assert_eq!(map.references().count(), 2);
+    }
+
+    #[test]
+    fn dynamic_import_attributes() {
+        let options = |key: &str| {
+            JsValue::object(vec![ObjectPart::KeyValue(
+                key.into(),
+                JsValue::object(vec![ObjectPart::KeyValue("type".into(), "json".into())]),
+            )])
+        };
+        for key in ["with", "assert"] {
+            let annotations = ImportAnnotations::from_dynamic_import_options(&options(key));
+            assert_eq!(annotations.module_type(), Some("json"));
+        }
+        let annotations = ImportAnnotations::from_dynamic_import_options(&options("other"));
+        assert!(annotations.is_empty());
+    }
+}
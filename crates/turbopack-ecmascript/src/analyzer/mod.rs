@@ -9,7 +9,7 @@ use std::{
 };
 
 use indexmap::IndexSet;
-use num_bigint::BigInt;
+use num_bigint::{BigInt, Sign};
 use swc_core::{
     common::Mark,
     ecma::{
@@ -23,6 +23,7 @@ use self::imports::ImportAnnotations;
 pub(crate) use self::imports::ImportMap;
 
 pub mod builtin;
+pub mod dynamic_scope;
 pub mod graph;
 pub mod imports;
 pub mod linker;
@@ -76,6 +77,110 @@ impl PartialEq for ConstantNumber {
 
 impl Eq for ConstantNumber {}
 
+impl ConstantNumber {
+    /// Formats the number like JavaScript's `Number.prototype.toString` does,
+    /// e.g. `1` instead of `1.0` and `1e+21` instead of a long list of digits.
+    pub fn to_js_string(&self) -> String {
+        let n = self.0;
+        if n.is_nan() {
+            return "NaN".to_string();
+        }
+        if n == 0.0 {
+            // This also covers `-0`, which is formatted as `0`.
+            return "0".to_string();
+        }
+        if n.is_infinite() {
+            return if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+        }
+        let sign = if n < 0.0 { "-" } else { "" };
+        // `{:e}` formats the shortest representation that roundtrips, e.g.
+        // `1.5e-7`, which gives us the digits and the exponent.
+        let formatted = format!("{:e}", n.abs());
+        let (mantissa, exponent) = formatted.split_once('e').unwrap();
+        let digits = mantissa.replace('.', "");
+        let k = digits.len() as i32;
+        // The position of the decimal point relative to the digits.
+        let point = exponent.parse::<i32>().unwrap() + 1;
+        let result = if k <= point && point <= 21 {
+            format!("{digits}{}", "0".repeat((point - k) as usize))
+        } else if 0 < point && point <= 21 {
+            let (int, frac) = digits.split_at(point as usize);
+            format!("{int}.{frac}")
+        } else if -6 < point && point <= 0 {
+            format!("0.{}{digits}", "0".repeat(-point as usize))
+        } else {
+            let exponent = point - 1;
+            let exponent_sign = if exponent < 0 { "-" } else { "+" };
+            let (first, rest) = digits.split_at(1);
+            if rest.is_empty() {
+                format!("{first}e{exponent_sign}{}", exponent.abs())
+            } else {
+                format!("{first}.{rest}e{exponent_sign}{}", exponent.abs())
+            }
+        };
+        format!("{sign}{result}")
+    }
+}
+
+/// Whether `c` is white space or a line terminator in JavaScript, which
+/// differs from Unicode's `White_Space` in U+0085 and U+FEFF.
+fn is_js_whitespace(c: char) -> bool {
+    c == '\u{feff}' || (c.is_whitespace() && c != '\u{85}')
+}
+
+/// Parses `digits` in `radix`. Returns `None` when there are no digits or an
+/// invalid one.
+fn parse_radix_digits(digits: &str, radix: u32) -> Option<f64> {
+    if digits.is_empty() {
+        return None;
+    }
+    // Exact as long as it fits, like JavaScript, which rounds the result
+    let mut exact = Some(0u128);
+    let mut approximate = 0.0;
+    for c in digits.chars() {
+        let digit = c.to_digit(radix)?;
+        exact = exact.and_then(|n| n.checked_mul(radix as u128)?.checked_add(digit as u128));
+        approximate = approximate * radix as f64 + digit as f64;
+    }
+    Some(exact.map_or(approximate, |n| n as f64))
+}
+
+/// Converts a string to a number like JavaScript's `Number(string)` does.
+fn string_to_number(str: &str) -> f64 {
+    let str = str.trim_matches(is_js_whitespace);
+    if str.is_empty() {
+        return 0.0;
+    }
+    let radix = match str.get(..2) {
+        Some("0x" | "0X") => Some(16),
+        Some("0o" | "0O") => Some(8),
+        Some("0b" | "0B") => Some(2),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        return parse_radix_digits(&str[2..], radix).unwrap_or(f64::NAN);
+    }
+    if str
+        .strip_prefix(|c: char| c == '+' || c == '-')
+        .unwrap_or(str)
+        == "Infinity"
+    {
+        return if str.starts_with('-') {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+    }
+    // Rust also parses `inf` and `nan`
+    if !str
+        .chars()
+        .all(|c| matches!(c, '0'..='9' | '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return f64::NAN;
+    }
+    str.parse().unwrap_or(f64::NAN)
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum ConstantValue {
     Undefined,
@@ -97,6 +202,94 @@ impl ConstantValue {
             _ => None,
         }
     }
+
+    /// Converts the constant to a string like JavaScript's `String(value)`
+    /// does.
+    pub fn to_js_string(&self) -> String {
+        match self {
+            ConstantValue::Undefined => "undefined".to_string(),
+            ConstantValue::StrWord(str) => str.to_string(),
+            ConstantValue::StrAtom(str) => str.to_string(),
+            ConstantValue::Num(n) => n.to_js_string(),
+            ConstantValue::True => "true".to_string(),
+            ConstantValue::False => "false".to_string(),
+            ConstantValue::Null => "null".to_string(),
+            ConstantValue::BigInt(n) => n.to_string(),
+            ConstantValue::Regex(exp, flags) => format!("/{exp}/{flags}"),
+        }
+    }
+
+    /// Converts the constant to a number like JavaScript's `Number(value)`
+    /// does.
+    pub fn to_js_number(&self) -> f64 {
+        match self {
+            ConstantValue::Undefined | ConstantValue::Regex(..) => f64::NAN,
+            ConstantValue::Null | ConstantValue::False => 0.0,
+            ConstantValue::True => 1.0,
+            ConstantValue::StrWord(str) => string_to_number(str),
+            ConstantValue::StrAtom(str) => string_to_number(str),
+            ConstantValue::Num(ConstantNumber(n)) => *n,
+            // Rounds to the nearest number
+            ConstantValue::BigInt(n) => n.to_string().parse().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// Converts the constant to a boolean like JavaScript's `Boolean(value)`
+    /// does.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            ConstantValue::Undefined | ConstantValue::Null | ConstantValue::False => false,
+            ConstantValue::StrWord(s) => !s.is_empty(),
+            ConstantValue::StrAtom(s) => !s.is_empty(),
+            ConstantValue::Num(ConstantNumber(n)) => *n != 0.0 && !n.is_nan(),
+            ConstantValue::BigInt(n) => n.sign() != Sign::NoSign,
+            ConstantValue::True | ConstantValue::Regex(..) => true,
+        }
+    }
+
+    /// Compares the constants like JavaScript's `===` does. Returns `None`
+    /// for regular expressions, which are compared by identity.
+    pub fn strict_equals(&self, other: &ConstantValue) -> Option<bool> {
+        Some(match (self, other) {
+            (ConstantValue::Regex(..), _) | (_, ConstantValue::Regex(..)) => return None,
+            (ConstantValue::Num(ConstantNumber(l)), ConstantValue::Num(ConstantNumber(r))) => {
+                l == r
+            }
+            (ConstantValue::BigInt(l), ConstantValue::BigInt(r)) => l == r,
+            (ConstantValue::True, ConstantValue::True)
+            | (ConstantValue::False, ConstantValue::False)
+            | (ConstantValue::Null, ConstantValue::Null)
+            | (ConstantValue::Undefined, ConstantValue::Undefined) => true,
+            (l, r) => match (l.as_str(), r.as_str()) {
+                (Some(l), Some(r)) => l == r,
+                _ => false,
+            },
+        })
+    }
+
+    /// Compares the constants like JavaScript's `==` does. Returns `None`
+    /// when the operands would be coerced to a different type.
+    pub fn loose_equals(&self, other: &ConstantValue) -> Option<bool> {
+        fn is_nullish(v: &ConstantValue) -> bool {
+            matches!(v, ConstantValue::Null | ConstantValue::Undefined)
+        }
+        if is_nullish(self) || is_nullish(other) {
+            return Some(is_nullish(self) && is_nullish(other));
+        }
+        if std::mem::discriminant(self) == std::mem::discriminant(other)
+            || (self.as_str().is_some() && other.as_str().is_some())
+            || matches!(
+                (self, other),
+                (
+                    ConstantValue::True | ConstantValue::False,
+                    ConstantValue::True | ConstantValue::False
+                )
+            )
+        {
+            return self.strict_equals(other);
+        }
+        None
+    }
 }
 
 impl Default for ConstantValue {
@@ -135,12 +328,53 @@ impl Display for ConstantValue {
             ConstantValue::False => write!(f, "false"),
             ConstantValue::Null => write!(f, "null"),
             ConstantValue::Num(ConstantNumber(n)) => write!(f, "{n}"),
-            ConstantValue::BigInt(n) => write!(f, "{n}"),
+            ConstantValue::BigInt(n) => write!(f, "{n}n"),
             ConstantValue::Regex(exp, flags) => write!(f, "/{exp}/{flags}"),
         }
     }
 }
 
+/// A comparison operator of a [JsValue::Binary].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum BinaryOperator {
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `===`
+    StrictEqual,
+    /// `!==`
+    StrictNotEqual,
+}
+
+impl BinaryOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::StrictEqual => "===",
+            BinaryOperator::StrictNotEqual => "!==",
+        }
+    }
+
+    /// Compares two constants. Returns `None` when the result depends on
+    /// type coercions that are not modeled.
+    pub fn evaluate(&self, left: &ConstantValue, right: &ConstantValue) -> Option<bool> {
+        match self {
+            BinaryOperator::Equal => left.loose_equals(right),
+            BinaryOperator::NotEqual => left.loose_equals(right).map(|eq| !eq),
+            BinaryOperator::StrictEqual => left.strict_equals(right),
+            BinaryOperator::StrictNotEqual => left.strict_equals(right).map(|eq| !eq),
+        }
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct ModuleValue {
     pub module: JsWord,
@@ -184,6 +418,10 @@ pub enum JsValue {
     /// `obj[prop]`
     Member(usize, Box<JsValue>, Box<JsValue>),
 
+    /// `(left, op, right)`, a comparison which is folded to a boolean when
+    /// both sides are constants.
+    Binary(usize, Box<JsValue>, BinaryOperator, Box<JsValue>),
+
     /// This is a reference to a imported module
     Module(ModuleValue),
 
@@ -337,6 +575,7 @@ impl Display for JsValue {
                     .join(", ")
             ),
             JsValue::Member(_, obj, prop) => write!(f, "{}[{}]", obj, prop),
+            JsValue::Binary(_, left, op, right) => write!(f, "({} {} {})", left, op, right),
             JsValue::Module(ModuleValue {
                 module: name,
                 annotations,
@@ -455,6 +694,15 @@ impl JsValue {
         Self::Member(1 + o.total_nodes() + p.total_nodes(), o, p)
     }
 
+    pub fn binary(left: Box<JsValue>, op: BinaryOperator, right: Box<JsValue>) -> Self {
+        Self::Binary(
+            1 + left.total_nodes() + right.total_nodes(),
+            left,
+            op,
+            right,
+        )
+    }
+
     pub fn total_nodes(&self) -> usize {
         match self {
             JsValue::Constant(_)
@@ -475,6 +723,7 @@ impl JsValue {
             | JsValue::Call(c, _, _)
             | JsValue::MemberCall(c, _, _, _)
             | JsValue::Member(c, _, _)
+            | JsValue::Binary(c, _, _, _)
             | JsValue::Function(c, _) => *c,
         }
     }
@@ -513,7 +762,7 @@ impl JsValue {
             JsValue::MemberCall(c, o, m, list) => {
                 *c = 1 + o.total_nodes() + m.total_nodes() + total_nodes(list);
             }
-            JsValue::Member(c, o, p) => {
+            JsValue::Member(c, o, p) | JsValue::Binary(c, o, _, p) => {
                 *c = 1 + o.total_nodes() + p.total_nodes();
             }
             JsValue::Function(c, r) => {
@@ -566,7 +815,7 @@ impl JsValue {
                     make_max_unknown([&mut **o, &mut **p].into_iter().chain(args.iter_mut()));
                     self.update_total_nodes();
                 }
-                JsValue::Member(_, o, p) => {
+                JsValue::Member(_, o, p) | JsValue::Binary(_, o, _, p) => {
                     make_max_unknown([&mut **o, &mut **p].into_iter());
                     self.update_total_nodes();
                 }
@@ -803,6 +1052,14 @@ impl JsValue {
                     prop.explain_internal_inner(hints, indent_depth, depth, unknown_depth)
                 )
             }
+            JsValue::Binary(_, left, op, right) => {
+                format!(
+                    "({} {} {})",
+                    left.explain_internal_inner(hints, indent_depth, depth, unknown_depth),
+                    op,
+                    right.explain_internal_inner(hints, indent_depth, depth, unknown_depth)
+                )
+            }
             JsValue::Module(ModuleValue {
                 module: name,
                 annotations,
@@ -863,6 +1120,10 @@ impl JsValue {
                         "process",
                         "The Node.js process module: https://nodejs.org/api/process.html",
                     ),
+                    WellKnownObjectKind::NodeProcessEnv => (
+                        "process.env",
+                        "The Node.js process.env property: https://nodejs.org/api/process.html#processenv",
+                    ),
                     WellKnownObjectKind::NodePreGyp => (
                         "@mapbox/node-pre-gyp",
                         "The Node.js @mapbox/node-pre-gyp module: https://github.com/mapbox/node-pre-gyp",
@@ -879,6 +1140,14 @@ impl JsValue {
                         "require.cache",
                         "The CommonJS require.cache object: https://nodejs.org/api/modules.html#requirecache"
                     ),
+                    WellKnownObjectKind::ImportMeta => (
+                        "import.meta",
+                        "The import.meta object: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Operators/import.meta"
+                    ),
+                    WellKnownObjectKind::ImportMetaEnv => (
+                        "import.meta.env",
+                        "The environment variables of the bundler: https://vitejs.dev/guide/env-and-mode.html"
+                    ),
                 };
                 if depth > 0 {
                     let i = hints.len();
@@ -894,6 +1163,10 @@ impl JsValue {
                         "Object.assign".to_string(),
                         "Object.assign method: https://developer.mozilla.org/zh-CN/docs/Web/JavaScript/Reference/Global_Objects/Object/assign",
                     ),
+                    WellKnownFunctionKind::ArrayFrom => (
+                        "Array.from".to_string(),
+                        "The Array.from method: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Array/from",
+                    ),
                     WellKnownFunctionKind::PathJoin => (
                         "path.join".to_string(),
                         "The Node.js path.join method: https://nodejs.org/api/path.html#pathjoinpaths",
@@ -912,6 +1185,7 @@ impl JsValue {
                     ),
                     WellKnownFunctionKind::Require => ("require".to_string(), "The require method from CommonJS"),
                     WellKnownFunctionKind::RequireResolve => ("require.resolve".to_string(), "The require.resolve method from CommonJS"),
+                    WellKnownFunctionKind::RequireContext => ("require.context".to_string(), "The require.context method from webpack"),
                     WellKnownFunctionKind::Define => ("define".to_string(), "The define method from AMD"),
                     WellKnownFunctionKind::FsReadMethod(name) => (
                         format!("fs.{name}"),
@@ -981,6 +1255,34 @@ impl JsValue {
                       "load/loadSync".to_string(),
                       "require('@grpc/proto-loader').load(filepath, { includeDirs: [root] }) https://github.com/grpc/grpc-node"
                     ),
+                    WellKnownFunctionKind::Worker => (
+                      "Worker".to_string(),
+                      "The Worker constructor: https://developer.mozilla.org/en-US/docs/Web/API/Worker/Worker"
+                    ),
+                    WellKnownFunctionKind::SharedWorker => (
+                      "SharedWorker".to_string(),
+                      "The SharedWorker constructor: https://developer.mozilla.org/en-US/docs/Web/API/SharedWorker/SharedWorker"
+                    ),
+                    WellKnownFunctionKind::URLConstructor => (
+                      "URL".to_string(),
+                      "The URL constructor: https://developer.mozilla.org/en-US/docs/Web/API/URL/URL"
+                    ),
+                    WellKnownFunctionKind::StringMethod(this, name) => (
+                      format!("{this}.{name}"),
+                      "A String.prototype method: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/String"
+                    ),
+                    WellKnownFunctionKind::ParseInt => (
+                      "parseInt".to_string(),
+                      "The parseInt function: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/parseInt"
+                    ),
+                    WellKnownFunctionKind::ParseFloat => (
+                      "parseFloat".to_string(),
+                      "The parseFloat function: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/parseFloat"
+                    ),
+                    WellKnownFunctionKind::Number => (
+                      "Number".to_string(),
+                      "The Number function: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Number/Number"
+                    ),
                 };
                 if depth > 0 {
                     let i = hints.len();
@@ -1037,7 +1339,8 @@ impl JsValue {
             | JsValue::Object(..)
             | JsValue::Alternatives(..)
             | JsValue::Concat(..)
-            | JsValue::Add(..) => {
+            | JsValue::Add(..)
+            | JsValue::Binary(..) => {
                 let mut result = false;
                 self.for_each_children(&mut |child| {
                     result = result || child.has_placeholder();
@@ -1138,7 +1441,7 @@ macro_rules! for_each_children_async {
                 $value.update_total_nodes();
                 ($value, modified)
             }
-            JsValue::Member(_, box obj, box prop) => {
+            JsValue::Member(_, box obj, box prop) | JsValue::Binary(_, box obj, _, box prop) => {
                 let (v, m1) = $visit_fn(take(obj), $($args),+).await?;
                 *obj = v;
                 let (v, m2) = $visit_fn(take(prop), $($args),+).await?;
@@ -1354,7 +1657,7 @@ impl JsValue {
                 self.update_total_nodes();
                 modified
             }
-            JsValue::Member(_, obj, prop) => {
+            JsValue::Member(_, obj, prop) | JsValue::Binary(_, obj, _, prop) => {
                 let m1 = visitor(obj);
                 let m2 = visitor(prop);
                 self.update_total_nodes();
@@ -1416,7 +1719,7 @@ impl JsValue {
             JsValue::Function(_, return_value) => {
                 visitor(return_value);
             }
-            JsValue::Member(_, obj, prop) => {
+            JsValue::Member(_, obj, prop) | JsValue::Binary(_, obj, _, prop) => {
                 visitor(obj);
                 visitor(prop);
             }
@@ -1432,6 +1735,15 @@ impl JsValue {
         }
     }
 
+    /// Returns true when the value is known to be `null` or `undefined`.
+    pub fn is_nullish(&self) -> bool {
+        match self {
+            JsValue::Constant(ConstantValue::Null | ConstantValue::Undefined) => true,
+            JsValue::FreeVar(FreeVarKind::Other(name)) => &**name == "undefined",
+            _ => false,
+        }
+    }
+
     pub fn is_string(&self) -> bool {
         match self {
             JsValue::Constant(ConstantValue::StrWord(..))
@@ -1451,7 +1763,11 @@ impl JsValue {
                 | FreeVarKind::Require
                 | FreeVarKind::Define
                 | FreeVarKind::Import
-                | FreeVarKind::NodeProcess,
+                | FreeVarKind::NodeProcess
+                | FreeVarKind::Worker
+                | FreeVarKind::SharedWorker
+                | FreeVarKind::ImportMeta
+                | FreeVarKind::Url,
             ) => false,
             JsValue::FreeVar(FreeVarKind::Other(_)) => false,
 
@@ -1467,6 +1783,7 @@ impl JsValue {
                 _,
             ) => true,
             JsValue::Call(..) | JsValue::MemberCall(..) | JsValue::Member(..) => false,
+            JsValue::Binary(..) => false,
             JsValue::WellKnownObject(_) | JsValue::WellKnownFunction(_) => false,
         }
     }
@@ -1603,6 +1920,15 @@ impl JsValue {
                 }
             }
             JsValue::Concat(_, v) => {
+                // Constants are coerced to strings when concatenated
+                for item in v.iter_mut() {
+                    if let JsValue::Constant(c) = item {
+                        if c.as_str().is_none() {
+                            *item = c.to_js_string().into();
+                        }
+                    }
+                }
+
                 // Remove empty strings
                 v.retain(|v| v.as_str() != Some(""));
 
@@ -1625,13 +1951,24 @@ impl JsValue {
                         new.push(v);
                     }
                 }
-                if new.len() == 1 {
+                if new.is_empty() {
+                    *self = "".into();
+                } else if new.len() == 1 {
                     *self = new.into_iter().next().unwrap();
                 } else {
                     *v = new;
                     self.update_total_nodes();
                 }
             }
+            JsValue::Binary(_, box JsValue::Constant(l), op, box JsValue::Constant(r)) => {
+                if let Some(result) = op.evaluate(l, r) {
+                    *self = JsValue::Constant(if result {
+                        ConstantValue::True
+                    } else {
+                        ConstantValue::False
+                    });
+                }
+            }
             JsValue::Add(_, v) => {
                 let mut added: Vec<JsValue> = Vec::new();
                 let mut iter = take(v).into_iter();
@@ -1653,8 +1990,45 @@ impl JsValue {
                             1 + concat.iter().map(|v| v.total_nodes()).sum::<usize>(),
                             concat,
                         );
+                        // Fold constants into the string
+                        self.normalize_shallow();
                         return;
                     } else {
+                        // Numbers are only summed up while all previous
+                        // operands are known numbers, since `x + 1 + 2` is
+                        // `"x12"` when `x` is a string.
+                        match (&mut added[..], &item) {
+                            (
+                                [JsValue::Constant(ConstantValue::Num(ConstantNumber(l)))],
+                                JsValue::Constant(ConstantValue::Num(ConstantNumber(r))),
+                            ) => {
+                                *l += r;
+                                continue;
+                            }
+                            (
+                                [JsValue::Constant(ConstantValue::BigInt(l))],
+                                JsValue::Constant(ConstantValue::BigInt(r)),
+                            ) => {
+                                *l += r;
+                                continue;
+                            }
+                            (
+                                [JsValue::Constant(ConstantValue::Num(_))],
+                                JsValue::Constant(ConstantValue::BigInt(_)),
+                            )
+                            | (
+                                [JsValue::Constant(ConstantValue::BigInt(_))],
+                                JsValue::Constant(ConstantValue::Num(_)),
+                            ) => {
+                                // Mixing BigInt and Number throws a TypeError
+                                added.push(item);
+                                added.extend(iter);
+                                *self = JsValue::add(added);
+                                self.make_unknown("addition of BigInt and Number");
+                                return;
+                            }
+                            _ => {}
+                        }
                         added.push(item);
                     }
                 }
@@ -1729,6 +2103,9 @@ impl JsValue {
             (JsValue::Member(lc, lo, lp), JsValue::Member(rc, ro, rp)) => {
                 lc == rc && lo.similar(ro, depth - 1) && lp.similar(rp, depth - 1)
             }
+            (JsValue::Binary(lc, ll, lop, lr), JsValue::Binary(rc, rl, rop, rr)) => {
+                lc == rc && lop == rop && ll.similar(rl, depth - 1) && lr.similar(rr, depth - 1)
+            }
             (
                 JsValue::Module(ModuleValue {
                     module: l,
@@ -1802,6 +2179,11 @@ impl JsValue {
                 o.similar_hash(state, depth - 1);
                 p.similar_hash(state, depth - 1);
             }
+            JsValue::Binary(_, l, op, r) => {
+                l.similar_hash(state, depth - 1);
+                Hash::hash(op, state);
+                r.similar_hash(state, depth - 1);
+            }
             JsValue::Module(ModuleValue {
                 module: v,
                 annotations: a,
@@ -1857,6 +2239,18 @@ pub enum FreeVarKind {
     /// Node.js process
     NodeProcess,
 
+    /// The `Worker` constructor
+    Worker,
+
+    /// The `SharedWorker` constructor
+    SharedWorker,
+
+    /// `import.meta`
+    ImportMeta,
+
+    /// The `URL` constructor. `new URL(...)` is evaluated like a call.
+    Url,
+
     /// `abc` `some_global`
     Other(JsWord),
 }
@@ -1876,15 +2270,20 @@ pub enum WellKnownObjectKind {
     OsModule,
     OsModuleDefault,
     NodeProcess,
+    NodeProcessEnv,
     NodePreGyp,
     NodeExpressApp,
     NodeProtobufLoader,
     RequireCache,
+    ImportMeta,
+    ImportMetaEnv,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum WellKnownFunctionKind {
     ObjectAssign,
+    /// `Array.from`, used for spread elements in array literals
+    ArrayFrom,
     PathJoin,
     PathDirname,
     /// `0` is the current working directory.
@@ -1892,6 +2291,8 @@ pub enum WellKnownFunctionKind {
     Import,
     Require,
     RequireResolve,
+    /// `require.context(directory, useSubdirectories, regExp)` from webpack
+    RequireContext,
     Define,
     FsReadMethod(JsWord),
     PathToFileUrl,
@@ -1910,6 +2311,18 @@ pub enum WellKnownFunctionKind {
     NodeStrongGlobalizeSetRootDir,
     NodeResolveFrom,
     NodeProtobufLoad,
+    Worker,
+    SharedWorker,
+    URLConstructor,
+    /// A `String.prototype` method, bound to the constant string it's called
+    /// on.
+    StringMethod(Box<JsValue>, JsWord),
+    /// `parseInt`, also `Number.parseInt`
+    ParseInt,
+    /// `parseFloat`, also `Number.parseFloat`
+    ParseFloat,
+    /// The `Number` function
+    Number,
 }
 
 fn is_unresolved(i: &Ident, unresolved_mark: Mark) -> bool {
@@ -1922,14 +2335,23 @@ pub mod test_utils {
 
     use anyhow::Result;
     use turbopack_core::environment::EnvironmentVc;
+    use url::Url;
 
     use super::{
-        well_known::replace_well_known, FreeVarKind, JsValue, ModuleValue, WellKnownFunctionKind,
-        WellKnownObjectKind,
+        well_known::{global_function, replace_well_known, ProcessEnvConfig},
+        FreeVarKind, JsValue, ModuleValue, WellKnownFunctionKind, WellKnownObjectKind,
     };
     use crate::analyzer::builtin::replace_builtin;
 
     pub async fn visitor(v: JsValue, environment: EnvironmentVc) -> Result<(JsValue, bool)> {
+        visitor_with_process_env(v, environment, &ProcessEnvConfig::default()).await
+    }
+
+    pub async fn visitor_with_process_env(
+        v: JsValue,
+        environment: EnvironmentVc,
+        process_env: &ProcessEnvConfig,
+    ) -> Result<(JsValue, bool)> {
         let mut new_value = match v {
             JsValue::Call(
                 _,
@@ -1950,6 +2372,22 @@ pub mod test_utils {
             JsValue::FreeVar(FreeVarKind::NodeProcess) => {
                 JsValue::WellKnownObject(WellKnownObjectKind::NodeProcess)
             }
+            JsValue::FreeVar(FreeVarKind::ImportMeta) => {
+                JsValue::WellKnownObject(WellKnownObjectKind::ImportMeta)
+            }
+            JsValue::FreeVar(FreeVarKind::Url) => {
+                JsValue::WellKnownFunction(WellKnownFunctionKind::URLConstructor)
+            }
+            JsValue::Member(
+                _,
+                box JsValue::WellKnownObject(WellKnownObjectKind::ImportMeta),
+                box ref prop,
+            ) if prop.as_str() == Some("url") => {
+                JsValue::Url(Url::parse("file:///ROOT/index.js").unwrap())
+            }
+            JsValue::FreeVar(FreeVarKind::Other(ref name)) if global_function(name).is_some() => {
+                JsValue::WellKnownFunction(global_function(name).unwrap())
+            }
             JsValue::FreeVar(kind) => {
                 JsValue::Unknown(Some(Arc::new(JsValue::FreeVar(kind))), "unknown global")
             }
@@ -1964,7 +2402,7 @@ pub mod test_utils {
                 _ => return Ok((v, false)),
             },
             _ => {
-                let (mut v, m1) = replace_well_known(v, environment).await?;
+                let (mut v, m1) = replace_well_known(v, environment, process_env).await?;
                 let m2 = replace_builtin(&mut v);
                 return Ok((v, m1 || m2));
             }
@@ -1976,10 +2414,16 @@ pub mod test_utils {
 
 #[cfg(test)]
 mod tests {
-    use std::{path::PathBuf, sync::Mutex, time::Instant};
+    use std::{
+        collections::{BTreeMap, HashMap},
+        path::PathBuf,
+        sync::Mutex,
+        time::Instant,
+    };
 
+    use num_bigint::BigInt;
     use swc_core::{
-        common::Mark,
+        common::{FileName, Mark},
         ecma::{
             ast::EsVersion, parser::parse_file_as_program, transforms::base::resolver,
             visit::VisitMutWith,
@@ -1993,12 +2437,822 @@ mod tests {
         },
         target::{Arch, CompileTarget, Endianness, Libc, Platform},
     };
+    use url::Url;
 
     use super::{
-        graph::{create_graph, EvalContext},
+        builtin::replace_builtin,
+        graph::{create_graph, Effect, EvalContext, VarGraph},
         linker::{link, LinkCache},
-        JsValue,
+        well_known::{object_assign, ProcessEnvConfig},
+        BinaryOperator, ConstantNumber, ConstantValue, FreeVarKind, JsValue, ObjectPart,
     };
+    use crate::EcmascriptOptions;
+
+    fn graph(src: &str) -> VarGraph {
+        run_test(false, |cm, handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let mut m = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| err.into_diagnostic(handler).emit())?;
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            m.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+            let eval_context = EvalContext::new(&m, unresolved_mark);
+            Ok(create_graph(&m, &eval_context))
+        })
+        .unwrap()
+    }
+
+    fn effects(src: &str) -> Vec<Effect> {
+        graph(src).effects
+    }
+
+    /// The first arguments of calls of the `require` parameters of AMD
+    /// factories.
+    fn amd_require_calls(src: &str) -> Vec<JsValue> {
+        let VarGraph {
+            values, effects, ..
+        } = graph(src);
+        effects
+            .into_iter()
+            .filter_map(|effect| match effect {
+                Effect::Call {
+                    func: JsValue::Variable(id),
+                    mut args,
+                    ..
+                } if !args.is_empty()
+                    && values.get(&id) == Some(&JsValue::FreeVar(FreeVarKind::Require)) =>
+                {
+                    Some(args.swap_remove(0))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn worker_effects(src: &str) -> Vec<(JsValue, JsValue, JsValue)> {
+        effects(src)
+            .into_iter()
+            .filter_map(|effect| match effect {
+                Effect::Worker {
+                    func,
+                    input,
+                    options,
+                    ..
+                } => Some((func, input, options)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn dynamic_imports(src: &str) -> Vec<JsValue> {
+        effects(src)
+            .into_iter()
+            .filter_map(|effect| match effect {
+                Effect::Call {
+                    func: JsValue::FreeVar(FreeVarKind::Import),
+                    mut args,
+                    ..
+                } if !args.is_empty() => Some(args.swap_remove(0)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn conditional_dynamic_imports() {
+        let imports = dynamic_imports(
+            r#"
+cond ? import("./a") : import("./b");
+x || import("./c");
+y && import("./d");
+"#,
+        );
+        assert_eq!(
+            imports,
+            vec!["./a".into(), "./b".into(), "./c".into(), "./d".into()]
+        );
+    }
+
+    #[test]
+    fn nested_conditional_dynamic_imports() {
+        let imports = dynamic_imports(
+            r#"
+a ? (b ? import("./e") : import(dynamic)) : import("./f") || import("./g");
+"#,
+        );
+        assert_eq!(
+            imports,
+            vec![
+                "./e".into(),
+                JsValue::FreeVar(FreeVarKind::Other("dynamic".into())),
+                "./f".into(),
+                "./g".into(),
+            ]
+        );
+    }
+
+    #[test]
+    fn amd_wrapper() {
+        let calls = amd_require_calls(
+            r#"
+define(function (require, exports, module) {
+  var a = require("./a");
+  exports.b = require("./b");
+});
+"#,
+        );
+        assert_eq!(calls, vec!["./a".into(), "./b".into()]);
+    }
+
+    #[test]
+    fn amd_dependencies() {
+        let effects = effects(
+            r#"define("name", ["./a", "require"], function (a, req) { return req("./b"); });"#,
+        );
+        let deps = effects.iter().find_map(|effect| match effect {
+            Effect::Call {
+                func: JsValue::FreeVar(FreeVarKind::Define),
+                args,
+                ..
+            } => Some(args[1].clone()),
+            _ => None,
+        });
+        assert_eq!(
+            deps,
+            Some(JsValue::array(vec!["./a".into(), "require".into()]))
+        );
+
+        let calls = amd_require_calls(
+            r#"
+define("name", ["./a", "require"], function (a, req) { return req("./b"); });
+require(["require", "./c"], function (r, c) { r("./d"); c("./e"); });
+"#,
+        );
+        assert_eq!(calls, vec!["./b".into(), "./d".into()]);
+    }
+
+    #[test]
+    fn worker() {
+        let workers = worker_effects(
+            r#"
+new Worker(new URL("./worker.js", import.meta.url));
+new SharedWorker(new URL("./shared.js", import.meta.url));
+new Worker(new URL("./other.js", location.href));
+new Worker("./plain.js");
+"#,
+        );
+        assert_eq!(
+            workers,
+            vec![
+                (
+                    JsValue::FreeVar(FreeVarKind::Worker),
+                    "./worker.js".into(),
+                    JsValue::Constant(ConstantValue::Undefined)
+                ),
+                (
+                    JsValue::FreeVar(FreeVarKind::SharedWorker),
+                    "./shared.js".into(),
+                    JsValue::Constant(ConstantValue::Undefined)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn module_worker() {
+        let workers = worker_effects(
+            r#"new Worker(new URL("./worker.js", import.meta.url), { type: "module" });"#,
+        );
+        assert_eq!(workers.len(), 1);
+        assert_eq!(
+            workers[0].2,
+            JsValue::object(vec![ObjectPart::KeyValue("type".into(), "module".into())])
+        );
+    }
+
+    #[test]
+    fn aliased_worker() {
+        // The alias is resolved when linking the constructor
+        let workers = worker_effects(
+            r#"
+const MyWorker = Worker;
+new MyWorker(new URL("./worker.js", import.meta.url));
+"#,
+        );
+        assert_eq!(workers.len(), 1);
+        assert!(
+            matches!(workers[0].0, JsValue::Variable(_)),
+            "{:?}",
+            workers[0].0
+        );
+    }
+
+    /// Whether the conditions of `if` statements and conditional expressions
+    /// depend on reassigned bindings.
+    fn reassigned_conditions(src: &str) -> Vec<bool> {
+        let graph = graph(src);
+        graph
+            .effects
+            .iter()
+            .filter_map(|effect| match effect {
+                Effect::Conditional { condition, .. } => {
+                    Some(graph.depends_on_reassigned(condition))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reassigned_condition() {
+        // `i` is linked to `0`, since `i++` isn't part of its values, so
+        // `sep()` must not be removed
+        assert_eq!(
+            reassigned_conditions("for (let i = 0; i < n; i++) {\n  if (i) sep();\n}"),
+            vec![true]
+        );
+        assert_eq!(
+            reassigned_conditions("let x = 0;\nx += 1;\nconst y = x ? a : b;"),
+            vec![true]
+        );
+        assert_eq!(
+            reassigned_conditions("let x = 1;\n[x] = list;\nif (x) f();"),
+            vec![true]
+        );
+        assert_eq!(
+            reassigned_conditions("const DEBUG = false;\nif (DEBUG) log();"),
+            vec![false]
+        );
+    }
+
+    fn normalized(mut value: JsValue) -> JsValue {
+        value.normalize();
+        value
+    }
+
+    #[test]
+    fn fold_string_and_number() {
+        assert_eq!(
+            normalized(JsValue::add(vec!["a".into(), 1.0.into()])),
+            "a1".into()
+        );
+        assert_eq!(
+            normalized(JsValue::add(vec![1.0.into(), "a".into()])),
+            "1a".into()
+        );
+        assert_eq!(
+            normalized(JsValue::add(vec!["./".into(), "a".into(), ".js".into()])),
+            "./a.js".into()
+        );
+        assert_eq!(
+            normalized(JsValue::add(vec![1.0.into(), 2.0.into(), "a".into()])),
+            "3a".into()
+        );
+        assert_eq!(
+            normalized(JsValue::add(vec!["a".into(), 1.0.into(), 2.0.into()])),
+            "a12".into()
+        );
+        assert_eq!(
+            normalized(JsValue::add(vec![1.0.into(), 2.0.into()])),
+            3.0.into()
+        );
+    }
+
+    #[test]
+    fn fold_mixed_known_and_unknown() {
+        let unknown = || JsValue::Unknown(None, "");
+        assert_eq!(
+            normalized(JsValue::add(vec![unknown(), "a".into(), 1.0.into()])),
+            JsValue::concat(vec![unknown(), "a1".into()])
+        );
+        assert_eq!(
+            normalized(JsValue::add(vec![
+                "a".into(),
+                unknown(),
+                1.0.into(),
+                ".js".into()
+            ])),
+            JsValue::concat(vec!["a".into(), unknown(), "1.js".into()])
+        );
+        // `x + 1 + 2` can't be folded, as `x` might be a string
+        assert_eq!(
+            normalized(JsValue::add(vec![unknown(), 1.0.into(), 2.0.into()])),
+            JsValue::add(vec![unknown(), 1.0.into(), 2.0.into()])
+        );
+        assert_eq!(
+            normalized(JsValue::add(vec![unknown(), 1.0.into(), "a".into()])),
+            JsValue::concat(vec![JsValue::add(vec![unknown(), 1.0.into()]), "a".into()])
+        );
+    }
+
+    #[test]
+    fn fold_bigint() {
+        let big = |n: i64| JsValue::from(BigInt::from(n));
+        assert_eq!(normalized(JsValue::add(vec![big(1), big(2)])), big(3));
+        assert_eq!(
+            normalized(JsValue::add(vec![big(1), big(2), "a".into()])),
+            "3a".into()
+        );
+        assert_eq!(big(10).to_string(), "10n");
+
+        let mixed = normalized(JsValue::add(vec![big(1), 2.0.into()]));
+        assert!(matches!(mixed, JsValue::Unknown(..)), "{mixed:?}");
+        let mixed = normalized(JsValue::add(vec![1.0.into(), big(2), "a".into()]));
+        assert!(matches!(mixed, JsValue::Unknown(..)), "{mixed:?}");
+    }
+
+    #[test]
+    fn number_to_js_string() {
+        let cases = [
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (0.1, "0.1"),
+            (-0.0, "0"),
+            (1e21, "1e+21"),
+            (123e18, "123000000000000000000"),
+            (1.5e-7, "1.5e-7"),
+            (0.000001, "0.000001"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ];
+        for (n, expected) in cases {
+            assert_eq!(ConstantNumber(n).to_js_string(), expected);
+        }
+    }
+
+    #[test]
+    fn fold_comparison() {
+        let compare = |l: JsValue, op, r: JsValue| normalized(JsValue::binary(box l, op, box r));
+        assert_eq!(
+            compare("a".into(), BinaryOperator::StrictEqual, "a".into()),
+            JsValue::Constant(ConstantValue::True)
+        );
+        assert_eq!(
+            compare("a".into(), BinaryOperator::StrictNotEqual, "a".into()),
+            JsValue::Constant(ConstantValue::False)
+        );
+        assert_eq!(
+            compare(1.0.into(), BinaryOperator::StrictEqual, "1".into()),
+            JsValue::Constant(ConstantValue::False)
+        );
+        assert_eq!(
+            compare(
+                JsValue::Constant(ConstantValue::Null),
+                BinaryOperator::Equal,
+                JsValue::Constant(ConstantValue::Undefined)
+            ),
+            JsValue::Constant(ConstantValue::True)
+        );
+        // `1 == "1"` coerces the string, which is not modeled
+        assert!(matches!(
+            compare(1.0.into(), BinaryOperator::Equal, "1".into()),
+            JsValue::Binary(..)
+        ));
+    }
+
+    fn member(obj: JsValue, prop: &str) -> JsValue {
+        let mut value = JsValue::member(box obj, box prop.into());
+        replace_builtin(&mut value);
+        value
+    }
+
+    #[test]
+    fn object_assign_literals() {
+        let merged = object_assign(vec![
+            JsValue::object(vec![
+                ObjectPart::KeyValue("a".into(), 1.0.into()),
+                ObjectPart::KeyValue("b".into(), 2.0.into()),
+            ]),
+            JsValue::object(vec![ObjectPart::KeyValue("b".into(), 3.0.into())]),
+        ]);
+        assert_eq!(member(merged.clone(), "a"), 1.0.into());
+        assert_eq!(member(merged.clone(), "b"), 3.0.into());
+        assert_eq!(
+            member(merged, "c"),
+            JsValue::FreeVar(FreeVarKind::Other("undefined".into()))
+        );
+    }
+
+    #[test]
+    fn object_assign_unknown() {
+        let merged = object_assign(vec![
+            JsValue::object(vec![ObjectPart::KeyValue("a".into(), 1.0.into())]),
+            JsValue::Unknown(None, "unknown source"),
+            JsValue::object(vec![ObjectPart::KeyValue("b".into(), 2.0.into())]),
+        ]);
+        // The unknown source could override `a`, but not `b`
+        let a = member(merged.clone(), "a");
+        assert!(matches!(a, JsValue::Unknown(..)), "{a:?}");
+        assert_eq!(member(merged, "b"), 2.0.into());
+    }
+
+    fn link_with_process_env(src: &str, process_env: ProcessEnvConfig) -> HashMap<String, JsValue> {
+        crate::register();
+        run_test(false, |cm, handler| {
+            let r = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap();
+            r.block_on(async move {
+                let fm = cm.new_source_file(FileName::Anon, src.into());
+                let mut m = parse_file_as_program(
+                    &fm,
+                    Default::default(),
+                    EsVersion::latest(),
+                    None,
+                    &mut vec![],
+                )
+                .map_err(|err| err.into_diagnostic(handler).emit())?;
+
+                let unresolved_mark = Mark::new();
+                let top_level_mark = Mark::new();
+                m.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+                let eval_context = EvalContext::new(&m, unresolved_mark);
+                let var_graph = create_graph(&m, &eval_context);
+
+                let cache = Mutex::new(LinkCache::new());
+                let mut values = HashMap::new();
+                for ((name, _), val) in var_graph.values.iter() {
+                    let res = turbo_tasks_testing::VcStorage::with(link(
+                        &var_graph,
+                        val.clone(),
+                        &(|val| {
+                            Box::pin(super::test_utils::visitor_with_process_env(
+                                val,
+                                EnvironmentVc::new(
+                                    Value::new(ExecutionEnvironment::NodeJsLambda(
+                                        NodeJsEnvironment::default().into(),
+                                    )),
+                                    Value::new(EnvironmentIntention::ServerRendering),
+                                ),
+                                &process_env,
+                            ))
+                        }),
+                        &cache,
+                    ))
+                    .await
+                    .unwrap();
+                    values.insert(name.to_string(), res);
+                }
+                Ok(values)
+            })
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn array_callbacks() {
+        let values = link_with_process_env(
+            r#"
+var a = ["./a", "./b"].map(x => x + ".js");
+var b = ["./a", "./b"].map((x, i) => `${x}-${i}`);
+var c = ["./a", "./b"].filter(x => x !== "./b");
+var d = ["./a", "./b"].map(x => { count++; return x; });
+var e = list.map(x => x + ".js");
+var f = ["./a", "./b"].map(x => { log(x); return x; });
+var g = ["./a", "./b"].map(x => (log(x), x));
+var h = ["./a", "./b"].map(x => new Wrapper(x));
+var i = ["./a", "./b"].map(x => new URL(x, import.meta.url));
+"#,
+            ProcessEnvConfig::default(),
+        );
+        assert_eq!(
+            values["a"],
+            JsValue::array(vec!["./a.js".into(), "./b.js".into()])
+        );
+        assert_eq!(
+            values["b"],
+            JsValue::array(vec!["./a-0".into(), "./b-1".into()])
+        );
+        assert_eq!(values["c"], JsValue::array(vec!["./a".into()]));
+        // The callback has side effects
+        assert!(
+            !matches!(values["d"], JsValue::Array(..)),
+            "{:?}",
+            values["d"]
+        );
+        // The array is not known
+        assert!(
+            matches!(values["e"], JsValue::Unknown(..)),
+            "{:?}",
+            values["e"]
+        );
+        // Calls in the callback may have side effects
+        for name in ["f", "g", "h"] {
+            assert!(
+                !matches!(values[name], JsValue::Array(..)),
+                "{name}: {:?}",
+                values[name]
+            );
+        }
+        // Except for `new URL(...)`, which is used for lists of assets
+        assert!(
+            matches!(values["i"], JsValue::Array(..)),
+            "{:?}",
+            values["i"]
+        );
+    }
+
+    #[test]
+    fn array_spread() {
+        let values = link_values(
+            r#"
+var base = ["./a", "./b"];
+var a = [...base, "./c"];
+var b = ["./x", ...base, ...["./y"]];
+var c = ["./x", ...list, "./z"];
+"#,
+        );
+        assert_eq!(
+            values["a"],
+            JsValue::array(vec!["./a".into(), "./b".into(), "./c".into()])
+        );
+        assert_eq!(
+            values["b"],
+            JsValue::array(vec!["./x".into(), "./a".into(), "./b".into(), "./y".into()])
+        );
+        // The length is not known, but the elements before the spread are
+        assert!(
+            values["c"].to_string().starts_with(r#"["./x"]["concat"]("#),
+            "{}",
+            values["c"]
+        );
+    }
+
+    #[test]
+    fn string_spread() {
+        let values = link_values(
+            r#"
+var name = "./ab";
+var a = [...name];
+var b = ["./x", ...name];
+"#,
+        );
+        // A spread string is split into its characters, it's not a single element
+        assert_eq!(values["a"].to_string(), r#"[]["concat"](???)"#);
+        assert_eq!(values["b"].to_string(), r#"["./x"]["concat"](???)"#);
+    }
+
+    #[test]
+    fn object_spread() {
+        let values = link_values(
+            r#"
+var defaults = { mode: "development", entry: "./a" };
+var a = { ...defaults, mode: "production" }.mode;
+var b = { ...defaults, mode: "production" }.entry;
+var c = { mode: "production", ...defaults }.mode;
+var d = { ...defaults, ...overrides }.entry;
+var e = { ...overrides, entry: "./b" }.entry;
+"#,
+        );
+        assert_eq!(values["a"], "production".into());
+        assert_eq!(values["b"], "./a".into());
+        assert_eq!(values["c"], "development".into());
+        // The unknown spread could override `entry`
+        assert!(
+            matches!(values["d"], JsValue::Unknown(..)),
+            "{:?}",
+            values["d"]
+        );
+        assert_eq!(values["e"], "./b".into());
+    }
+
+    #[test]
+    fn computed_member() {
+        let values = link_values(
+            r#"
+var obj = { a: "./a", 1: "./b" };
+var a = obj.a;
+var b = obj["a"];
+var c = obj[`a`];
+var d = obj["1"];
+var e = obj[1];
+var list = ["./x", "./y"];
+var f = list[1];
+var g = list["1"];
+var h = list["01"];
+var i = obj[key];
+"#,
+        );
+        assert_eq!(values["a"], "./a".into());
+        assert_eq!(values["b"], values["a"]);
+        assert_eq!(values["c"], values["a"]);
+        assert_eq!(values["d"], "./b".into());
+        assert_eq!(values["e"], values["d"]);
+        assert_eq!(values["f"], "./y".into());
+        assert_eq!(values["g"], values["f"]);
+        // Not an array index
+        assert!(
+            matches!(values["h"], JsValue::Unknown(..)),
+            "{:?}",
+            values["h"]
+        );
+        // The key is not known
+        assert_eq!(values["i"].as_str(), None, "{:?}", values["i"]);
+    }
+
+    #[test]
+    fn process_env() {
+        let values = link_with_process_env(
+            r#"
+var a = process.env.NODE_ENV;
+var b = process.env.NODE_ENV === "production";
+var c = process.env["NODE_ENV"] !== "production";
+var d = process.env.MISSING;
+var e = process.env[key];
+"#,
+            ProcessEnvConfig::new(HashMap::from([(
+                "NODE_ENV".to_string(),
+                "production".into(),
+            )])),
+        );
+        assert_eq!(values["a"], "production".into());
+        assert_eq!(values["b"], JsValue::Constant(ConstantValue::True));
+        assert_eq!(values["c"], JsValue::Constant(ConstantValue::False));
+        assert!(
+            matches!(values["d"], JsValue::Unknown(..)),
+            "{:?}",
+            values["d"]
+        );
+        assert!(
+            matches!(values["e"], JsValue::Unknown(..)),
+            "{:?}",
+            values["e"]
+        );
+    }
+
+    #[test]
+    fn process_env_from_options() {
+        let options = EcmascriptOptions {
+            process_env: BTreeMap::from([("NODE_ENV".to_string(), "production".to_string())]),
+            import_meta_env: BTreeMap::from([("MODE".to_string(), "production".to_string())]),
+            ..Default::default()
+        };
+        let values = link_with_process_env(
+            r#"
+var a = process.env.NODE_ENV === "production";
+var b = import.meta.env.MODE;
+"#,
+            ProcessEnvConfig::from_options(&options),
+        );
+        assert_eq!(values["a"], JsValue::Constant(ConstantValue::True));
+        assert_eq!(values["b"], "production".into());
+    }
+
+    #[test]
+    fn import_meta() {
+        let values = link_with_process_env(
+            r#"
+var a = import.meta.url;
+var b = new URL("./a.js", import.meta.url);
+var c = new URL("../assets/" + "logo.png", import.meta.url);
+var d = new URL(name, import.meta.url);
+var e = import.meta.env.MODE;
+var f = import.meta.env.MODE === "development";
+var g = import.meta.env.MISSING;
+var h = import.meta.hot;
+"#,
+            ProcessEnvConfig {
+                import_meta_env: HashMap::from([("MODE".to_string(), "development".into())]),
+                ..Default::default()
+            },
+        );
+        let url = |url: &str| JsValue::Url(Url::parse(url).unwrap());
+        assert_eq!(values["a"], url("file:///ROOT/index.js"));
+        assert_eq!(values["b"], url("file:///ROOT/a.js"));
+        assert_eq!(values["c"], url("file:///assets/logo.png"));
+        assert_eq!(values["e"], "development".into());
+        assert_eq!(values["f"], JsValue::Constant(ConstantValue::True));
+        for name in ["d", "g", "h"] {
+            assert!(
+                matches!(values[name], JsValue::Unknown(..)),
+                "{name}: {:?}",
+                values[name]
+            );
+        }
+    }
+
+    fn link_values(src: &str) -> HashMap<String, JsValue> {
+        link_with_process_env(src, ProcessEnvConfig::default())
+    }
+
+    #[test]
+    fn string_replace() {
+        let values = link_values(
+            r#"
+var a = "./x.js".replace(".js", ".mjs");
+var b = "a-b-c".replace("-", "+");
+var c = "a-b-c".replaceAll("-", "+");
+var d = "./x.js".replace(/\.js$/, ".mjs");
+var e = "./x.js".replace(".js", ext);
+var f = "./x.js".replace(".js", "$&.map");
+"#,
+        );
+        assert_eq!(values["a"], "./x.mjs".into());
+        assert_eq!(values["b"], "a+b-c".into());
+        assert_eq!(values["c"], "a+b+c".into());
+        for name in ["d", "e", "f"] {
+            assert!(
+                matches!(values[name], JsValue::Unknown(..)),
+                "{name}: {:?}",
+                values[name]
+            );
+        }
+    }
+
+    #[test]
+    fn string_slice() {
+        let values = link_values(
+            r#"
+var a = "./dir/file.js".slice(2);
+var b = "./dir/file.js".slice(0, -3);
+var c = "./dir/file.js".slice(-7, -3);
+var d = "abc".slice(2, 1);
+var e = "abc".slice(start);
+"#,
+        );
+        assert_eq!(values["a"], "dir/file.js".into());
+        assert_eq!(values["b"], "./dir/file".into());
+        assert_eq!(values["c"], "file".into());
+        assert_eq!(values["d"], "".into());
+        assert!(
+            matches!(values["e"], JsValue::Unknown(..)),
+            "{:?}",
+            values["e"]
+        );
+    }
+
+    #[test]
+    fn string_case_and_concat() {
+        let values = link_values(
+            r#"
+var a = "./Locales/EN.json".toLowerCase();
+var b = "./locale-".concat("en", ".json").toUpperCase();
+var c = "  ./a.js\n".trim();
+var d = "./a.js".padStart(10);
+"#,
+        );
+        assert_eq!(values["a"], "./locales/en.json".into());
+        assert_eq!(values["b"], "./LOCALE-EN.JSON".into());
+        assert_eq!(values["c"], "./a.js".into());
+        // Not a supported method
+        assert!(
+            matches!(values["d"], JsValue::Unknown(..)),
+            "{:?}",
+            values["d"]
+        );
+    }
+
+    #[test]
+    fn number_functions() {
+        let values = link_values(
+            r#"
+var a = parseInt("0x10");
+var b = parseInt("8", 10);
+var c = Number("abc");
+var d = parseInt("  -12px");
+var e = parseFloat("3.5e2rem");
+var f = Number(" 0b101\n");
+var g = Number.parseInt("z", 36);
+var h = parseInt("10", 1);
+var i = parseFloat(".");
+var j = Number("");
+var k = parseInt(count);
+"#,
+        );
+        let num = |n| JsValue::Constant(ConstantValue::Num(ConstantNumber(n)));
+        assert_eq!(values["a"], num(16.0));
+        assert_eq!(values["b"], num(8.0));
+        assert_eq!(values["d"], num(-12.0));
+        assert_eq!(values["e"], num(350.0));
+        assert_eq!(values["f"], num(5.0));
+        assert_eq!(values["g"], num(35.0));
+        assert_eq!(values["j"], num(0.0));
+        for name in ["c", "h", "i"] {
+            assert!(
+                matches!(
+                    values[name],
+                    JsValue::Constant(ConstantValue::Num(ConstantNumber(n))) if n.is_nan()
+                ),
+                "{name}: {:?}",
+                values[name]
+            );
+        }
+        assert!(
+            matches!(values["k"], JsValue::Unknown(..)),
+            "{:?}",
+            values["k"]
+        );
+    }
 
     #[fixture("tests/analyzer/graph/**/input.js")]
     fn fixture(input: PathBuf) {
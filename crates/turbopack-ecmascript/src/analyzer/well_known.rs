@@ -1,17 +1,59 @@
-use std::{mem::take, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem::take,
+    sync::Arc,
+};
 
 use anyhow::Result;
+use swc_core::ecma::atoms::JsWord;
 use turbopack_core::environment::EnvironmentVc;
 use url::Url;
 
 use super::{
-    imports::ImportAnnotations, ConstantValue, JsValue, ModuleValue, WellKnownFunctionKind,
-    WellKnownObjectKind,
+    imports::ImportAnnotations, is_js_whitespace, parse_radix_digits, ConstantNumber,
+    ConstantValue, JsValue, ModuleValue, ObjectPart, WellKnownFunctionKind, WellKnownObjectKind,
 };
+use crate::EcmascriptOptions;
+
+/// Values of `process.env` variables that are inlined by the analyzer.
+///
+/// Only statically keyed accesses like `process.env.NODE_ENV` are replaced,
+/// all other accesses stay unknown.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessEnvConfig {
+    pub values: HashMap<String, JsValue>,
+    /// Values of `import.meta.env` variables, which some frameworks use
+    /// instead of `process.env`, e.g. `import.meta.env.MODE`.
+    pub import_meta_env: HashMap<String, JsValue>,
+}
+
+impl ProcessEnvConfig {
+    pub fn new(values: HashMap<String, JsValue>) -> Self {
+        Self {
+            values,
+            import_meta_env: HashMap::new(),
+        }
+    }
+
+    /// The values configured by [EcmascriptOptions::process_env] and
+    /// [EcmascriptOptions::import_meta_env].
+    pub fn from_options(options: &EcmascriptOptions) -> Self {
+        let to_values = |env: &BTreeMap<String, String>| {
+            env.iter()
+                .map(|(key, value)| (key.clone(), value.clone().into()))
+                .collect()
+        };
+        Self {
+            values: to_values(&options.process_env),
+            import_meta_env: to_values(&options.import_meta_env),
+        }
+    }
+}
 
 pub async fn replace_well_known(
     value: JsValue,
     environment: EnvironmentVc,
+    process_env: &ProcessEnvConfig,
 ) -> Result<(JsValue, bool)> {
     Ok(match value {
         JsValue::Call(_, box JsValue::WellKnownFunction(kind), args) => (
@@ -34,7 +76,7 @@ pub async fn replace_well_known(
             (JsValue::Call(usize, callee, args), false)
         }
         JsValue::Member(_, box JsValue::WellKnownObject(kind), box prop) => (
-            well_known_object_member(kind, prop, environment).await?,
+            well_known_object_member(kind, prop, environment, process_env).await?,
             true,
         ),
         JsValue::Member(_, box JsValue::WellKnownFunction(kind), box prop) => {
@@ -52,6 +94,7 @@ pub async fn well_known_function_call(
 ) -> Result<JsValue> {
     Ok(match kind {
         WellKnownFunctionKind::ObjectAssign => object_assign(args),
+        WellKnownFunctionKind::ArrayFrom => array_from(args),
         WellKnownFunctionKind::PathJoin => path_join(args),
         WellKnownFunctionKind::PathDirname => path_dirname(args),
         WellKnownFunctionKind::PathResolve(cwd) => path_resolve(*cwd, args),
@@ -64,6 +107,7 @@ pub async fn well_known_function_call(
         ),
         WellKnownFunctionKind::Require => require(args),
         WellKnownFunctionKind::PathToFileUrl => path_to_file_url(args),
+        WellKnownFunctionKind::URLConstructor => url_constructor(args),
         WellKnownFunctionKind::OsArch => environment.compile_target().await?.arch.as_str().into(),
         WellKnownFunctionKind::OsPlatform => {
             environment.compile_target().await?.platform.as_str().into()
@@ -94,6 +138,10 @@ pub async fn well_known_function_call(
         WellKnownFunctionKind::NodeResolveFrom => {
             JsValue::WellKnownFunction(WellKnownFunctionKind::NodeResolveFrom)
         }
+        WellKnownFunctionKind::StringMethod(this, method) => string_method(*this, method, args),
+        WellKnownFunctionKind::ParseInt => parse_int(args),
+        WellKnownFunctionKind::ParseFloat => parse_float(args),
+        WellKnownFunctionKind::Number => number(args),
 
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::call(
@@ -105,37 +153,282 @@ pub async fn well_known_function_call(
     })
 }
 
+/// The global function `name` that is evaluated by the analyzer, if any.
+pub fn global_function(name: &str) -> Option<WellKnownFunctionKind> {
+    Some(match name {
+        "parseInt" => WellKnownFunctionKind::ParseInt,
+        "parseFloat" => WellKnownFunctionKind::ParseFloat,
+        "Number" => WellKnownFunctionKind::Number,
+        _ => return None,
+    })
+}
+
 pub fn object_assign(args: Vec<JsValue>) -> JsValue {
-    if args.iter().all(|arg| matches!(arg, JsValue::Object(..))) {
-        if let Some(merged_object) = args.into_iter().reduce(|mut acc, cur| {
-            if let JsValue::Object(_, parts) = &mut acc {
-                if let JsValue::Object(_, next_parts) = &cur {
-                    parts.extend_from_slice(next_parts);
-                }
-            }
-            acc
-        }) {
-            merged_object
-        } else {
-            JsValue::Unknown(
-                Some(Arc::new(JsValue::Call(
-                    0,
-                    box JsValue::WellKnownFunction(WellKnownFunctionKind::ObjectAssign),
-                    vec![],
-                ))),
-                "empty arguments for Object.assign",
-            )
-        }
-    } else {
-        JsValue::Unknown(
-            Some(Arc::new(JsValue::Call(
-                args.len(),
+    if args.is_empty() {
+        return JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
                 box JsValue::WellKnownFunction(WellKnownFunctionKind::ObjectAssign),
+                vec![],
+            ))),
+            "empty arguments for Object.assign",
+        );
+    }
+    let mut parts = Vec::new();
+    for arg in args {
+        match arg {
+            JsValue::Object(_, inner) => parts.extend(inner),
+            // `null` and `undefined` sources are ignored
+            JsValue::Constant(ConstantValue::Null | ConstantValue::Undefined) => {}
+            // An unknown source only hides the keys assigned before it, keys of
+            // later sources override it.
+            arg => parts.push(ObjectPart::Spread(arg)),
+        }
+    }
+    JsValue::object(parts)
+}
+
+/// `Array.from(value)`, which is how spread elements of array literals are
+/// evaluated. Only arrays are known to be copied as they are, e.g. a string is
+/// split into its characters.
+pub fn array_from(args: Vec<JsValue>) -> JsValue {
+    match <[JsValue; 1]>::try_from(args) {
+        Ok([array @ JsValue::Array(..)]) => array,
+        Ok([value]) => JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownFunction(WellKnownFunctionKind::ArrayFrom),
+                vec![value],
+            ))),
+            "only arrays are spread",
+        ),
+        Err(args) => JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownFunction(WellKnownFunctionKind::ArrayFrom),
                 args,
             ))),
-            "only const object assign is supported",
-        )
+            "unsupported arguments for Array.from",
+        ),
+    }
+}
+
+/// Calls the `String.prototype` method `method` on the constant string
+/// `this`. The result is unknown when an argument is not a constant or when
+/// the call behaves in ways that are not modeled, e.g. `replace` with a
+/// regular expression or with a replacement pattern like `$&`.
+pub fn string_method(this: JsValue, method: JsWord, args: Vec<JsValue>) -> JsValue {
+    let result = this.as_str().and_then(|str| {
+        let args = args
+            .iter()
+            .map(|arg| match arg {
+                JsValue::Constant(arg) => Some(arg),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        eval_string_method(str, &method, &args)
+    });
+    match result {
+        Some(result) => result.into(),
+        None => JsValue::Unknown(
+            Some(Arc::new(JsValue::call(
+                box JsValue::WellKnownFunction(WellKnownFunctionKind::StringMethod(
+                    box this, method,
+                )),
+                args,
+            ))),
+            "unsupported string method call",
+        ),
+    }
+}
+
+/// Calls the function `kind`, which converts its arguments to a number with
+/// `eval`. The result is unknown when an argument is not a constant.
+fn number_function(
+    kind: WellKnownFunctionKind,
+    args: Vec<JsValue>,
+    eval: impl FnOnce(&[&ConstantValue]) -> f64,
+) -> JsValue {
+    let constants = args
+        .iter()
+        .map(|arg| match arg {
+            JsValue::Constant(arg) => Some(arg),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    if let Some(constants) = constants {
+        return JsValue::Constant(ConstantValue::Num(ConstantNumber(eval(&constants))));
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(kind),
+            args,
+        ))),
+        "non constant argument",
+    )
+}
+
+/// `ToInt32` of the ECMAScript spec.
+fn to_int32(n: f64) -> i32 {
+    if !n.is_finite() {
+        return 0;
     }
+    n.trunc().rem_euclid(4294967296.0) as u32 as i32
+}
+
+/// The string argument of `parseInt` and `parseFloat`, without leading white
+/// space.
+fn string_arg(args: &[&ConstantValue]) -> String {
+    let str = args
+        .first()
+        .map_or_else(|| "undefined".to_string(), |arg| arg.to_js_string());
+    str.trim_start_matches(is_js_whitespace).to_string()
+}
+
+/// `parseInt(string, radix)`, which parses the longest prefix of integer
+/// digits. `0x` selects the radix 16 when no radix is given.
+pub fn parse_int(args: Vec<JsValue>) -> JsValue {
+    number_function(WellKnownFunctionKind::ParseInt, args, |args| {
+        let str = string_arg(args);
+        let (negative, str) = match str.strip_prefix('-') {
+            Some(str) => (true, str),
+            None => (false, str.strip_prefix('+').unwrap_or(&str)),
+        };
+        let mut radix = args
+            .get(1)
+            .map_or(0, |radix| to_int32(radix.to_js_number()));
+        let mut digits = str;
+        if radix == 0 || radix == 16 {
+            if let Some(hex) = str.strip_prefix("0x").or_else(|| str.strip_prefix("0X")) {
+                digits = hex;
+                radix = 16;
+            }
+        }
+        if radix == 0 {
+            radix = 10;
+        }
+        if !(2..=36).contains(&radix) {
+            return f64::NAN;
+        }
+        let end = digits
+            .find(|c: char| !c.is_digit(radix as u32))
+            .unwrap_or(digits.len());
+        match parse_radix_digits(&digits[..end], radix as u32) {
+            Some(value) if negative => -value,
+            Some(value) => value,
+            None => f64::NAN,
+        }
+    })
+}
+
+/// `parseFloat(string)`, which parses the longest prefix that is a decimal
+/// number.
+pub fn parse_float(args: Vec<JsValue>) -> JsValue {
+    number_function(WellKnownFunctionKind::ParseFloat, args, |args| {
+        let str = string_arg(args);
+        let unsigned = str
+            .strip_prefix(|c: char| c == '+' || c == '-')
+            .unwrap_or(&str);
+        if unsigned.starts_with("Infinity") {
+            return if str.starts_with('-') {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            };
+        }
+        let bytes = unsigned.as_bytes();
+        let digits = |from: usize| {
+            bytes[from..]
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count()
+        };
+        let mut end = digits(0);
+        if bytes.get(end) == Some(&b'.') {
+            let fraction = digits(end + 1);
+            // `1.` and `.5` are numbers, `.` is not
+            if end + fraction > 0 {
+                end += 1 + fraction;
+            }
+        }
+        if end == 0 {
+            return f64::NAN;
+        }
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+            let exponent = digits(end + 1 + sign);
+            if exponent > 0 {
+                end += 1 + sign + exponent;
+            }
+        }
+        let sign = str.len() - unsigned.len();
+        str[..sign + end].parse().unwrap_or(f64::NAN)
+    })
+}
+
+/// `Number(value)`
+pub fn number(args: Vec<JsValue>) -> JsValue {
+    number_function(WellKnownFunctionKind::Number, args, |args| {
+        args.first().map_or(0.0, |arg| arg.to_js_number())
+    })
+}
+
+fn eval_string_method(str: &str, method: &str, args: &[&ConstantValue]) -> Option<String> {
+    Some(match method {
+        "concat" => {
+            let mut result = str.to_string();
+            for arg in args {
+                result.push_str(&arg.to_js_string());
+            }
+            result
+        }
+        "replace" | "replaceAll" => {
+            let [pattern, replacement] = args else {
+                return None;
+            };
+            // Regular expressions and replacement patterns are not supported
+            let (Some(pattern), Some(replacement)) = (pattern.as_str(), replacement.as_str()) else {
+                return None;
+            };
+            if replacement.contains('$') {
+                return None;
+            }
+            if method == "replace" {
+                str.replacen(pattern, replacement, 1)
+            } else if pattern.is_empty() {
+                // Inserts between UTF-16 code units
+                return None;
+            } else {
+                str.replace(pattern, replacement)
+            }
+        }
+        "slice" => {
+            // Indices are in UTF-16 code units
+            let units = str.encode_utf16().collect::<Vec<_>>();
+            let start = relative_index(args.first().copied(), units.len(), 0)?;
+            let end = relative_index(args.get(1).copied(), units.len(), units.len())?;
+            String::from_utf16(&units[start..end.max(start)]).ok()?
+        }
+        "toLowerCase" => str.to_lowercase(),
+        "toUpperCase" => str.to_uppercase(),
+        "trim" => str.trim_matches(is_js_whitespace).to_string(),
+        "trimEnd" => str.trim_end_matches(is_js_whitespace).to_string(),
+        "trimStart" => str.trim_start_matches(is_js_whitespace).to_string(),
+        _ => return None,
+    })
+}
+
+/// Converts an index argument of `slice` to an index into a string of length
+/// `len`. Negative indices count from the end.
+fn relative_index(arg: Option<&ConstantValue>, len: usize, default: usize) -> Option<usize> {
+    let index = match arg {
+        None | Some(ConstantValue::Undefined) => return Some(default),
+        Some(ConstantValue::Num(ConstantNumber(index))) if index.is_nan() => 0.0,
+        Some(ConstantValue::Num(ConstantNumber(index))) => index.trunc(),
+        _ => return None,
+    };
+    Some(if index < 0.0 {
+        (len as f64 + index).max(0.0) as usize
+    } else {
+        index.min(len as f64) as usize
+    })
 }
 
 pub fn path_join(args: Vec<JsValue>) -> JsValue {
@@ -320,6 +613,22 @@ pub fn require(args: Vec<JsValue>) -> JsValue {
     }
 }
 
+/// `new URL(input, base)` with a known `base`, e.g. `import.meta.url`.
+pub fn url_constructor(args: Vec<JsValue>) -> JsValue {
+    if let [input, JsValue::Url(base)] = &args[..] {
+        if let Some(url) = input.as_str().and_then(|input| base.join(input).ok()) {
+            return JsValue::Url(url);
+        }
+    }
+    JsValue::Unknown(
+        Some(Arc::new(JsValue::call(
+            box JsValue::WellKnownFunction(WellKnownFunctionKind::URLConstructor),
+            args,
+        ))),
+        "new URL() is only supported with a constant input and a known base",
+    )
+}
+
 pub fn path_to_file_url(args: Vec<JsValue>) -> JsValue {
     if args.len() == 1 {
         if let Some(path) = args[0].as_str() {
@@ -360,6 +669,9 @@ pub fn well_known_function_member(kind: WellKnownFunctionKind, prop: JsValue) ->
         (WellKnownFunctionKind::Require, Some("resolve")) => {
             JsValue::WellKnownFunction(WellKnownFunctionKind::RequireResolve)
         }
+        (WellKnownFunctionKind::Require, Some("context")) => {
+            JsValue::WellKnownFunction(WellKnownFunctionKind::RequireContext)
+        }
         (WellKnownFunctionKind::Require, Some("cache")) => {
             JsValue::WellKnownObject(WellKnownObjectKind::RequireCache)
         }
@@ -369,6 +681,12 @@ pub fn well_known_function_member(kind: WellKnownFunctionKind, prop: JsValue) ->
         (WellKnownFunctionKind::NodeResolveFrom, Some("silent")) => {
             JsValue::WellKnownFunction(WellKnownFunctionKind::NodeResolveFrom)
         }
+        (WellKnownFunctionKind::Number, Some("parseInt")) => {
+            JsValue::WellKnownFunction(WellKnownFunctionKind::ParseInt)
+        }
+        (WellKnownFunctionKind::Number, Some("parseFloat")) => {
+            JsValue::WellKnownFunction(WellKnownFunctionKind::ParseFloat)
+        }
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::member(
                 box JsValue::WellKnownFunction(kind),
@@ -383,6 +701,7 @@ pub async fn well_known_object_member(
     kind: WellKnownObjectKind,
     prop: JsValue,
     environment: EnvironmentVc,
+    process_env: &ProcessEnvConfig,
 ) -> Result<JsValue> {
     Ok(match kind {
         WellKnownObjectKind::GlobalObject => global_object(prop),
@@ -402,9 +721,12 @@ pub async fn well_known_object_member(
             os_module_member(kind, prop)
         }
         WellKnownObjectKind::NodeProcess => node_process_member(prop, environment).await?,
+        WellKnownObjectKind::NodeProcessEnv => node_process_env_member(prop, process_env),
         WellKnownObjectKind::NodePreGyp => node_pre_gyp(prop),
         WellKnownObjectKind::NodeExpressApp => express(prop),
         WellKnownObjectKind::NodeProtobufLoader => protobuf_loader(prop),
+        WellKnownObjectKind::ImportMeta => import_meta_member(prop),
+        WellKnownObjectKind::ImportMetaEnv => import_meta_env_member(prop, process_env),
         #[allow(unreachable_patterns)]
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::member(
@@ -543,6 +865,7 @@ async fn node_process_member(prop: JsValue, environment: EnvironmentVc) -> Resul
         Some("arch") => environment.compile_target().await?.arch.as_str().into(),
         Some("platform") => environment.compile_target().await?.platform.as_str().into(),
         Some("cwd") => JsValue::WellKnownFunction(WellKnownFunctionKind::ProcessCwd),
+        Some("env") => JsValue::WellKnownObject(WellKnownObjectKind::NodeProcessEnv),
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::member(
                 box JsValue::WellKnownObject(WellKnownObjectKind::NodeProcess),
@@ -553,6 +876,49 @@ async fn node_process_member(prop: JsValue, environment: EnvironmentVc) -> Resul
     })
 }
 
+fn node_process_env_member(prop: JsValue, process_env: &ProcessEnvConfig) -> JsValue {
+    match prop.as_str().and_then(|key| process_env.values.get(key)) {
+        Some(value) => value.clone(),
+        None => JsValue::Unknown(
+            Some(Arc::new(JsValue::member(
+                box JsValue::WellKnownObject(WellKnownObjectKind::NodeProcessEnv),
+                box prop,
+            ))),
+            "process.env variable is not configured",
+        ),
+    }
+}
+
+/// `import.meta.url` depends on the module and is replaced by the caller.
+fn import_meta_member(prop: JsValue) -> JsValue {
+    match prop.as_str() {
+        Some("env") => JsValue::WellKnownObject(WellKnownObjectKind::ImportMetaEnv),
+        _ => JsValue::Unknown(
+            Some(Arc::new(JsValue::member(
+                box JsValue::WellKnownObject(WellKnownObjectKind::ImportMeta),
+                box prop,
+            ))),
+            "unsupported property on import.meta",
+        ),
+    }
+}
+
+fn import_meta_env_member(prop: JsValue, process_env: &ProcessEnvConfig) -> JsValue {
+    match prop
+        .as_str()
+        .and_then(|key| process_env.import_meta_env.get(key))
+    {
+        Some(value) => value.clone(),
+        None => JsValue::Unknown(
+            Some(Arc::new(JsValue::member(
+                box JsValue::WellKnownObject(WellKnownObjectKind::ImportMetaEnv),
+                box prop,
+            ))),
+            "import.meta.env variable is not configured",
+        ),
+    }
+}
+
 fn node_pre_gyp(prop: JsValue) -> JsValue {
     match prop.as_str() {
         Some("find") => JsValue::WellKnownFunction(WellKnownFunctionKind::NodePreGypFind),
@@ -0,0 +1,147 @@
+//! Support for emitting ASCII-only code.
+//!
+//! Non-ASCII characters in string literals and identifiers are escaped by the
+//! emitter when [swc_core::ecma::codegen::Config::ascii_only] is set. Template
+//! literals are emitted from their raw source text, so [AsciiOnlyTemplates]
+//! escapes the raw text of untagged templates before emitting. Spans are kept,
+//! so source maps stay aligned.
+
+use std::borrow::Cow;
+
+use swc_core::ecma::{
+    ast::{TaggedTpl, TplElement},
+    visit::{VisitMut, VisitMutWith},
+};
+
+/// Escapes all non-ASCII characters in the raw text of a template element.
+/// Code points of the basic multilingual plane are escaped as `\uXXXX`,
+/// astral-plane code points as `\u{XXXXX}`, which keeps the cooked value of
+/// the template identical.
+pub fn escape_template_raw(raw: &str) -> Cow<'_, str> {
+    if raw.is_ascii() {
+        return Cow::Borrowed(raw);
+    }
+    let mut result = String::with_capacity(raw.len() + 8);
+    let mut escaped = false;
+    for c in raw.chars() {
+        if c.is_ascii() {
+            result.push(c);
+            escaped = c == '\\' && !escaped;
+            continue;
+        }
+        // A backslash before the character is an identity escape, so it's
+        // reused for the unicode escape. A backslash before a line separator
+        // is a line continuation, which can't be expressed with an escape.
+        if escaped && matches!(c, '\u{2028}' | '\u{2029}') {
+            result.push(c);
+        } else {
+            if !escaped {
+                result.push('\\');
+            }
+            let code_point = c as u32;
+            if code_point > 0xffff {
+                result.push_str(&format!("u{{{code_point:X}}}"));
+            } else {
+                result.push_str(&format!("u{code_point:04X}"));
+            }
+        }
+        escaped = false;
+    }
+    Cow::Owned(result)
+}
+
+/// Escapes non-ASCII characters in untagged template literals. Tagged
+/// templates are skipped, since the tag function can observe the raw text.
+pub struct AsciiOnlyTemplates;
+
+impl VisitMut for AsciiOnlyTemplates {
+    fn visit_mut_tagged_tpl(&mut self, tagged: &mut TaggedTpl) {
+        tagged.tag.visit_mut_with(self);
+        for expr in tagged.tpl.exprs.iter_mut() {
+            expr.visit_mut_with(self);
+        }
+    }
+
+    fn visit_mut_tpl_element(&mut self, element: &mut TplElement) {
+        if let Cow::Owned(raw) = escape_template_raw(&element.raw) {
+            element.raw = raw.into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_core::{
+        common::{errors::HANDLER, FileName, SourceMap},
+        ecma::{
+            ast::EsVersion,
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::{escape_template_raw, AsciiOnlyTemplates};
+
+    fn emit_ascii_only(cm: &Arc<SourceMap>, src: &str) -> String {
+        let fm = cm.new_source_file(FileName::Anon, src.into());
+        let mut module = parse_file_as_module(
+            &fm,
+            Default::default(),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+        .unwrap();
+        module.visit_mut_with(&mut AsciiOnlyTemplates);
+
+        let mut bytes = Vec::new();
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                ascii_only: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+        };
+        emitter.emit_module(&module).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn string_with_emoji() {
+        run_test(false, |cm, _handler| {
+            let output = emit_ascii_only(&cm, "console.log(\"caf\u{e9} \u{1f600}\");");
+            assert!(output.is_ascii(), "{output}");
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn template_with_emoji() {
+        run_test(false, |cm, _handler| {
+            let output = emit_ascii_only(&cm, "const a = `\u{1f600} ${b} caf\u{e9}`;");
+            assert!(output.is_ascii(), "{output}");
+            assert!(output.contains("`\\u{1F600} ${b} caf\\u00E9`"), "{output}");
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn escape_raw() {
+        assert_eq!(escape_template_raw("abc"), "abc");
+        assert_eq!(escape_template_raw("\u{e9}"), "\\u00E9");
+        assert_eq!(escape_template_raw("\u{1f600}"), "\\u{1F600}");
+        // identity escape
+        assert_eq!(escape_template_raw("\\\u{e9}"), "\\u00E9");
+        // escaped backslash
+        assert_eq!(escape_template_raw("\\\\\u{e9}"), "\\\\\\u00E9");
+    }
+}
@@ -2,13 +2,16 @@ use std::io::Write as _;
 
 use anyhow::{anyhow, bail, Result};
 use indexmap::IndexSet;
-use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringVc},
+    ValueToString, ValueToStringVc,
+};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     chunk::{
         ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkReferenceVc, ChunkVc, ChunkableAsset,
-        ChunkableAssetVc, ChunkingContextVc, ChunksVc,
+        ChunkableAssetVc, ChunkingContextVc, ChunksVc, ModuleId,
     },
     reference::AssetReferencesVc,
 };
@@ -77,8 +80,6 @@ impl EcmascriptChunkItem for ManifestLoaderItem {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
-        let mut code = Vec::new();
-
         let manifest = self.manifest.await?;
         let asset = manifest.asset.as_asset();
         let chunk = self.manifest.as_chunk(self.context);
@@ -110,29 +111,45 @@ impl EcmascriptChunkItem for ManifestLoaderItem {
             .await?
             .ok_or_else(|| anyhow!("asset is not placeable in ecmascript chunk"))?;
         let dynamic_id = &*placeable.as_chunk_item(self.context).id().await?;
-
-        // TODO: a dedent macro with expression interpolation would be awesome.
-        write!(
-            code,
-            "
-__turbopack_export_value__((__turbopack_import__) => {{
-    return __turbopack_load__({chunk_server_path}).then(() => {{
-        return __turbopack_require__({item_id});
-    }}).then(() => __turbopack_import__({dynamic_id}));
-}});",
-            chunk_server_path = stringify_str(chunk_server_path),
-            item_id = stringify_module_id(item_id),
-            dynamic_id = stringify_module_id(dynamic_id),
-        )?;
+        // Like a static import, a CommonJS module needs a namespace with its
+        // `module.exports` as default export.
+        let is_cjs = matches!(
+            &*placeable.get_exports().await?,
+            EcmascriptExports::CommonJs(_)
+        );
 
         Ok(EcmascriptChunkItemContent {
-            inner_code: code.into(),
+            inner_code: loader_code(chunk_server_path, item_id, dynamic_id, is_cjs).into(),
             ..Default::default()
         }
         .into())
     }
 }
 
+/// The code of the manifest loader item, which loads the chunk at
+/// `chunk_server_path`, waits for the manifest chunk item `item_id` to load its
+/// chunks and finally imports the module `dynamic_id`.
+pub(crate) fn loader_code(
+    chunk_server_path: &str,
+    item_id: &ModuleId,
+    dynamic_id: &ModuleId,
+    is_cjs: bool,
+) -> String {
+    // TODO: a dedent macro with expression interpolation would be awesome.
+    format!(
+        "
+__turbopack_export_value__((__turbopack_import__) => {{
+    return __turbopack_load__({chunk_server_path}).then(() => {{
+        return __turbopack_require__({item_id});
+    }}).then(() => __turbopack_import__({dynamic_id}{is_cjs}));
+}});",
+        chunk_server_path = stringify_str(chunk_server_path),
+        item_id = stringify_module_id(item_id),
+        dynamic_id = stringify_module_id(dynamic_id),
+        is_cjs = if is_cjs { ", true" } else { "" },
+    )
+}
+
 /// The manifest chunk is deferred until requested by the manifest loader
 /// item when the dynamic `import()` expression is reached. Its responsibility
 /// is to generate a Promise that will resolve only after all the necessary
@@ -147,22 +164,35 @@ __turbopack_export_value__((__turbopack_import__) => {{
 pub struct ManifestChunkAsset {
     pub asset: ChunkableAssetVc,
     pub chunking_context: ChunkingContextVc,
+    /// The name of the chunk that contains the asset
+    pub chunk_name: OptionStringVc,
 }
 
 #[turbo_tasks::value_impl]
 impl ManifestChunkAssetVc {
     #[turbo_tasks::function]
-    pub fn new(asset: ChunkableAssetVc, chunking_context: ChunkingContextVc) -> Self {
+    pub fn new(
+        asset: ChunkableAssetVc,
+        chunking_context: ChunkingContextVc,
+        chunk_name: OptionStringVc,
+    ) -> Self {
         Self::cell(ManifestChunkAsset {
             asset,
             chunking_context,
+            chunk_name,
         })
     }
 
     #[turbo_tasks::function]
     async fn chunks(self) -> Result<ChunksVc> {
         let this = self.await?;
-        let chunk_group = ChunkGroupVc::from_asset(this.asset, this.chunking_context);
+        let placeable = EcmascriptChunkPlaceableVc::resolve_from(this.asset).await?;
+        let chunk_group = match (&*this.chunk_name.await?, placeable) {
+            (Some(name), Some(placeable)) => ChunkGroupVc::from_chunk(
+                EcmascriptChunkVc::new_named(this.chunking_context, placeable, name).into(),
+            ),
+            _ => ChunkGroupVc::from_asset(this.asset, this.chunking_context),
+        };
         Ok(chunk_group.chunks())
     }
 }
@@ -170,8 +200,11 @@ impl ManifestChunkAssetVc {
 #[turbo_tasks::value_impl]
 impl Asset for ManifestChunkAsset {
     #[turbo_tasks::function]
-    fn path(&self) -> FileSystemPathVc {
-        self.asset.path().join("manifest-chunk.js")
+    async fn path(&self) -> Result<FileSystemPathVc> {
+        Ok(match &*self.chunk_name.await? {
+            Some(name) => self.asset.path().join(&format!("manifest-chunk-{name}.js")),
+            None => self.asset.path().join("manifest-chunk.js"),
+        })
     }
 
     #[turbo_tasks::function]
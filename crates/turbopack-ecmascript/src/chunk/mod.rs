@@ -1,5 +1,7 @@
 pub mod loader;
 pub(crate) mod optimize;
+pub mod output_format;
+pub mod scope_hoisting;
 pub mod source_map;
 
 use std::{fmt::Write, io::Write as _, slice::Iter};
@@ -8,22 +10,23 @@ use anyhow::{anyhow, bail, Result};
 use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{
-    primitives::{JsonValueVc, StringReadRef, StringVc, StringsVc, UsizeVc},
+    primitives::{JsonValueVc, OptionStringVc, StringReadRef, StringVc, StringsVc, UsizeVc},
     trace::TraceRawVcs,
-    TryJoinIterExt, ValueToString, ValueToStringVc,
+    TryJoinIterExt, Value, ValueToString, ValueToStringVc,
 };
 use turbo_tasks_fs::{
     embed_file, rope::Rope, File, FileContent, FileSystemPathOptionVc, FileSystemPathVc,
 };
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64, Xxh3Hash64Hasher};
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
     chunk::{
-        chunk_content, chunk_content_split,
+        chunk_content, chunk_content_split, content_chunk_id,
         optimize::{ChunkOptimizerVc, OptimizableChunk, OptimizableChunkVc},
         Chunk, ChunkContentResult, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem, ChunkItemVc,
-        ChunkReferenceVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc,
-        FromChunkableAsset, ModuleId, ModuleIdReadRef, ModuleIdVc, ModuleIdsVc,
+        ChunkLoadingHint, ChunkLoadingHintOptionVc, ChunkReferenceVc, ChunkVc, ChunkableAsset,
+        ChunkableAssetVc, ChunkingContextVc, FromChunkableAsset, ModuleId, ModuleIdReadRef,
+        ModuleIdVc, ModuleIdsVc,
     },
     code_builder::{Code, CodeBuilder, CodeReadRef, CodeVc},
     introspect::{
@@ -41,12 +44,16 @@ use turbopack_core::{
 use self::{
     loader::{ManifestChunkAssetVc, ManifestLoaderItemVc},
     optimize::EcmascriptChunkOptimizerVc,
+    output_format::{OutputFormat, EXPORTS_VAR},
+    scope_hoisting::hoist_chunk_content,
     source_map::EcmascriptChunkSourceMapAssetReferenceVc,
 };
 use crate::{
     parse::ParseResultSourceMapVc,
-    references::esm::EsmExportsVc,
+    references::{cjs_exports::CjsExportsVc, esm::EsmExportsVc},
+    tree_shake::shake_chunk_content,
     utils::{stringify_module_id, stringify_str, FormatIter},
+    ModuleChunkItemVc,
 };
 
 #[turbo_tasks::value]
@@ -55,6 +62,8 @@ pub struct EcmascriptChunk {
     main_entries: EcmascriptChunkPlaceablesVc,
     omit_entries: Option<EcmascriptChunkPlaceablesVc>,
     evaluate: Option<EcmascriptChunkEvaluateVc>,
+    /// An explicit name for the chunk, e.g. from a `webpackChunkName` comment
+    name: Option<String>,
 }
 
 #[turbo_tasks::value_impl]
@@ -71,6 +80,24 @@ impl EcmascriptChunkVc {
             main_entries,
             omit_entries,
             evaluate,
+            name: None,
+        }
+        .cell()
+    }
+
+    /// Creates a chunk that is placed at a path derived from `name`.
+    #[turbo_tasks::function]
+    pub fn new_named(
+        context: ChunkingContextVc,
+        main_entry: EcmascriptChunkPlaceableVc,
+        name: &str,
+    ) -> Self {
+        EcmascriptChunk {
+            context,
+            main_entries: EcmascriptChunkPlaceablesVc::cell(vec![main_entry]),
+            omit_entries: None,
+            evaluate: None,
+            name: Some(name.to_string()),
         }
         .cell()
     }
@@ -86,10 +113,27 @@ impl EcmascriptChunkVc {
     }
 
     #[turbo_tasks::function]
-    pub async fn new_evaluate(
+    pub fn new_evaluate(
+        context: ChunkingContextVc,
+        main_entry: EcmascriptChunkPlaceableVc,
+        runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+    ) -> Self {
+        Self::new_evaluate_with_format(
+            context,
+            main_entry,
+            runtime_entries,
+            Value::new(OutputFormat::Runtime),
+        )
+    }
+
+    /// Creates an evaluated chunk in the `output_format`, e.g. a standalone
+    /// IIFE or UMD bundle that provides the exports of `main_entry`.
+    #[turbo_tasks::function]
+    pub async fn new_evaluate_with_format(
         context: ChunkingContextVc,
         main_entry: EcmascriptChunkPlaceableVc,
         runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+        output_format: Value<OutputFormat>,
     ) -> Result<Self> {
         let mut entries = Vec::new();
         if let Some(runtime_entries) = runtime_entries {
@@ -105,12 +149,53 @@ impl EcmascriptChunkVc {
                 EcmascriptChunkEvaluate {
                     evaluate_entries: entries,
                     chunk_group: None,
+                    output_format: output_format.into_value(),
                 }
                 .cell(),
             ),
         ))
     }
 
+    /// A deterministic id of the chunk that is derived from its entries and
+    /// the sources of its modules, see [content_chunk_id].
+    ///
+    /// The generated code isn't hashed, since it contains the paths of other
+    /// chunks, e.g. for dynamic imports, and chunks that load each other would
+    /// make their paths depend on each other.
+    #[turbo_tasks::function]
+    pub async fn content_id(self) -> Result<StringVc> {
+        let this = self.await?;
+        let entries = this
+            .main_entries
+            .await?
+            .iter()
+            .map(|entry| entry.as_chunk_item(this.context).id())
+            .try_join()
+            .await?;
+        let content =
+            ecmascript_chunk_content(this.context, this.main_entries, this.omit_entries).await?;
+        let modules = content
+            .chunk_items
+            .to_set()
+            .await?
+            .iter()
+            .map(|chunk_item| async move {
+                Ok((
+                    chunk_item.id().await?,
+                    chunk_item_source_hash(*chunk_item).await?,
+                ))
+            })
+            .try_join()
+            .await?;
+        Ok(StringVc::cell(content_chunk_id(
+            &entries.iter().map(|id| &**id).collect::<Vec<_>>(),
+            &modules
+                .iter()
+                .map(|(id, hash)| (&**id, *hash))
+                .collect::<Vec<_>>(),
+        )))
+    }
+
     /// Return the most specific directory which contains all elements of the
     /// chunk.
     #[turbo_tasks::function]
@@ -189,6 +274,8 @@ pub struct EcmascriptChunkEvaluate {
     /// All chunks of this chunk group need to be ready for execution to start.
     /// When None, it will use a chunk group created from the current chunk.
     chunk_group: Option<ChunkGroupVc>,
+    /// The exports of the last entry are provided by standalone formats.
+    output_format: OutputFormat,
 }
 
 #[turbo_tasks::value_impl]
@@ -199,10 +286,11 @@ impl EcmascriptChunkEvaluateVc {
         context: ChunkingContextVc,
         origin_chunk: EcmascriptChunkVc,
     ) -> Result<EcmascriptChunkContentEvaluateVc> {
-        let &EcmascriptChunkEvaluate {
+        let EcmascriptChunkEvaluate {
             evaluate_entries,
             chunk_group,
-        } = &*self.await?;
+            ref output_format,
+        } = *self.await?;
         let chunk_group =
             chunk_group.unwrap_or_else(|| ChunkGroupVc::from_chunk(origin_chunk.into()));
         let evaluate_chunks = chunk_group.chunks().await?;
@@ -218,6 +306,14 @@ impl EcmascriptChunkEvaluateVc {
                 }
             }
         }
+        if output_format.is_standalone() && !chunks_server_paths.is_empty() {
+            bail!(
+                "the chunk {} is standalone, but its chunk group needs the chunks {}, which it \
+                 can't load",
+                origin_chunk.path().to_string().await?,
+                chunks_server_paths.join(", ")
+            );
+        }
         let entry_modules_ids = evaluate_entries
             .await?
             .iter()
@@ -226,6 +322,7 @@ impl EcmascriptChunkEvaluateVc {
         Ok(EcmascriptChunkContentEvaluate {
             chunks_server_paths: StringsVc::cell(chunks_server_paths),
             entry_modules_ids: ModuleIdsVc::cell(entry_modules_ids),
+            output_format: output_format.clone(),
         }
         .cell())
     }
@@ -236,6 +333,7 @@ pub struct EcmascriptChunkContentResult {
     pub chunk_items: EcmascriptChunkItemsVc,
     pub chunks: Vec<ChunkVc>,
     pub async_chunk_groups: Vec<ChunkGroupVc>,
+    pub loading_hints: Vec<(ChunkGroupVc, ChunkLoadingHint)>,
     pub external_asset_references: Vec<AssetReferenceVc>,
 }
 
@@ -254,6 +352,7 @@ impl From<ChunkContentResult<EcmascriptChunkItemVc>> for EcmascriptChunkContentR
                 .cell(),
             chunks: from.chunks,
             async_chunk_groups: from.async_chunk_groups,
+            loading_hints: from.loading_hints,
             external_asset_references: from.external_asset_references,
         }
     }
@@ -265,9 +364,14 @@ fn ecmascript_chunk_content(
     main_entries: EcmascriptChunkPlaceablesVc,
     omit_entries: Option<EcmascriptChunkPlaceablesVc>,
 ) -> EcmascriptChunkContentResultVc {
-    let mut chunk_content = ecmascript_chunk_content_internal(context, main_entries);
+    let content = |entries| {
+        let content = ecmascript_chunk_content_internal(context, entries);
+        let content = shake_chunk_content(context, entries, content);
+        hoist_chunk_content(context, entries, content)
+    };
+    let mut chunk_content = content(main_entries);
     if let Some(omit_entries) = omit_entries {
-        let omit_chunk_content = ecmascript_chunk_content_internal(context, omit_entries);
+        let omit_chunk_content = content(omit_entries);
         chunk_content = chunk_content.filter(omit_chunk_content);
     }
     chunk_content
@@ -292,6 +396,7 @@ async fn ecmascript_chunk_content_internal(
     let mut all_chunk_items = IndexSet::<EcmascriptChunkItemVc>::new();
     let mut all_chunks = IndexSet::<ChunkVc>::new();
     let mut all_async_chunk_groups = IndexSet::<ChunkGroupVc>::new();
+    let mut all_loading_hints = IndexMap::<ChunkGroupVc, ChunkLoadingHint>::new();
     let mut all_external_asset_references = IndexSet::<AssetReferenceVc>::new();
 
     for content in contents {
@@ -299,6 +404,7 @@ async fn ecmascript_chunk_content_internal(
             chunk_items,
             chunks,
             async_chunk_groups,
+            loading_hints,
             external_asset_references,
         } = &*content.await?;
         for chunk in chunk_items.await?.iter() {
@@ -306,6 +412,7 @@ async fn ecmascript_chunk_content_internal(
         }
         all_chunks.extend(chunks.iter().copied());
         all_async_chunk_groups.extend(async_chunk_groups.iter().copied());
+        all_loading_hints.extend(loading_hints.iter().copied());
         all_external_asset_references.extend(external_asset_references.iter().copied());
     }
 
@@ -315,6 +422,7 @@ async fn ecmascript_chunk_content_internal(
         chunk_items: EcmascriptChunkItemsVc::cell(chunk_items),
         chunks: all_chunks.into_iter().collect(),
         async_chunk_groups: all_async_chunk_groups.into_iter().collect(),
+        loading_hints: all_loading_hints.into_iter().collect(),
         external_asset_references: all_external_asset_references.into_iter().collect(),
     }
     .cell())
@@ -500,6 +608,21 @@ impl EcmascriptChunkContentEntryVc {
     }
 }
 
+/// Hashes the source of the module of a chunk item. Chunk items without a
+/// module, e.g. loaders of other chunks, are only identified by their id.
+async fn chunk_item_source_hash(chunk_item: EcmascriptChunkItemVc) -> Result<u64> {
+    let Some(module_chunk_item) = ModuleChunkItemVc::resolve_from(chunk_item).await? else {
+        return Ok(0);
+    };
+    let module = module_chunk_item.await?.module.await?;
+    if let AssetContent::File(file) = &*module.source.content().await? {
+        if let FileContent::Content(file) = &*file.await? {
+            return Ok(hash_xxh3_hash64(file.content()));
+        }
+    }
+    Ok(0)
+}
+
 #[turbo_tasks::function]
 async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc> {
     let content = content.await?;
@@ -522,10 +645,19 @@ async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc>
     if content.options.exports {
         args.push("e: exports");
     }
+    if content.options.async_module {
+        args.push("a: __turbopack_async_module__");
+    }
     let mut code = CodeBuilder::default();
     let args = FormatIter(|| args.iter().copied().intersperse(", "));
     if content.options.this {
         write!(code, "(function({{ {} }}) {{ !function() {{\n\n", args,)?;
+    } else if content.options.async_module {
+        write!(
+            code,
+            "(({{ {} }}) => (__turbopack_async_module__(async () => {{\n\n",
+            args,
+        )?;
     } else {
         write!(code, "(({{ {} }}) => (() => {{\n\n", args,)?;
     }
@@ -534,6 +666,8 @@ async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc>
     code.push_source(&content.inner_code, source_map);
     if content.options.this {
         code += "\n}.call(this) })";
+    } else if content.options.async_module {
+        code += "\n})))";
     } else {
         code += "\n})())";
     }
@@ -578,8 +712,22 @@ impl EcmascriptChunkContentVc {
                 this.output_root.to_string().await?
             );
         };
+        let evaluate = match this.evaluate {
+            Some(evaluate) => Some(evaluate.await?),
+            None => None,
+        };
+        let output_format = evaluate
+            .as_ref()
+            .map_or(&OutputFormat::Runtime, |evaluate| &evaluate.output_format);
+
+        let chunks_server_paths = match &evaluate {
+            Some(evaluate) => evaluate.chunks_server_paths.await?.clone_value(),
+            None => Vec::new(),
+        };
+
         let mut code = CodeBuilder::default();
-        code += "(self.TURBOPACK = self.TURBOPACK || []).push([";
+        write!(code, "{}", output_format.header(&chunks_server_paths))?;
+        write!(code, "{}", output_format.registration_start())?;
 
         writeln!(code, "{}, {{", stringify_str(chunk_server_path))?;
         for entry in &this.module_factories {
@@ -589,11 +737,8 @@ impl EcmascriptChunkContentVc {
         }
         code += "\n}";
 
-        if let Some(evaluate) = &this.evaluate {
-            let evaluate = evaluate.await?;
-            let condition = evaluate
-                .chunks_server_paths
-                .await?
+        if let Some(evaluate) = &evaluate {
+            let condition = chunks_server_paths
                 .iter()
                 .map(|path| format!(" && loadedChunks.has({})", stringify_str(path)))
                 .collect::<Vec<_>>()
@@ -601,10 +746,19 @@ impl EcmascriptChunkContentVc {
             let entries_ids = &*evaluate.entry_modules_ids.await?;
             let entries_instantiations = entries_ids
                 .iter()
-                .map(|id| async move {
+                .enumerate()
+                .map(|(i, id)| async move {
                     let id = id.await?;
                     let id = stringify_module_id(&id);
-                    Ok(format!(r#"instantiateRuntimeModule({id});"#)) as Result<_>
+                    // Standalone and ESM formats provide the exports of the main entry,
+                    // which is the last one
+                    Ok(
+                        if output_format.provides_exports() && i == entries_ids.len() - 1 {
+                            format!(r#"{EXPORTS_VAR} = instantiateRuntimeModule({id}).exports;"#)
+                        } else {
+                            format!(r#"instantiateRuntimeModule({id});"#)
+                        },
+                    ) as Result<_>
                 })
                 .try_join()
                 .await?
@@ -623,14 +777,15 @@ impl EcmascriptChunkContentVc {
 }}"
             )?;
         }
-        code += "]);\n";
-        if this.evaluate.is_some() {
+        write!(code, "{}", output_format.registration_end())?;
+        if evaluate.is_some() {
             let runtime_code = embed_file!("js/src/runtime.js").await?;
             match &*runtime_code {
                 FileContent::NotFound => return Err(anyhow!("runtime code is not found")),
                 FileContent::Content(file) => code.push_source(file.content(), None),
             };
         }
+        write!(code, "{}", output_format.footer())?;
 
         if code.has_source_map() {
             let filename = chunk_path.file_name();
@@ -875,6 +1030,21 @@ impl EcmascriptChunkVc {
         ))
     }
 
+    /// The estimated size of the chunk in bytes, which is the size of the
+    /// code of all its chunk items.
+    #[turbo_tasks::function]
+    async fn estimated_size(self) -> Result<UsizeVc> {
+        let entries = self
+            .chunk_content_result()
+            .await?
+            .chunk_items
+            .to_entry_snapshot()
+            .await?;
+        Ok(UsizeVc::cell(
+            entries.iter().map(|entry| entry.source_code().len()).sum(),
+        ))
+    }
+
     #[turbo_tasks::function]
     async fn chunk_content(self) -> Result<EcmascriptChunkContentVc> {
         let this = self.await?;
@@ -906,6 +1076,14 @@ impl Asset for EcmascriptChunk {
         let mut hasher = Xxh3Hash64Hasher::new();
         let mut need_hash = false;
 
+        // With content hashing the path changes whenever the sources of the
+        // modules change, and only then. Without it the path stays the same
+        // across edits, which HMR relies on.
+        if *this.context.is_content_hashing_enabled().await? {
+            hasher.write_ref(&*self_vc.content_id().await?);
+            need_hash = true;
+        }
+
         // evalute only contributes to the hashed info
         if let Some(evaluate) = this.evaluate {
             let evaluate = evaluate.content(this.context, self_vc).await?;
@@ -919,6 +1097,18 @@ impl Asset for EcmascriptChunk {
             }
         }
         let main_entries = this.main_entries.await?;
+        // Named chunks use the name as named info. The entries are hashed, so
+        // different chunks with the same name don't collide.
+        if let Some(name) = &this.name {
+            for entry in &main_entries {
+                hasher.write_value(entry.path().to_string().await?);
+            }
+            let hash = encode_hex(hasher.finish());
+            let truncated_hash = &hash[..6];
+            return Ok(this
+                .context
+                .named_chunk_path(&format!("{name}_{truncated_hash}"), ".js"));
+        }
         // If there is only a single entry we can used that for the named info.
         // If there are multiple entries we hash them and use the common parent as named
         // info.
@@ -970,7 +1160,18 @@ impl Asset for EcmascriptChunk {
             references.push(ChunkReferenceVc::new_parallel(*chunk).into());
         }
         for chunk_group in content.async_chunk_groups.iter() {
-            references.push(ChunkGroupReferenceVc::new(*chunk_group).into());
+            let loading_hint = content
+                .loading_hints
+                .iter()
+                .find(|(group, _)| group == chunk_group)
+                .map(|(_, hint)| *hint);
+            references.push(
+                ChunkGroupReferenceVc::new_with_loading_hint(
+                    *chunk_group,
+                    ChunkLoadingHintOptionVc::cell(loading_hint),
+                )
+                .into(),
+            );
         }
 
         references.push(
@@ -1055,6 +1256,7 @@ impl GenerateSourceMap for EcmascriptChunk {
 struct EcmascriptChunkContentEvaluate {
     chunks_server_paths: StringsVc,
     entry_modules_ids: ModuleIdsVc,
+    output_format: OutputFormat,
 }
 
 #[turbo_tasks::value]
@@ -1088,7 +1290,7 @@ impl EcmascriptChunkContextVc {
 #[turbo_tasks::value(shared)]
 pub enum EcmascriptExports {
     EsmExports(EsmExportsVc),
-    CommonJs,
+    CommonJs(CjsExportsVc),
     Value,
     None,
 }
@@ -1124,6 +1326,9 @@ pub struct EcmascriptChunkItemOptions {
     pub module: bool,
     pub exports: bool,
     pub this: bool,
+    /// The module uses top-level await or imports an async module. Its code is
+    /// run in an async function that is passed to `__turbopack_async_module__`.
+    pub async_module: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -1148,8 +1353,9 @@ impl FromChunkableAsset for EcmascriptChunkItemVc {
     async fn from_async_asset(
         context: ChunkingContextVc,
         asset: ChunkableAssetVc,
+        chunk_name: OptionStringVc,
     ) -> Result<Option<(Self, ChunkableAssetVc)>> {
-        let chunk = ManifestChunkAssetVc::new(asset, context);
+        let chunk = ManifestChunkAssetVc::new(asset, context, chunk_name);
         Ok(Some((
             ManifestLoaderItemVc::new(context, chunk).into(),
             chunk.into(),
@@ -1,4 +1,4 @@
-use std::{cmp::Ordering, collections::HashSet};
+use std::{cmp::Ordering, collections::HashSet, ops::Range};
 
 use anyhow::{bail, Result};
 use indexmap::IndexSet;
@@ -27,8 +27,9 @@ impl EcmascriptChunkOptimizerVc {
 impl ChunkOptimizer for EcmascriptChunkOptimizer {
     #[turbo_tasks::function]
     async fn optimize(&self, chunks: ChunksVc, chunk_group: ChunkGroupVc) -> Result<ChunksVc> {
+        let context = self.0;
         optimize_by_common_parent(chunks, get_common_parent, |local, children| {
-            optimize_ecmascript(local, children, chunk_group)
+            optimize_ecmascript(local, children, chunk_group, context)
         })
         .await
     }
@@ -269,38 +270,70 @@ async fn merge_duplicated_and_contained(
     Ok(())
 }
 
-async fn merge_by_size(chunks: &[EcmascriptChunkVc]) -> Result<Vec<EcmascriptChunkVc>> {
-    let mut merged = Vec::new();
-    let mut current = Vec::new();
-    let mut current_items = 0;
-    for &chunk in chunks {
-        let chunk_items = *chunk.chunk_items_count().await?;
-        if chunk_items >= MAX_CHUNK_ITEMS_PER_CHUNK {
-            // chunk is too big, keep it separate
-            merged.push(chunk);
-        } else if current_items + chunk_items < MAX_CHUNK_ITEMS_PER_CHUNK {
-            // fits in this chunk
-            current.push(chunk);
-            current_items += chunk_items;
+/// The size of a chunk that is considered when merging chunks.
+#[derive(Debug, Default, Clone, Copy)]
+struct ChunkSize {
+    chunk_items: usize,
+    bytes: usize,
+}
+
+/// Groups consecutive chunks so that a group has less than
+/// MAX_CHUNK_ITEMS_PER_CHUNK chunk items and doesn't exceed `budget` bytes.
+/// Chunks keep the order in which they are referenced, so chunks that are
+/// imported together end up in the same group. A chunk that exceeds the
+/// limits on its own gets a group of its own.
+fn group_by_size(sizes: &[ChunkSize], budget: Option<usize>) -> Vec<Range<usize>> {
+    let fits = |size: ChunkSize| {
+        size.chunk_items < MAX_CHUNK_ITEMS_PER_CHUNK
+            && budget.map_or(true, |budget| size.bytes <= budget)
+    };
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut current = ChunkSize::default();
+    for (i, &size) in sizes.iter().enumerate() {
+        let merged = ChunkSize {
+            chunk_items: current.chunk_items + size.chunk_items,
+            bytes: current.bytes + size.bytes,
+        };
+        if i > start && !fits(merged) {
+            groups.push(start..i);
+            start = i;
+            current = size;
         } else {
-            // doesn't fit in this chunk, merge current and start a new one
-            if !current.is_empty() {
-                if current.len() == 1 {
-                    merged.push(current.pop().unwrap());
-                } else {
-                    merged.push(merge_chunks(*current.first().unwrap(), &current).await?);
-                    current.clear();
-                }
-            }
-            current.push(chunk);
-            current_items = chunk_items;
+            current = merged;
         }
     }
-    if !current.is_empty() {
-        if current.len() == 1 {
-            merged.push(current.pop().unwrap());
+    if start < sizes.len() {
+        groups.push(start..sizes.len());
+    }
+    groups
+}
+
+async fn merge_by_size(
+    chunks: &[EcmascriptChunkVc],
+    budget: Option<usize>,
+) -> Result<Vec<EcmascriptChunkVc>> {
+    let sizes = chunks
+        .iter()
+        .map(|&chunk| async move {
+            Ok(ChunkSize {
+                chunk_items: *chunk.chunk_items_count().await?,
+                bytes: if budget.is_some() {
+                    *chunk.estimated_size().await?
+                } else {
+                    0
+                },
+            })
+        })
+        .try_join()
+        .await?;
+    let mut merged = Vec::new();
+    for group in group_by_size(&sizes, budget) {
+        let group = &chunks[group];
+        if let [chunk] = group {
+            merged.push(*chunk);
         } else {
-            merged.push(merge_chunks(*current.first().unwrap(), &current).await?);
+            merged.push(merge_chunks(group[0], group).await?);
         }
     }
     Ok(merged)
@@ -311,7 +344,9 @@ async fn optimize_ecmascript(
     local: Option<ChunksVc>,
     children: Option<ChunksVc>,
     chunk_group: ChunkGroupVc,
+    context: ChunkingContextVc,
 ) -> Result<ChunksVc> {
+    let budget = *context.chunk_size_budget().await?;
     let mut chunks = Vec::new();
     // TODO optimize
     let mut unoptimized_count = 0;
@@ -321,7 +356,7 @@ async fn optimize_ecmascript(
         let mut local = local.await?.iter().copied().map(ecma).try_join().await?;
         // Merge all local chunks when they are too many
         if local.len() > LOCAL_CHUNK_MERGE_THRESHOLD {
-            local = merge_by_size(&local).await?;
+            local = merge_by_size(&local, budget).await?;
         }
         for chunk in local.iter_mut() {
             let content = (*chunk).await?;
@@ -335,6 +370,7 @@ async fn optimize_ecmascript(
                         EcmascriptChunkEvaluate {
                             evaluate_entries: evaluate.evaluate_entries,
                             chunk_group: Some(chunk_group),
+                            output_format: evaluate.output_format.clone(),
                         }
                         .cell(),
                     ),
@@ -367,9 +403,50 @@ async fn optimize_ecmascript(
     // When there are too many chunks, try hard to reduce the number of chunks to
     // limit the request count.
     if chunks.len() > TOTAL_CHUNK_MERGE_THRESHOLD {
-        chunks = merge_by_size(&chunks).await?;
+        chunks = merge_by_size(&chunks, budget).await?;
     }
     Ok(ChunksVc::cell(
         chunks.into_iter().map(|c| c.as_chunk()).collect(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{group_by_size, ChunkSize};
+
+    fn chunks(bytes: &[usize]) -> Vec<ChunkSize> {
+        bytes
+            .iter()
+            .map(|&bytes| ChunkSize {
+                chunk_items: 1,
+                bytes,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_by_budget() {
+        let sizes = chunks(&[40, 40, 40, 40, 40]);
+        assert_eq!(group_by_size(&sizes, None), vec![0..5]);
+        assert_eq!(group_by_size(&sizes, Some(100)), vec![0..2, 2..4, 4..5]);
+        assert_eq!(group_by_size(&sizes, Some(10)).len(), 5);
+    }
+
+    #[test]
+    fn large_chunk_is_kept_separate() {
+        let sizes = chunks(&[30, 30, 150, 30]);
+        assert_eq!(group_by_size(&sizes, Some(100)), vec![0..2, 2..3, 3..4]);
+    }
+
+    #[test]
+    fn split_by_chunk_items() {
+        let sizes = vec![
+            ChunkSize {
+                chunk_items: 2000,
+                bytes: 0,
+            };
+            3
+        ];
+        assert_eq!(group_by_size(&sizes, None), vec![0..1, 1..2, 2..3]);
+    }
+}
@@ -0,0 +1,483 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use turbo_tasks::trace::TraceRawVcs;
+
+use crate::utils::stringify_str;
+
+/// The variable that the exports of the main entry of a standalone chunk are
+/// assigned to.
+pub(crate) const EXPORTS_VAR: &str = "__turbopack_exports__";
+
+/// The variable that holds the registration of a standalone chunk until its
+/// runtime picks it up.
+const CHUNK_VAR: &str = "__turbopack_chunk__";
+
+/// The format of an evaluated chunk.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Hash, Debug, Clone)]
+pub enum OutputFormat {
+    /// The chunk registers itself with the turbopack runtime, which is shared
+    /// with the other chunks of the page.
+    Runtime,
+    /// A standalone script, which evaluates the chunk with a runtime of its
+    /// own. The namespace object of the entry, with all of its exports, is
+    /// assigned to a variable named `global_name`, which must be a valid
+    /// identifier.
+    ///
+    /// A standalone chunk can't load other chunks, so its chunk group must
+    /// consist of the chunk alone.
+    Iife { global_name: Option<String> },
+    /// A standalone module that works as an AMD module, a CommonJS module and
+    /// a script that assigns the exports of the entry to `global_name`.
+    /// External modules of the chunk are loaded from the `dependencies`.
+    Umd {
+        global_name: String,
+        dependencies: Vec<UmdDependency>,
+    },
+    /// A native ES module, for `<script type="module">`. It shares the
+    /// turbopack runtime with the other chunks of the page like
+    /// [OutputFormat::Runtime], but statically imports the other chunks of its
+    /// chunk group, so the module loader of the browser loads them before the
+    /// chunk is evaluated. Chunks that are loaded later, e.g. for dynamic
+    /// imports, are loaded with `import()`.
+    ///
+    /// The namespace object of the entry is the default export of the chunk.
+    ///
+    /// Chunks only register their modules when they are evaluated, and the
+    /// entries run once all chunks of the group are registered, so circular
+    /// imports between chunks are fine.
+    Esm,
+}
+
+/// An external module of an [OutputFormat::Umd] chunk.
+#[derive(Hash, Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct UmdDependency {
+    /// The request of the external module, as passed to `require` or
+    /// `define`.
+    pub request: String,
+    /// The global variable that holds the module when loaded as a script.
+    pub global: String,
+}
+
+impl OutputFormat {
+    /// Standalone chunks evaluate the chunk with a runtime of their own, which
+    /// gets a private `self` to pick up the chunk from. Only the modules of the
+    /// chunk itself are available.
+    pub fn is_standalone(&self) -> bool {
+        !matches!(self, OutputFormat::Runtime | OutputFormat::Esm)
+    }
+
+    /// Whether the exports of the main entry are assigned to [EXPORTS_VAR].
+    pub(crate) fn provides_exports(&self) -> bool {
+        !matches!(self, OutputFormat::Runtime)
+    }
+
+    /// The code before the chunk. `chunks_server_paths` are the other chunks
+    /// of the chunk group, which have to be loaded before the entries are
+    /// evaluated.
+    pub(crate) fn header(&self, chunks_server_paths: &[String]) -> String {
+        let scope = format!("var {EXPORTS_VAR};\n");
+        match self {
+            OutputFormat::Runtime => String::new(),
+            OutputFormat::Esm => {
+                let mut header = String::new();
+                for path in chunks_server_paths {
+                    writeln!(header, "import {};", stringify_str(&format!("/{path}"))).unwrap();
+                }
+                // Makes the runtime load chunks with `import()`
+                writeln!(header, "self.TURBOPACK_CHUNK_FORMAT = \"esm\";").unwrap();
+                writeln!(header, "var {EXPORTS_VAR};").unwrap();
+                header
+            }
+            OutputFormat::Iife { global_name } => {
+                let assignment = match global_name {
+                    Some(global_name) => format!("var {global_name} = "),
+                    None => String::new(),
+                };
+                format!("{assignment}(function () {{\n{scope}")
+            }
+            OutputFormat::Umd {
+                global_name,
+                dependencies,
+            } => {
+                let requests = dependencies
+                    .iter()
+                    .map(|dependency| stringify_str(&dependency.request))
+                    .collect::<Vec<_>>();
+                let requires = requests
+                    .iter()
+                    .map(|request| format!("require({request})"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let globals = dependencies
+                    .iter()
+                    .map(|dependency| format!("root[{}]", stringify_str(&dependency.global)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let params = (0..dependencies.len())
+                    .map(|i| format!("__turbopack_umd_dep_{i}__"))
+                    .collect::<Vec<_>>();
+                let deps = if dependencies.is_empty() {
+                    "{}".to_string()
+                } else {
+                    let props = requests
+                        .iter()
+                        .zip(params.iter())
+                        .map(|(request, param)| format!("{request}: {param}"))
+                        .collect::<Vec<_>>();
+                    format!("{{ {} }}", props.join(", "))
+                };
+                let requests = requests.join(", ");
+                let params = params.join(", ");
+                let global_name = stringify_str(global_name);
+                // The runtime loads external modules with `require`, which is
+                // shadowed to return the dependencies.
+                format!(
+                    r#"(function (root, factory) {{
+    if (typeof define === "function" && define.amd) {{
+        define([{requests}], factory);
+    }} else if (typeof module === "object" && module.exports) {{
+        module.exports = factory({requires});
+    }} else {{
+        root[{global_name}] = factory({globals});
+    }}
+}})(typeof self !== "undefined" ? self : this, function ({params}) {{
+var __turbopack_umd_deps__ = {deps};
+function require(id) {{
+    if (!Object.prototype.hasOwnProperty.call(__turbopack_umd_deps__, id)) {{
+        throw new Error("Cannot find module '" + id + "'");
+    }}
+    return __turbopack_umd_deps__[id];
+}}
+{scope}"#
+                )
+            }
+        }
+    }
+
+    /// The code before the registration of the chunk, i.e. its path, module
+    /// factories and entries.
+    pub(crate) fn registration_start(&self) -> String {
+        if self.is_standalone() {
+            format!("var {CHUNK_VAR} = [")
+        } else {
+            "(self.TURBOPACK = self.TURBOPACK || []).push([".to_string()
+        }
+    }
+
+    /// The code after the registration of the chunk. The runtime of a
+    /// standalone chunk is evaluated with a private `self`, while the module
+    /// factories are defined outside of it and see the global `self`.
+    pub(crate) fn registration_end(&self) -> String {
+        if self.is_standalone() {
+            format!("];\n(function (self) {{\nself.TURBOPACK = [{CHUNK_VAR}];\n")
+        } else {
+            "]);\n".to_string()
+        }
+    }
+
+    /// The code after the chunk and its runtime.
+    pub(crate) fn footer(&self) -> String {
+        let runtime_end = "\n})({});";
+        match self {
+            OutputFormat::Runtime => String::new(),
+            OutputFormat::Esm => format!("\nexport {{ {EXPORTS_VAR} as default }};\n"),
+            OutputFormat::Iife { .. } => {
+                format!("{runtime_end}\nreturn {EXPORTS_VAR};\n}})();\n")
+            }
+            OutputFormat::Umd { .. } => format!("{runtime_end}\nreturn {EXPORTS_VAR};\n}});\n"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use swc_core::{
+        common::{errors::HANDLER, FileName},
+        ecma::{
+            ast::EsVersion,
+            parser::{parse_file_as_module, parse_file_as_script},
+        },
+        testing::run_test,
+    };
+    use turbopack_core::chunk::ModuleId;
+
+    use super::{OutputFormat, UmdDependency};
+    use crate::chunk::loader::loader_code;
+
+    /// The registration of a tiny evaluated chunk with an entry that has two
+    /// named exports, like the chunk code emits it.
+    const CHUNK: &str = r#""entry.js", {
+"[project]/entry.js": (({ r: __turbopack_require__, s: __turbopack_esm__ }) => (() => {
+__turbopack_esm__({ a: () => a, b: () => b });
+const a = __turbopack_require__("react").a, b = 2;
+})()),
+}, ({ loadedChunks, instantiateRuntimeModule }) => {
+    __turbopack_exports__ = instantiateRuntimeModule("[project]/entry.js").exports;
+}"#;
+
+    /// Like [CHUNK], but the entry has no dependencies, so it can be run. It
+    /// also exports whether it sees the global `self`.
+    const STANDALONE_CHUNK: &str = r#""entry.js", {
+"[project]/entry.js": (({ s: __turbopack_esm__ }) => (() => {
+__turbopack_esm__({ a: () => a, b: () => b, globalSelf: () => globalSelf });
+const a = 1, b = 2, globalSelf = self === globalThis;
+})()),
+}, ({ loadedChunks, instantiateRuntimeModule }) => {
+    __turbopack_exports__ = instantiateRuntimeModule("[project]/entry.js").exports;
+}"#;
+
+    /// A chunk of the same chunk group that isn't evaluated, which is always
+    /// emitted as is.
+    const SHARED_CHUNK: &str = r#"(self.TURBOPACK = self.TURBOPACK || []).push(["shared.js", {
+"[project]/shared.js": (({ e: exports }) => (() => {
+exports.c = 3;
+})()),
+}]);"#;
+
+    /// Checks that `code` is a valid script, or a valid module.
+    fn parse(code: &str, module: bool) {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, code.to_string());
+            let result = if module {
+                parse_file_as_module(
+                    &fm,
+                    Default::default(),
+                    EsVersion::latest(),
+                    None,
+                    &mut vec![],
+                )
+                .map(drop)
+            } else {
+                parse_file_as_script(
+                    &fm,
+                    Default::default(),
+                    EsVersion::latest(),
+                    None,
+                    &mut vec![],
+                )
+                .map(drop)
+            };
+            result.map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    /// Emits `chunk` in the `format` like the chunk code does, followed by
+    /// `runtime` for evaluated chunks.
+    fn emit_chunk(
+        format: &OutputFormat,
+        chunks_server_paths: &[String],
+        chunk: &str,
+        runtime: &str,
+    ) -> String {
+        format!(
+            "{}{}{chunk}{}{runtime}{}",
+            format.header(chunks_server_paths),
+            format.registration_start(),
+            format.registration_end(),
+            format.footer()
+        )
+    }
+
+    fn emit_with_chunks(format: &OutputFormat, chunks_server_paths: &[String]) -> String {
+        let code = emit_chunk(format, chunks_server_paths, CHUNK, "");
+        parse(&code, matches!(format, OutputFormat::Esm));
+        code
+    }
+
+    fn emit(format: &OutputFormat) -> String {
+        emit_with_chunks(format, &[])
+    }
+
+    /// Runs the standalone chunk in the `format` with the actual runtime in
+    /// Node.js and returns what `script` prints.
+    fn run(format: &OutputFormat, script: &str) -> String {
+        run_chunk(format, STANDALONE_CHUNK, script)
+    }
+
+    fn run_chunk(format: &OutputFormat, chunk: &str, script: &str) -> String {
+        let code = emit_chunk(format, &[], chunk, include_str!("../../js/src/runtime.js"));
+        let output = Command::new("node")
+            .arg("-e")
+            .arg(format!("var self = globalThis;\n{code}\n{script}"))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    #[test]
+    fn iife() {
+        let code = emit(&OutputFormat::Iife {
+            global_name: Some("MyLib".to_string()),
+        });
+        assert!(code.starts_with(
+            "var MyLib = (function () {\nvar __turbopack_exports__;\nvar __turbopack_chunk__ = \
+             [\"entry.js\""
+        ));
+        // The runtime gets a `self` of its own to pick up the chunk from
+        assert!(code.contains("}];\n(function (self) {\nself.TURBOPACK = [__turbopack_chunk__];\n"));
+        // Returns the namespace object, which has both `a` and `b`
+        assert!(code.ends_with("\n})({});\nreturn __turbopack_exports__;\n})();\n"));
+
+        let code = emit(&OutputFormat::Iife { global_name: None });
+        assert!(code.starts_with("(function () {\nvar __turbopack_exports__;"));
+    }
+
+    #[test]
+    fn iife_exports() {
+        let format = OutputFormat::Iife {
+            global_name: Some("MyLib".to_string()),
+        };
+        // The modules see the global `self`, not the one of the runtime
+        assert_eq!(
+            run(&format, "console.log(JSON.stringify(MyLib));"),
+            r#"{"a":1,"b":2,"globalSelf":true}"#
+        );
+    }
+
+    #[test]
+    fn umd() {
+        let code = emit(&OutputFormat::Umd {
+            global_name: "MyLib".to_string(),
+            dependencies: vec![
+                UmdDependency {
+                    request: "react".to_string(),
+                    global: "React".to_string(),
+                },
+                UmdDependency {
+                    request: "lodash/fp".to_string(),
+                    global: "_".to_string(),
+                },
+            ],
+        });
+        assert!(code.contains(r#"define(["react", "lodash/fp"], factory);"#));
+        assert!(
+            code.contains(r#"module.exports = factory(require("react"), require("lodash/fp"));"#)
+        );
+        assert!(code.contains(r#"root["MyLib"] = factory(root["React"], root["_"]);"#));
+        assert!(code.contains(
+            "function (__turbopack_umd_dep_0__, __turbopack_umd_dep_1__) {\nvar \
+             __turbopack_umd_deps__ = { \"react\": __turbopack_umd_dep_0__, \"lodash/fp\": \
+             __turbopack_umd_dep_1__ };"
+        ));
+        assert!(code.ends_with("\n})({});\nreturn __turbopack_exports__;\n});\n"));
+
+        let code = emit(&OutputFormat::Umd {
+            global_name: "MyLib".to_string(),
+            dependencies: vec![],
+        });
+        assert!(code.contains("define([], factory);"));
+        assert!(code.contains("var __turbopack_umd_deps__ = {};"));
+    }
+
+    #[test]
+    fn umd_exports() {
+        let format = OutputFormat::Umd {
+            global_name: "MyLib".to_string(),
+            dependencies: vec![],
+        };
+        // `node -e` provides `module`, so it's loaded as a CommonJS module
+        assert_eq!(
+            run(&format, "console.log(JSON.stringify(module.exports));"),
+            r#"{"a":1,"b":2,"globalSelf":true}"#
+        );
+    }
+
+    #[test]
+    fn esm() {
+        let code = emit_with_chunks(&OutputFormat::Esm, &["shared.js".to_string()]);
+        assert!(code.starts_with(
+            "import \"/shared.js\";\nself.TURBOPACK_CHUNK_FORMAT = \"esm\";\nvar \
+             __turbopack_exports__;\n(self.TURBOPACK"
+        ));
+        assert!(code.ends_with("}]);\nexport { __turbopack_exports__ as default };\n"));
+        // The imported chunk is a valid module too
+        parse(SHARED_CHUNK, true);
+
+        let code = emit(&OutputFormat::Esm);
+        assert!(code.starts_with("self.TURBOPACK_CHUNK_FORMAT"));
+    }
+
+    #[test]
+    fn cjs_interop() {
+        // The entry imports CommonJS modules statically and with a manifest
+        // loader, whose chunk is the standalone chunk itself.
+        let loader = loader_code(
+            "entry.js",
+            &ModuleId::String("[project]/manifest.js".to_string()),
+            &ModuleId::String("[project]/flagged.js".to_string()),
+            true,
+        );
+        let chunk = format!(
+            r#""entry.js", {{
+"[project]/entry.js": (({{
+    r: __turbopack_require__, i: __turbopack_import__, s: __turbopack_esm__
+}}) => (() => {{
+const plain = __turbopack_import__("[project]/plain.js", true);
+const babel = __turbopack_import__("[project]/babel.js", true);
+const flagged = __turbopack_import__("[project]/flagged.js", true);
+const dynamic = __turbopack_require__("[project]/loader.js")(__turbopack_import__);
+__turbopack_esm__({{
+    plain: () => plain.default.value,
+    babel: () => babel.default,
+    flagged: () => flagged.default.value,
+    dynamic: () => dynamic,
+}});
+}})()),
+"[project]/plain.js": (({{ e: exports }}) => (() => {{
+exports.value = 1;
+}})()),
+"[project]/babel.js": (({{ e: exports }}) => (() => {{
+exports.__esModule = true;
+exports.default = 2;
+}})()),
+"[project]/flagged.js": (({{ e: exports }}) => (() => {{
+exports.__esModule = true;
+exports.value = 3;
+}})()),
+"[project]/manifest.js": (({{ v: __turbopack_export_value__ }}) => (() => {{
+__turbopack_export_value__(Promise.resolve());
+}})()),
+"[project]/loader.js": (({{
+    r: __turbopack_require__, v: __turbopack_export_value__, l: __turbopack_load__
+}}) => (() => {{
+{loader}
+}})()),
+}}, ({{ loadedChunks, instantiateRuntimeModule }}) => {{
+    __turbopack_exports__ = instantiateRuntimeModule("[project]/entry.js").exports;
+}}"#
+        );
+        let format = OutputFormat::Iife {
+            global_name: Some("MyLib".to_string()),
+        };
+        // `module.exports` is the default export, unless the module sets
+        // `exports.default` itself, no matter how it's imported
+        assert_eq!(
+            run_chunk(
+                &format,
+                &chunk,
+                "MyLib.dynamic.then((ns) => console.log(JSON.stringify([MyLib.plain, MyLib.babel, \
+                 MyLib.flagged, ns.default.value])));"
+            ),
+            "[1,2,3,3]"
+        );
+    }
+
+    #[test]
+    fn runtime() {
+        assert_eq!(
+            emit(&OutputFormat::Runtime),
+            format!("(self.TURBOPACK = self.TURBOPACK || []).push([{CHUNK}]);\n")
+        );
+    }
+}
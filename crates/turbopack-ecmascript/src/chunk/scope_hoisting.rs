@@ -0,0 +1,1255 @@
+//! Scope hoisting (module concatenation) of ESM modules.
+//!
+//! A group of ESM modules that only reference each other via static imports
+//! can be emitted as a single module scope instead of separate runtime
+//! modules. The exports of the inner modules become plain declarations, the
+//! imports between the modules are replaced by references to these
+//! declarations and top level bindings are renamed so they don't collide.
+//!
+//! When [turbopack_core::chunk::ChunkingContext::is_scope_hoisting_enabled],
+//! [hoist_chunk_content] concatenates the modules of a chunk that have no side
+//! effects and are only imported by a single other module of the chunk into
+//! that module.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::{bail, Result};
+use indexmap::IndexSet;
+use swc_core::{
+    common::{
+        comments::{Comments, SingleThreadedComments},
+        FileName, Globals, Mark, SourceMap, SyntaxContext, DUMMY_SP, GLOBALS,
+    },
+    ecma::{
+        ast::{
+            AssignPat, BindingIdent, CallExpr, Callee, ClassDecl, Decl, DefaultDecl, EsVersion,
+            ExportDecl, ExportDefaultDecl, ExportDefaultExpr, ExportNamedSpecifier,
+            ExportSpecifier, Expr, FnDecl, Id, Ident, ImportNamedSpecifier, ImportSpecifier,
+            KeyValuePatProp, KeyValueProp, Lit, MemberProp, MetaPropExpr, MetaPropKind, Module,
+            ModuleDecl, ModuleExportName, ModuleItem, NamedExport, ObjectPatProp, Pat, Program,
+            Prop, PropName, Stmt, SuperProp,
+        },
+        atoms::JsWord,
+        codegen::{text_writer::JsWriter, Emitter},
+        parser::{parse_file_as_module, EsConfig, Syntax},
+        transforms::base::resolver,
+        utils::private_ident,
+        visit::{Visit, VisitMut, VisitMutWith, VisitWith},
+    },
+    quote,
+};
+use turbo_tasks::Value;
+use turbo_tasks_fs::File;
+use turbopack_core::{
+    chunk::{ChunkItem, ChunkableAssetReferenceVc, ChunkingContextVc},
+    resolve::parse::RequestVc,
+    virtual_asset::VirtualAssetVc,
+};
+
+use super::{
+    EcmascriptChunkContentResult, EcmascriptChunkContentResultVc, EcmascriptChunkItems,
+    EcmascriptChunkItemsVc, EcmascriptChunkPlaceablesVc,
+};
+use crate::{
+    analyzer::dynamic_scope::uses_dynamic_scope,
+    parse::{parse, ParseResult},
+    references::esm::{base::ReferencedAsset, EsmAssetReferenceVc},
+    EcmascriptInputTransformsVc, EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+    ModuleChunkItemVc,
+};
+
+/// A module that takes part in scope hoisting.
+pub struct HoistedModule {
+    /// The module, with the resolver applied using the marks below.
+    pub module: Module,
+    pub unresolved_mark: Mark,
+    pub top_level_mark: Mark,
+    /// Maps the import sources of this module that point into the hoisted
+    /// group to the index of the imported module.
+    pub imports: HashMap<JsWord, usize>,
+}
+
+/// Returns true when the module can be concatenated with other modules. The
+/// module must be pure ESM: it must not use CommonJS or a direct `eval` and
+/// must not re-export other modules, since these need the module namespace
+/// object or the original bindings at runtime. It must not
+/// refer to its own location with `import.meta`, `__dirname` or `__filename`
+/// either, since the concatenated module has the location of the root.
+pub fn is_hoistable(module: &Module, unresolved_mark: Mark) -> bool {
+    if uses_dynamic_scope(module, unresolved_mark) {
+        return false;
+    }
+    let mut visitor = HoistableVisitor {
+        unresolved: SyntaxContext::empty().apply_mark(unresolved_mark),
+        hoistable: true,
+    };
+    module.visit_with(&mut visitor);
+    visitor.hoistable
+}
+
+struct HoistableVisitor {
+    unresolved: SyntaxContext,
+    hoistable: bool,
+}
+
+impl Visit for HoistableVisitor {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if ident.span.ctxt == self.unresolved
+            && matches!(
+                &*ident.sym,
+                "module" | "exports" | "require" | "__dirname" | "__filename"
+            )
+        {
+            self.hoistable = false;
+        }
+    }
+
+    fn visit_meta_prop_expr(&mut self, expr: &MetaPropExpr) {
+        if expr.kind == MetaPropKind::ImportMeta {
+            self.hoistable = false;
+        }
+    }
+
+    fn visit_member_prop(&mut self, prop: &MemberProp) {
+        if let MemberProp::Computed(computed) = prop {
+            computed.visit_with(self);
+        }
+    }
+
+    fn visit_module_decl(&mut self, decl: &ModuleDecl) {
+        match decl {
+            ModuleDecl::ExportAll(_)
+            | ModuleDecl::ExportNamed(NamedExport { src: Some(_), .. }) => {
+                self.hoistable = false;
+            }
+            _ => decl.visit_children_with(self),
+        }
+    }
+}
+
+/// Concatenates the modules into a single module. The modules must be passed
+/// in dependency order, i. e. a module can only import modules with a lower
+/// index. The last module is the root of the group and keeps its exports, the
+/// exports of all other modules are only reachable from within the group.
+///
+/// Bindings of the root module keep their names where possible. Colliding
+/// bindings of the other modules are renamed to `{name}_{index}`.
+pub fn concatenate_modules(modules: Vec<HoistedModule>) -> Result<Module> {
+    let Some(root_index) = modules.len().checked_sub(1) else {
+        bail!("scope hoisting requires at least one module");
+    };
+
+    let mut reserved = HashSet::new();
+    let mut hoisted = Vec::with_capacity(modules.len());
+    for (index, module) in modules.into_iter().enumerate() {
+        let top_level = SyntaxContext::empty().apply_mark(module.top_level_mark);
+
+        let mut bindings = BindingCollector {
+            top_level,
+            top_level_bindings: IndexSet::new(),
+            reserved: &mut reserved,
+        };
+        module.module.visit_with(&mut bindings);
+        let mut top_level_bindings = bindings.top_level_bindings;
+
+        let mut dynamic_imports = DynamicImportVisitor {
+            imports: &module.imports,
+            found: None,
+        };
+        module.module.visit_with(&mut dynamic_imports);
+        if let Some(src) = dynamic_imports.found {
+            bail!("module {index} dynamically imports {src} from the hoisted group");
+        }
+
+        let mut items = module.module.body;
+        let mut exports = HashMap::new();
+        if index != root_index {
+            items = strip_exports(items, top_level, &mut exports, &mut top_level_bindings)?;
+        }
+
+        let mut imports = HashMap::new();
+        let mut body = Vec::with_capacity(items.len());
+        for item in items {
+            match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => {
+                    let Some(&target) = module.imports.get(&import.src.value) else {
+                        body.push(ModuleItem::ModuleDecl(ModuleDecl::Import(import)));
+                        continue;
+                    };
+                    if target >= index {
+                        bail!(
+                            "module {index} imports module {target}, but modules must be in \
+                             dependency order"
+                        );
+                    }
+                    let target_exports = &hoisted[target].exports;
+                    for specifier in import.specifiers {
+                        let (local, name): (Ident, JsWord) = match specifier {
+                            ImportSpecifier::Named(ImportNamedSpecifier {
+                                local,
+                                imported,
+                                ..
+                            }) => {
+                                let name = match imported {
+                                    Some(ModuleExportName::Ident(ident)) => ident.sym,
+                                    Some(ModuleExportName::Str(s)) => s.value,
+                                    None => local.sym.clone(),
+                                };
+                                (local, name)
+                            }
+                            ImportSpecifier::Default(default) => (default.local, "default".into()),
+                            ImportSpecifier::Namespace(_) => {
+                                bail!("namespace import of {} can't be hoisted", import.src.value);
+                            }
+                        };
+                        let Some(id) = target_exports.get(&name) else {
+                            bail!("{} has no export named {name}", import.src.value);
+                        };
+                        let local = local.to_id();
+                        top_level_bindings.shift_remove(&local);
+                        imports.insert(local, (target, id.clone()));
+                    }
+                }
+                item => body.push(item),
+            }
+        }
+
+        hoisted.push(HoistedState {
+            body,
+            top_level_bindings,
+            exports,
+            imports,
+        });
+    }
+
+    // Assign the final names. The root module goes first so its bindings keep
+    // their names, followed by the other modules from the closest dependency
+    // to the farthest one.
+    let mut used = reserved;
+    let mut names: Vec<HashMap<Id, JsWord>> = (0..hoisted.len()).map(|_| HashMap::new()).collect();
+    for index in (0..hoisted.len()).rev() {
+        let mut bindings = hoisted[index]
+            .top_level_bindings
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for id in bindings {
+            let name = if used.contains(&id.0) {
+                let mut name: JsWord = format!("{}_{index}", id.0).into();
+                let mut suffix = 1;
+                while used.contains(&name) {
+                    name = format!("{}_{index}_{suffix}", id.0).into();
+                    suffix += 1;
+                }
+                name
+            } else {
+                id.0.clone()
+            };
+            used.insert(name.clone());
+            names[index].insert(id, name);
+        }
+    }
+
+    let mut result = Vec::new();
+    for (index, state) in hoisted.into_iter().enumerate() {
+        let mut renames: HashMap<Id, JsWord> = names[index]
+            .iter()
+            .filter(|(id, name)| id.0 != **name)
+            .map(|(id, name)| (id.clone(), name.clone()))
+            .collect();
+        for (local, (target, id)) in state.imports {
+            let name = names[target][&id].clone();
+            if local.0 != name {
+                renames.insert(local, name);
+            }
+        }
+
+        let mut body = state.body;
+        if index == root_index {
+            body = split_renamed_exports(body, &renames);
+        }
+        let mut renamer = Renamer { renames: &renames };
+        body.visit_mut_with(&mut renamer);
+        result.extend(body);
+    }
+
+    Ok(Module {
+        span: DUMMY_SP,
+        body: result,
+        shebang: None,
+    })
+}
+
+struct HoistedState {
+    body: Vec<ModuleItem>,
+    top_level_bindings: IndexSet<Id>,
+    /// Maps export names to the declarations that are exported.
+    exports: HashMap<JsWord, Id>,
+    /// Maps the local bindings of hoisted imports to the imported module and
+    /// declaration.
+    imports: HashMap<Id, (usize, Id)>,
+}
+
+/// Turns the exports of a non-root module into plain declarations and records
+/// the exported bindings in `exports`.
+fn strip_exports(
+    items: Vec<ModuleItem>,
+    top_level: SyntaxContext,
+    exports: &mut HashMap<JsWord, Id>,
+    top_level_bindings: &mut IndexSet<Id>,
+) -> Result<Vec<ModuleItem>> {
+    let mut default_ident = || {
+        let ident = private_ident!("_default");
+        top_level_bindings.insert(ident.to_id());
+        ident
+    };
+
+    let mut body = Vec::with_capacity(items.len());
+    for item in items {
+        let ModuleItem::ModuleDecl(decl) = item else {
+            body.push(item);
+            continue;
+        };
+        match decl {
+            ModuleDecl::ExportDecl(ExportDecl { decl, .. }) => {
+                match &decl {
+                    Decl::Class(ClassDecl { ident, .. }) | Decl::Fn(FnDecl { ident, .. }) => {
+                        exports.insert(ident.sym.clone(), ident.to_id());
+                    }
+                    Decl::Var(var) => {
+                        let mut collector = TopLevelBindingIdents {
+                            top_level,
+                            ids: Vec::new(),
+                        };
+                        var.decls
+                            .iter()
+                            .for_each(|decl| decl.name.visit_with(&mut collector));
+                        for id in collector.ids {
+                            exports.insert(id.0.clone(), id);
+                        }
+                    }
+                    _ => bail!("typescript declarations are unexpected here"),
+                }
+                body.push(ModuleItem::Stmt(Stmt::Decl(decl)));
+            }
+            ModuleDecl::ExportNamed(NamedExport {
+                specifiers,
+                src: None,
+                ..
+            }) => {
+                for specifier in specifiers {
+                    let ExportSpecifier::Named(ExportNamedSpecifier {
+                        orig: ModuleExportName::Ident(orig),
+                        exported,
+                        ..
+                    }) = specifier else {
+                        bail!("unsupported export specifier {specifier:?}");
+                    };
+                    let name = match exported {
+                        Some(ModuleExportName::Ident(ident)) => ident.sym,
+                        Some(ModuleExportName::Str(s)) => s.value,
+                        None => orig.sym.clone(),
+                    };
+                    exports.insert(name, orig.to_id());
+                }
+            }
+            ModuleDecl::ExportDefaultExpr(ExportDefaultExpr { box expr, .. }) => {
+                let name = default_ident();
+                exports.insert("default".into(), name.to_id());
+                body.push(ModuleItem::Stmt(quote!(
+                    "const $name = $expr;" as Stmt,
+                    name = name,
+                    expr: Expr = expr
+                )));
+            }
+            ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { decl, .. }) => {
+                let decl = match decl {
+                    DefaultDecl::Class(class) => {
+                        let ident = class.ident.unwrap_or_else(&mut default_ident);
+                        exports.insert("default".into(), ident.to_id());
+                        Decl::Class(ClassDecl {
+                            ident,
+                            declare: false,
+                            class: class.class,
+                        })
+                    }
+                    DefaultDecl::Fn(fn_expr) => {
+                        let ident = fn_expr.ident.unwrap_or_else(&mut default_ident);
+                        exports.insert("default".into(), ident.to_id());
+                        Decl::Fn(FnDecl {
+                            ident,
+                            declare: false,
+                            function: fn_expr.function,
+                        })
+                    }
+                    DefaultDecl::TsInterfaceDecl(_) => {
+                        bail!("typescript declarations are unexpected here");
+                    }
+                };
+                body.push(ModuleItem::Stmt(Stmt::Decl(decl)));
+            }
+            ModuleDecl::Import(import) => {
+                body.push(ModuleItem::ModuleDecl(ModuleDecl::Import(import)));
+            }
+            decl => bail!("module item can't be hoisted: {decl:?}"),
+        }
+    }
+    Ok(body)
+}
+
+/// Splits `export const a = ...` into a declaration and an `export { a }`
+/// when `a` is renamed, so the export name stays the same.
+fn split_renamed_exports(items: Vec<ModuleItem>, renames: &HashMap<Id, JsWord>) -> Vec<ModuleItem> {
+    let mut body = Vec::with_capacity(items.len());
+    for item in items {
+        let ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, span })) = item else {
+            body.push(item);
+            continue;
+        };
+        let idents = match &decl {
+            Decl::Class(ClassDecl { ident, .. }) | Decl::Fn(FnDecl { ident, .. }) => {
+                vec![ident.clone()]
+            }
+            Decl::Var(var) => {
+                let mut collector = BindingIdents(Vec::new());
+                var.decls
+                    .iter()
+                    .for_each(|decl| decl.name.visit_with(&mut collector));
+                collector.0
+            }
+            _ => Vec::new(),
+        };
+        if idents
+            .iter()
+            .any(|ident| renames.contains_key(&ident.to_id()))
+        {
+            body.push(ModuleItem::Stmt(Stmt::Decl(decl)));
+            body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(
+                NamedExport {
+                    span,
+                    specifiers: idents
+                        .into_iter()
+                        .map(|ident| {
+                            ExportSpecifier::Named(ExportNamedSpecifier {
+                                span: DUMMY_SP,
+                                orig: ModuleExportName::Ident(ident),
+                                exported: None,
+                                is_type_only: false,
+                            })
+                        })
+                        .collect(),
+                    src: None,
+                    type_only: false,
+                    asserts: None,
+                },
+            )));
+        } else {
+            body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                span,
+                decl,
+            })));
+        }
+    }
+    body
+}
+
+/// Collects the top level bindings of a module and reserves the names of all
+/// other bindings and unresolved references, since renamed bindings must not
+/// shadow them.
+struct BindingCollector<'a> {
+    top_level: SyntaxContext,
+    top_level_bindings: IndexSet<Id>,
+    reserved: &'a mut HashSet<JsWord>,
+}
+
+impl Visit for BindingCollector<'_> {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if ident.span.ctxt == self.top_level {
+            self.top_level_bindings.insert(ident.to_id());
+        } else {
+            self.reserved.insert(ident.sym.clone());
+        }
+    }
+
+    fn visit_member_prop(&mut self, prop: &MemberProp) {
+        if let MemberProp::Computed(computed) = prop {
+            computed.visit_with(self);
+        }
+    }
+
+    fn visit_super_prop(&mut self, prop: &SuperProp) {
+        if let SuperProp::Computed(computed) = prop {
+            computed.visit_with(self);
+        }
+    }
+
+    fn visit_prop_name(&mut self, name: &PropName) {
+        if let PropName::Computed(computed) = name {
+            computed.visit_with(self);
+        }
+    }
+
+    fn visit_export_named_specifier(&mut self, specifier: &ExportNamedSpecifier) {
+        specifier.orig.visit_with(self);
+    }
+
+    fn visit_import_named_specifier(&mut self, specifier: &ImportNamedSpecifier) {
+        specifier.local.visit_with(self);
+    }
+}
+
+/// Finds dynamic `import()`s of modules in the hoisted group.
+struct DynamicImportVisitor<'a> {
+    imports: &'a HashMap<JsWord, usize>,
+    found: Option<JsWord>,
+}
+
+impl Visit for DynamicImportVisitor<'_> {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Import(_) = call.callee {
+            if let Some(Expr::Lit(Lit::Str(src))) = call.args.first().map(|arg| &*arg.expr) {
+                if self.imports.contains_key(&src.value) {
+                    self.found = Some(src.value.clone());
+                }
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+struct TopLevelBindingIdents {
+    top_level: SyntaxContext,
+    ids: Vec<Id>,
+}
+
+impl Visit for TopLevelBindingIdents {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        if ident.id.span.ctxt == self.top_level {
+            self.ids.push(ident.id.to_id());
+        }
+    }
+}
+
+struct BindingIdents(Vec<Ident>);
+
+impl Visit for BindingIdents {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.0.push(ident.id.clone());
+    }
+
+    fn visit_expr(&mut self, _expr: &Expr) {
+        // Default values can't declare top level bindings.
+    }
+}
+
+/// Renames bindings and their references while keeping property names and
+/// export names intact.
+struct Renamer<'a> {
+    renames: &'a HashMap<Id, JsWord>,
+}
+
+impl Renamer<'_> {
+    fn renamed(&self, ident: &Ident) -> Option<Ident> {
+        self.renames
+            .get(&ident.to_id())
+            .map(|name| Ident::new(name.clone(), ident.span))
+    }
+}
+
+impl VisitMut for Renamer<'_> {
+    fn visit_mut_ident(&mut self, ident: &mut Ident) {
+        if let Some(renamed) = self.renamed(ident) {
+            *ident = renamed;
+        }
+    }
+
+    fn visit_mut_member_prop(&mut self, prop: &mut MemberProp) {
+        if let MemberProp::Computed(computed) = prop {
+            computed.visit_mut_with(self);
+        }
+    }
+
+    fn visit_mut_super_prop(&mut self, prop: &mut SuperProp) {
+        if let SuperProp::Computed(computed) = prop {
+            computed.visit_mut_with(self);
+        }
+    }
+
+    fn visit_mut_prop_name(&mut self, name: &mut PropName) {
+        if let PropName::Computed(computed) = name {
+            computed.visit_mut_with(self);
+        }
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            if let Some(renamed) = self.renamed(ident) {
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(ident.clone()),
+                    value: box Expr::Ident(renamed),
+                });
+            }
+            return;
+        }
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_object_pat_prop(&mut self, prop: &mut ObjectPatProp) {
+        if let ObjectPatProp::Assign(assign) = prop {
+            if let Some(renamed) = self.renamed(&assign.key) {
+                let mut value = assign.value.take();
+                value.visit_mut_with(self);
+                let binding = Pat::Ident(BindingIdent::from(renamed));
+                *prop = ObjectPatProp::KeyValue(KeyValuePatProp {
+                    key: PropName::Ident(assign.key.clone()),
+                    value: box match value {
+                        Some(right) => Pat::Assign(AssignPat {
+                            span: assign.span,
+                            left: box binding,
+                            right,
+                            type_ann: None,
+                        }),
+                        None => binding,
+                    },
+                });
+                return;
+            }
+        }
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_export_named_specifier(&mut self, specifier: &mut ExportNamedSpecifier) {
+        if let ModuleExportName::Ident(orig) = &mut specifier.orig {
+            if let Some(renamed) = self.renamed(orig) {
+                if specifier.exported.is_none() {
+                    specifier.exported = Some(ModuleExportName::Ident(orig.clone()));
+                }
+                *orig = renamed;
+            }
+        }
+    }
+
+    fn visit_mut_import_named_specifier(&mut self, specifier: &mut ImportNamedSpecifier) {
+        if let Some(renamed) = self.renamed(&specifier.local) {
+            if specifier.imported.is_none() {
+                specifier.imported = Some(ModuleExportName::Ident(specifier.local.clone()));
+            }
+            specifier.local = renamed;
+        }
+    }
+}
+
+/// Concatenates the modules of the chunk `content` that can be inlined into
+/// the module that imports them, see [hoisting_groups], when
+/// [turbopack_core::chunk::ChunkingContext::is_scope_hoisting_enabled]. The
+/// concatenated module keeps the path and the module id of the root of its
+/// group, and the chunk items of the inlined modules are removed from the
+/// chunk. Groups that can't be concatenated are left as they are.
+///
+/// A module that is inlined in different chunks is instantiated once per
+/// chunk, which is fine since only modules without side effects are inlined.
+#[turbo_tasks::function]
+pub(crate) async fn hoist_chunk_content(
+    context: ChunkingContextVc,
+    entries: EcmascriptChunkPlaceablesVc,
+    content: EcmascriptChunkContentResultVc,
+) -> Result<EcmascriptChunkContentResultVc> {
+    if !*context.is_scope_hoisting_enabled().await? {
+        return Ok(content);
+    }
+    let this = content.await?;
+    let mut chunk_items = Vec::new();
+    for chunk in this.chunk_items.await?.iter() {
+        chunk_items.extend(chunk.await?.iter().copied());
+    }
+
+    // The ESM modules of the chunk, with the index of their chunk item
+    let mut modules = Vec::new();
+    let mut module_indices = HashMap::new();
+    let mut item_modules = HashMap::new();
+    for (item_index, chunk_item) in chunk_items.iter().enumerate() {
+        if let Some(chunk_item) = ModuleChunkItemVc::resolve_from(chunk_item).await? {
+            let module = chunk_item.await?.module.resolve().await?;
+            module_indices.insert(module, modules.len());
+            item_modules.insert(item_index, modules.len());
+            modules.push((item_index, module));
+        }
+    }
+    if modules.len() < 2 {
+        return Ok(content);
+    }
+
+    // Chunk items that aren't modules are importers with an index after the
+    // modules
+    let mut importers = vec![HashSet::new(); modules.len()];
+    for (item_index, chunk_item) in chunk_items.iter().enumerate() {
+        let importer = item_modules
+            .get(&item_index)
+            .copied()
+            .unwrap_or(modules.len() + item_index);
+        for reference in chunk_item.references().await?.iter() {
+            for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                if let Some(module) = EcmascriptModuleAssetVc::resolve_from(asset).await? {
+                    if let Some(&index) = module_indices.get(&module) {
+                        importers[index].insert(importer);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut entry_modules = HashSet::new();
+    for entry in entries.await?.iter() {
+        if let Some(module) = EcmascriptModuleAssetVc::resolve_from(entry).await? {
+            entry_modules.insert(module);
+        }
+    }
+    let mut imports = Vec::with_capacity(modules.len());
+    let mut inlinable = Vec::with_capacity(modules.len());
+    for &(_, module) in &modules {
+        let (module_imports, imports_other_assets) =
+            static_imports(module, &module_indices).await?;
+        inlinable.push(
+            !imports_other_assets
+                && !entry_modules.contains(&module)
+                && !module.analyze().await?.uses_dynamic_scope
+                && !*module.has_side_effects().await?
+                && only_imports_modules(context, module).await?,
+        );
+        imports.push(module_imports);
+    }
+    let groups = hoisting_groups(
+        &imports
+            .iter()
+            .map(|imports| imports.iter().map(|(_, index)| *index).collect())
+            .collect::<Vec<_>>(),
+        &importers,
+        &inlinable,
+    );
+
+    let mut removed = HashSet::new();
+    let mut replaced = HashMap::new();
+    for group in groups {
+        let group_modules = group
+            .iter()
+            .map(|&index| modules[index].1)
+            .collect::<Vec<_>>();
+        let group_imports = group
+            .iter()
+            .map(|&index| {
+                imports[index]
+                    .iter()
+                    .filter_map(|(src, target)| {
+                        let position = group.iter().position(|index| index == target)?;
+                        Some((src.clone(), position))
+                    })
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+        let Some(concatenated) = concatenated_module(&group_modules, group_imports).await? else {
+            continue;
+        };
+        let (root, inlined) = group.split_last().unwrap();
+        removed.extend(inlined.iter().map(|&index| modules[index].0));
+        replaced.insert(
+            modules[*root].0,
+            concatenated
+                .as_ecmascript_chunk_placeable()
+                .as_chunk_item(context),
+        );
+    }
+    if replaced.is_empty() {
+        return Ok(content);
+    }
+
+    let chunk_items = chunk_items
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !removed.contains(index))
+        .map(|(index, chunk_item)| replaced.get(&index).copied().unwrap_or(chunk_item))
+        .collect::<Vec<_>>();
+    Ok(EcmascriptChunkContentResult {
+        chunk_items: EcmascriptChunkItemsVc::cell(EcmascriptChunkItems::make_chunks(&chunk_items)),
+        chunks: this.chunks.clone(),
+        async_chunk_groups: this.async_chunk_groups.clone(),
+        loading_hints: this.loading_hints.clone(),
+        external_asset_references: this.external_asset_references.clone(),
+    }
+    .cell())
+}
+
+/// The static imports of `module` that resolve to one of the `modules`, with
+/// their import source. The flag is true when the module also imports other
+/// assets or is not an ES module at all.
+async fn static_imports(
+    module: EcmascriptModuleAssetVc,
+    modules: &HashMap<EcmascriptModuleAssetVc, usize>,
+) -> Result<(Vec<(JsWord, usize)>, bool)> {
+    let this = module.await?;
+    let parsed = parse(this.source, Value::new(this.ty), this.transforms).await?;
+    let ParseResult::Ok { eval_context, degraded: false, .. } = &*parsed else {
+        return Ok((Vec::new(), true));
+    };
+    if !eval_context.is_esm() {
+        return Ok((Vec::new(), true));
+    }
+    let mut imports = Vec::new();
+    let mut imports_other_assets = false;
+    for (i, (src, annotations)) in eval_context.imports.references().enumerate() {
+        // The same reference as the one of the analysis of the module
+        let reference = EsmAssetReferenceVc::new(
+            module.as_resolve_origin(),
+            RequestVc::parse(Value::new(src.to_string().into())),
+            Value::new(annotations.clone()),
+            eval_context.imports.has_bindings(i),
+        );
+        let index = match &*reference.get_referenced_asset().await? {
+            ReferencedAsset::Some(asset) => {
+                match EcmascriptModuleAssetVc::resolve_from(asset).await? {
+                    Some(imported) => modules.get(&imported).copied(),
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        match index {
+            Some(index) => imports.push((src.clone(), index)),
+            None => imports_other_assets = true,
+        }
+    }
+    Ok((imports, imports_other_assets))
+}
+
+/// Whether all references of `module` that put something into a chunk are
+/// static imports. Other references, like `import()` or `new URL()`, are
+/// relative to the location of the module, which changes when it's inlined.
+async fn only_imports_modules(
+    context: ChunkingContextVc,
+    module: EcmascriptModuleAssetVc,
+) -> Result<bool> {
+    for reference in module.analyze().await?.references.await?.iter() {
+        let Some(chunkable) = ChunkableAssetReferenceVc::resolve_from(reference).await? else {
+            continue;
+        };
+        if chunkable.chunking_type(context).await?.is_some()
+            && EsmAssetReferenceVc::resolve_from(reference)
+                .await?
+                .is_none()
+        {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Selects the groups of modules that are concatenated. Module `i` is inlined
+/// into module `p` when it's `inlinable`, `p` statically imports it, `p` is
+/// the only one of its `importers` and all modules that module `i` imports
+/// are inlined into it as well. Importers can be other chunk items than
+/// modules, which have an index of at least the number of modules.
+///
+/// The groups are returned in dependency order with the root, which is not
+/// inlined, last.
+fn hoisting_groups(
+    imports: &[Vec<usize>],
+    importers: &[HashSet<usize>],
+    inlinable: &[bool],
+) -> Vec<Vec<usize>> {
+    let count = imports.len();
+    let mut parents = (0..count)
+        .map(|index| {
+            if !inlinable[index] {
+                return None;
+            }
+            let mut importers = importers[index].iter().copied();
+            match (importers.next(), importers.next()) {
+                (Some(parent), None)
+                    if parent < count && parent != index && imports[parent].contains(&index) =>
+                {
+                    Some(parent)
+                }
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    // A module is only inlined when all modules it imports are inlined into it,
+    // so not inlining a module can prevent inlining its importer
+    loop {
+        let mut changed = false;
+        for index in 0..count {
+            if parents[index].is_some()
+                && imports[index]
+                    .iter()
+                    .any(|&imported| parents[imported] != Some(index))
+            {
+                parents[index] = None;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    fn visit(
+        index: usize,
+        imports: &[Vec<usize>],
+        parents: &[Option<usize>],
+        group: &mut Vec<usize>,
+    ) {
+        for &imported in &imports[index] {
+            if parents[imported] == Some(index) && !group.contains(&imported) {
+                visit(imported, imports, parents, group);
+            }
+        }
+        group.push(index);
+    }
+
+    // Modules that are inlined into each other in a cycle can't be reached from
+    // a root and stay as they are
+    (0..count)
+        .filter(|&index| parents[index].is_none())
+        .filter_map(|root| {
+            let mut group = Vec::new();
+            visit(root, imports, &parents, &mut group);
+            (group.len() > 1).then_some(group)
+        })
+        .collect()
+}
+
+/// Concatenates the `modules` of a group from [hoisting_groups] into a new
+/// module at the path of the root, which is the last module. `imports` are the
+/// import sources of each module that refer to other modules of the group.
+///
+/// The modules are concatenated after their transforms have been applied, so
+/// the new module has no transforms. Returns `None` when the transformed
+/// modules aren't plain ES modules or can't be concatenated.
+async fn concatenated_module(
+    modules: &[EcmascriptModuleAssetVc],
+    imports: Vec<HashMap<JsWord, usize>>,
+) -> Result<Option<EcmascriptModuleAssetVc>> {
+    let mut sources = Vec::with_capacity(modules.len());
+    for module in modules {
+        let module = module.await?;
+        let parsed = parse(module.source, Value::new(module.ty), module.transforms).await?;
+        let ParseResult::Ok { program, comments, source_map, degraded: false, .. } = &*parsed else {
+            return Ok(None);
+        };
+        sources.push(to_code(source_map, program, comments)?);
+    }
+
+    let code = GLOBALS.set(&Globals::new(), || -> Result<Option<String>> {
+        let cm = Arc::new(SourceMap::default());
+        let comments = SingleThreadedComments::default();
+        let count = sources.len();
+        let mut hoisted = Vec::with_capacity(count);
+        for (index, (source, imports)) in sources.into_iter().zip(imports).enumerate() {
+            let fm = cm.new_source_file(FileName::Anon, source);
+            let mut errors = Vec::new();
+            let Ok(mut module) = parse_file_as_module(
+                &fm,
+                Syntax::Es(EsConfig {
+                    jsx: true,
+                    ..Default::default()
+                }),
+                EsVersion::latest(),
+                Some(&comments),
+                &mut errors,
+            ) else {
+                return Ok(None);
+            };
+            if !errors.is_empty() {
+                return Ok(None);
+            }
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            if index + 1 < count && !is_hoistable(&module, unresolved_mark) {
+                return Ok(None);
+            }
+            hoisted.push(HoistedModule {
+                module,
+                unresolved_mark,
+                top_level_mark,
+                imports,
+            });
+        }
+        let Ok(module) = concatenate_modules(hoisted) else {
+            return Ok(None);
+        };
+        Ok(Some(to_code(&cm, &Program::Module(module), &comments)?))
+    })?;
+    let Some(code) = code else {
+        return Ok(None);
+    };
+
+    let root = modules.last().unwrap().await?;
+    let source = VirtualAssetVc::new(root.source.path(), File::from(code).into());
+    Ok(Some(EcmascriptModuleAssetVc::new(
+        source.into(),
+        root.context,
+        Value::new(EcmascriptModuleAssetType::Ecmascript),
+        EcmascriptInputTransformsVc::cell(Vec::new()),
+        root.options,
+        root.environment,
+    )))
+}
+
+pub(crate) fn to_code(
+    cm: &Arc<SourceMap>,
+    program: &Program,
+    comments: &dyn Comments,
+) -> Result<String> {
+    let mut bytes = Vec::new();
+    let mut emitter = Emitter {
+        cfg: Default::default(),
+        cm: cm.clone(),
+        comments: Some(comments),
+        wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+    };
+    emitter.emit_program(program)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    };
+
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark, SourceMap},
+        ecma::{
+            ast::{EsVersion, Module},
+            atoms::JsWord,
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::{concatenate_modules, hoisting_groups, is_hoistable, HoistedModule};
+
+    fn hoisted_module(cm: &Arc<SourceMap>, src: &str, imports: &[(&str, usize)]) -> HoistedModule {
+        let fm = cm.new_source_file(FileName::Anon, src.into());
+        let mut module = parse_file_as_module(
+            &fm,
+            Default::default(),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+        .unwrap();
+
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        HoistedModule {
+            module,
+            unresolved_mark,
+            top_level_mark,
+            imports: imports
+                .iter()
+                .map(|&(src, index)| (JsWord::from(src), index))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn emit(cm: &Arc<SourceMap>, module: &Module) -> String {
+        let mut bytes = Vec::new();
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+        };
+        emitter.emit_module(module).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn two_module_chain() {
+        run_test(false, |cm, _handler| {
+            let dep = "const helper = 1; export const value = helper + 1;";
+            let index = "import { value } from \"./dep\"; const helper = 2; export const result = \
+                         value + helper;";
+
+            let not_hoisted = hoisted_module(&cm, index, &[]);
+            assert_eq!(
+                emit(&cm, &not_hoisted.module),
+                "import{value}from\"./dep\";const helper=2;export const result=value+helper;"
+            );
+
+            let modules = vec![
+                hoisted_module(&cm, dep, &[]),
+                hoisted_module(&cm, index, &[("./dep", 0)]),
+            ];
+            assert!(modules
+                .iter()
+                .all(|m| is_hoistable(&m.module, m.unresolved_mark)));
+            let hoisted = concatenate_modules(modules).unwrap();
+            assert_eq!(
+                emit(&cm, &hoisted),
+                "const helper_0=1;const value=helper_0+1;const helper=2;export const \
+                 result=value+helper;"
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn default_export() {
+        run_test(false, |cm, _handler| {
+            let modules = vec![
+                hoisted_module(&cm, "export default 42;", &[]),
+                hoisted_module(
+                    &cm,
+                    "import answer from \"./dep\"; export { answer };",
+                    &[("./dep", 0)],
+                ),
+            ];
+            let hoisted = concatenate_modules(modules).unwrap();
+            assert_eq!(
+                emit(&cm, &hoisted),
+                "const _default=42;export{_default as answer};"
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn not_hoistable() {
+        run_test(false, |cm, _handler| {
+            let commonjs = hoisted_module(&cm, "module.exports = 1;", &[]);
+            assert!(!is_hoistable(&commonjs.module, commonjs.unresolved_mark));
+
+            let reexport = hoisted_module(&cm, "export * from \"./dep\";", &[]);
+            assert!(!is_hoistable(&reexport.module, reexport.unresolved_mark));
+
+            for src in [
+                "export const a = 1;\neval(\"a\");",
+                "export const url = new URL(\"./a.png\", import.meta.url);",
+                "export const dir = __dirname;",
+            ] {
+                let module = hoisted_module(&cm, src, &[]);
+                assert!(
+                    !is_hoistable(&module.module, module.unresolved_mark),
+                    "{src}"
+                );
+            }
+
+            // Code evaluated in the global scope can't access the bindings
+            for src in [
+                "export const a = 1;\n(0, eval)(\"a\");",
+                "export const f = new Function(\"return 1\");",
+            ] {
+                let module = hoisted_module(&cm, src, &[]);
+                assert!(
+                    is_hoistable(&module.module, module.unresolved_mark),
+                    "{src}"
+                );
+            }
+
+            let modules = vec![
+                hoisted_module(&cm, "export const a = 1;", &[]),
+                hoisted_module(
+                    &cm,
+                    "import * as dep from \"./dep\"; console.log(dep);",
+                    &[("./dep", 0)],
+                ),
+            ];
+            assert!(concatenate_modules(modules).is_err());
+
+            let modules = vec![
+                hoisted_module(&cm, "export const a = 1;", &[]),
+                hoisted_module(&cm, "import(\"./dep\");", &[("./dep", 0)]),
+            ];
+            assert!(concatenate_modules(modules).is_err());
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn groups(imports: &[&[usize]], importers: &[&[usize]], inlinable: &[bool]) -> Vec<Vec<usize>> {
+        hoisting_groups(
+            &imports
+                .iter()
+                .map(|imports| imports.to_vec())
+                .collect::<Vec<_>>(),
+            &importers
+                .iter()
+                .map(|importers| importers.iter().copied().collect::<HashSet<_>>())
+                .collect::<Vec<_>>(),
+            inlinable,
+        )
+    }
+
+    #[test]
+    fn chain_groups() {
+        // 2 imports 1, which imports 0
+        assert_eq!(
+            groups(&[&[], &[0], &[1]], &[&[1], &[2], &[]], &[true, true, true]),
+            vec![vec![0, 1, 2]]
+        );
+        // 0 is imported by 1 and 2, so neither 0 nor the modules importing it
+        // can be inlined
+        assert_eq!(
+            groups(
+                &[&[], &[0], &[0], &[1, 2]],
+                &[&[1, 2], &[3], &[3], &[]],
+                &[true, true, true, true]
+            ),
+            Vec::<Vec<usize>>::new()
+        );
+        // 0 is not inlinable, so 1, which imports it, isn't either
+        assert_eq!(
+            groups(&[&[], &[0], &[1]], &[&[1], &[2], &[]], &[false, true, true]),
+            Vec::<Vec<usize>>::new()
+        );
+        // 1 is also referenced by a chunk item that isn't a module
+        assert_eq!(
+            groups(
+                &[&[], &[0], &[1]],
+                &[&[1], &[2, 5], &[]],
+                &[true, true, true]
+            ),
+            vec![vec![0, 1]]
+        );
+    }
+
+    #[test]
+    fn cycle_groups() {
+        // 0 and 1 import each other and 2 imports 1, so 0 isn't inlined into 1
+        // as it would have to be evaluated after 1
+        assert_eq!(
+            groups(
+                &[&[1], &[0], &[1]],
+                &[&[1], &[0, 2], &[]],
+                &[true, true, true]
+            ),
+            Vec::<Vec<usize>>::new()
+        );
+        // 0 and 1 only import each other
+        assert_eq!(
+            groups(&[&[1], &[0]], &[&[1], &[0]], &[true, true]),
+            Vec::<Vec<usize>>::new()
+        );
+    }
+}
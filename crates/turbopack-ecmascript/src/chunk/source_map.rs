@@ -5,7 +5,6 @@ use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
     resolve::{ResolveResult, ResolveResultVc},
-    source_map::GenerateSourceMap,
 };
 
 use super::{EcmascriptChunkContentEntryVc, EcmascriptChunkItemVc, EcmascriptChunkVc};
@@ -35,12 +34,10 @@ impl Asset for EcmascriptChunkSourceMapAsset {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<AssetContentVc> {
-        let sm = self
-            .chunk
-            .chunk_content()
-            .generate_source_map()
-            .to_rope()
-            .await?;
+        // The maps of the chunk items are appended one after another instead
+        // of building a sectioned map of the whole chunk
+        let code = self.chunk.chunk_content().code().await?;
+        let sm = code.write_source_map(Vec::new()).await?;
         Ok(File::from(sm).into())
     }
 
@@ -83,12 +80,11 @@ impl Asset for EcmascriptChunkEntrySourceMapAsset {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<AssetContentVc> {
-        let sm = EcmascriptChunkContentEntryVc::new(self.chunk_item)
+        let code = EcmascriptChunkContentEntryVc::new(self.chunk_item)
             .await?
             .code_vc
-            .generate_source_map()
-            .to_rope()
             .await?;
+        let sm = code.write_source_map(Vec::new()).await?;
         Ok(File::from(sm).into())
     }
 
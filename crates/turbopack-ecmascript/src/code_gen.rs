@@ -1,4 +1,11 @@
-use swc_core::ecma::visit::{AstParentKind, VisitMut};
+use anyhow::Result;
+use swc_core::{
+    common::comments::Comments,
+    ecma::{
+        ast::{ModuleItem, Stmt},
+        visit::{AstParentKind, VisitMut},
+    },
+};
 use turbopack_core::chunk::ChunkingContextVc;
 
 /// impl of code generation inferred from a AssetReference.
@@ -18,6 +25,106 @@ pub struct CodeGeneration {
 
 pub trait VisitorFactory: Send + Sync {
     fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a>;
+
+    /// A name for the visitor, used for diagnostics. Defaults to the type name.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Creates the visitor for the node at `ast_path`. This allows visitors
+    /// to make decisions based on the parent chain of the node they are
+    /// applied to, e.g. with [crate::path_visitor::ParentKind::from_path].
+    /// Defaults to [VisitorFactory::create].
+    fn create_with_path<'a>(
+        &'a self,
+        _ast_path: &[AstParentKind],
+    ) -> Box<dyn VisitMut + Send + Sync + 'a> {
+        self.create()
+    }
+
+    /// Creates a visitor for the node at `ast_path` that can decline to
+    /// handle the node, see [PathVisitor]. Defaults to wrapping
+    /// [VisitorFactory::create_with_path].
+    fn create_path_visitor<'a>(&'a self, ast_path: &[AstParentKind]) -> Box<dyn PathVisitor + 'a> {
+        box PlainPathVisitor(self.create_with_path(ast_path))
+    }
+
+    /// Like [VisitorFactory::create_path_visitor], but allows to fail when the
+    /// node can't be handled. The error is reported by
+    /// [crate::path_visitor::ApplyVisitors::take_error] and stops applying
+    /// further visitors.
+    fn try_create_path_visitor<'a>(
+        &'a self,
+        ast_path: &[AstParentKind],
+    ) -> Result<Box<dyn PathVisitor + 'a>> {
+        Ok(self.create_path_visitor(ast_path))
+    }
+
+    /// Like [VisitorFactory::try_create_path_visitor], but the visitor can
+    /// also consult the comments of the module, e.g. to check for a magic
+    /// comment next to the node. Only used when comments are passed with
+    /// [crate::path_visitor::ApplyVisitors::with_comments]. Defaults to
+    /// ignoring the comments.
+    fn try_create_path_visitor_with_comments<'a>(
+        &'a self,
+        ast_path: &[AstParentKind],
+        _comments: &'a (dyn Comments + Sync),
+    ) -> Result<Box<dyn PathVisitor + 'a>> {
+        self.try_create_path_visitor(ast_path)
+    }
+
+    /// Returns a [ListVisitor] when this visitor needs access to the list
+    /// containing the node. Such visitors are not applied to the node itself.
+    fn as_list_visitor(&self) -> Option<&dyn ListVisitor> {
+        None
+    }
+}
+
+/// A visitor that is applied to the list containing the node at its path,
+/// which allows it to replace the node with multiple nodes or to insert
+/// siblings. `index` is the position of the node in the list.
+///
+/// List visitors are applied after the nodes of the list have been visited,
+/// in descending order of `index`, so they don't invalidate each other.
+pub trait ListVisitor: Send + Sync {
+    fn visit_mut_stmts(&self, _stmts: &mut Vec<Stmt>, _index: usize) {}
+
+    fn visit_mut_module_items(&self, _items: &mut Vec<ModuleItem>, _index: usize) {}
+}
+
+/// The result of applying a [PathVisitor] to a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitOutcome {
+    /// The visitor handled the node. Remaining visitors registered for the
+    /// same path are not applied.
+    Applied,
+    /// The visitor declined the node. The next visitor registered for the
+    /// same path gets a chance to handle it.
+    Skipped,
+}
+
+/// A visitor that reports whether it handled the node it was applied to.
+/// This allows to register fallback visitors for the same path.
+pub trait PathVisitor: Send + Sync {
+    /// The visitor that is applied to the node.
+    fn visitor(&mut self) -> &mut (dyn VisitMut + Send + Sync);
+
+    /// Called after the visitor has been applied.
+    fn outcome(&self) -> VisitOutcome;
+}
+
+struct PlainPathVisitor<'a>(Box<dyn VisitMut + Send + Sync + 'a>);
+
+impl PathVisitor for PlainPathVisitor<'_> {
+    fn visitor(&mut self) -> &mut (dyn VisitMut + Send + Sync) {
+        &mut *self.0
+    }
+
+    fn outcome(&self) -> VisitOutcome {
+        // Plain visitors never consume the node, so all of them are applied
+        // in registration order.
+        VisitOutcome::Skipped
+    }
 }
 
 #[turbo_tasks::value_trait]
@@ -64,19 +171,36 @@ macro_rules! create_visitor {
             $name: T,
         }
 
+        /// Applies the visitor to a single node. Visiting more than one node
+        /// means the visitor recursed into the children of the node it was
+        /// applied to, which would double-apply nested visitors. This is
+        /// checked in debug builds.
+        struct ShallowVisitor<'a, T: Fn(&mut swc_core::ecma::ast::$ty) + Send + Sync> {
+            visitor: &'a Visitor<T>,
+            visited: bool,
+        }
+
         impl<T: Fn(&mut swc_core::ecma::ast::$ty) + Send + Sync> $crate::code_gen::VisitorFactory
             for Box<Visitor<T>>
         {
             fn create<'a>(&'a self) -> Box<dyn swc_core::ecma::visit::VisitMut + Send + Sync + 'a> {
-                box &**self
+                box ShallowVisitor {
+                    visitor: &**self,
+                    visited: false,
+                }
             }
         }
 
         impl<'a, T: Fn(&mut swc_core::ecma::ast::$ty) + Send + Sync> swc_core::ecma::visit::VisitMut
-            for &'a Visitor<T>
+            for ShallowVisitor<'a, T>
         {
             fn $name(&mut self, $arg: &mut swc_core::ecma::ast::$ty) {
-                (self.$name)($arg);
+                debug_assert!(
+                    !std::mem::replace(&mut self.visited, true),
+                    "visitor must be shallow, but {} has been applied to multiple nodes",
+                    stringify!($name)
+                );
+                (self.visitor.$name)($arg);
             }
         }
 
@@ -0,0 +1,171 @@
+use swc_core::{
+    base::SwcComments,
+    common::{
+        comments::{Comment, Comments},
+        BytePos, Spanned,
+    },
+    ecma::{
+        ast::{ModuleItem, Program, Stmt},
+        visit::{Visit, VisitWith},
+    },
+};
+
+/// Returns true for comments that must be kept in minified output, i.e.
+/// `/*! ... */` banners and comments containing `@license` or `@preserve`.
+pub fn is_legal_comment(comment: &Comment) -> bool {
+    let text = &*comment.text;
+    text.starts_with('!') || text.contains("@license") || text.contains("@preserve")
+}
+
+/// Collects the legal comments of the parsed module for emitting `program`.
+///
+/// Transforms might remove or move the nodes the comments were attached to,
+/// so every comment is attached as a leading comment to the closest statement
+/// of `program` that starts after it, or to the last statement when there is
+/// none.
+pub fn legal_comments(comments: &SwcComments, program: &Program) -> SwcComments {
+    let mut legal = comments
+        .leading
+        .iter()
+        .chain(comments.trailing.iter())
+        .flat_map(|entry| entry.value().clone())
+        .filter(is_legal_comment)
+        .collect::<Vec<_>>();
+    legal.sort_by_key(|comment| comment.span.lo);
+
+    let mut positions = StatementPositions(Vec::new());
+    program.visit_with(&mut positions);
+    let mut positions = positions.0;
+    positions.sort();
+
+    let result = SwcComments::default();
+    for comment in legal {
+        let index = positions.partition_point(|&pos| pos < comment.span.hi);
+        if let Some(&pos) = positions.get(index).or_else(|| positions.last()) {
+            result.add_leading(pos, comment);
+        }
+    }
+    result
+}
+
+/// Collects the start positions of all statements in the program.
+struct StatementPositions(Vec<BytePos>);
+
+impl Visit for StatementPositions {
+    fn visit_module_item(&mut self, item: &ModuleItem) {
+        if let ModuleItem::ModuleDecl(decl) = item {
+            self.push(decl.span().lo);
+        }
+        item.visit_children_with(self);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.push(stmt.span().lo);
+        stmt.visit_children_with(self);
+    }
+}
+
+impl StatementPositions {
+    fn push(&mut self, pos: BytePos) {
+        if !pos.is_dummy() {
+            self.0.push(pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_core::{
+        base::SwcComments,
+        common::{errors::HANDLER, FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Module, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+        },
+        testing::run_test,
+    };
+
+    use super::legal_comments;
+
+    fn minify(cm: &Arc<SourceMap>, src: &str, transform: impl FnOnce(&mut Module)) -> String {
+        let fm = cm.new_source_file(FileName::Anon, src.into());
+        let comments = SwcComments::default();
+        let mut module = parse_file_as_module(
+            &fm,
+            Default::default(),
+            EsVersion::latest(),
+            Some(&comments),
+            &mut vec![],
+        )
+        .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+        .unwrap();
+        transform(&mut module);
+
+        let program = Program::Module(module);
+        let comments = legal_comments(&comments, &program);
+        let mut bytes = Vec::new();
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: Some(&comments),
+            wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+        };
+        emitter.emit_program(&program).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn keeps_banner() {
+        run_test(false, |cm, _handler| {
+            let output = minify(
+                &cm,
+                "/*! banner v1.0 | MIT */\n// ordinary comment\nconst a = 1;\n/* block */\nexport \
+                 { a };",
+                |_| {},
+            );
+            assert!(output.contains("/*! banner v1.0 | MIT */"), "{output}");
+            assert!(!output.contains("ordinary comment"), "{output}");
+            assert!(!output.contains("block"), "{output}");
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn license_annotations() {
+        run_test(false, |cm, _handler| {
+            let output = minify(
+                &cm,
+                "/** @license MIT */\nfunction a() {}\n// @preserve this\nfunction b() {}",
+                |_| {},
+            );
+            assert!(output.contains("@license MIT"), "{output}");
+            assert!(output.contains("@preserve this"), "{output}");
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn removed_node() {
+        run_test(false, |cm, _handler| {
+            let output = minify(
+                &cm,
+                "/*! banner */\nimport \"./polyfill\";\nconst a = 1;",
+                |module| {
+                    module.body.remove(0);
+                },
+            );
+            assert!(output.starts_with("/*! banner */"), "{output}");
+            assert!(!output.contains("polyfill"), "{output}");
+            Ok(())
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,302 @@
+//! A dump of the references between modules, to debug how a module graph is
+//! built.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use indexmap::IndexSet;
+use serde::Serialize;
+use swc_core::common::{SourceMap, Span};
+use turbo_tasks::{Value, ValueToString};
+use turbopack_core::{asset::Asset, reference::AssetReferenceVc};
+
+use crate::{
+    parse::{parse, ParseResult},
+    path_visitor::span_at_path,
+    references::{
+        cjs::{CjsAssetReferenceVc, CjsRequireAssetReferenceVc, CjsRequireResolveAssetReferenceVc},
+        esm::{EsmAssetReferenceVc, EsmAsyncAssetReferenceVc},
+        worker::WorkerAssetReferenceVc,
+        AstPathVc,
+    },
+    utils::stringify_str,
+    EcmascriptModuleAssetVc,
+};
+
+/// The kind of a reference of a module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyKind {
+    /// `import ... from "..."` or `export ... from "..."`.
+    EsmImport,
+    /// `import("...")`.
+    DynamicImport,
+    /// `require("...")`.
+    Require,
+    /// `require.resolve("...")`.
+    RequireResolve,
+    /// A CommonJS reference that isn't a call, e.g. a re-export.
+    CommonJs,
+    /// `new Worker(new URL("...", import.meta.url))`.
+    Worker,
+    /// Any other reference, e.g. to a `package.json` or a source map.
+    Other,
+}
+
+impl DependencyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyKind::EsmImport => "esm-import",
+            DependencyKind::DynamicImport => "dynamic-import",
+            DependencyKind::Require => "require",
+            DependencyKind::RequireResolve => "require-resolve",
+            DependencyKind::CommonJs => "common-js",
+            DependencyKind::Worker => "worker",
+            DependencyKind::Other => "other",
+        }
+    }
+}
+
+/// A position in a source file. The line is 1-based, the column 0-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SourcePos {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SourceSpan {
+    pub start: SourcePos,
+    pub end: SourcePos,
+}
+
+impl SourceSpan {
+    fn new(source_map: &SourceMap, span: Span) -> Self {
+        let pos = |pos| {
+            let loc = source_map.lookup_char_pos(pos);
+            SourcePos {
+                line: loc.line,
+                column: loc.col.0,
+            }
+        };
+        SourceSpan {
+            start: pos(span.lo),
+            end: pos(span.hi),
+        }
+    }
+}
+
+/// A reference of a module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyEdge {
+    /// The path of the referencing module.
+    pub from: String,
+    pub kind: DependencyKind,
+    /// The description of the reference.
+    pub reference: String,
+    /// The paths of the assets that the reference resolves to. Empty when the
+    /// reference can't be resolved.
+    pub targets: Vec<String>,
+    /// Where the reference is created in the referencing module. Only known
+    /// for references of a single expression, like `require()` or `import()`,
+    /// not for ESM imports, which are shared by all imports of a request.
+    pub span: Option<SourceSpan>,
+}
+
+/// The references of modules, see [dependency_graph].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DependencyGraph {
+    /// The paths of the modules whose references are listed.
+    pub modules: Vec<String>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl DependencyGraph {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Formats the graph in the DOT language of Graphviz. Edges are labeled
+    /// with their kind, unresolved references point to a dashed node.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph {\n".to_string();
+        for module in self.modules.iter() {
+            writeln!(dot, "  {};", stringify_str(module)).unwrap();
+        }
+        for (index, edge) in self.edges.iter().enumerate() {
+            let from = stringify_str(&edge.from);
+            let kind = edge.kind.as_str();
+            if edge.targets.is_empty() {
+                let unresolved = format!("unresolved {index}");
+                writeln!(
+                    dot,
+                    "  {} [label={}, style=dashed];",
+                    stringify_str(&unresolved),
+                    stringify_str(&edge.reference)
+                )
+                .unwrap();
+                writeln!(
+                    dot,
+                    "  {from} -> {} [label=\"{kind}\", style=dashed];",
+                    stringify_str(&unresolved)
+                )
+                .unwrap();
+            }
+            for target in edge.targets.iter() {
+                writeln!(
+                    dot,
+                    "  {from} -> {} [label=\"{kind}\"];",
+                    stringify_str(target)
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Collects the references of `entry`. When `recursive` is set, the
+/// references of all ecmascript modules that are reachable from `entry` are
+/// collected too.
+pub async fn dependency_graph(
+    entry: EcmascriptModuleAssetVc,
+    recursive: bool,
+) -> Result<DependencyGraph> {
+    let mut modules = IndexSet::new();
+    modules.insert(entry.resolve().await?);
+    let mut graph = DependencyGraph::default();
+    // Modules are appended to `modules` while they are visited
+    while let Some(&module) = modules.get_index(graph.modules.len()) {
+        let from = module.path().to_string().await?.clone_value();
+        let this = module.await?;
+        let parsed = parse(this.source, Value::new(this.ty), this.transforms).await?;
+        for reference in module.analyze().await?.references.await?.iter() {
+            let (kind, path) = reference_kind(*reference).await?;
+            let span = match (&*parsed, path) {
+                (
+                    ParseResult::Ok {
+                        program,
+                        source_map,
+                        ..
+                    },
+                    Some(path),
+                ) => span_at_path(program, &path.await?)
+                    .map(|span| SourceSpan::new(source_map, span)),
+                _ => None,
+            };
+            let mut targets = Vec::new();
+            for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                targets.push(asset.path().to_string().await?.clone_value());
+                if recursive {
+                    if let Some(module) = EcmascriptModuleAssetVc::resolve_from(*asset).await? {
+                        modules.insert(module);
+                    }
+                }
+            }
+            graph.edges.push(DependencyEdge {
+                from: from.clone(),
+                kind,
+                reference: reference.to_string().await?.clone_value(),
+                targets,
+                span,
+            });
+        }
+        graph.modules.push(from);
+    }
+    Ok(graph)
+}
+
+/// The kind of `reference` and the path of the expression that creates it.
+async fn reference_kind(
+    reference: AssetReferenceVc,
+) -> Result<(DependencyKind, Option<AstPathVc>)> {
+    if EsmAssetReferenceVc::resolve_from(reference)
+        .await?
+        .is_some()
+    {
+        return Ok((DependencyKind::EsmImport, None));
+    }
+    if let Some(reference) = EsmAsyncAssetReferenceVc::resolve_from(reference).await? {
+        return Ok((DependencyKind::DynamicImport, Some(reference.await?.path)));
+    }
+    if let Some(reference) = CjsRequireAssetReferenceVc::resolve_from(reference).await? {
+        return Ok((DependencyKind::Require, Some(reference.await?.path)));
+    }
+    if let Some(reference) = CjsRequireResolveAssetReferenceVc::resolve_from(reference).await? {
+        return Ok((DependencyKind::RequireResolve, Some(reference.await?.path)));
+    }
+    if CjsAssetReferenceVc::resolve_from(reference)
+        .await?
+        .is_some()
+    {
+        return Ok((DependencyKind::CommonJs, None));
+    }
+    if let Some(reference) = WorkerAssetReferenceVc::resolve_from(reference).await? {
+        return Ok((DependencyKind::Worker, Some(reference.await?.path)));
+    }
+    Ok((DependencyKind::Other, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DependencyEdge, DependencyGraph, DependencyKind, SourcePos, SourceSpan};
+
+    /// `import { a } from "./lib"; import("./lazy");`
+    fn graph() -> DependencyGraph {
+        DependencyGraph {
+            modules: vec!["project/entry.js".to_string()],
+            edges: vec![
+                DependencyEdge {
+                    from: "project/entry.js".to_string(),
+                    kind: DependencyKind::EsmImport,
+                    reference: "import(esm) ./lib".to_string(),
+                    targets: vec!["project/lib.js".to_string()],
+                    span: None,
+                },
+                DependencyEdge {
+                    from: "project/entry.js".to_string(),
+                    kind: DependencyKind::DynamicImport,
+                    reference: "dynamic import ./lazy".to_string(),
+                    targets: vec![],
+                    span: Some(SourceSpan {
+                        start: SourcePos {
+                            line: 1,
+                            column: 28,
+                        },
+                        end: SourcePos {
+                            line: 1,
+                            column: 44,
+                        },
+                    }),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn json() {
+        let json: serde_json::Value = serde_json::from_str(&graph().to_json().unwrap()).unwrap();
+        let edges = json["edges"].as_array().unwrap();
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0]["kind"], "esm-import");
+        assert_eq!(edges[0]["targets"][0], "project/lib.js");
+        assert!(edges[0]["span"].is_null());
+        assert_eq!(edges[1]["kind"], "dynamic-import");
+        assert_eq!(edges[1]["span"]["start"]["column"], 28);
+    }
+
+    #[test]
+    fn dot() {
+        assert_eq!(
+            graph().to_dot(),
+            r#"digraph {
+  "project/entry.js";
+  "project/entry.js" -> "project/lib.js" [label="esm-import"];
+  "unresolved 1" [label="dynamic import ./lazy", style=dashed];
+  "project/entry.js" -> "unresolved 1" [label="dynamic-import", style=dashed];
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Module level directives of React Server Components.
+//!
+//! A module that starts with `"use client"` is the entry of the client part of
+//! the module graph, one that starts with `"use server"` exports server
+//! actions. The directive is kept in the output of the module.
+//!
+//! Other directives, like `"use strict"` or `"use asm"`, only have an effect
+//! at the start of the module too. Transforms and code generation that insert
+//! statements at the start of the module put them before the directives, so
+//! the directive prologue is moved back to the start afterwards, see
+//! [restore_directive_prologue].
+
+use std::mem::take;
+
+use swc_core::{
+    common::{errors::Handler, Span},
+    ecma::ast::{Expr, ExprStmt, Lit, Program, Stmt},
+};
+
+/// The directive of a module, see [module_directive].
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug, Clone, Copy)]
+pub enum ModuleDirective {
+    None,
+    UseClient,
+    UseServer,
+}
+
+/// Reads the directive from the directive prologue of `program`, i.e. the
+/// string literal statements at its start. Strings after the first other
+/// statement are not directives and are ignored.
+///
+/// A module can't be both a client and a server module. When it has both
+/// directives, an error is emitted to `handler` and the first directive wins.
+pub fn module_directive(program: &Program, handler: &Handler) -> ModuleDirective {
+    let mut directive: Option<(ModuleDirective, Span)> = None;
+    for (value, span) in directive_prologue(program) {
+        let found = match value {
+            "use client" => ModuleDirective::UseClient,
+            "use server" => ModuleDirective::UseServer,
+            _ => continue,
+        };
+        match directive {
+            None => directive = Some((found, span)),
+            Some((first, first_span)) if first != found => {
+                handler
+                    .struct_span_err(
+                        span,
+                        "A module can't have both a \"use client\" and a \"use server\" directive",
+                    )
+                    .span_note(first_span, "the other directive is here")
+                    .emit();
+            }
+            Some(_) => {}
+        }
+    }
+    directive.map_or(ModuleDirective::None, |(directive, _)| directive)
+}
+
+/// The values and spans of the string literal statements at the start of
+/// `program`. Parenthesized strings are not directives.
+fn directive_prologue(program: &Program) -> impl Iterator<Item = (&str, Span)> {
+    let stmts: Box<dyn Iterator<Item = Option<&Stmt>> + '_> = match program {
+        Program::Module(m) => box m.body.iter().map(|item| item.as_stmt()),
+        Program::Script(s) => box s.body.iter().map(Some),
+    };
+    stmts.map_while(|stmt| match stmt? {
+        Stmt::Expr(ExprStmt {
+            expr: box Expr::Lit(Lit::Str(s)),
+            span,
+        }) => Some((&*s.value, *span)),
+        _ => None,
+    })
+}
+
+/// The spans of the directives in the directive prologue of `program`, to
+/// restore the prologue with [restore_directive_prologue].
+pub fn directive_prologue_spans(program: &Program) -> Vec<Span> {
+    directive_prologue(program)
+        .map(|(_, span)| span)
+        .filter(|span| !span.is_dummy())
+        .collect()
+}
+
+/// Moves the directives of `prologue`, as returned by
+/// [directive_prologue_spans], back to the start of `program`, in their
+/// original order. Statements that were inserted before them, like helpers,
+/// are placed after them. Directives that were removed stay removed.
+pub fn restore_directive_prologue(program: &mut Program, prologue: &[Span]) {
+    if prologue.is_empty() {
+        return;
+    }
+    match program {
+        Program::Module(m) => move_to_front(&mut m.body, prologue, |item| {
+            item.as_stmt().and_then(directive_span)
+        }),
+        Program::Script(s) => move_to_front(&mut s.body, prologue, directive_span),
+    }
+}
+
+/// The span of `stmt` when it's a string literal statement.
+fn directive_span(stmt: &Stmt) -> Option<Span> {
+    match stmt {
+        Stmt::Expr(ExprStmt {
+            expr: box Expr::Lit(Lit::Str(_)),
+            span,
+        }) => Some(*span),
+        _ => None,
+    }
+}
+
+fn move_to_front<T>(
+    body: &mut Vec<T>,
+    prologue: &[Span],
+    directive_span: impl Fn(&T) -> Option<Span>,
+) {
+    let position = |item: &T| {
+        let span = directive_span(item)?;
+        prologue.iter().position(|directive| *directive == span)
+    };
+    let (mut directives, rest): (Vec<T>, Vec<T>) = take(body)
+        .into_iter()
+        .partition(|item| position(item).is_some());
+    directives.sort_by_key(|item| position(item));
+    *body = directives;
+    body.extend(rest);
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName},
+        ecma::{
+            ast::{EsVersion, Program, Stmt},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_program,
+        },
+        quote,
+        testing::run_test,
+    };
+
+    use super::{
+        directive_prologue_spans, module_directive, restore_directive_prologue, ModuleDirective,
+    };
+    use crate::references::esm::base::insert_hoisted_stmt;
+
+    /// The directive of `src` and whether an error was emitted.
+    fn directive(src: &str) -> (ModuleDirective, bool) {
+        run_test(false, |cm, handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let program = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let directive = module_directive(&program, handler);
+            Ok((directive, handler.has_errors()))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn use_client() {
+        assert_eq!(
+            directive("'use client';\nimport { useState } from 'react';\nexport default 1;"),
+            (ModuleDirective::UseClient, false)
+        );
+        assert_eq!(
+            directive("'use strict';\n\"use server\";\nexport async function action() {}"),
+            (ModuleDirective::UseServer, false)
+        );
+        assert_eq!(
+            directive("export default 1;"),
+            (ModuleDirective::None, false)
+        );
+    }
+
+    #[test]
+    fn misplaced_directive() {
+        assert_eq!(
+            directive("import React from 'react';\n'use client';\nexport default 1;"),
+            (ModuleDirective::None, false)
+        );
+        assert_eq!(
+            directive("('use client');\nexport default 1;"),
+            (ModuleDirective::None, false)
+        );
+        assert_eq!(
+            directive("function f() {\n  'use client';\n}"),
+            (ModuleDirective::None, false)
+        );
+    }
+
+    #[test]
+    fn conflicting_directives() {
+        assert_eq!(
+            directive("'use client';\n'use server';\nexport default 1;"),
+            (ModuleDirective::UseClient, true)
+        );
+    }
+
+    /// Applies `transform` to `src` and restores the directive prologue.
+    /// Returns the minified output.
+    fn transformed(src: &str, transform: impl FnOnce(&mut Program)) -> String {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let mut program = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let prologue = directive_prologue_spans(&program);
+            transform(&mut program);
+            restore_directive_prologue(&mut program, &prologue);
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn prologue_after_helper_injection() {
+        let src = "'use strict';\nimport a from './a';\nfoo(a);";
+        let output = transformed(src, |program| {
+            insert_hoisted_stmt(program, quote!("helper();" as Stmt));
+        });
+        assert!(output.starts_with(r#"'use strict';helper();"#), "{output}");
+        // Scripts too
+        let output = transformed("'use asm';\nfunction f() {}", |program| {
+            let Program::Script(script) = program else {
+                unreachable!();
+            };
+            script.body.insert(0, quote!("helper();" as Stmt));
+        });
+        assert_eq!(output, "'use asm';helper();function f(){}");
+    }
+
+    #[test]
+    fn reordered_prologue() {
+        let output = transformed("'use strict';\n'use asm';\nfoo();", |program| {
+            let Program::Script(script) = program else {
+                unreachable!();
+            };
+            script.body.reverse();
+        });
+        assert_eq!(output, "'use strict';'use asm';foo();");
+        // Strings that weren't directives are left alone
+        let output = transformed("foo();\n'use strict';", |program| {
+            let Program::Script(script) = program else {
+                unreachable!();
+            };
+            script.body.reverse();
+        });
+        assert_eq!(output, "'use strict';foo();");
+    }
+}
@@ -0,0 +1,407 @@
+//! Helpers are functions that code generation injects into modules, e.g. the
+//! function that a `require.context` call evaluates to. Instead of inlining a
+//! helper into every module that uses it, it's placed into a module of its
+//! own, which all modules of a chunk share.
+//!
+//! Helpers are identified by their code, so identical helpers end up as the
+//! same module. The module id of that module is what the call sites refer to,
+//! which keeps the references consistent across modules.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use swc_core::{
+    common::{SourceMap, DUMMY_SP},
+    ecma::{
+        ast::{
+            Expr, ExprStmt, Function, Ident, MemberProp, MetaPropExpr, Module, ModuleItem, Number,
+            ParenExpr, PropName, Stmt, Str, ThisExpr,
+        },
+        codegen::{text_writer::JsWriter, Emitter},
+        visit::{Visit, VisitMut, VisitMutWith, VisitWith},
+    },
+    quote,
+};
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{File, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
+    reference::AssetReferencesVc,
+};
+
+use crate::{
+    chunk::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+    },
+    utils::module_id_to_lit,
+};
+
+/// Free variables that refer to the state of the module they are used in. The
+/// `__turbopack_*` variables are bound to the module as well.
+const MODULE_BINDINGS: &[&str] = &[
+    "module",
+    "exports",
+    "require",
+    "arguments",
+    "__dirname",
+    "__filename",
+];
+
+/// The code of a helper, which is its structural identity.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HelperDefinition {
+    name: String,
+    code: String,
+}
+
+impl HelperDefinition {
+    /// Creates the definition of a helper that evaluates to `expr`. Returns
+    /// `None` when `expr` might use the state of the module it's used in,
+    /// which can't be shared. This is conservative: any variable with the
+    /// name of a module binding prevents sharing, even when it's shadowed.
+    pub fn new(name: &str, expr: &Expr) -> Option<Self> {
+        let mut finder = ModuleStateFinder::default();
+        expr.visit_with(&mut finder);
+        if finder.found {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            code: normalized_code(expr),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// A stable identifier of the helper, which is derived from its code.
+    pub fn id(&self) -> String {
+        helper_id(&self.name, &self.code)
+    }
+}
+
+fn helper_id(name: &str, code: &str) -> String {
+    format!("{name}-{}", encode_hex(hash_xxh3_hash64(code.as_bytes())))
+}
+
+/// The code of the module that exports the helper.
+fn module_code(code: &str) -> String {
+    format!("__turbopack_export_value__({code});")
+}
+
+/// Emits `expr` without formatting, comments and the raw representation of
+/// literals, so helpers that only differ in those are identical.
+fn normalized_code(expr: &Expr) -> String {
+    let mut expr = expr.clone();
+    expr.visit_mut_with(&mut DropRaw);
+    let module = Module {
+        span: DUMMY_SP,
+        body: vec![ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+            span: DUMMY_SP,
+            expr: box Expr::Paren(ParenExpr {
+                span: DUMMY_SP,
+                expr: box expr,
+            }),
+        }))],
+        shebang: None,
+    };
+
+    let cm = Arc::new(SourceMap::default());
+    let mut bytes = Vec::new();
+    let mut emitter = Emitter {
+        cfg: swc_core::ecma::codegen::Config {
+            minify: true,
+            ..Default::default()
+        },
+        cm: cm.clone(),
+        comments: None,
+        wr: JsWriter::new(cm, "\n", &mut bytes, None),
+    };
+    emitter
+        .emit_module(&module)
+        .expect("emitting into a Vec can't fail");
+    String::from_utf8(bytes)
+        .expect("the emitter emits valid utf-8")
+        .trim_end_matches(|c| c == ';' || c == '\n')
+        .to_string()
+}
+
+struct DropRaw;
+
+impl VisitMut for DropRaw {
+    fn visit_mut_str(&mut self, s: &mut Str) {
+        s.raw = None;
+    }
+
+    fn visit_mut_number(&mut self, n: &mut Number) {
+        n.raw = None;
+    }
+}
+
+/// Looks for uses of the state of the module, i.e. module bindings,
+/// `import.meta` and a top-level `this`.
+#[derive(Default)]
+struct ModuleStateFinder {
+    in_function: bool,
+    found: bool,
+}
+
+impl Visit for ModuleStateFinder {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if MODULE_BINDINGS.contains(&&*ident.sym) || ident.sym.starts_with("__turbopack_") {
+            self.found = true;
+        }
+    }
+
+    // Property names are not variables, e.g. `obj.module`
+    fn visit_member_prop(&mut self, prop: &MemberProp) {
+        if let MemberProp::Computed(prop) = prop {
+            prop.visit_with(self);
+        }
+    }
+
+    fn visit_prop_name(&mut self, name: &PropName) {
+        if let PropName::Computed(name) = name {
+            name.visit_with(self);
+        }
+    }
+
+    fn visit_this_expr(&mut self, _: &ThisExpr) {
+        if !self.in_function {
+            self.found = true;
+        }
+    }
+
+    fn visit_meta_prop_expr(&mut self, _: &MetaPropExpr) {
+        self.found = true;
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        let in_function = std::mem::replace(&mut self.in_function, true);
+        function.visit_children_with(self);
+        self.in_function = in_function;
+    }
+}
+
+/// The module of a helper. It's placed into the chunks of the modules that
+/// use it by including it in the resolve result of the reference that needs
+/// the helper.
+#[turbo_tasks::value]
+pub struct EcmascriptHelperAsset {
+    root: FileSystemPathVc,
+    name: String,
+    code: String,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptHelperAssetVc {
+    /// Identical helpers of the same `root` are the same asset.
+    #[turbo_tasks::function]
+    pub fn new(root: FileSystemPathVc, name: String, code: String) -> Self {
+        Self::cell(EcmascriptHelperAsset { root, name, code })
+    }
+}
+
+impl EcmascriptHelperAssetVc {
+    pub fn from_definition(root: FileSystemPathVc, definition: &HelperDefinition) -> Self {
+        Self::new(root, definition.name.clone(), definition.code.clone())
+    }
+
+    /// An expression that evaluates to the helper in a module of `context`.
+    pub async fn expr(self, context: ChunkingContextVc) -> Result<Expr> {
+        let id = self
+            .as_ecmascript_chunk_placeable()
+            .as_chunk_item(context)
+            .id()
+            .await?;
+        Ok(quote!(
+            "__turbopack_require__($id)" as Expr,
+            id: Expr = module_id_to_lit(&id)
+        ))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EcmascriptHelperAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.root.join(&format!(
+            "__turbopack_helpers__/{}.js",
+            helper_id(&self.name, &self.code)
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        File::from(module_code(&self.code)).into()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for EcmascriptHelperAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(self_vc: EcmascriptHelperAssetVc, context: ChunkingContextVc) -> ChunkVc {
+        EcmascriptChunkVc::new(context, self_vc.as_ecmascript_chunk_placeable()).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkPlaceable for EcmascriptHelperAsset {
+    #[turbo_tasks::function]
+    fn as_chunk_item(
+        self_vc: EcmascriptHelperAssetVc,
+        context: ChunkingContextVc,
+    ) -> EcmascriptChunkItemVc {
+        EcmascriptHelperChunkItemVc::cell(EcmascriptHelperChunkItem {
+            helper: self_vc,
+            context,
+        })
+        .into()
+    }
+
+    /// The helper is the value of `module.exports`.
+    #[turbo_tasks::function]
+    fn get_exports(&self) -> EcmascriptExportsVc {
+        EcmascriptExports::Value.cell()
+    }
+}
+
+#[turbo_tasks::value]
+struct EcmascriptHelperChunkItem {
+    helper: EcmascriptHelperAssetVc,
+    context: ChunkingContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for EcmascriptHelperChunkItem {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "{} (helper)",
+            self.helper.path().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkItem for EcmascriptHelperChunkItem {
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkItem for EcmascriptHelperChunkItem {
+    #[turbo_tasks::function]
+    fn chunking_context(&self) -> ChunkingContextVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let helper = self.helper.await?;
+        Ok(EcmascriptChunkItemContent {
+            inner_code: module_code(&helper.code).into(),
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexSet;
+    use swc_core::{
+        common::{errors::HANDLER, FileName},
+        ecma::{
+            ast::{EsVersion, Expr, ModuleItem, Stmt},
+            parser::parse_file_as_module,
+        },
+        testing::run_test,
+    };
+
+    use super::{module_code, HelperDefinition};
+
+    /// The helper that the module `src` defines as `const helper = ...`.
+    fn helper(src: &str) -> Option<HelperDefinition> {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let init = module
+                .body
+                .iter()
+                .find_map(|item| match item {
+                    ModuleItem::Stmt(Stmt::Decl(decl)) => decl.as_var()?.decls[0].init.clone(),
+                    _ => None,
+                })
+                .unwrap();
+            let init: Expr = *init;
+            Ok(HelperDefinition::new("interop", &init))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn identical_helpers() {
+        // Two modules that need the same interop helper, formatted differently
+        let a = helper(
+            "const helper = (obj) => obj && obj.__esModule ? obj : { default: obj \
+             };\nhelper(require_a());",
+        )
+        .unwrap();
+        let b = helper(
+            "import x from 'b';\n// Interop\nconst helper = (obj) =>\n  obj && \
+             obj[\"__esModule\"] ? obj\n  : { 'default': obj };",
+        )
+        .unwrap();
+        // `obj["__esModule"]` is a different expression
+        assert_ne!(a, b);
+
+        let c = helper("let helper = obj => obj && obj.__esModule ? obj : {default: obj}").unwrap();
+        assert_eq!(a, c);
+        assert_eq!(a.id(), c.id());
+
+        let definitions = [a.clone(), b, c].into_iter().collect::<IndexSet<_>>();
+        assert_eq!(definitions.len(), 2);
+        let output = definitions
+            .iter()
+            .map(|definition| module_code(definition.code()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(output.matches(&module_code(a.code())).count(), 1);
+    }
+
+    #[test]
+    fn module_state() {
+        assert!(helper("const helper = () => module.exports;").is_none());
+        assert!(helper("const helper = (id) => __turbopack_require__(id);").is_none());
+        assert!(helper("const helper = () => this;").is_none());
+        assert!(helper("const helper = () => import.meta.url;").is_none());
+        // Property names are not module bindings
+        assert!(helper("const helper = (obj) => ({ module: obj.exports });").is_some());
+        // `this` of a function is bound when it's called
+        assert!(helper("const helper = function () { return this; };").is_some());
+    }
+}
@@ -7,38 +7,61 @@
 #![recursion_limit = "256"]
 
 pub mod analyzer;
+pub mod ascii_only;
 pub mod chunk;
 pub mod chunk_group_files_asset;
 pub mod code_gen;
+pub mod comments;
+pub mod dependency_graph;
+pub mod directives;
 mod errors;
+pub mod helper;
+pub mod magic_comments;
 pub mod magic_identifier;
 pub(crate) mod parse;
-mod path_visitor;
+pub mod parse_cache;
+pub mod path_visitor;
 pub(crate) mod references;
 pub mod resolve;
+pub mod side_effects;
 pub(crate) mod special_cases;
+pub mod top_level_await;
 pub(crate) mod transform;
+pub mod tree_shake;
+pub mod type_exports;
 pub mod typescript;
 pub mod utils;
 pub mod webpack;
 
+use std::collections::{BTreeMap, HashMap, HashSet};
+
 use anyhow::Result;
 use chunk::{
     EcmascriptChunkItem, EcmascriptChunkItemVc, EcmascriptChunkPlaceablesVc, EcmascriptChunkVc,
 };
 use code_gen::CodeGenerateableVc;
-use parse::{parse, ParseResult, ParseResultSourceMap};
+use parse::{parse, renamed_identifiers, ParseResult, ParseResultSourceMap};
 use path_visitor::ApplyVisitors;
-use references::AnalyzeEcmascriptModuleResult;
+pub use references::esm::{used_exports, UsedExports, UsedExportsVc};
+use references::{
+    esm::{base::ReferencedAsset, EsmAssetReferenceVc},
+    AnalyzeEcmascriptModuleResult,
+};
 use swc_core::{
-    common::GLOBALS,
+    common::{comments::Comments, GLOBALS},
     ecma::{
         codegen::{text_writer::JsWriter, Emitter},
         visit::{VisitMutWith, VisitMutWithPath},
     },
 };
-pub use transform::{EcmascriptInputTransform, EcmascriptInputTransformsVc};
-use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString, ValueToStringVc};
+pub use transform::{
+    inline_defines, DecoratorsKind, EcmascriptInputTransform, EcmascriptInputTransformsVc,
+    TransformPlugin, TransformPluginStage, TransformPluginVc,
+};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    TryJoinIterExt, Value, ValueToString, ValueToStringVc,
+};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
@@ -50,15 +73,22 @@ use turbopack_core::{
 };
 
 use self::{
+    ascii_only::AsciiOnlyTemplates,
     chunk::{
         EcmascriptChunkItemContent, EcmascriptChunkItemContentVc, EcmascriptChunkItemOptions,
         EcmascriptExportsVc,
     },
+    comments::legal_comments,
+    directives::{
+        directive_prologue_spans, restore_directive_prologue, ModuleDirective, ModuleDirectiveVc,
+    },
     references::AnalyzeEcmascriptModuleResultVc,
+    side_effects::module_has_side_effects,
 };
 use crate::{
     chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc},
     references::analyze_ecmascript_module,
+    transform::apply_plugins,
 };
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -69,6 +99,40 @@ pub enum EcmascriptModuleAssetType {
     TypescriptDeclaration,
 }
 
+/// Options for the analysis and code generation of ecmascript modules, which,
+/// unlike [EcmascriptInputTransform]s, don't change the parsed program.
+#[turbo_tasks::value(shared)]
+#[derive(Default, Clone, Debug)]
+pub struct EcmascriptOptions {
+    /// Removes the branch of `if` statements and conditional expressions
+    /// that isn't taken when their condition is known at build time, e.g.
+    /// `process.env.NODE_ENV === "production"`.
+    pub fold_constant_conditions: bool,
+    /// Values of `process.env` variables that are inlined by the analysis,
+    /// e.g. `NODE_ENV`.
+    pub process_env: BTreeMap<String, String>,
+    /// Values of `import.meta.env` variables that are inlined by the
+    /// analysis, e.g. `MODE`.
+    pub import_meta_env: BTreeMap<String, String>,
+    /// Adds references to the static CSS of CSS-in-JS tagged templates with
+    /// these tag names, e.g. `styled` and `css`, for extraction at build time.
+    pub css_in_js_tags: Vec<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptOptionsVc {
+    #[turbo_tasks::function]
+    pub fn default() -> Self {
+        Self::cell(Default::default())
+    }
+}
+
+impl Default for EcmascriptOptionsVc {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
 #[turbo_tasks::value]
 #[derive(Clone, Copy)]
 pub struct EcmascriptModuleAsset {
@@ -76,9 +140,13 @@ pub struct EcmascriptModuleAsset {
     pub context: AssetContextVc,
     pub ty: EcmascriptModuleAssetType,
     pub transforms: EcmascriptInputTransformsVc,
+    pub options: EcmascriptOptionsVc,
     pub environment: EnvironmentVc,
 }
 
+#[turbo_tasks::value(transparent)]
+pub struct EcmascriptModuleAssets(Vec<EcmascriptModuleAssetVc>);
+
 #[turbo_tasks::value_impl]
 impl EcmascriptModuleAssetVc {
     #[turbo_tasks::function]
@@ -87,6 +155,7 @@ impl EcmascriptModuleAssetVc {
         context: AssetContextVc,
         ty: Value<EcmascriptModuleAssetType>,
         transforms: EcmascriptInputTransformsVc,
+        options: EcmascriptOptionsVc,
         environment: EnvironmentVc,
     ) -> Self {
         Self::cell(EcmascriptModuleAsset {
@@ -94,6 +163,7 @@ impl EcmascriptModuleAssetVc {
             context,
             ty: ty.into_value(),
             transforms,
+            options,
             environment,
         })
     }
@@ -115,9 +185,129 @@ impl EcmascriptModuleAssetVc {
             self.as_resolve_origin(),
             Value::new(this.ty),
             this.transforms,
+            this.options,
             this.environment,
         ))
     }
+
+    /// Whether the module has side effects according to the `sideEffects`
+    /// field of its package.json. Modules with syntax errors are always
+    /// considered to have side effects, since their analysis is incomplete.
+    #[turbo_tasks::function]
+    pub async fn has_side_effects(self) -> Result<BoolVc> {
+        let this = self.await?;
+        let parsed = parse(this.source, Value::new(this.ty), this.transforms).await?;
+        if let ParseResult::Ok { degraded: true, .. } = &*parsed {
+            return Ok(BoolVc::cell(true));
+        }
+        Ok(module_has_side_effects(this.source.path()))
+    }
+
+    /// The `"use client"` or `"use server"` directive of the module, which
+    /// marks a boundary between the client and the server part of the graph.
+    #[turbo_tasks::function]
+    pub async fn directive(self) -> Result<ModuleDirectiveVc> {
+        let this = self.await?;
+        let parsed = parse(this.source, Value::new(this.ty), this.transforms).await?;
+        Ok(match &*parsed {
+            ParseResult::Ok { directive, .. } => directive.cell(),
+            ParseResult::Unparseable | ParseResult::NotFound => ModuleDirective::None.cell(),
+        })
+    }
+
+    /// Whether the module uses top-level await.
+    #[turbo_tasks::function]
+    pub async fn has_top_level_await(self) -> Result<BoolVc> {
+        let this = self.await?;
+        let parsed = parse(this.source, Value::new(this.ty), this.transforms).await?;
+        Ok(BoolVc::cell(matches!(
+            &*parsed,
+            ParseResult::Ok { is_async: true, .. }
+        )))
+    }
+
+    /// The ecmascript modules that the module imports statically with ESM
+    /// imports.
+    #[turbo_tasks::function]
+    pub async fn esm_imports(self) -> Result<EcmascriptModuleAssetsVc> {
+        let mut imports = Vec::new();
+        for reference in self.analyze().await?.references.await?.iter() {
+            let Some(reference) = EsmAssetReferenceVc::resolve_from(reference).await? else {
+                continue;
+            };
+            let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? else {
+                continue;
+            };
+            if let Some(imported) = EcmascriptModuleAssetVc::resolve_from(asset).await? {
+                if !imports.contains(&imported) {
+                    imports.push(imported);
+                }
+            }
+        }
+        Ok(EcmascriptModuleAssetsVc::cell(imports))
+    }
+
+    /// The modules that are in an import cycle with the module, i.e. its
+    /// strongly connected component in the graph of [Self::esm_imports],
+    /// including the module itself.
+    #[turbo_tasks::function]
+    pub async fn import_cycle(self) -> Result<EcmascriptModuleAssetsVc> {
+        // The modules that can be reached from the module, with their imports
+        let mut imports = HashMap::new();
+        let mut queue = vec![self];
+        while let Some(module) = queue.pop() {
+            if imports.contains_key(&module) {
+                continue;
+            }
+            let module_imports = module.esm_imports().await?;
+            queue.extend(module_imports.iter().copied());
+            imports.insert(module, module_imports);
+        }
+
+        // Of those, the ones that can reach the module again
+        let mut importers: HashMap<_, Vec<_>> = HashMap::new();
+        for (module, module_imports) in imports.iter() {
+            for imported in module_imports.iter() {
+                importers.entry(*imported).or_default().push(*module);
+            }
+        }
+        let mut cycle = vec![self];
+        let mut visited = HashSet::from([self]);
+        let mut queue = vec![self];
+        while let Some(module) = queue.pop() {
+            for importer in importers.get(&module).into_iter().flatten() {
+                if visited.insert(*importer) {
+                    cycle.push(*importer);
+                    queue.push(*importer);
+                }
+            }
+        }
+        Ok(EcmascriptModuleAssetsVc::cell(cycle))
+    }
+
+    /// Whether the module is an async module, i.e. its evaluation only
+    /// completes asynchronously. That's the case when it uses top-level await
+    /// or statically imports an async module.
+    #[turbo_tasks::function]
+    pub async fn is_async(self) -> Result<BoolVc> {
+        // All modules of an import cycle wait for each other, so they are async
+        // as a whole. Depending on the `is_async` of the modules in the cycle
+        // would be circular, but imports that leave the cycle never lead back.
+        let cycle = self.import_cycle().await?;
+        for module in cycle.iter() {
+            if *module.has_top_level_await().await? {
+                return Ok(BoolVc::cell(true));
+            }
+        }
+        for module in cycle.iter() {
+            for imported in module.esm_imports().await?.iter() {
+                if !cycle.contains(imported) && *imported.is_async().await? {
+                    return Ok(BoolVc::cell(true));
+                }
+            }
+        }
+        Ok(BoolVc::cell(false))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -180,8 +370,8 @@ impl ResolveOrigin for EcmascriptModuleAsset {
 }
 
 #[turbo_tasks::value]
-struct ModuleChunkItem {
-    module: EcmascriptModuleAssetVc,
+pub(crate) struct ModuleChunkItem {
+    pub(crate) module: EcmascriptModuleAssetVc,
     context: ChunkingContextVc,
 }
 
@@ -199,8 +389,19 @@ impl ValueToString for ModuleChunkItem {
 #[turbo_tasks::value_impl]
 impl ChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
-    fn references(&self) -> AssetReferencesVc {
-        self.module.references()
+    async fn references(&self) -> Result<AssetReferencesVc> {
+        let mut references = Vec::new();
+        for reference in self.module.references().await?.iter() {
+            // Modules without side effects that are imported without bindings
+            // are not placed into the chunk
+            if let Some(esm_reference) = EsmAssetReferenceVc::resolve_from(reference).await? {
+                if *esm_reference.is_unused().await? {
+                    continue;
+                }
+            }
+            references.push(*reference);
+        }
+        Ok(AssetReferencesVc::cell(references))
     }
 }
 
@@ -213,6 +414,30 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let module = self.module.await?;
+        let parsed = parse(module.source, Value::new(module.ty), module.transforms).await?;
+        // Modules that couldn't be parsed throw when they are evaluated. Programs that
+        // were recovered from syntax errors are emitted as they are.
+        let ParseResult::Ok {
+            program: original_program,
+            source_map,
+            globals,
+            eval_context,
+            comments,
+            ..
+        } = &*parsed else {
+            return Ok(EcmascriptChunkItemContent {
+                inner_code: format!(
+                    "const e = new Error(\"Could not parse module '{path}'\");\ne.code = \
+                     'MODULE_UNPARSEABLE';\nthrow e;",
+                    path = self.module.path().to_string().await?
+                )
+                .into(),
+                ..Default::default()
+            }
+            .into());
+        };
+
         let AnalyzeEcmascriptModuleResult {
             references,
             code_generation,
@@ -244,82 +469,94 @@ impl EcmascriptChunkItem for ModuleChunkItem {
             }
         }
 
-        let module = self.module.await?;
-        let parsed = parse(module.source, Value::new(module.ty), module.transforms).await?;
-
-        if let ParseResult::Ok {
-            program,
-            source_map,
-            globals,
-            eval_context,
-            ..
-        } = &*parsed
-        {
-            let mut program = program.clone();
-
-            GLOBALS.set(globals, || {
-                if !visitors.is_empty() {
-                    program.visit_mut_with_path(
-                        &mut ApplyVisitors::new(visitors),
-                        &mut Default::default(),
-                    );
-                }
-                for visitor in root_visitors {
-                    program.visit_mut_with(&mut visitor.create());
-                }
-                program.visit_mut_with(&mut swc_core::ecma::transforms::base::fixer::fixer(None));
-            });
+        let mut plugins = Vec::new();
+        for transform in module.transforms.await?.iter() {
+            if let EcmascriptInputTransform::Plugin(plugin) = transform {
+                plugins.push(plugin.await?);
+            }
+        }
+        let plugins = plugins.iter().map(|plugin| &**plugin).collect::<Vec<_>>();
 
-            let mut bytes: Vec<u8> = vec![];
-            // TODO: Insert this as a sourceless segment so that sourcemaps aren't affected.
-            // = format!("/* {} */\n", self.module.path().to_string().await?).into_bytes();
+        let mut program = original_program.clone();
+        let ascii_only = *context.is_ascii_only_output_enabled().await?;
 
-            let mut srcmap = vec![];
+        GLOBALS.set(globals, || -> Result<()> {
+            if !visitors.is_empty() {
+                let mut apply_visitors = ApplyVisitors::new(visitors);
+                program.visit_mut_with_path(&mut apply_visitors, &mut Default::default());
+                apply_visitors.take_error()?;
+            }
+            for visitor in root_visitors {
+                program.visit_mut_with(&mut visitor.create());
+            }
+            // Hoisted imports and exports are inserted at the start
+            restore_directive_prologue(&mut program, &directive_prologue_spans(original_program));
+            apply_plugins(
+                plugins.iter().copied(),
+                TransformPluginStage::AfterReferences,
+                &mut program,
+            );
+            program.visit_mut_with(&mut swc_core::ecma::transforms::base::fixer::fixer(None));
+            if ascii_only {
+                program.visit_mut_with(&mut AsciiOnlyTemplates);
+            }
+            apply_plugins(
+                plugins.iter().copied(),
+                TransformPluginStage::BeforeEmit,
+                &mut program,
+            );
+            Ok(())
+        })?;
 
-            let mut emitter = Emitter {
-                cfg: swc_core::ecma::codegen::Config {
-                    ..Default::default()
-                },
-                cm: source_map.clone(),
-                comments: None,
-                wr: JsWriter::new(source_map.clone(), "\n", &mut bytes, Some(&mut srcmap)),
-            };
+        let mut bytes: Vec<u8> = vec![];
+        // TODO: Insert this as a sourceless segment so that sourcemaps aren't affected.
+        // = format!("/* {} */\n", self.module.path().to_string().await?).into_bytes();
 
-            emitter.emit_program(&program)?;
+        let mut srcmap = vec![];
 
-            let srcmap = ParseResultSourceMap::new(source_map.clone(), srcmap).cell();
+        let minify = *context.is_minification_enabled().await?;
+        // Minified output drops all comments except the legal ones
+        let preserved_comments = minify.then(|| legal_comments(comments, &program));
 
-            Ok(EcmascriptChunkItemContent {
-                inner_code: bytes.into(),
-                source_map: Some(srcmap),
-                options: if eval_context.is_esm() {
-                    EcmascriptChunkItemOptions {
-                        ..Default::default()
-                    }
-                } else {
-                    EcmascriptChunkItemOptions {
-                        // These things are not available in ESM
-                        module: true,
-                        exports: true,
-                        this: true,
-                        ..Default::default()
-                    }
-                },
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify,
+                ascii_only,
                 ..Default::default()
-            }
-            .into())
-        } else {
-            Ok(EcmascriptChunkItemContent {
-                inner_code: format!(
-                    "const e = new Error(\"Could not parse module '{path}'\");\ne.code = \
-                     'MODULE_UNPARSEABLE';\nthrow e;",
-                    path = self.module.path().to_string().await?
-                )
-                .into(),
-                ..Default::default()
-            }
-            .into())
+            },
+            cm: source_map.clone(),
+            comments: preserved_comments.as_ref().map(|c| c as &dyn Comments),
+            wr: JsWriter::new(source_map.clone(), "\n", &mut bytes, Some(&mut srcmap)),
+        };
+
+        emitter.emit_program(&program)?;
+
+        let names = renamed_identifiers(original_program, &program);
+        let sources_content_limit = *context.sources_content_limit().await?;
+        let srcmap =
+            ParseResultSourceMap::new(source_map.clone(), srcmap, names, sources_content_limit)
+                .cell();
+
+        Ok(EcmascriptChunkItemContent {
+            inner_code: bytes.into(),
+            source_map: Some(srcmap),
+            options: if eval_context.is_esm() {
+                EcmascriptChunkItemOptions {
+                    async_module: *self.module.is_async().await?,
+                    ..Default::default()
+                }
+            } else {
+                EcmascriptChunkItemOptions {
+                    // These things are not available in ESM
+                    module: true,
+                    exports: true,
+                    this: true,
+                    ..Default::default()
+                }
+            },
+            ..Default::default()
         }
+        .into())
     }
 }
 
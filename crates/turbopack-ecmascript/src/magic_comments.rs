@@ -0,0 +1,356 @@
+//! Support for webpack-style magic comments, which are block comments inside
+//! of an expression that configure how it is handled, e.g.
+//! `import(/* webpackChunkName: "vendors" */ "./vendors")`.
+//!
+//! Magic comments are read from the leading comments of the first argument of
+//! `import()` and `new URL()`.
+
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use swc_core::{
+    base::SwcComments,
+    common::{
+        comments::{Comment, CommentKind},
+        Span, Spanned,
+    },
+    ecma::{
+        ast::{CallExpr, Callee, Expr, Lit, NewExpr, Program},
+        visit::{Visit, VisitWith},
+    },
+};
+use turbopack_core::chunk::ChunkLoadingHint;
+
+/// The magic comments of an expression.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MagicComments {
+    /// The name of the chunk that is created for a dynamic import, with the
+    /// placeholders already replaced.
+    pub chunk_name: Option<String>,
+    /// Set by `webpackIgnore: true`. No reference is created for the
+    /// expression, so it's left as is and resolved at runtime.
+    pub ignore: bool,
+    /// Set by `webpackPreload` or `webpackPrefetch`, with either `true` or a
+    /// number as order. A preload hint takes precedence over a prefetch hint.
+    pub loading_hint: Option<ChunkLoadingHint>,
+}
+
+/// Parses the magic comments from a list of comments. Later comments override
+/// earlier ones.
+pub fn parse_magic_comments(comments: &[Comment]) -> MagicComments {
+    lazy_static! {
+        static ref CHUNK_NAME: Regex =
+            Regex::new(r#"webpackChunkName\s*:\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+        static ref IGNORE: Regex = Regex::new(r"webpackIgnore\s*:\s*(true|false)").unwrap();
+        static ref PRELOAD: Regex = Regex::new(r"webpackPreload\s*:\s*(true|false|-?\d+)").unwrap();
+        static ref PREFETCH: Regex =
+            Regex::new(r"webpackPrefetch\s*:\s*(true|false|-?\d+)").unwrap();
+    }
+    let mut magic_comments = MagicComments::default();
+    for comment in comments {
+        if comment.kind != CommentKind::Block {
+            continue;
+        }
+        if let Some(m) = CHUNK_NAME.captures(&comment.text) {
+            let name = m.get(1).or_else(|| m.get(2)).unwrap().as_str().trim();
+            if !name.is_empty() {
+                magic_comments.chunk_name = Some(name.to_string());
+            }
+        }
+        if let Some(m) = IGNORE.captures(&comment.text) {
+            magic_comments.ignore = &m[1] == "true";
+        }
+        let preload = PRELOAD.captures(&comment.text).map(|m| hint_order(&m[1]));
+        let prefetch = PREFETCH.captures(&comment.text).map(|m| hint_order(&m[1]));
+        match (preload, prefetch) {
+            (Some(Some(order)), _) => {
+                magic_comments.loading_hint = Some(ChunkLoadingHint::Preload { order })
+            }
+            (_, Some(Some(order))) => {
+                magic_comments.loading_hint = Some(ChunkLoadingHint::Prefetch { order })
+            }
+            (None, None) => {}
+            _ => magic_comments.loading_hint = None,
+        }
+    }
+    magic_comments
+}
+
+/// The order of a `webpackPreload` or `webpackPrefetch` value, or `None` when
+/// the hint is disabled.
+fn hint_order(value: &str) -> Option<i32> {
+    match value {
+        "true" => Some(0),
+        "false" => None,
+        order => order.parse().ok(),
+    }
+}
+
+/// Replaces the `[request]` and `[index]` placeholders of a chunk name.
+///
+/// `[request]` is replaced with the request, where every sequence of
+/// characters that are not valid in a file name is replaced by a `-`, e.g.
+/// `./pages/home` becomes `pages-home`. `[index]` is replaced with the index of
+/// the dynamic import in the module.
+pub fn resolve_chunk_name(template: &str, request: &str, index: usize) -> String {
+    lazy_static! {
+        static ref INVALID_CHARS: Regex = Regex::new(r"[^a-zA-Z0-9_]+").unwrap();
+    }
+    let mut name = template.to_string();
+    if name.contains("[request]") {
+        let request = INVALID_CHARS.replace_all(request, "-");
+        name = name.replace("[request]", request.trim_matches('-'));
+    }
+    if name.contains("[index]") {
+        name = name.replace("[index]", &index.to_string());
+    }
+    name
+}
+
+/// Collects the magic comments of all `import()` and `new URL()` expressions
+/// in the program, keyed by the span of the expression.
+pub fn collect_magic_comments(
+    program: &Program,
+    comments: &SwcComments,
+) -> HashMap<Span, MagicComments> {
+    let mut visitor = MagicCommentsVisitor {
+        comments,
+        index: 0,
+        result: HashMap::new(),
+    };
+    program.visit_with(&mut visitor);
+    visitor.result
+}
+
+struct MagicCommentsVisitor<'a> {
+    comments: &'a SwcComments,
+    index: usize,
+    result: HashMap<Span, MagicComments>,
+}
+
+impl MagicCommentsVisitor<'_> {
+    fn argument_magic_comments(&self, arg: &Expr) -> Option<MagicComments> {
+        let comments = self.comments.leading.get(&arg.span().lo)?;
+        let magic_comments = parse_magic_comments(&comments);
+        (magic_comments != MagicComments::default()).then_some(magic_comments)
+    }
+}
+
+impl Visit for MagicCommentsVisitor<'_> {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let (Callee::Import(_), Some(arg)) = (&call.callee, call.args.first()) {
+            let index = self.index;
+            self.index += 1;
+            if let Some(mut magic_comments) = self.argument_magic_comments(&arg.expr) {
+                if let Some(chunk_name) = magic_comments.chunk_name.take() {
+                    let request = request_text(&arg.expr);
+                    magic_comments.chunk_name =
+                        Some(resolve_chunk_name(&chunk_name, &request, index));
+                }
+                self.result.insert(call.span, magic_comments);
+            }
+        }
+        call.visit_children_with(self);
+    }
+
+    fn visit_new_expr(&mut self, new: &NewExpr) {
+        if let (Expr::Ident(callee), Some(arg)) = (
+            &*new.callee,
+            new.args.as_ref().and_then(|args| args.first()),
+        ) {
+            if &*callee.sym == "URL" {
+                if let Some(magic_comments) = self.argument_magic_comments(&arg.expr) {
+                    self.result.insert(new.span, magic_comments);
+                }
+            }
+        }
+        new.visit_children_with(self);
+    }
+}
+
+/// The text of a request that is used for the `[request]` placeholder. Only
+/// the constant parts of template literals are used.
+fn request_text(expr: &Expr) -> String {
+    match expr {
+        Expr::Lit(Lit::Str(str)) => str.value.to_string(),
+        Expr::Tpl(tpl) => tpl
+            .quasis
+            .iter()
+            .map(|quasi| {
+                quasi
+                    .cooked
+                    .as_ref()
+                    .map_or_else(|| quasi.raw.to_string(), |cooked| cooked.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join("-"),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_core::{
+        base::SwcComments,
+        common::{errors::HANDLER, FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::parse_file_as_module,
+        },
+        testing::run_test,
+    };
+    use turbopack_core::chunk::ChunkLoadingHint;
+
+    use super::{collect_magic_comments, resolve_chunk_name, MagicComments};
+
+    fn magic_comments(cm: &Arc<SourceMap>, src: &str) -> Vec<MagicComments> {
+        let fm = cm.new_source_file(FileName::Anon, src.into());
+        let comments = SwcComments::default();
+        let module = parse_file_as_module(
+            &fm,
+            Default::default(),
+            EsVersion::latest(),
+            Some(&comments),
+            &mut vec![],
+        )
+        .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+        .unwrap();
+        let program = Program::Module(module);
+        let mut result = collect_magic_comments(&program, &comments)
+            .into_iter()
+            .collect::<Vec<_>>();
+        result.sort_by_key(|(span, _)| span.lo);
+        result
+            .into_iter()
+            .map(|(_, magic_comments)| magic_comments)
+            .collect()
+    }
+
+    fn chunk_names(cm: &Arc<SourceMap>, src: &str) -> Vec<Option<String>> {
+        magic_comments(cm, src)
+            .into_iter()
+            .map(|magic_comments| magic_comments.chunk_name)
+            .collect()
+    }
+
+    #[test]
+    fn literal_chunk_name() {
+        run_test(false, |cm, _handler| {
+            let names = chunk_names(
+                &cm,
+                "import(/* webpackChunkName: \"vendors\" */ \
+                 './vendors');\nimport(/*webpackChunkName:'ui'*/ './ui');\nimport(/* \
+                 webpackPrefetch: true, webpackChunkName:   \"admin\"  */ './admin');\nimport(/* \
+                 unrelated */ './other');",
+            );
+            assert_eq!(
+                names,
+                vec![
+                    Some("vendors".to_string()),
+                    Some("ui".to_string()),
+                    Some("admin".to_string()),
+                ]
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn placeholder_chunk_name() {
+        run_test(false, |cm, _handler| {
+            let names = chunk_names(
+                &cm,
+                "import('./a');\nimport(/* webpackChunkName: \"page-[request]\" */ \
+                 './pages/home');\nimport(/* webpackChunkName: \"lazy-[index]\" */ './lazy');",
+            );
+            assert_eq!(
+                names,
+                vec![
+                    Some("page-pages-home".to_string()),
+                    Some("lazy-2".to_string()),
+                ]
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn ignored_import() {
+        run_test(false, |cm, _handler| {
+            let magic_comments = magic_comments(
+                &cm,
+                "import(/* webpackIgnore: true */ 'https://cdn.example.com/lib.js');\nimport(/* \
+                 webpackIgnore: false */ './a');\nimport('./b');",
+            );
+            assert_eq!(magic_comments.len(), 2);
+            assert!(magic_comments[0].ignore);
+            assert!(!magic_comments[1].ignore);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn ignored_new_url() {
+        run_test(false, |cm, _handler| {
+            let magic_comments = magic_comments(
+                &cm,
+                "new Worker(new URL(/* webpackIgnore: true */ './worker.js', \
+                 import.meta.url));\nnew URL('./a.js', import.meta.url);\nnew Foo(/* \
+                 webpackIgnore: true */ './b.js');",
+            );
+            assert_eq!(
+                magic_comments,
+                vec![MagicComments {
+                    chunk_name: None,
+                    ignore: true,
+                    loading_hint: None,
+                }]
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn loading_hints() {
+        run_test(false, |cm, _handler| {
+            let hints = magic_comments(
+                &cm,
+                "import(/* webpackPrefetch: true */ './a');\nimport(/* webpackPreload: true */ \
+                 './b');\nimport(/* webpackPrefetch: 3 */ './c');\nimport(/* webpackPreload: -1, \
+                 webpackPrefetch: true */ './d');\nimport(/* webpackPrefetch: false, \
+                 webpackChunkName: \"e\" */ './e');",
+            )
+            .into_iter()
+            .map(|magic_comments| magic_comments.loading_hint)
+            .collect::<Vec<_>>();
+            assert_eq!(
+                hints,
+                vec![
+                    Some(ChunkLoadingHint::Prefetch { order: 0 }),
+                    Some(ChunkLoadingHint::Preload { order: 0 }),
+                    Some(ChunkLoadingHint::Prefetch { order: 3 }),
+                    Some(ChunkLoadingHint::Preload { order: -1 }),
+                    None,
+                ]
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn resolve_placeholders() {
+        assert_eq!(resolve_chunk_name("vendors", "./x", 0), "vendors");
+        assert_eq!(
+            resolve_chunk_name("[request]-[index]", "../lib/@scope/pkg.js", 3),
+            "lib-scope-pkg-js-3"
+        );
+    }
+}
@@ -1,22 +1,28 @@
-use std::{future::Future, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap},
+    future::Future,
+    sync::Arc,
+};
 
 use anyhow::Result;
 use swc_core::{
     base::SwcComments,
     common::{
+        comments::Comments,
         errors::{Handler, HANDLER},
         input::StringInput,
         source_map::SourceMapGenConfig,
-        BytePos, FileName, Globals, LineCol, Mark, SourceMap, GLOBALS,
+        BytePos, FileName, Globals, LineCol, Mark, SourceFile, SourceMap, Span, GLOBALS,
     },
     ecma::{
-        ast::{EsVersion, Program},
-        parser::{lexer::Lexer, EsConfig, Parser, Syntax, TsConfig},
+        ast::{EsVersion, Ident, Module, ModuleItem, Program, Script},
+        atoms::JsWord,
+        parser::{error::Error as ParserError, lexer::Lexer, EsConfig, Parser, Syntax, TsConfig},
         transforms::base::{
             helpers::{Helpers, HELPERS},
             resolver,
         },
-        visit::VisitMutWith,
+        visit::{Visit, VisitMutWith, VisitWith},
     },
 };
 use turbo_tasks::{primitives::U64Vc, Value};
@@ -31,7 +37,13 @@ use turbopack_swc_utils::emitter::IssueEmitter;
 use super::EcmascriptModuleAssetType;
 use crate::{
     analyzer::graph::EvalContext,
-    transform::{EcmascriptInputTransformsVc, TransformContext},
+    directives::{
+        directive_prologue_spans, module_directive, restore_directive_prologue, ModuleDirective,
+    },
+    parse_cache::PARSE_CACHE,
+    top_level_await::has_top_level_await,
+    transform::{DecoratorsKind, EcmascriptInputTransformsVc, TransformContext},
+    type_exports::type_exports,
     utils::WrapFuture,
     EcmascriptInputTransform,
 };
@@ -50,6 +62,17 @@ pub enum ParseResult {
         globals: Globals,
         #[turbo_tasks(debug_ignore, trace_ignore)]
         source_map: Arc<SourceMap>,
+        /// The source contains syntax errors the parser recovered from, so
+        /// `program` might miss parts of the source. Analysis results of a
+        /// degraded program are incomplete, so it's not used for tree shaking.
+        degraded: bool,
+        /// The directive of the source, before any transform was applied.
+        directive: ModuleDirective,
+        /// The source uses top-level await.
+        is_async: bool,
+        /// The exports of the source that only exist in type space, see
+        /// [type_exports].
+        type_exports: BTreeSet<String>,
     },
     Unparseable,
     NotFound,
@@ -76,23 +99,84 @@ pub struct ParseResultSourceMap {
     /// SourceMap.
     #[turbo_tasks(debug_ignore, trace_ignore)]
     mappings: Vec<(BytePos, LineCol)>,
+
+    /// The original names of identifiers that have been renamed, by their
+    /// position in the source. These populate the `names` of the source map.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    names: HashMap<BytePos, JsWord>,
+
+    /// The size in bytes up to which sources are inlined into the
+    /// `sourcesContent`, `None` inlines all sources.
+    sources_content_limit: Option<usize>,
 }
 
 impl PartialEq for ParseResultSourceMap {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.source_map, &other.source_map) && self.mappings == other.mappings
+        Arc::ptr_eq(&self.source_map, &other.source_map)
+            && self.mappings == other.mappings
+            && self.names == other.names
+            && self.sources_content_limit == other.sources_content_limit
     }
 }
 
 impl ParseResultSourceMap {
-    pub fn new(source_map: Arc<SourceMap>, mappings: Vec<(BytePos, LineCol)>) -> Self {
+    pub fn new(
+        source_map: Arc<SourceMap>,
+        mappings: Vec<(BytePos, LineCol)>,
+        names: HashMap<BytePos, JsWord>,
+        sources_content_limit: Option<usize>,
+    ) -> Self {
         ParseResultSourceMap {
             source_map,
             mappings,
+            names,
+            sources_content_limit,
         }
     }
 }
 
+/// Finds the identifiers of `transformed` that have a different name than
+/// the identifier at the same position in `original`, and returns their
+/// original names by position.
+pub fn renamed_identifiers(original: &Program, transformed: &Program) -> HashMap<BytePos, JsWord> {
+    struct Names<'a> {
+        original: Option<&'a HashMap<BytePos, JsWord>>,
+        names: HashMap<BytePos, JsWord>,
+    }
+
+    impl Visit for Names<'_> {
+        fn visit_ident(&mut self, ident: &Ident) {
+            if ident.span.is_dummy() {
+                return;
+            }
+            match self.original {
+                None => {
+                    self.names.insert(ident.span.lo, ident.sym.clone());
+                }
+                Some(original) => {
+                    if let Some(name) = original.get(&ident.span.lo) {
+                        if *name != ident.sym {
+                            self.names.insert(ident.span.lo, name.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut original_names = Names {
+        original: None,
+        names: HashMap::new(),
+    };
+    original.visit_with(&mut original_names);
+    let mut renamed = Names {
+        original: Some(&original_names.names),
+        names: HashMap::new(),
+    };
+    transformed.visit_with(&mut renamed);
+    renamed.names
+}
+
 #[turbo_tasks::value_impl]
 impl GenerateSourceMap for ParseResultSourceMap {
     #[turbo_tasks::function]
@@ -100,18 +184,27 @@ impl GenerateSourceMap for ParseResultSourceMap {
         let map = self.source_map.build_source_map_with_config(
             &self.mappings,
             None,
-            InlineSourcesContentConfig {},
+            InlineSourcesContentConfig {
+                source_map: &self.source_map,
+                names: &self.names,
+                limit: self.sources_content_limit,
+            },
         );
         SourceMapVc::new_regular(map)
     }
 }
 
 /// A config to generate a source map which includes the source content of every
-/// source file. SWC doesn't inline sources content by default when generating a
-/// sourcemap, so we need to provide a custom config to do it.
-struct InlineSourcesContentConfig {}
+/// source file that isn't larger than `limit`. SWC doesn't inline sources
+/// content by default when generating a sourcemap, so we need to provide a
+/// custom config to do it.
+struct InlineSourcesContentConfig<'a> {
+    source_map: &'a SourceMap,
+    names: &'a HashMap<BytePos, JsWord>,
+    limit: Option<usize>,
+}
 
-impl SourceMapGenConfig for InlineSourcesContentConfig {
+impl SourceMapGenConfig for InlineSourcesContentConfig<'_> {
     fn file_name_to_source(&self, f: &FileName) -> String {
         match f {
             // The Custom filename surrounds the name with <>.
@@ -120,8 +213,19 @@ impl SourceMapGenConfig for InlineSourcesContentConfig {
         }
     }
 
-    fn inline_sources_content(&self, _f: &FileName) -> bool {
-        true
+    fn name_for_bytepos(&self, pos: BytePos) -> Option<&str> {
+        self.names.get(&pos).map(|name| &**name)
+    }
+
+    fn inline_sources_content(&self, f: &FileName) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        self.source_map
+            .files()
+            .iter()
+            .find(|file| file.name == *f)
+            .map_or(false, |file| file.src.len() <= limit)
     }
 }
 
@@ -192,60 +296,54 @@ async fn parse_content(
 
             let comments = SwcComments::default();
 
-            let mut parsed_program = {
-                let lexer = Lexer::new(
-                    match ty {
-                        EcmascriptModuleAssetType::Ecmascript => Syntax::Es(EsConfig {
-                            jsx: true,
-                            fn_bind: true,
-                            decorators: true,
-                            decorators_before_export: true,
-                            export_default_from: true,
-                            import_assertions: true,
-                            allow_super_outside_method: true,
-                            allow_return_outside_function: true,
-                        }),
-                        EcmascriptModuleAssetType::Typescript => Syntax::Typescript(TsConfig {
-                            decorators: true,
-                            dts: false,
-                            no_early_errors: true,
-                            tsx: true,
-                        }),
-                        EcmascriptModuleAssetType::TypescriptDeclaration => {
-                            Syntax::Typescript(TsConfig {
-                                decorators: true,
-                                dts: true,
-                                no_early_errors: true,
-                                tsx: true,
-                            })
-                        }
-                    },
-                    EsVersion::latest(),
-                    StringInput::from(&*fm),
-                    Some(&comments),
-                );
-
-                let mut parser = Parser::new_from(lexer);
-
-                let mut has_errors = false;
-                for e in parser.take_errors() {
-                    e.into_diagnostic(&handler).emit();
-                    has_errors = true
-                }
-
-                if has_errors {
-                    return Ok(ParseResult::Unparseable);
-                }
-
-                match parser.parse_program() {
-                    Ok(parsed_program) => parsed_program,
-                    Err(e) => {
-                        e.into_diagnostic(&handler).emit();
-                        return Ok(ParseResult::Unparseable);
+            // Decorators of the TC39 proposal are placed after `export`
+            let decorators_before_export = !transforms.iter().any(|transform| {
+                matches!(
+                    transform,
+                    EcmascriptInputTransform::Decorators {
+                        kind: DecoratorsKind::Ecma,
+                        ..
                     }
-                }
+                )
+            });
+            let syntax = match ty {
+                EcmascriptModuleAssetType::Ecmascript => Syntax::Es(EsConfig {
+                    jsx: true,
+                    fn_bind: true,
+                    decorators: true,
+                    decorators_before_export,
+                    export_default_from: true,
+                    import_assertions: true,
+                    allow_super_outside_method: true,
+                    allow_return_outside_function: true,
+                }),
+                EcmascriptModuleAssetType::Typescript => Syntax::Typescript(TsConfig {
+                    decorators: true,
+                    dts: false,
+                    no_early_errors: true,
+                    tsx: true,
+                }),
+                EcmascriptModuleAssetType::TypescriptDeclaration => Syntax::Typescript(TsConfig {
+                    decorators: true,
+                    dts: true,
+                    no_early_errors: true,
+                    tsx: true,
+                }),
             };
 
+            let (parsed_program, errors) =
+                parse_program_with_recovery(&fm, syntax, EsVersion::latest(), Some(&comments));
+            let degraded = !errors.is_empty();
+            for e in errors {
+                e.into_diagnostic(&handler).emit();
+            }
+            let Some(mut parsed_program) = parsed_program else {
+                return Ok(ParseResult::Unparseable);
+            };
+            let directive = module_directive(&parsed_program, &handler);
+            let is_async = has_top_level_await(&parsed_program);
+            let type_exports = type_exports(&parsed_program);
+
             let unresolved_mark = Mark::new();
             let top_level_mark = Mark::new();
 
@@ -268,9 +366,11 @@ async fn parse_content(
                 file_name_str: fs_path.file_name(),
                 file_name_hash: file_path_hash,
             };
+            let prologue = directive_prologue_spans(&parsed_program);
             for transform in transforms.iter() {
                 transform.apply(&mut parsed_program, &context).await?;
             }
+            restore_directive_prologue(&mut parsed_program, &prologue);
 
             let eval_context = EvalContext::new(&parsed_program, unresolved_mark);
 
@@ -282,6 +382,10 @@ async fn parse_content(
                 // borrowed
                 globals: Globals::new(),
                 source_map,
+                degraded,
+                directive,
+                is_async,
+                type_exports,
             })
         },
     )
@@ -296,6 +400,136 @@ async fn parse_content(
     Ok(result.cell())
 }
 
+/// The maximum number of syntax errors that [parse_program_with_recovery]
+/// skips over before it gives up on the rest of the source.
+const MAX_RECOVERY_ATTEMPTS: usize = 16;
+
+/// Parses the file as program. Syntax errors are returned together with the
+/// recovered program, which misses the parts of the source that couldn't be
+/// parsed. No program is returned when nothing could be recovered.
+///
+/// Programs without errors are cached in [PARSE_CACHE].
+pub fn parse_program_with_recovery(
+    fm: &SourceFile,
+    syntax: Syntax,
+    target: EsVersion,
+    comments: Option<&SwcComments>,
+) -> (Option<Program>, Vec<ParserError>) {
+    PARSE_CACHE.parse_program(fm, syntax, target, comments, || {
+        let lexer = Lexer::new(
+            syntax,
+            target,
+            StringInput::from(fm),
+            comments.map(|comments| comments as &dyn Comments),
+        );
+        let mut parser = Parser::new_from(lexer);
+        match parser.parse_program() {
+            Ok(program) => (Some(program), parser.take_errors()),
+            Err(err) => {
+                let mut errors = parser.take_errors();
+                let error_pos = err.span().lo;
+                errors.push(err);
+                let program = recover_program(fm, syntax, target, error_pos, &mut errors);
+                (program, errors)
+            }
+        }
+    })
+}
+
+/// Recovers the statements around a syntax error at `error_pos` that the
+/// parser can't recover from by itself. The lines of the error are skipped:
+/// the statements before them are parsed up to the last line break before the
+/// error, and parsing resumes after the first line break after it, where the
+/// same happens for further errors. Errors after the first one are added to
+/// `errors`.
+fn recover_program(
+    fm: &SourceFile,
+    syntax: Syntax,
+    target: EsVersion,
+    error_pos: BytePos,
+    errors: &mut Vec<ParserError>,
+) -> Option<Program> {
+    let src = &*fm.src;
+    let offset = |pos: BytePos| ((pos.max(fm.start_pos) - fm.start_pos).0 as usize).min(src.len());
+    let parse_range = |start: usize, end: usize| {
+        let input = StringInput::new(
+            &src[start..end],
+            fm.start_pos + BytePos(start as u32),
+            fm.start_pos + BytePos(end as u32),
+        );
+        let mut parser = Parser::new_from(Lexer::new(syntax, target, input, None));
+        let program = parser.parse_program();
+        (program, parser.take_errors())
+    };
+
+    let mut programs = Vec::new();
+    let mut start = 0;
+    let mut error_offset = offset(error_pos);
+    for _ in 0..MAX_RECOVERY_ATTEMPTS {
+        let error_offset_in_range = error_offset.max(start);
+        // The error might be in a statement that spans multiple lines, so the
+        // prefix is shortened line by line until it can be parsed
+        let prefix = src[start..error_offset_in_range]
+            .rmatch_indices('\n')
+            .take(MAX_RECOVERY_ATTEMPTS)
+            .find_map(|(end, _)| match parse_range(start, start + end) {
+                (Ok(program), prefix_errors) if prefix_errors.is_empty() => Some(program),
+                _ => None,
+            });
+        programs.extend(prefix);
+
+        let Some(line_end) = src[error_offset_in_range..].find('\n') else {
+            break;
+        };
+        start = error_offset_in_range + line_end + 1;
+        match parse_range(start, src.len()) {
+            (Ok(program), rest_errors) => {
+                programs.push(program);
+                errors.extend(rest_errors);
+                break;
+            }
+            (Err(err), rest_errors) => {
+                error_offset = offset(err.span().lo);
+                errors.extend(rest_errors);
+                errors.push(err);
+            }
+        }
+    }
+
+    if programs.is_empty() {
+        return None;
+    }
+    let span = Span::new(fm.start_pos, fm.end_pos, Default::default());
+    let shebang = match &programs[0] {
+        Program::Module(module) => module.shebang.clone(),
+        Program::Script(script) => script.shebang.clone(),
+    };
+    Some(if programs.iter().all(|program| program.is_script()) {
+        Program::Script(Script {
+            span,
+            body: programs
+                .into_iter()
+                .flat_map(|program| program.expect_script().body)
+                .collect(),
+            shebang,
+        })
+    } else {
+        Program::Module(Module {
+            span,
+            body: programs
+                .into_iter()
+                .flat_map(|program| match program {
+                    Program::Module(module) => module.body,
+                    Program::Script(script) => {
+                        script.body.into_iter().map(ModuleItem::Stmt).collect()
+                    }
+                })
+                .collect(),
+            shebang,
+        })
+    })
+}
+
 #[turbo_tasks::function]
 async fn hash_file_path(file_path_vc: FileSystemPathVc) -> Result<U64Vc> {
     let file_path = &*file_path_vc.await?;
@@ -303,3 +537,169 @@ async fn hash_file_path(file_path_vc: FileSystemPathVc) -> Result<U64Vc> {
     hasher.write_bytes(file_path.file_name().as_bytes());
     Ok(U64Vc::cell(hasher.finish()))
 }
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Spanned},
+        ecma::{
+            ast::{EsVersion, Ident, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_program,
+            visit::{VisitMut, VisitMutWith},
+        },
+        testing::run_test,
+    };
+
+    use super::{parse_program_with_recovery, renamed_identifiers, InlineSourcesContentConfig};
+
+    struct Rename;
+
+    impl VisitMut for Rename {
+        fn visit_mut_ident(&mut self, ident: &mut Ident) {
+            if &*ident.sym == "longName" {
+                ident.sym = "a".into();
+            }
+        }
+    }
+
+    #[test]
+    fn source_map_names() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "function longName() {}\nlongName(other);".into(),
+            );
+            let original: Program = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+            .unwrap();
+            let mut program = original.clone();
+            program.visit_mut_with(&mut Rename);
+
+            let names = renamed_identifiers(&original, &program);
+            assert_eq!(names.len(), 2);
+
+            let mut bytes = Vec::new();
+            let mut srcmap = Vec::new();
+            let mut emitter = Emitter {
+                cfg: Default::default(),
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, Some(&mut srcmap)),
+            };
+            emitter.emit_program(&program).unwrap();
+
+            let map = cm.build_source_map_with_config(
+                &srcmap,
+                None,
+                InlineSourcesContentConfig {
+                    source_map: &cm,
+                    names: &names,
+                    limit: None,
+                },
+            );
+            assert!(map.names().any(|name| name == "longName"));
+            assert!(!map.names().any(|name| name == "other"));
+            assert!(map
+                .tokens()
+                .any(|token| token.get_name() == Some("longName") && token.get_src_line() == 1));
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sources_content() {
+        let sources_content = |limit| {
+            run_test(false, |cm, _handler| {
+                let src = "export const a = 1;";
+                let fm = cm.new_source_file(FileName::Custom("a.js".to_string()), src.into());
+                let program = parse_file_as_program(
+                    &fm,
+                    Default::default(),
+                    EsVersion::latest(),
+                    None,
+                    &mut vec![],
+                )
+                .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+                .unwrap();
+
+                let mut bytes = Vec::new();
+                let mut srcmap = Vec::new();
+                let mut emitter = Emitter {
+                    cfg: Default::default(),
+                    cm: cm.clone(),
+                    comments: None,
+                    wr: JsWriter::new(cm.clone(), "\n", &mut bytes, Some(&mut srcmap)),
+                };
+                emitter.emit_program(&program).unwrap();
+
+                let names = Default::default();
+                let map = cm.build_source_map_with_config(
+                    &srcmap,
+                    None,
+                    InlineSourcesContentConfig {
+                        source_map: &cm,
+                        names: &names,
+                        limit,
+                    },
+                );
+                assert_eq!(map.get_source(0), Some("/a.js"));
+                Ok(map
+                    .get_source_contents(0)
+                    .map(|content| content.to_string()))
+            })
+            .unwrap()
+        };
+        let content = Some("export const a = 1;".to_string());
+        assert_eq!(sources_content(None), content);
+        assert_eq!(sources_content(Some(1024)), content);
+        // Larger sources are only referenced
+        assert_eq!(sources_content(Some(8)), None);
+        assert_eq!(sources_content(Some(0)), None);
+    }
+
+    #[test]
+    fn recover_from_syntax_error() {
+        run_test(false, |cm, _handler| {
+            // Legacy octal literals aren't allowed in modules, which the parser
+            // recovers from
+            let fm =
+                cm.new_source_file(FileName::Anon, "export const a = 010;\nconst b = 2;".into());
+            let (program, errors) =
+                parse_program_with_recovery(&fm, Default::default(), EsVersion::latest(), None);
+            assert_eq!(errors.len(), 1);
+            assert_eq!(program.unwrap().expect_module().body.len(), 2);
+
+            // A missing `)` isn't recoverable for the parser, so the line is
+            // skipped
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "const a = 1;\nconsole.log(a;\nconst b = 2;".into(),
+            );
+            let (program, errors) =
+                parse_program_with_recovery(&fm, Default::default(), EsVersion::latest(), None);
+            assert_eq!(errors.len(), 1);
+            let body = program.unwrap().expect_script().body;
+            assert_eq!(body.len(), 2);
+            assert_eq!(cm.span_to_snippet(body[0].span()).unwrap(), "const a = 1;");
+            assert_eq!(cm.span_to_snippet(body[1].span()).unwrap(), "const b = 2;");
+
+            let fm = cm.new_source_file(FileName::Anon, "console.log(a;".into());
+            let (program, errors) =
+                parse_program_with_recovery(&fm, Default::default(), EsVersion::latest(), None);
+            assert_eq!(errors.len(), 1);
+            assert!(program.is_none());
+
+            Ok(())
+        })
+        .unwrap();
+    }
+}
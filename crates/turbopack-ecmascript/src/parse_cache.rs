@@ -0,0 +1,234 @@
+//! A cache of parsed programs, keyed by the source content and the parser
+//! options.
+//!
+//! The same source is often parsed multiple times, e.g. when a vendor file is
+//! used in multiple compilations. Only the output of the parser is cached,
+//! i.e. before the resolver and any transforms are applied, so the cached
+//! program doesn't contain any marks and can be reused with other globals.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use once_cell::sync::Lazy;
+use swc_core::{
+    base::SwcComments,
+    common::{
+        comments::{Comment, Comments},
+        BytePos, SourceFile,
+    },
+    ecma::{
+        ast::{EsVersion, Program},
+        parser::{error::Error, Syntax},
+    },
+};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+/// The cache that is used by [crate::parse::parse_program_with_recovery].
+pub static PARSE_CACHE: Lazy<ParseCache> = Lazy::new(ParseCache::new);
+
+/// The cache is cleared when it grows beyond this number of entries.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(PartialEq, Eq, Hash)]
+struct ParseCacheKey {
+    content_hash: u64,
+    /// Spans are relative to the start of the file in the source map.
+    start_pos: BytePos,
+    syntax: String,
+    target: EsVersion,
+    comments: bool,
+}
+
+struct CachedProgram {
+    src: Arc<String>,
+    program: Program,
+    leading_comments: Vec<(BytePos, Vec<Comment>)>,
+    trailing_comments: Vec<(BytePos, Vec<Comment>)>,
+}
+
+pub struct ParseCache {
+    entries: Mutex<HashMap<ParseCacheKey, CachedProgram>>,
+    enabled: AtomicBool,
+    hits: AtomicUsize,
+}
+
+impl Default for ParseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParseCache {
+    pub fn new() -> Self {
+        ParseCache {
+            entries: Mutex::new(HashMap::new()),
+            enabled: AtomicBool::new(true),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// Enables or disables the cache. A disabled cache always parses the
+    /// source, which is useful to verify that the cache doesn't change the
+    /// results.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+        if !enabled {
+            self.entries.lock().unwrap().clear();
+        }
+    }
+
+    /// The number of parses that have been served from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    /// Returns a copy of a previously parsed program with the same content and
+    /// options, or parses the file with `parse`, which adds the comments of the
+    /// file to `comments`. A cached program adds the comments of the file to
+    /// `comments` too.
+    ///
+    /// Programs with errors are not cached.
+    pub fn parse_program(
+        &self,
+        fm: &SourceFile,
+        syntax: Syntax,
+        target: EsVersion,
+        comments: Option<&SwcComments>,
+        parse: impl FnOnce() -> (Option<Program>, Vec<Error>),
+    ) -> (Option<Program>, Vec<Error>) {
+        let enabled = self.enabled.load(Ordering::SeqCst);
+        let key = ParseCacheKey {
+            content_hash: hash_xxh3_hash64(fm.src.as_bytes()),
+            start_pos: fm.start_pos,
+            syntax: format!("{syntax:?}"),
+            target,
+            comments: comments.is_some(),
+        };
+
+        if enabled {
+            if let Some(cached) = self.entries.lock().unwrap().get(&key) {
+                if cached.src == fm.src {
+                    self.hits.fetch_add(1, Ordering::SeqCst);
+                    if let Some(comments) = comments {
+                        for (pos, list) in cached.leading_comments.iter() {
+                            comments.add_leading_comments(*pos, list.clone());
+                        }
+                        for (pos, list) in cached.trailing_comments.iter() {
+                            comments.add_trailing_comments(*pos, list.clone());
+                        }
+                    }
+                    return (Some(cached.program.clone()), Vec::new());
+                }
+            }
+        }
+
+        let (program, errors) = parse();
+        let program = match program {
+            Some(program) if errors.is_empty() => program,
+            program => return (program, errors),
+        };
+
+        if enabled {
+            let (leading_comments, trailing_comments) = comments
+                .map(|comments| {
+                    let leading = comments
+                        .leading
+                        .iter()
+                        .map(|entry| (*entry.key(), entry.value().clone()))
+                        .collect();
+                    let trailing = comments
+                        .trailing
+                        .iter()
+                        .map(|entry| (*entry.key(), entry.value().clone()))
+                        .collect();
+                    (leading, trailing)
+                })
+                .unwrap_or_default();
+            let mut entries = self.entries.lock().unwrap();
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+            entries.insert(
+                key,
+                CachedProgram {
+                    src: fm.src.clone(),
+                    program: program.clone(),
+                    leading_comments,
+                    trailing_comments,
+                },
+            );
+        }
+
+        (Some(program), errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_core::{
+        base::SwcComments,
+        common::{comments::Comments, input::StringInput, FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::{lexer::Lexer, Parser, Syntax, TsConfig},
+        },
+        testing::run_test,
+    };
+
+    use super::ParseCache;
+
+    const SOURCE: &str = "// comment\nexport const a = 1;";
+
+    fn parse(cache: &ParseCache, syntax: Syntax) -> (Program, SwcComments) {
+        // Every parse uses a new source map, like the parse module does.
+        let cm = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Anon, SOURCE.into());
+        let comments = SwcComments::default();
+        let (program, errors) =
+            cache.parse_program(&fm, syntax, EsVersion::latest(), Some(&comments), || {
+                let lexer = Lexer::new(
+                    syntax,
+                    EsVersion::latest(),
+                    StringInput::from(&*fm),
+                    Some(&comments as &dyn Comments),
+                );
+                let mut parser = Parser::new_from(lexer);
+                let program = parser.parse_program().unwrap();
+                (Some(program), parser.take_errors())
+            });
+        assert!(errors.is_empty());
+        (program.unwrap(), comments)
+    }
+
+    #[test]
+    fn cache_hit() {
+        run_test(false, |_cm, _handler| {
+            let cache = ParseCache::new();
+            let (first, _) = parse(&cache, Default::default());
+            assert_eq!(cache.hits(), 0);
+
+            let (second, comments) = parse(&cache, Default::default());
+            assert_eq!(cache.hits(), 1);
+            assert_eq!(first, second);
+            assert_eq!(comments.leading.len(), 1);
+
+            // Different parser options
+            parse(&cache, Syntax::Typescript(TsConfig::default()));
+            assert_eq!(cache.hits(), 1);
+
+            cache.set_enabled(false);
+            parse(&cache, Default::default());
+            assert_eq!(cache.hits(), 1);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+}
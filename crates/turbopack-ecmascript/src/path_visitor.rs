@@ -1,33 +1,242 @@
-use std::borrow::Cow;
+use std::{
+    any::Any,
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::Hash,
+    ops::Range,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
+use anyhow::{anyhow, bail, Context, Result};
 use swc_core::{
-    common::pass::AstKindPath,
+    common::{
+        comments::Comments,
+        pass::{AstKindPath, AstNodePath},
+        BytePos, Span, Spanned,
+    },
     ecma::{
         ast::*,
-        visit::{AstParentKind, VisitMut, VisitMutAstPath, VisitMutWith, VisitMutWithPath},
+        visit::{
+            fields::*, AstParentKind, AstParentNodeRef, Visit, VisitAstPath, VisitMut,
+            VisitMutAstPath, VisitMutWith, VisitMutWithPath, VisitWith, VisitWithPath,
+        },
     },
 };
 
-use crate::code_gen::VisitorFactory;
+use crate::code_gen::{ListVisitor, VisitOutcome, VisitorFactory};
+
+/// A single step in an [AstPath]. It encodes the kind of the parent node
+/// together with the field that is descended into, including the child index
+/// for list fields (e.g. `SeqExprField::Exprs(1)`). Matching is therefore
+/// positional and doesn't depend on spans being unique.
+///
+/// The kinds are validated at every step, so a stale path that leads to a
+/// node of a different kind is reported as unmatched instead of mutating it.
+pub type PathSegment = AstParentKind;
+
+pub type AstPath = Vec<PathSegment>;
+
+/// The node the helpers of this module start their traversal at: a [Program],
+/// or a [Module] or [Script] directly. Paths are relative to that node, e.g.
+/// the paths found in a [Program] start with an [AstParentKind::Program].
+///
+/// Scripts contain [Stmt]s instead of [ModuleItem]s, so visitors targeting a
+/// [ModuleDecl] never apply to them.
+pub trait AstRoot {
+    fn visit_root<V: VisitAstPath>(&self, visitor: &mut V);
+
+    fn visit_mut_root<V: VisitMutAstPath>(&mut self, visitor: &mut V);
+}
+
+macro_rules! impl_ast_root {
+    ($($T:ty),*) => {
+        $(
+            impl AstRoot for $T {
+                fn visit_root<V: VisitAstPath>(&self, visitor: &mut V) {
+                    self.visit_with_path(visitor, &mut Default::default());
+                }
+
+                fn visit_mut_root<V: VisitMutAstPath>(&mut self, visitor: &mut V) {
+                    self.visit_mut_with_path(visitor, &mut Default::default());
+                }
+            }
+        )*
+    };
+}
+
+impl_ast_root!(Program, Module, Script);
 
-pub type AstPath = Vec<AstParentKind>;
+/// The structural position of a node within its parent, for visitors that
+/// need to rewrite depending on the context of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParentKind {
+    /// The callee of a call or `new` expression.
+    Callee,
+    /// The argument at the index of a call or `new` expression.
+    Argument(usize),
+    /// The element at the index of an array literal.
+    ArrayElement(usize),
+    /// The value of a key-value property in an object literal.
+    ObjectValue,
+    Other,
+}
+
+impl ParentKind {
+    /// Determines the position of the node at `ast_path`. Identifiers,
+    /// literals and the strings of string literals are treated like the
+    /// expression wrapping them.
+    pub fn from_path(ast_path: &[AstParentKind]) -> Self {
+        let ast_path = match ast_path {
+            [rest @ .., AstParentKind::Expr(ExprField::Lit), AstParentKind::Lit(LitField::Str)]
+            | [rest @ .., AstParentKind::Expr(_)] => rest,
+            _ => ast_path,
+        };
+        match ast_path {
+            [.., AstParentKind::Callee(CalleeField::Expr)]
+            | [.., AstParentKind::NewExpr(NewExprField::Callee)] => ParentKind::Callee,
+            [.., AstParentKind::CallExpr(CallExprField::Args(index))
+            | AstParentKind::NewExpr(NewExprField::Args(index)), AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr)] => {
+                ParentKind::Argument(*index)
+            }
+            [.., AstParentKind::ArrayLit(ArrayLitField::Elems(index)), AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr)] => {
+                ParentKind::ArrayElement(*index)
+            }
+            [.., AstParentKind::KeyValueProp(KeyValuePropField::Value)] => ParentKind::ObjectValue,
+            _ => ParentKind::Other,
+        }
+    }
+}
 
 // Invariant: Each [AstPath] in `visitors` contains a value at position `index`.
+//
+// `visitors` is sorted once on construction, which turns it into a flattened
+// trie: the visitors sharing a path prefix are a contiguous sub-slice, so
+// descending into a node only narrows the slice and never allocates.
 pub struct ApplyVisitors<'a, 'b> {
     /// `VisitMut` should be shallow. In other words, it should not visit
-    /// children of the node.
+    /// children of the node. Visitors created by [crate::create_visitor]
+    /// check that in debug builds.
     visitors: Cow<'b, [(&'a AstPath, &'a dyn VisitorFactory)]>,
 
+    /// The index of the first of `visitors` in the visitors of the outermost
+    /// [ApplyVisitors], which identifies visitors in the shared state.
+    offset: usize,
+
     index: usize,
+
+    order: TraversalOrder,
+
+    /// When set, the comments of nodes whose span is changed by a visitor are
+    /// moved to the new span, and the comments are passed to the visitors.
+    comments: Option<&'b (dyn Comments + Sync)>,
+
+    /// Nodes nested deeper than this are not visited, see
+    /// [ApplyVisitors::with_max_depth].
+    max_depth: usize,
+
+    /// Shared with the nested [ApplyVisitors].
+    state: Rc<ApplyVisitorsState>,
+}
+
+#[derive(Default)]
+struct ApplyVisitorsState {
+    /// Whether the visitor at the index in the sorted visitors of the
+    /// outermost [ApplyVisitors] has been applied. Only tracked when
+    /// requested.
+    reached: Option<RefCell<Vec<bool>>>,
+    /// The first error that occurred while creating a visitor or when the
+    /// maximum depth was exceeded.
+    error: RefCell<Option<anyhow::Error>>,
+    /// The number of visitor invocations.
+    applied: Cell<usize>,
+    /// Visitors that would have been applied. Only set in record only mode, in
+    /// which no visitors are applied.
+    planned: Option<RefCell<Vec<PlannedEdit>>>,
+    /// The invocations of visitors by [VisitorFactory::name]. Only tracked
+    /// when requested.
+    stats: Option<RefCell<HashMap<&'static str, VisitorStats>>>,
+    /// Paths whose visitors are applied after the nested nodes have been
+    /// visited, even in [TraversalOrder::PreOrder]. Only set by
+    /// [apply_in_source_order].
+    nested_first: Option<HashSet<AstPath>>,
+}
+
+impl ApplyVisitorsState {
+    /// Invokes the visitor named `name` with `apply`.
+    fn invoke<T>(&self, name: &'static str, apply: impl FnOnce() -> T) -> T {
+        self.applied.set(self.applied.get() + 1);
+        let Some(stats) = &self.stats else {
+            return apply();
+        };
+        let start = Instant::now();
+        let result = apply();
+        let mut stats = stats.borrow_mut();
+        let stats = stats.entry(name).or_default();
+        stats.invocations += 1;
+        stats.duration += start.elapsed();
+        result
+    }
+}
+
+/// How often a visitor has been invoked by [ApplyVisitors] and how long that
+/// took, see [ApplyVisitors::with_stats].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VisitorStats {
+    /// The number of nodes the visitor was applied to, including the nodes it
+    /// declined.
+    pub invocations: usize,
+    /// The time spent in the visitor, excluding its creation.
+    pub duration: Duration,
+}
+
+/// A visitor that would have been applied by [ApplyVisitors] in record only
+/// mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedEdit {
+    pub path: AstPath,
+    /// The span of the node that the visitor would have been applied to.
+    pub span: Span,
+    /// See [VisitorFactory::name].
+    pub visitor_name: &'static str,
+}
+
+/// Determines when visitors are applied relative to the visitors of nested
+/// nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraversalOrder {
+    /// Visitors are applied before visiting nested nodes. Changes made by them
+    /// can invalidate the paths of nested visitors.
+    PreOrder,
+    /// Visitors are applied after all nested nodes have been visited.
+    #[default]
+    PostOrder,
+}
+
+/// The margin added to the length of the longest path for the default maximum
+/// depth of [ApplyVisitors].
+const MAX_DEPTH_MARGIN: usize = 16;
+
+/// The default maximum depth of [ApplyVisitors]. Nodes deeper than the longest
+/// path are never visited, so this only trips on malformed paths.
+fn default_max_depth(visitors: &[(&AstPath, &dyn VisitorFactory)]) -> usize {
+    visitors
+        .iter()
+        .map(|(path, _)| path.len())
+        .max()
+        .unwrap_or(0)
+        + MAX_DEPTH_MARGIN
 }
 
-/// Do two binary searches to find the sub-slice that has `path[index] == kind`.
-/// Returns None if no item matches that. `visitors` need to be sorted by path.
-fn find_range<'a, 'b>(
-    visitors: &'b [(&'a AstPath, &'a dyn VisitorFactory)],
+/// Do two binary searches to find the range of the sub-slice that has
+/// `path[index] == kind`. Returns None if no item matches that. `visitors` need
+/// to be sorted by path.
+fn find_range(
+    visitors: &[(&AstPath, &dyn VisitorFactory)],
     kind: &AstParentKind,
     index: usize,
-) -> Option<&'b [(&'a AstPath, &'a dyn VisitorFactory)]> {
+) -> Option<Range<usize>> {
     // Precondition: visitors is never empty
     let start = if visitors.first().unwrap().0[index] >= *kind {
         // Fast path: It's likely that the whole range is selected
@@ -48,32 +257,218 @@ fn find_range<'a, 'b>(
         return None;
     }
     // Postcondition: return value is never empty
-    Some(&visitors[start..end])
+    Some(start..end)
 }
 
 impl<'a, 'b> ApplyVisitors<'a, 'b> {
     /// `visitors` must have an non-empty [AstPath].
-    pub fn new(mut visitors: Vec<(&'a AstPath, &'a dyn VisitorFactory)>) -> Self {
+    ///
+    /// The order in which visitors are applied only depends on their paths:
+    /// Visitors for nested nodes are applied before visitors for their
+    /// parents (unless [TraversalOrder::PreOrder] is used), and siblings are
+    /// applied in the order of their position in the parent. Only visitors
+    /// registered for the exact same path are applied in the order they
+    /// have been passed in.
+    pub fn new(visitors: Vec<(&'a AstPath, &'a dyn VisitorFactory)>) -> Self {
+        Self::new_with_tracking(visitors, false)
+    }
+
+    /// Like [ApplyVisitors::new], but doesn't apply any visitors. Instead it
+    /// records which visitors would have been applied at which path, which can
+    /// be retrieved with [ApplyVisitors::into_planned_edits]. As visitors
+    /// aren't applied, all visitors for a path are recorded, even if they
+    /// would have declined the node.
+    pub fn new_record_only(mut visitors: Vec<(&'a AstPath, &'a dyn VisitorFactory)>) -> Self {
+        assert!(!visitors.is_empty());
+        visitors.sort_by_key(|(path, _)| *path);
+        let max_depth = default_max_depth(&visitors);
+        Self {
+            visitors: Cow::Owned(visitors),
+            offset: 0,
+            index: 0,
+            order: TraversalOrder::PostOrder,
+            comments: None,
+            max_depth,
+            state: Rc::new(ApplyVisitorsState {
+                planned: Some(Default::default()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Returns the visitors that would have been applied, in application
+    /// order. Always empty when not created with
+    /// [ApplyVisitors::new_record_only].
+    pub fn into_planned_edits(self) -> Vec<PlannedEdit> {
+        match &self.state.planned {
+            Some(planned) => planned.take(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Applies multiple independent sets of visitors in a single traversal.
+    /// Visitors registered for the same path are applied in the order of
+    /// their sets, and in registration order within a set.
+    pub fn merged(visitor_sets: Vec<Vec<(&'a AstPath, &'a dyn VisitorFactory)>>) -> Self {
+        Self::new(visitor_sets.into_iter().flatten().collect())
+    }
+
+    /// Like [ApplyVisitors::new], but when `track_unmatched` is set, the paths
+    /// of visitors that were never applied can be retrieved after the
+    /// traversal with [ApplyVisitors::into_unmatched].
+    pub fn new_with_tracking(
+        mut visitors: Vec<(&'a AstPath, &'a dyn VisitorFactory)>,
+        track_unmatched: bool,
+    ) -> Self {
         assert!(!visitors.is_empty());
         visitors.sort_by_key(|(path, _)| *path);
+        let max_depth = default_max_depth(&visitors);
+        let reached = track_unmatched.then(|| RefCell::new(vec![false; visitors.len()]));
         Self {
             visitors: Cow::Owned(visitors),
+            offset: 0,
             index: 0,
+            order: TraversalOrder::PostOrder,
+            comments: None,
+            max_depth,
+            state: Rc::new(ApplyVisitorsState {
+                reached,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Sets the order in which visitors of nested nodes are applied. Defaults
+    /// to [TraversalOrder::PostOrder].
+    pub fn with_order(mut self, order: TraversalOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Limits how deep nodes are nested that are visited. When a node deeper
+    /// than `max_depth` would be visited, an error is recorded (see
+    /// [ApplyVisitors::take_error]) and no further visitors are applied,
+    /// instead of risking a stack overflow. Defaults to the length of the
+    /// longest path plus a margin.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Moves the leading and trailing comments of a node to its new span when
+    /// a visitor replaces the node with one that has a different span, e.g.
+    /// to keep `// eslint-disable-next-line` or license comments. Comments
+    /// are only moved once all visitors for the node have been applied, and
+    /// they stay in place when the new node has a dummy span (see
+    /// [assign_synthetic_spans]).
+    ///
+    /// Visitors are created with
+    /// [VisitorFactory::try_create_path_visitor_with_comments] then, so they
+    /// can consult the comments too.
+    pub fn with_comments(mut self, comments: &'b (dyn Comments + Sync)) -> Self {
+        self.comments = Some(comments);
+        self
+    }
+
+    /// Returns the paths of all visitors that didn't match any node. Always
+    /// empty when tracking wasn't enabled in
+    /// [ApplyVisitors::new_with_tracking].
+    pub fn into_unmatched(self) -> Vec<AstPath> {
+        let Some(reached) = &self.state.reached else {
+            return Vec::new();
+        };
+        let reached = reached.borrow();
+        self.visitors
+            .iter()
+            .zip(reached.iter())
+            .filter(|(_, reached)| !**reached)
+            .map(|((path, _), _)| (*path).clone())
+            .collect()
+    }
+
+    /// Records how often each visitor is invoked and the time spent in it, to
+    /// profile a set of transforms. The statistics can be retrieved with
+    /// [ApplyVisitors::into_stats]. Without this, nothing is measured.
+    pub fn with_stats(mut self) -> Self {
+        Rc::get_mut(&mut self.state)
+            .expect("stats need to be enabled before visiting")
+            .stats = Some(Default::default());
+        self
+    }
+
+    /// Applies the visitors of `paths` after visiting the nested nodes, see
+    /// [ApplyVisitorsState::nested_first].
+    fn with_nested_first(mut self, paths: HashSet<AstPath>) -> Self {
+        Rc::get_mut(&mut self.state)
+            .expect("the order needs to be set before visiting")
+            .nested_first = Some(paths);
+        self
+    }
+
+    /// Returns the statistics of the visitors by [VisitorFactory::name].
+    /// Visitors with the same name are combined. Always empty when not
+    /// enabled with [ApplyVisitors::with_stats].
+    pub fn into_stats(self) -> HashMap<&'static str, VisitorStats> {
+        match &self.state.stats {
+            Some(stats) => stats.take(),
+            None => HashMap::new(),
+        }
+    }
+
+    /// Returns the number of times a visitor has been applied to a node.
+    ///
+    /// This counts visitor invocations, including those of visitors that
+    /// declined the node or left it unchanged. A count of zero guarantees that
+    /// the AST hasn't been modified, any other count doesn't guarantee a
+    /// modification.
+    pub fn applied_count(&self) -> usize {
+        self.state.applied.get()
+    }
+
+    /// Returns true when at least one visitor has been applied. See
+    /// [ApplyVisitors::applied_count].
+    pub fn changed(&self) -> bool {
+        self.applied_count() > 0
+    }
+
+    /// Returns the first error that occurred while creating a visitor or
+    /// because the maximum depth was exceeded. No visitors are applied after
+    /// an error occurred.
+    pub fn take_error(&self) -> Result<()> {
+        match self.state.error.borrow_mut().take() {
+            Some(err) => Err(err),
+            None => Ok(()),
         }
     }
 
     #[inline(never)]
     fn visit_if_required<N>(&mut self, n: &mut N, ast_path: &mut AstKindPath<AstParentKind>)
     where
-        N: for<'aa> VisitMutWith<dyn VisitMut + Send + Sync + 'aa>
+        N: Spanned
+            + for<'aa> VisitMutWith<dyn VisitMut + Send + Sync + 'aa>
             + for<'aa, 'bb> VisitMutWithPath<ApplyVisitors<'aa, 'bb>>,
     {
+        if self.state.error.borrow().is_some() {
+            return;
+        }
+        if ast_path.len() > self.max_depth {
+            *self.state.error.borrow_mut() = Some(anyhow!(
+                "visiting a node at depth {} exceeds the maximum depth of {}",
+                ast_path.len(),
+                self.max_depth
+            ));
+            return;
+        }
         let mut index = self.index;
         let mut current_visitors = self.visitors.as_ref();
+        // The index of the first of `current_visitors` in `self.visitors`
+        let mut current_start = 0;
         while index < ast_path.len() {
             let current = index == ast_path.len() - 1;
             let kind = ast_path[index];
-            if let Some(visitors) = find_range(current_visitors, &kind, index) {
+            if let Some(range) = find_range(current_visitors, &kind, index) {
+                let visitors = &current_visitors[range.clone()];
+                let visitors_start = self.offset + current_start + range.start;
                 // visitors contains all items that match kind at index. Some of them terminate
                 // here, some need furth visiting. The terminating items are at the start due to
                 // sorting of the list.
@@ -83,24 +478,46 @@ impl<'a, 'b> ApplyVisitors<'a, 'b> {
                 let nested_visitors_start =
                     visitors.partition_point(|(path, _)| path.len() == index);
                 if current {
+                    let (terminal_visitors, nested_visitors) =
+                        visitors.split_at(nested_visitors_start);
+                    let pre_order = self.is_pre_order(terminal_visitors);
+                    if pre_order {
+                        self.apply_terminal_visitors(
+                            terminal_visitors,
+                            visitors_start,
+                            n,
+                            ast_path,
+                        );
+                    }
                     // Potentially skip visiting this sub tree
-                    if nested_visitors_start < visitors.len() {
+                    if !nested_visitors.is_empty() {
                         n.visit_mut_children_with_path(
                             &mut ApplyVisitors {
                                 // We only select visitors starting from `nested_visitors_start`
                                 // which maintains the invariant.
-                                visitors: Cow::Borrowed(&visitors[nested_visitors_start..]),
+                                visitors: Cow::Borrowed(nested_visitors),
+                                offset: visitors_start + nested_visitors_start,
                                 index,
+                                order: self.order,
+                                comments: self.comments,
+                                max_depth: self.max_depth,
+                                state: self.state.clone(),
                             },
                             ast_path,
                         );
                     }
-                    for (_, visitor) in visitors[..nested_visitors_start].iter() {
-                        n.visit_mut_with(&mut visitor.create());
+                    if !pre_order {
+                        self.apply_terminal_visitors(
+                            terminal_visitors,
+                            visitors_start,
+                            n,
+                            ast_path,
+                        );
                     }
                     return;
                 } else {
                     current_visitors = &visitors[nested_visitors_start..];
+                    current_start += range.start + nested_visitors_start;
                 }
             } else {
                 // Skip visiting this sub tree
@@ -110,173 +527,3218 @@ impl<'a, 'b> ApplyVisitors<'a, 'b> {
         // Ast path is unchanged, just keep visiting
         n.visit_mut_children_with_path(self, ast_path);
     }
-}
 
-macro_rules! method {
-    ($name:ident, $T:ty) => {
-        fn $name(&mut self, n: &mut $T, ast_path: &mut AstKindPath<AstParentKind>) {
-            self.visit_if_required(n, ast_path);
+    /// Whether `terminal_visitors`, which all have the same path, are applied
+    /// before the nested nodes are visited.
+    fn is_pre_order(&self, terminal_visitors: &[(&'a AstPath, &'a dyn VisitorFactory)]) -> bool {
+        if self.order == TraversalOrder::PostOrder {
+            return false;
         }
-    };
-}
+        match (&self.state.nested_first, terminal_visitors.first()) {
+            (Some(nested_first), Some((path, _))) => !nested_first.contains(*path),
+            _ => true,
+        }
+    }
 
-impl VisitMutAstPath for ApplyVisitors<'_, '_> {
-    // TODO: we need a macro to apply that for all methods
-    method!(visit_mut_prop, Prop);
-    method!(visit_mut_expr, Expr);
-    method!(visit_mut_pat, Pat);
-    method!(visit_mut_stmt, Stmt);
-    method!(visit_mut_module_decl, ModuleDecl);
-    method!(visit_mut_module_item, ModuleItem);
-    method!(visit_mut_call_expr, CallExpr);
-    method!(visit_mut_lit, Lit);
-    method!(visit_mut_str, Str);
+    /// Applies the visitors whose path terminates at `n`. `offset` is the
+    /// index of the first of them, see [ApplyVisitors::offset].
+    fn apply_terminal_visitors<N>(
+        &self,
+        visitors: &[(&'a AstPath, &'a dyn VisitorFactory)],
+        offset: usize,
+        n: &mut N,
+        ast_path: &AstKindPath<AstParentKind>,
+    ) where
+        N: Spanned + for<'aa> VisitMutWith<dyn VisitMut + Send + Sync + 'aa>,
+    {
+        if let Some(reached) = &self.state.reached {
+            reached.borrow_mut()[offset..offset + visitors.len()].fill(true);
+        }
+        if let Some(planned) = &self.state.planned {
+            planned
+                .borrow_mut()
+                .extend(visitors.iter().map(|(path, visitor)| PlannedEdit {
+                    path: (*path).clone(),
+                    span: n.span(),
+                    visitor_name: visitor.name(),
+                }));
+            return;
+        }
+        let old_span = self.comments.map(|_| n.span());
+        for (_, visitor) in visitors.iter() {
+            if visitor.as_list_visitor().is_some() {
+                // Applied by the containing list
+                continue;
+            }
+            let name = visitor.name();
+            let visitor = match self.comments {
+                Some(comments) => {
+                    visitor.try_create_path_visitor_with_comments(&ast_path[..], comments)
+                }
+                None => visitor.try_create_path_visitor(&ast_path[..]),
+            };
+            let mut visitor = match visitor
+                .with_context(|| format!("failed to create visitor for {:?}", &ast_path[..]))
+            {
+                Ok(visitor) => visitor,
+                Err(err) => {
+                    *self.state.error.borrow_mut() = Some(err);
+                    return;
+                }
+            };
+            self.state
+                .invoke(name, || n.visit_mut_with(visitor.visitor()));
+            if visitor.outcome() == VisitOutcome::Applied {
+                break;
+            }
+        }
+        if let (Some(comments), Some(old_span)) = (self.comments, old_span) {
+            move_comments(comments, old_span, n.span());
+        }
+    }
+
+    /// Visits the items of a list and afterwards applies the [ListVisitor]s
+    /// targeting items of that list.
+    fn visit_list_if_required<T>(
+        &mut self,
+        n: &mut Vec<T>,
+        ast_path: &mut AstKindPath<AstParentKind>,
+        apply: impl Fn(&dyn ListVisitor, &mut Vec<T>, usize),
+    ) where
+        Vec<T>: for<'aa, 'bb> VisitMutWithPath<ApplyVisitors<'aa, 'bb>>,
+    {
+        n.visit_mut_children_with_path(self, ast_path);
+        if self.state.planned.is_some() {
+            // Already recorded when visiting the items
+            return;
+        }
+
+        // The path of a list ends with the field of the list with an index of
+        // `usize::MAX`, visitors targeting an item have the index of that item.
+        let len = ast_path.len();
+        let list_kind = ast_path[len - 1];
+        // Indexed by the kind of the targeted item, in registration order
+        let mut list_visitors: HashMap<AstParentKind, Vec<_>> = HashMap::new();
+        for (path, visitor) in self.visitors.iter() {
+            if path.len() != len || path[self.index..len - 1] != ast_path[self.index..len - 1] {
+                continue;
+            }
+            let Some(list_visitor) = visitor.as_list_visitor() else {
+                continue;
+            };
+            let mut kind = path[len - 1];
+            kind.set_index(usize::MAX);
+            if kind != list_kind {
+                continue;
+            }
+            list_visitors
+                .entry(path[len - 1])
+                .or_default()
+                .push((visitor.name(), list_visitor));
+        }
+        // From the last item to the first, so splicing doesn't shift the items
+        // that are visited later
+        for index in (0..n.len()).rev() {
+            if list_visitors.is_empty() {
+                break;
+            }
+            let mut kind = list_kind;
+            kind.set_index(index);
+            let Some(item_visitors) = list_visitors.remove(&kind) else {
+                continue;
+            };
+            for (name, list_visitor) in item_visitors {
+                self.state.invoke(name, || apply(list_visitor, n, index));
+            }
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use std::sync::Arc;
+/// Moves the comments attached to `old` to `new`.
+fn move_comments(comments: &dyn Comments, old: Span, new: Span) {
+    if new.is_dummy() {
+        return;
+    }
+    if old.lo != new.lo {
+        if let Some(leading) = comments.take_leading(old.lo) {
+            comments.add_leading_comments(new.lo, leading);
+        }
+    }
+    if old.hi != new.hi {
+        if let Some(trailing) = comments.take_trailing(old.hi) {
+            comments.add_trailing_comments(new.hi, trailing);
+        }
+    }
+}
 
-    use swc_core::{
-        common::{errors::HANDLER, FileName, Mark, SourceFile, SourceMap},
-        ecma::{
-            ast::*,
-            codegen::{text_writer::JsWriter, Emitter},
-            parser::parse_file_as_module,
-            transforms::base::resolver,
-            visit::{fields::*, AstParentKind, VisitMut, VisitMutWith, VisitMutWithPath},
-        },
-        testing::run_test,
-    };
+/// Creates a visitor that replaces the source of the import or re-export at
+/// `ast_path`, which needs to point to a [ModuleDecl].
+pub fn rewrite_import_source(
+    ast_path: &[AstParentKind],
+    new: String,
+) -> (AstPath, Box<dyn VisitorFactory>) {
+    fn rewrite(src: &mut Str, new: &str) {
+        src.value = new.into();
+        src.raw = None;
+    }
 
-    use super::{ApplyVisitors, VisitorFactory};
+    crate::create_visitor!(exact ast_path, visit_mut_module_decl(decl: &mut ModuleDecl) {
+        match decl {
+            ModuleDecl::Import(ImportDecl { src, .. })
+            | ModuleDecl::ExportAll(ExportAll { src, .. }) => rewrite(src, &new),
+            ModuleDecl::ExportNamed(NamedExport { src: Some(src), .. }) => rewrite(src, &new),
+            _ => {}
+        }
+    })
+}
 
-    fn parse(fm: &SourceFile) -> Module {
-        let mut m = parse_file_as_module(
-            fm,
-            Default::default(),
-            EsVersion::latest(),
-            None,
-            &mut vec![],
-        )
-        .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
-        .unwrap();
+/// Collects substring replacements in string literals and applies all
+/// replacements of a literal together, to its original value. Independent
+/// visitors that each replace a substring see the value changed by the
+/// visitors applied before them, so they can clobber each other's results.
+///
+/// The occurrences of all substrings are located in the original value. When
+/// occurrences overlap, the replacement that was added first wins.
+#[derive(Default)]
+pub struct StrEdits {
+    edits: BTreeMap<AstPath, Vec<(String, String)>>,
+}
 
-        let unresolved_mark = Mark::new();
-        let top_level_mark = Mark::new();
-        m.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+impl StrEdits {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        m
+    /// Replaces all occurrences of `from` with `to` in the [Str] at
+    /// `ast_path`.
+    pub fn replace(
+        &mut self,
+        ast_path: &[AstParentKind],
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) {
+        self.edits
+            .entry(ast_path.to_vec())
+            .or_default()
+            .push((from.into(), to.into()));
     }
 
-    struct StrReplacer<'a> {
-        from: &'a str,
-        to: &'a str,
+    /// Creates a single visitor for each string literal with replacements.
+    pub fn into_visitors(self) -> Vec<(AstPath, Box<dyn VisitorFactory>)> {
+        self.edits
+            .into_iter()
+            .map(|(ast_path, edits)| {
+                crate::create_visitor!(exact ast_path, visit_mut_str(s: &mut Str) {
+                    s.value = apply_str_edits(&s.value, &edits).into();
+                    s.raw = None;
+                })
+            })
+            .collect()
     }
+}
 
-    impl VisitorFactory for Box<StrReplacer<'_>> {
-        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
-            box &**self
+fn apply_str_edits(value: &str, edits: &[(String, String)]) -> String {
+    // The replaced ranges by their start, they never overlap
+    let mut replaced: BTreeMap<usize, (usize, &str)> = BTreeMap::new();
+    for (from, to) in edits {
+        if from.is_empty() {
+            continue;
+        }
+        for (start, _) in value.match_indices(from.as_str()) {
+            let end = start + from.len();
+            let overlaps = replaced
+                .range(..end)
+                .next_back()
+                .map_or(false, |(_, (replaced_end, _))| *replaced_end > start);
+            if !overlaps {
+                replaced.insert(start, (end, to));
+            }
         }
     }
+    let mut result = String::with_capacity(value.len());
+    let mut pos = 0;
+    for (start, (end, to)) in replaced {
+        result.push_str(&value[pos..start]);
+        result.push_str(to);
+        pos = end;
+    }
+    result.push_str(&value[pos..]);
+    result
+}
 
-    impl VisitMut for &'_ StrReplacer<'_> {
-        fn visit_mut_str(&mut self, s: &mut Str) {
-            s.value = s.value.replace(self.from, self.to).into();
-            s.raw = None;
+/// Builds an [AstPath] while navigating a real AST. Each step is validated
+/// against the actual node, so the resulting path is guaranteed to point to
+/// an existing node.
+///
+/// ```ignore
+/// let path = AstPathBuilder::new(&module)
+///     .child_stmt(0)?
+///     .expr()?
+///     .paren()?
+///     .seq_expr(1)?
+///     .into_path();
+/// ```
+pub struct AstPathBuilder<'a> {
+    node: AstPathNode<'a>,
+    path: AstPath,
+}
+
+#[derive(Clone, Copy)]
+enum AstPathNode<'a> {
+    Module(&'a Module),
+    Stmt(&'a Stmt),
+    Expr(&'a Expr),
+    Lit(&'a Lit),
+    Str(&'a Str),
+}
+
+impl AstPathNode<'_> {
+    fn kind(&self) -> &'static str {
+        match self {
+            AstPathNode::Module(_) => "Module",
+            AstPathNode::Stmt(_) => "Stmt",
+            AstPathNode::Expr(_) => "Expr",
+            AstPathNode::Lit(_) => "Lit",
+            AstPathNode::Str(_) => "Str",
         }
     }
+}
 
-    fn replacer(from: &'static str, to: &'static str) -> impl VisitorFactory {
-        box StrReplacer { from, to }
+impl<'a> AstPathBuilder<'a> {
+    pub fn new(module: &'a Module) -> Self {
+        Self {
+            node: AstPathNode::Module(module),
+            path: Vec::new(),
+        }
     }
 
-    fn to_js(m: &Module, cm: &Arc<SourceMap>) -> String {
-        let mut bytes = Vec::new();
-        let mut emitter = Emitter {
-            cfg: swc_core::ecma::codegen::Config {
-                minify: true,
-                ..Default::default()
-            },
-            cm: cm.clone(),
-            comments: None,
-            wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+    fn step(
+        mut self,
+        expected: &str,
+        segments: impl IntoIterator<Item = AstParentKind>,
+        next: impl FnOnce(AstPathNode<'a>) -> Option<AstPathNode<'a>>,
+    ) -> Result<Self> {
+        let Some(node) = next(self.node) else {
+            bail!(
+                "expected {} at {:?}, found {}",
+                expected,
+                self.path,
+                self.node.kind()
+            );
         };
+        self.node = node;
+        self.path.extend(segments);
+        Ok(self)
+    }
 
-        emitter.emit_module(m).unwrap();
+    /// Navigates to the `index`th statement of a module or block statement.
+    pub fn child_stmt(self, index: usize) -> Result<Self> {
+        match self.node {
+            AstPathNode::Module(_) => self.step(
+                "a statement",
+                [
+                    AstParentKind::Module(ModuleField::Body(index)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                ],
+                |node| match node {
+                    AstPathNode::Module(m) => match m.body.get(index)? {
+                        ModuleItem::Stmt(stmt) => Some(AstPathNode::Stmt(stmt)),
+                        ModuleItem::ModuleDecl(_) => None,
+                    },
+                    _ => None,
+                },
+            ),
+            _ => self.step(
+                "a block statement",
+                [
+                    AstParentKind::Stmt(StmtField::Block),
+                    AstParentKind::BlockStmt(BlockStmtField::Stmts(index)),
+                ],
+                |node| match node {
+                    AstPathNode::Stmt(Stmt::Block(block)) => {
+                        block.stmts.get(index).map(AstPathNode::Stmt)
+                    }
+                    _ => None,
+                },
+            ),
+        }
+    }
 
-        String::from_utf8(bytes).unwrap()
+    /// Navigates to the expression of an expression statement.
+    pub fn expr(self) -> Result<Self> {
+        self.step(
+            "an expression statement",
+            [
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+            ],
+            |node| match node {
+                AstPathNode::Stmt(Stmt::Expr(ExprStmt { expr, .. })) => {
+                    Some(AstPathNode::Expr(expr))
+                }
+                _ => None,
+            },
+        )
     }
 
-    #[test]
-    fn path_visitor() {
-        run_test(false, |cm, _handler| {
-            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar', ['baz']);".into());
+    /// Navigates to the inner expression of a parenthesized expression.
+    pub fn paren(self) -> Result<Self> {
+        self.step(
+            "a parenthesized expression",
+            [
+                AstParentKind::Expr(ExprField::Paren),
+                AstParentKind::ParenExpr(ParenExprField::Expr),
+            ],
+            |node| match node {
+                AstPathNode::Expr(Expr::Paren(ParenExpr { expr, .. })) => {
+                    Some(AstPathNode::Expr(expr))
+                }
+                _ => None,
+            },
+        )
+    }
 
-            let m = parse(&fm);
+    /// Navigates to the `index`th expression of a sequence expression.
+    pub fn seq_expr(self, index: usize) -> Result<Self> {
+        self.step(
+            "a sequence expression",
+            [
+                AstParentKind::Expr(ExprField::Seq),
+                AstParentKind::SeqExpr(SeqExprField::Exprs(index)),
+            ],
+            |node| match node {
+                AstPathNode::Expr(Expr::Seq(seq)) => {
+                    seq.exprs.get(index).map(|expr| AstPathNode::Expr(expr))
+                }
+                _ => None,
+            },
+        )
+    }
 
-            let module_kind = AstParentKind::Module(ModuleField::Body(0));
-            let module_item_kind = AstParentKind::ModuleItem(ModuleItemField::Stmt);
-            let stmt_kind = AstParentKind::Stmt(StmtField::Expr);
-            let expr_stmt_kind = AstParentKind::ExprStmt(ExprStmtField::Expr);
-            let expr_kind = AstParentKind::Expr(ExprField::Paren);
-            let paren_kind = AstParentKind::ParenExpr(ParenExprField::Expr);
-            let expr2_kind = AstParentKind::Expr(ExprField::Seq);
-            let seq_kind = AstParentKind::SeqExpr(SeqExprField::Exprs(1));
-            let expr3_kind = AstParentKind::Expr(ExprField::Lit);
-            let lit_kind = AstParentKind::Lit(LitField::Str);
+    /// Navigates to the `index`th element of an array literal. Holes and
+    /// spread elements can't be navigated to.
+    pub fn array_elem(self, index: usize) -> Result<Self> {
+        self.step(
+            "an array literal",
+            [
+                AstParentKind::Expr(ExprField::Array),
+                AstParentKind::ArrayLit(ArrayLitField::Elems(index)),
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+            ],
+            |node| match node {
+                AstPathNode::Expr(Expr::Array(array)) => match array.elems.get(index)? {
+                    Some(ExprOrSpread { spread: None, expr }) => Some(AstPathNode::Expr(expr)),
+                    _ => None,
+                },
+                _ => None,
+            },
+        )
+    }
 
-            {
-                let path = vec![
-                    module_kind,
-                    module_item_kind,
-                    stmt_kind,
-                    expr_stmt_kind,
-                    expr_kind,
-                    paren_kind,
-                    expr2_kind,
-                    seq_kind,
-                    expr3_kind,
-                    lit_kind,
-                ];
-                let bar_replacer = replacer("bar", "bar-success");
+    /// Navigates to the `index`th argument of a call expression. Spread
+    /// arguments can't be navigated to.
+    pub fn call_arg(self, index: usize) -> Result<Self> {
+        self.step(
+            "a call expression",
+            [
+                AstParentKind::Expr(ExprField::Call),
+                AstParentKind::CallExpr(CallExprField::Args(index)),
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+            ],
+            |node| match node {
+                AstPathNode::Expr(Expr::Call(call)) => match call.args.get(index)? {
+                    ExprOrSpread { spread: None, expr } => Some(AstPathNode::Expr(expr)),
+                    _ => None,
+                },
+                _ => None,
+            },
+        )
+    }
 
-                let mut m = m.clone();
-                m.visit_mut_with_path(
-                    &mut ApplyVisitors::new(vec![(&path, &bar_replacer)]),
-                    &mut Default::default(),
-                );
+    /// Navigates to the literal of a literal expression.
+    pub fn lit(self) -> Result<Self> {
+        self.step(
+            "a literal",
+            [AstParentKind::Expr(ExprField::Lit)],
+            |node| match node {
+                AstPathNode::Expr(Expr::Lit(lit)) => Some(AstPathNode::Lit(lit)),
+                _ => None,
+            },
+        )
+    }
 
-                let s = to_js(&m, &cm);
-                assert_eq!(s, r#"("foo","bar-success",["baz"]);"#);
-            }
+    /// Navigates to the string of a string literal.
+    pub fn str(self) -> Result<Self> {
+        self.step(
+            "a string literal",
+            [AstParentKind::Lit(LitField::Str)],
+            |node| match node {
+                AstPathNode::Lit(Lit::Str(str)) => Some(AstPathNode::Str(str)),
+                _ => None,
+            },
+        )
+    }
 
+    pub fn into_path(self) -> AstPath {
+        self.path
+    }
+}
+
+/// Assigns unique, zero-length spans to all nodes of `program` that have a
+/// dummy span (e.g. nodes synthesized by an earlier transform), which makes
+/// them addressable by span.
+///
+/// The spans are allocated after the end of the existing spans in the program,
+/// so they don't overlap with the real source. They don't belong to any source
+/// file, so source maps won't contain mappings for them.
+pub fn assign_synthetic_spans(program: &mut Program) {
+    struct MaxPos(u32);
+
+    impl Visit for MaxPos {
+        fn visit_span(&mut self, span: &Span) {
+            self.0 = self.0.max(span.hi.0);
+        }
+    }
+
+    struct SyntheticSpans {
+        next: u32,
+    }
+
+    impl VisitMut for SyntheticSpans {
+        fn visit_mut_span(&mut self, span: &mut Span) {
+            if span.lo.0 == 0 && span.hi.0 == 0 {
+                let pos = BytePos(self.next);
+                self.next += 1;
+                *span = Span::new(pos, pos, span.ctxt);
+            }
+        }
+    }
+
+    let mut max_pos = MaxPos(0);
+    program.visit_with(&mut max_pos);
+    program.visit_mut_with(&mut SyntheticSpans {
+        next: max_pos.0 + 1,
+    });
+}
+
+/// Resolves a legacy span based path (outermost to innermost span) into a
+/// positional [AstPath] by searching `program` for the node it describes.
+///
+/// Spans don't need to be listed for every node on the way, but they need to
+/// appear in order. The first matching node wins, which is exactly why span
+/// based paths are ambiguous and have been replaced.
+#[deprecated(note = "span based paths are ambiguous, record an `AstPath` instead")]
+pub fn ast_path_from_spans(program: &Program, spans: &[Span]) -> Option<AstPath> {
+    if spans.is_empty() {
+        return Some(Vec::new());
+    }
+    let mut resolver = SpanPathResolver {
+        spans,
+        matched: 0,
+        result: None,
+    };
+    program.visit_with_path(&mut resolver, &mut Default::default());
+    resolver.result
+}
+
+/// Returns the path of the node of `root` whose span is `span`, e.g. to
+/// apply a visitor to a node that was located by searching the source text.
+///
+/// Nested nodes can have the same span, e.g. a module item and the statement
+/// it contains. The first node in traversal order wins, which is the
+/// outermost one. Like visitors, only nodes that [ApplyVisitors] stops at are
+/// considered.
+pub fn path_to_span<R: AstRoot>(root: &R, span: Span) -> Option<AstPath> {
+    let mut resolver = SpanPathResolver {
+        spans: &[span],
+        matched: 0,
+        result: None,
+    };
+    root.visit_root(&mut resolver);
+    resolver.result
+}
+
+struct SpanPathResolver<'a> {
+    spans: &'a [Span],
+    /// Number of `spans` matched by the ancestors of the current node.
+    matched: usize,
+    result: Option<AstPath>,
+}
+
+impl SpanPathResolver<'_> {
+    fn check<'ast: 'r, 'r, N>(
+        &mut self,
+        n: &'ast N,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) where
+        N: Spanned + VisitWithPath<Self>,
+    {
+        if self.result.is_some() {
+            return;
+        }
+        let matched = self.matched;
+        if self.spans[matched] == n.span() {
+            if matched + 1 == self.spans.len() {
+                self.result = Some(ast_path.iter().map(|n| n.kind()).collect());
+                return;
+            }
+            self.matched += 1;
+        }
+        n.visit_children_with_path(self, ast_path);
+        self.matched = matched;
+    }
+}
+
+/// Returns the span of the node at `path` in `program`, if there is one. Like
+/// visitors, the path must end at a node that [ApplyVisitors] stops at.
+pub fn span_at_path(program: &Program, path: &[AstParentKind]) -> Option<Span> {
+    let mut finder = PathSpanFinder { path, result: None };
+    program.visit_with_path(&mut finder, &mut Default::default());
+    finder.result
+}
+
+struct PathSpanFinder<'a> {
+    path: &'a [AstParentKind],
+    result: Option<Span>,
+}
+
+impl PathSpanFinder<'_> {
+    fn check<'ast: 'r, 'r, N>(
+        &mut self,
+        n: &'ast N,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) where
+        N: Spanned + VisitWithPath<Self>,
+    {
+        if self.result.is_some()
+            || ast_path.len() > self.path.len()
+            || ast_path
+                .iter()
+                .zip(self.path.iter())
+                .any(|(node, kind)| node.kind() != *kind)
+        {
+            return;
+        }
+        if ast_path.len() == self.path.len() {
+            self.result = Some(n.span());
+            return;
+        }
+        n.visit_children_with_path(self, ast_path);
+    }
+}
+
+/// Invokes `$m!(visit_mut_method, visit_method, Type)` for every node type that
+/// visitors can be applied to.
+macro_rules! for_each_stop {
+    ($m:ident) => {
+        $m!(visit_mut_prop, visit_prop, Prop);
+        $m!(visit_mut_expr, visit_expr, Expr);
+        $m!(visit_mut_pat, visit_pat, Pat);
+        $m!(
+            visit_mut_object_pat_prop,
+            visit_object_pat_prop,
+            ObjectPatProp
+        );
+        // The elements of array patterns, which can be holes
+        $m!(visit_mut_opt_pat, visit_opt_pat, Option<Pat>);
+        $m!(visit_mut_stmt, visit_stmt, Stmt);
+        $m!(visit_mut_module_decl, visit_module_decl, ModuleDecl);
+        $m!(visit_mut_module_item, visit_module_item, ModuleItem);
+        // `export { a as b }` and `export { a } from "./x"`
+        $m!(visit_mut_named_export, visit_named_export, NamedExport);
+        $m!(
+            visit_mut_export_specifier,
+            visit_export_specifier,
+            ExportSpecifier
+        );
+        $m!(visit_mut_call_expr, visit_call_expr, CallExpr);
+        $m!(visit_mut_lit, visit_lit, Lit);
+        $m!(visit_mut_str, visit_str, Str);
+        $m!(visit_mut_class_member, visit_class_member, ClassMember);
+        $m!(visit_mut_jsx_element, visit_jsx_element, JSXElement);
+        $m!(
+            visit_mut_jsx_opening_element,
+            visit_jsx_opening_element,
+            JSXOpeningElement
+        );
+        $m!(visit_mut_jsx_attr, visit_jsx_attr, JSXAttr);
+        $m!(
+            visit_mut_jsx_expr_container,
+            visit_jsx_expr_container,
+            JSXExprContainer
+        );
+        $m!(visit_mut_member_expr, visit_member_expr, MemberExpr);
+        $m!(visit_mut_ident, visit_ident, Ident);
+    };
+}
+
+macro_rules! resolve_rule {
+    ($_:ident, $name:ident, $T:ty) => {
+        fn $name<'ast: 'r, 'r>(
+            &mut self,
+            n: &'ast $T,
+            ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+        ) {
+            self.check(n, ast_path);
+        }
+    };
+}
+
+// Needs to stop at the same nodes as [ApplyVisitors].
+impl VisitAstPath for SpanPathResolver<'_> {
+    for_each_stop!(resolve_rule);
+}
+
+impl VisitAstPath for PathSpanFinder<'_> {
+    for_each_stop!(resolve_rule);
+}
+
+macro_rules! method {
+    ($name:ident, $_:ident, $T:ty) => {
+        fn $name(&mut self, n: &mut $T, ast_path: &mut AstKindPath<AstParentKind>) {
+            self.visit_if_required(n, ast_path);
+        }
+    };
+}
+
+impl VisitMutAstPath for ApplyVisitors<'_, '_> {
+    fn visit_mut_stmts(&mut self, n: &mut Vec<Stmt>, ast_path: &mut AstKindPath<AstParentKind>) {
+        self.visit_list_if_required(n, ast_path, |v, n, index| v.visit_mut_stmts(n, index));
+    }
+
+    fn visit_mut_module_items(
+        &mut self,
+        n: &mut Vec<ModuleItem>,
+        ast_path: &mut AstKindPath<AstParentKind>,
+    ) {
+        self.visit_list_if_required(n, ast_path, |v, n, index| {
+            v.visit_mut_module_items(n, index)
+        });
+    }
+
+    for_each_stop!(method);
+}
+
+/// A predicate that selects the nodes an [ApplyTypedVisitors] visitor is
+/// applied to. It receives the node, which can be downcasted to the swc AST
+/// type, and the path of the node.
+pub type NodePredicate = dyn Fn(&dyn Any, &[AstParentKind]) -> bool + Send + Sync;
+
+/// Applies visitors to every node that matches a predicate, instead of to the
+/// node at a specific [AstPath] like [ApplyVisitors] does. Predicates are only
+/// evaluated for the node types [ApplyVisitors] stops at.
+///
+/// Like with [ApplyVisitors], visitors are applied to nested nodes first.
+pub struct ApplyTypedVisitors<'a> {
+    visitors: Vec<(&'a NodePredicate, &'a dyn VisitorFactory)>,
+}
+
+impl<'a> ApplyTypedVisitors<'a> {
+    pub fn new(visitors: Vec<(&'a NodePredicate, &'a dyn VisitorFactory)>) -> Self {
+        Self { visitors }
+    }
+
+    fn visit_if_required<N>(&mut self, n: &mut N, ast_path: &mut AstKindPath<AstParentKind>)
+    where
+        N: Any
+            + for<'aa> VisitMutWith<dyn VisitMut + Send + Sync + 'aa>
+            + for<'aa> VisitMutWithPath<ApplyTypedVisitors<'aa>>,
+    {
+        n.visit_mut_children_with_path(self, ast_path);
+        for (predicate, visitor) in self.visitors.iter() {
+            if predicate(&*n, &ast_path[..]) {
+                let mut visitor = visitor.create_path_visitor(&ast_path[..]);
+                n.visit_mut_with(visitor.visitor());
+                if visitor.outcome() == VisitOutcome::Applied {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl VisitMutAstPath for ApplyTypedVisitors<'_> {
+    for_each_stop!(method);
+}
+
+/// Computes the key of a node for [ApplyKeyedVisitors]. It receives the node,
+/// which can be downcasted to the swc AST type, and returns `None` for nodes
+/// that have no key.
+pub type NodeKey<K> = dyn Fn(&dyn Any) -> Option<K> + Send + Sync;
+
+/// Applies the visitor that's registered for the key of a node, e.g. the
+/// callee name of a call, to the node. Unlike [ApplyTypedVisitors], the
+/// visitor is looked up instead of evaluating a predicate per visitor. Keys are
+/// only computed for the node types [ApplyVisitors] stops at.
+///
+/// Like with [ApplyVisitors], visitors are applied to nested nodes first.
+pub struct ApplyKeyedVisitors<'a, K> {
+    key: &'a NodeKey<K>,
+    visitors: HashMap<K, &'a dyn VisitorFactory>,
+}
+
+impl<'a, K: Eq + Hash> ApplyKeyedVisitors<'a, K> {
+    pub fn new(key: &'a NodeKey<K>, visitors: HashMap<K, &'a dyn VisitorFactory>) -> Self {
+        Self { key, visitors }
+    }
+
+    fn visit_if_required<N>(&mut self, n: &mut N, ast_path: &mut AstKindPath<AstParentKind>)
+    where
+        N: Any + for<'aa> VisitMutWith<dyn VisitMut + Send + Sync + 'aa> + VisitMutWithPath<Self>,
+    {
+        n.visit_mut_children_with_path(self, ast_path);
+        let Some(key) = (self.key)(&*n) else {
+            return;
+        };
+        if let Some(visitor) = self.visitors.get(&key) {
+            let mut visitor = visitor.create_path_visitor(&ast_path[..]);
+            n.visit_mut_with(visitor.visitor());
+        }
+    }
+}
+
+impl<K: Eq + Hash> VisitMutAstPath for ApplyKeyedVisitors<'_, K> {
+    for_each_stop!(method);
+}
+
+/// A node that [ApplyVisitors] stops at, as passed to [codemod] matchers.
+#[derive(Clone, Copy, Debug)]
+pub enum Node<'a> {
+    Prop(&'a Prop),
+    Expr(&'a Expr),
+    Pat(&'a Pat),
+    ObjectPatProp(&'a ObjectPatProp),
+    /// An element of an array pattern, which can be a hole
+    OptPat(&'a Option<Pat>),
+    Stmt(&'a Stmt),
+    ModuleDecl(&'a ModuleDecl),
+    ModuleItem(&'a ModuleItem),
+    NamedExport(&'a NamedExport),
+    ExportSpecifier(&'a ExportSpecifier),
+    CallExpr(&'a CallExpr),
+    Lit(&'a Lit),
+    Str(&'a Str),
+    ClassMember(&'a ClassMember),
+    JSXElement(&'a JSXElement),
+    JSXOpeningElement(&'a JSXOpeningElement),
+    JSXAttr(&'a JSXAttr),
+    JSXExprContainer(&'a JSXExprContainer),
+    MemberExpr(&'a MemberExpr),
+    Ident(&'a Ident),
+}
+
+macro_rules! node_from {
+    ($($variant:ident($T:ty)),*) => {
+        $(
+            impl<'a> From<&'a $T> for Node<'a> {
+                fn from(n: &'a $T) -> Self {
+                    Node::$variant(n)
+                }
+            }
+        )*
+    };
+}
+
+// Needs to cover the same nodes as [for_each_stop].
+node_from!(
+    Prop(Prop),
+    Expr(Expr),
+    Pat(Pat),
+    ObjectPatProp(ObjectPatProp),
+    OptPat(Option<Pat>),
+    Stmt(Stmt),
+    ModuleDecl(ModuleDecl),
+    ModuleItem(ModuleItem),
+    NamedExport(NamedExport),
+    ExportSpecifier(ExportSpecifier),
+    CallExpr(CallExpr),
+    Lit(Lit),
+    Str(Str),
+    ClassMember(ClassMember),
+    JSXElement(JSXElement),
+    JSXOpeningElement(JSXOpeningElement),
+    JSXAttr(JSXAttr),
+    JSXExprContainer(JSXExprContainer),
+    MemberExpr(MemberExpr),
+    Ident(Ident)
+);
+
+/// Applies `rewriter` to every node for which `matcher` returns true. The
+/// matcher receives the path of the node and the [Node]. It's only evaluated
+/// for the node types [ApplyVisitors] stops at.
+///
+/// All matching paths are collected first and the rewriter is applied with
+/// [ApplyVisitors] afterwards, so the rewriter can't affect which nodes are
+/// matched. Nested nodes are rewritten before their parents.
+///
+/// ```
+/// use swc_core::{
+///     common::{sync::Lrc, FileName, SourceMap},
+///     ecma::{
+///         ast::{EsVersion, Str},
+///         codegen::{text_writer::JsWriter, Config, Emitter},
+///         parser::parse_file_as_module,
+///         visit::{fields::ArrayLitField, AstParentKind, VisitMut},
+///     },
+/// };
+/// use turbopack_ecmascript::{
+///     code_gen::VisitorFactory,
+///     path_visitor::{codemod, Node},
+/// };
+///
+/// struct Uppercase;
+///
+/// impl VisitorFactory for Uppercase {
+///     fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+///         Box::new(UppercaseStr)
+///     }
+/// }
+///
+/// struct UppercaseStr;
+///
+/// impl VisitMut for UppercaseStr {
+///     fn visit_mut_str(&mut self, s: &mut Str) {
+///         s.value = s.value.to_uppercase().into();
+///         s.raw = None;
+///     }
+/// }
+///
+/// let cm: Lrc<SourceMap> = Default::default();
+/// let fm = cm.new_source_file(FileName::Anon, r#"f("a", ["b", "c"]);"#.into());
+/// let mut module =
+///     parse_file_as_module(&fm, Default::default(), EsVersion::latest(), None, &mut vec![])
+///         .unwrap();
+///
+/// codemod(
+///     &mut module,
+///     |path, node| {
+///         matches!(node, Node::Str(_))
+///             && path
+///                 .iter()
+///                 .any(|kind| matches!(kind, AstParentKind::ArrayLit(ArrayLitField::Elems(_))))
+///     },
+///     Uppercase,
+/// )
+/// .unwrap();
+///
+/// let mut code = Vec::new();
+/// Emitter {
+///     cfg: Config {
+///         minify: true,
+///         ..Default::default()
+///     },
+///     cm: cm.clone(),
+///     comments: None,
+///     wr: JsWriter::new(cm.clone(), "\n", &mut code, None),
+/// }
+/// .emit_module(&module)
+/// .unwrap();
+/// assert_eq!(String::from_utf8(code).unwrap(), r#"f("a",["B","C"]);"#);
+/// ```
+pub fn codemod<R, F, G>(root: &mut R, matcher: F, rewriter: G) -> Result<()>
+where
+    R: AstRoot,
+    F: Fn(&AstPath, &Node) -> bool,
+    G: VisitorFactory,
+{
+    codemod_pruned(root, |_, _| true, matcher, rewriter)
+}
+
+/// Like [codemod], but the children of nodes for which `should_descend`
+/// returns false are neither matched nor rewritten. This avoids walking
+/// subtrees that can't contain matches, e.g. functions whose name starts with
+/// `_`. `should_descend` is called for the same nodes as `matcher`.
+pub fn codemod_pruned<R, D, F, G>(
+    root: &mut R,
+    should_descend: D,
+    matcher: F,
+    rewriter: G,
+) -> Result<()>
+where
+    R: AstRoot,
+    D: Fn(&AstPath, &Node) -> bool,
+    F: Fn(&AstPath, &Node) -> bool,
+    G: VisitorFactory,
+{
+    let paths = find_paths(root, should_descend, matcher);
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let visitors = paths
+        .iter()
+        .map(|path| (path, &rewriter as &dyn VisitorFactory))
+        .collect();
+    let mut apply_visitors = ApplyVisitors::new(visitors);
+    root.visit_mut_root(&mut apply_visitors);
+    apply_visitors.take_error()
+}
+
+/// Applies `visitors` to `root` in the order of the source positions of the
+/// nodes they target, i.e. from left to right, independent of the order they
+/// were registered in. Visitors for a node are applied before the nodes nested
+/// in it, unless a nested node that is targeted starts at the same position,
+/// in which case all nested nodes are visited first. Siblings are applied in
+/// the order of their position in the parent, and visitors for the same path
+/// in registration order.
+///
+/// The positions are determined in a first traversal, and all visitors are
+/// applied in a second one. Edits must not change the paths of nodes that are
+/// edited later, e.g. by inserting into a list before them. Visitors that
+/// don't match a node are ignored.
+pub fn apply_in_source_order<R: AstRoot>(
+    root: &mut R,
+    visitors: Vec<(&AstPath, &dyn VisitorFactory)>,
+) -> Result<()> {
+    if visitors.is_empty() {
+        return Ok(());
+    }
+    let mut recorder = ApplyVisitors::new_record_only(visitors.clone());
+    root.visit_mut_root(&mut recorder);
+    recorder.take_error()?;
+
+    // The edits are recorded in post-order, so the nested nodes of a node are
+    // on top of the stack when it's reached. Every entry holds the smallest
+    // position in the subtree of its path.
+    let mut nested_first = HashSet::new();
+    let mut stack: Vec<(AstPath, BytePos)> = Vec::new();
+    for edit in recorder.into_planned_edits() {
+        if matches!(stack.last(), Some((path, _)) if *path == edit.path) {
+            // Another visitor for the same path
+            continue;
+        }
+        let mut start = edit.span.lo;
+        let mut nested_start = None;
+        while let Some((path, _)) = stack.last() {
+            if !path.starts_with(&edit.path) {
+                break;
+            }
+            let (_, subtree_start) = stack.pop().unwrap();
+            nested_start = Some(
+                nested_start.map_or(subtree_start, |nested: BytePos| nested.min(subtree_start)),
+            );
+        }
+        if let Some(nested_start) = nested_start {
+            if nested_start <= start {
+                nested_first.insert(edit.path.clone());
+            }
+            start = start.min(nested_start);
+        }
+        stack.push((edit.path, start));
+    }
+
+    let mut apply_visitors = ApplyVisitors::new(visitors)
+        .with_order(TraversalOrder::PreOrder)
+        .with_nested_first(nested_first);
+    root.visit_mut_root(&mut apply_visitors);
+    apply_visitors.take_error()
+}
+
+/// Collects the paths of the nodes of `root` that `matcher` matches, in
+/// traversal order. The children of nodes for which `should_descend` returns
+/// false are skipped. See [codemod_pruned].
+pub fn find_paths<R, D, F>(root: &R, should_descend: D, matcher: F) -> Vec<AstPath>
+where
+    R: AstRoot,
+    D: Fn(&AstPath, &Node) -> bool,
+    F: Fn(&AstPath, &Node) -> bool,
+{
+    let mut paths = Vec::new();
+    for_each_match(root, should_descend, matcher, |path| {
+        paths.push(path.clone())
+    });
+    paths
+}
+
+/// Like [find_paths], but passes the path of each match to `on_match` as soon
+/// as the node is reached, instead of collecting all paths. This keeps the
+/// memory bounded when the paths are streamed to a file or a channel.
+pub fn for_each_match<R, D, F, C>(root: &R, should_descend: D, matcher: F, on_match: C)
+where
+    R: AstRoot,
+    D: Fn(&AstPath, &Node) -> bool,
+    F: Fn(&AstPath, &Node) -> bool,
+    C: FnMut(&AstPath),
+{
+    let mut collector = MatchCollector {
+        should_descend,
+        matcher,
+        on_match,
+        path: Vec::new(),
+    };
+    root.visit_root(&mut collector);
+}
+
+/// Reports the paths of the nodes matched by a [codemod] matcher.
+struct MatchCollector<D, F, C> {
+    should_descend: D,
+    matcher: F,
+    on_match: C,
+    /// The path of the node that is checked. It's shared by all nodes, and
+    /// only holds the segments up to the closest checked ancestor between
+    /// checks.
+    path: AstPath,
+}
+
+impl<D, F, C> MatchCollector<D, F, C>
+where
+    D: Fn(&AstPath, &Node) -> bool,
+    F: Fn(&AstPath, &Node) -> bool,
+    C: FnMut(&AstPath),
+{
+    fn check<'ast: 'r, 'r, N>(
+        &mut self,
+        n: &'ast N,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) where
+        N: VisitWithPath<Self>,
+        &'ast N: Into<Node<'ast>>,
+    {
+        let ancestor_len = self.path.len();
+        self.path
+            .extend(ast_path.iter().skip(ancestor_len).map(|n| n.kind()));
+        let node = n.into();
+        // Paths need to be non-empty for [ApplyVisitors]
+        if !self.path.is_empty() && (self.matcher)(&self.path, &node) {
+            (self.on_match)(&self.path);
+        }
+        // The path of the node is popped by the caller, skipping the children is
+        // fine
+        if (self.should_descend)(&self.path, &node) {
+            n.visit_children_with_path(self, ast_path);
+        }
+        self.path.truncate(ancestor_len);
+    }
+}
+
+// Needs to stop at the same nodes as [ApplyVisitors].
+impl<D, F, C> VisitAstPath for MatchCollector<D, F, C>
+where
+    D: Fn(&AstPath, &Node) -> bool,
+    F: Fn(&AstPath, &Node) -> bool,
+    C: FnMut(&AstPath),
+{
+    for_each_stop!(resolve_rule);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        any::Any,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use anyhow::{bail, Result};
+    use swc_core::{
+        base::SwcComments,
+        common::{
+            comments::Comments, errors::HANDLER, BytePos, FileName, Mark, SourceFile, SourceMap,
+            Span, Spanned, DUMMY_SP,
+        },
+        ecma::{
+            ast::*,
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::{parse_file_as_module, parse_file_as_script, EsConfig, Syntax},
+            transforms::base::resolver,
+            visit::{fields::*, AstParentKind, VisitMut, VisitMutWith, VisitMutWithPath},
+        },
+        testing::run_test,
+    };
+
+    #[allow(deprecated)]
+    use super::ast_path_from_spans;
+    use super::{
+        apply_in_source_order, assign_synthetic_spans, codemod, codemod_pruned, find_paths,
+        for_each_match, path_to_span, rewrite_import_source, ApplyKeyedVisitors,
+        ApplyTypedVisitors, ApplyVisitors, AstPath, AstPathBuilder, Node, ParentKind, StrEdits,
+        TraversalOrder, VisitorFactory,
+    };
+    use crate::code_gen::{ListVisitor, PathVisitor, VisitOutcome};
+
+    fn parse(fm: &SourceFile) -> Module {
+        parse_with_syntax(fm, Default::default())
+    }
+
+    fn parse_with_syntax(fm: &SourceFile, syntax: Syntax) -> Module {
+        let mut m = parse_file_as_module(fm, syntax, EsVersion::latest(), None, &mut vec![])
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+            .unwrap();
+
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        m.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        m
+    }
+
+    struct StrReplacer<'a> {
+        from: &'a str,
+        to: &'a str,
+    }
+
+    impl VisitorFactory for Box<StrReplacer<'_>> {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box &**self
+        }
+    }
+
+    impl VisitMut for &'_ StrReplacer<'_> {
+        fn visit_mut_str(&mut self, s: &mut Str) {
+            s.value = s.value.replace(self.from, self.to).into();
+            s.raw = None;
+        }
+    }
+
+    fn replacer(from: &'static str, to: &'static str) -> impl VisitorFactory {
+        box StrReplacer { from, to }
+    }
+
+    /// Only replaces strings that are nested in a call expression.
+    struct CallArgReplacer(StrReplacer<'static>);
+
+    impl VisitorFactory for CallArgReplacer {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box NoopVisitor
+        }
+
+        fn create_with_path<'a>(
+            &'a self,
+            ast_path: &[AstParentKind],
+        ) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            if ast_path
+                .iter()
+                .any(|kind| matches!(kind, AstParentKind::CallExpr(_)))
             {
-                let wrong_path = vec![
-                    module_kind,
-                    module_item_kind,
-                    stmt_kind,
-                    expr_stmt_kind,
-                    // expr_kind,
-                    paren_kind,
-                    expr2_kind,
-                    seq_kind,
-                    expr3_kind,
-                    lit_kind,
-                ];
-                let bar_replacer = replacer("bar", "bar-success");
+                box &self.0
+            } else {
+                self.create()
+            }
+        }
+    }
 
-                let mut m = m.clone();
-                m.visit_mut_with_path(
-                    &mut ApplyVisitors::new(vec![(&wrong_path, &bar_replacer)]),
-                    &mut Default::default(),
-                );
+    struct NoopVisitor;
 
-                let s = to_js(&m, &cm);
-                assert!(!s.contains("bar-success"));
+    impl VisitMut for NoopVisitor {}
+
+    /// Replaces `from` with `to`, but only in strings that are exactly `from`.
+    struct ExactStrReplacer {
+        from: &'static str,
+        to: &'static str,
+        outcome: VisitOutcome,
+    }
+
+    impl VisitMut for ExactStrReplacer {
+        fn visit_mut_str(&mut self, s: &mut Str) {
+            if &*s.value == self.from {
+                s.value = self.to.into();
+                s.raw = None;
+                self.outcome = VisitOutcome::Applied;
+            }
+        }
+    }
+
+    impl PathVisitor for ExactStrReplacer {
+        fn visitor(&mut self) -> &mut (dyn VisitMut + Send + Sync) {
+            self
+        }
+
+        fn outcome(&self) -> VisitOutcome {
+            self.outcome
+        }
+    }
+
+    struct ExactStrReplacerFactory {
+        from: &'static str,
+        to: &'static str,
+    }
+
+    impl VisitorFactory for ExactStrReplacerFactory {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box NoopVisitor
+        }
+
+        fn create_path_visitor<'a>(
+            &'a self,
+            _ast_path: &[AstParentKind],
+        ) -> Box<dyn PathVisitor + 'a> {
+            box ExactStrReplacer {
+                from: self.from,
+                to: self.to,
+                outcome: VisitOutcome::Skipped,
             }
+        }
+    }
 
-            drop(m);
+    struct StrAppender(&'static str);
+
+    impl VisitorFactory for StrAppender {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box self
+        }
+    }
+
+    impl VisitMut for &'_ StrAppender {
+        fn visit_mut_str(&mut self, s: &mut Str) {
+            s.value = format!("{}{}", s.value, self.0).into();
+            s.raw = None;
+        }
+    }
+
+    /// Appends the number of visitors it created before to strings, and
+    /// appends a new string with that number to arrays.
+    struct Sequenced(AtomicUsize);
+
+    impl VisitorFactory for Sequenced {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box SequencedVisitor(self.0.fetch_add(1, Ordering::SeqCst))
+        }
+    }
+
+    struct SequencedVisitor(usize);
+
+    impl VisitMut for SequencedVisitor {
+        fn visit_mut_str(&mut self, s: &mut Str) {
+            s.value = format!("{}-{}", s.value, self.0).into();
+            s.raw = None;
+        }
+
+        fn visit_mut_array_lit(&mut self, array: &mut ArrayLit) {
+            array.elems.push(Some(ExprOrSpread {
+                spread: None,
+                expr: box Expr::Lit(Lit::Str(Str {
+                    span: DUMMY_SP,
+                    value: format!("new-{}", self.0).into(),
+                    raw: None,
+                })),
+            }));
+        }
+    }
+
+    /// Names the binding of a hole or renames the binding of a rest element in
+    /// an array pattern.
+    struct ArrayPatBinding(&'static str);
+
+    impl VisitorFactory for ArrayPatBinding {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box self
+        }
+    }
+
+    impl VisitMut for &'_ ArrayPatBinding {
+        fn visit_mut_opt_pat(&mut self, pat: &mut Option<Pat>) {
+            let name = Ident::new(self.0.into(), DUMMY_SP);
+            match pat {
+                None => *pat = Some(Pat::Ident(name.into())),
+                Some(Pat::Rest(RestPat {
+                    arg: box Pat::Ident(binding),
+                    ..
+                })) => binding.id = name,
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Splits a variable declaration with multiple declarators into one
+    /// declaration per declarator.
+    struct SplitVarDecl;
+
+    impl VisitorFactory for SplitVarDecl {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box NoopVisitor
+        }
+
+        fn as_list_visitor(&self) -> Option<&dyn ListVisitor> {
+            Some(self)
+        }
+    }
+
+    impl SplitVarDecl {
+        fn split(var: &VarDecl) -> impl Iterator<Item = Stmt> + '_ {
+            var.decls.iter().map(|decl| {
+                Stmt::Decl(Decl::Var(box VarDecl {
+                    decls: vec![decl.clone()],
+                    ..var.clone()
+                }))
+            })
+        }
+    }
+
+    impl ListVisitor for SplitVarDecl {
+        fn visit_mut_stmts(&self, stmts: &mut Vec<Stmt>, index: usize) {
+            if let Stmt::Decl(Decl::Var(var)) = &stmts[index] {
+                let split = Self::split(var).collect::<Vec<_>>();
+                stmts.splice(index..=index, split);
+            }
+        }
+
+        fn visit_mut_module_items(&self, items: &mut Vec<ModuleItem>, index: usize) {
+            if let ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) = &items[index] {
+                let split = Self::split(var).map(ModuleItem::Stmt).collect::<Vec<_>>();
+                items.splice(index..=index, split);
+            }
+        }
+    }
+
+    /// Records the callee of each visited call expression.
+    struct CallRecorder<'a>(&'a Mutex<Vec<String>>);
+
+    impl VisitorFactory for CallRecorder<'_> {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box self
+        }
+    }
+
+    impl VisitMut for &'_ CallRecorder<'_> {
+        fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+            if let Callee::Expr(box Expr::Ident(ident)) = &call.callee {
+                self.0.lock().unwrap().push(ident.sym.to_string());
+            }
+        }
+    }
+
+    struct FailingFactory;
+
+    impl VisitorFactory for FailingFactory {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box NoopVisitor
+        }
+
+        fn try_create_path_visitor<'a>(
+            &'a self,
+            _ast_path: &[AstParentKind],
+        ) -> Result<Box<dyn PathVisitor + 'a>> {
+            bail!("unsupported syntax")
+        }
+    }
+
+    struct IdentRenamer {
+        from: &'static str,
+        to: &'static str,
+    }
+
+    impl VisitorFactory for IdentRenamer {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box self
+        }
+    }
+
+    impl VisitMut for &'_ IdentRenamer {
+        fn visit_mut_ident(&mut self, ident: &mut Ident) {
+            if &*ident.sym == self.from {
+                ident.sym = self.to.into();
+            }
+        }
+    }
+
+    fn to_js(m: &Module, cm: &Arc<SourceMap>) -> String {
+        let mut bytes = Vec::new();
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+        };
+
+        emitter.emit_module(m).unwrap();
+
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn path_visitor() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar', ['baz']);".into());
+
+            let m = parse(&fm);
+
+            let module_kind = AstParentKind::Module(ModuleField::Body(0));
+            let module_item_kind = AstParentKind::ModuleItem(ModuleItemField::Stmt);
+            let stmt_kind = AstParentKind::Stmt(StmtField::Expr);
+            let expr_stmt_kind = AstParentKind::ExprStmt(ExprStmtField::Expr);
+            let expr_kind = AstParentKind::Expr(ExprField::Paren);
+            let paren_kind = AstParentKind::ParenExpr(ParenExprField::Expr);
+            let expr2_kind = AstParentKind::Expr(ExprField::Seq);
+            let seq_kind = AstParentKind::SeqExpr(SeqExprField::Exprs(1));
+            let expr3_kind = AstParentKind::Expr(ExprField::Lit);
+            let lit_kind = AstParentKind::Lit(LitField::Str);
+
+            {
+                let path = vec![
+                    module_kind,
+                    module_item_kind,
+                    stmt_kind,
+                    expr_stmt_kind,
+                    expr_kind,
+                    paren_kind,
+                    expr2_kind,
+                    seq_kind,
+                    expr3_kind,
+                    lit_kind,
+                ];
+                let bar_replacer = replacer("bar", "bar-success");
+
+                let mut m = m.clone();
+                m.visit_mut_with_path(
+                    &mut ApplyVisitors::new(vec![(&path, &bar_replacer)]),
+                    &mut Default::default(),
+                );
+
+                let s = to_js(&m, &cm);
+                assert_eq!(s, r#"("foo","bar-success",["baz"]);"#);
+            }
+
+            {
+                let wrong_path = vec![
+                    module_kind,
+                    module_item_kind,
+                    stmt_kind,
+                    expr_stmt_kind,
+                    // expr_kind,
+                    paren_kind,
+                    expr2_kind,
+                    seq_kind,
+                    expr3_kind,
+                    lit_kind,
+                ];
+                let bar_replacer = replacer("bar", "bar-success");
+
+                let mut m = m.clone();
+                m.visit_mut_with_path(
+                    &mut ApplyVisitors::new(vec![(&wrong_path, &bar_replacer)]),
+                    &mut Default::default(),
+                );
+
+                let s = to_js(&m, &cm);
+                assert!(!s.contains("bar-success"));
+            }
+
+            drop(m);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn span_path_shim() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar', ['baz']);".into());
+
+            let mut m = parse(&fm);
+
+            let seq = match &m.body[0] {
+                ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                    expr:
+                        box Expr::Paren(ParenExpr {
+                            expr: box Expr::Seq(seq),
+                            ..
+                        }),
+                    ..
+                })) => seq,
+                _ => unreachable!(),
+            };
+            let spans = vec![seq.span, seq.exprs[1].span()];
+
+            let program = Program::Module(m.clone());
+            let path = ast_path_from_spans(&program, &spans).unwrap();
+            assert_eq!(
+                path.last(),
+                Some(&AstParentKind::SeqExpr(SeqExprField::Exprs(1)))
+            );
+
+            let bar_replacer = replacer("bar", "bar-success");
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &bar_replacer)]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"("foo","bar-success",["baz"]);"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn class_member() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "class A { foo() { return 'bar'; } baz() { return 'bar'; } }".into(),
+            );
+
+            let mut m = parse(&fm);
+
+            let path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Decl),
+                AstParentKind::Decl(DeclField::Class),
+                AstParentKind::ClassDecl(ClassDeclField::Class),
+                AstParentKind::Class(ClassField::Body(1)),
+            ];
+            let bar_replacer = replacer("bar", "bar-success");
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &bar_replacer)]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert!(s.contains(r#"foo(){return"bar"}"#));
+            assert!(s.contains(r#"baz(){return"bar-success"}"#));
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn jsx_attr() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, r#"<img src="./a.png" />;"#.into());
+
+            let mut m = parse_with_syntax(
+                &fm,
+                Syntax::Es(EsConfig {
+                    jsx: true,
+                    ..Default::default()
+                }),
+            );
+
+            let path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::JSXElement),
+                AstParentKind::JSXElement(JSXElementField::Opening),
+                AstParentKind::JSXOpeningElement(JSXOpeningElementField::Attrs(0)),
+                AstParentKind::JSXAttrOrSpread(JSXAttrOrSpreadField::JSXAttr),
+                AstParentKind::JSXAttr(JSXAttrField::Value),
+                AstParentKind::JSXAttrValue(JSXAttrValueField::Lit),
+                AstParentKind::Lit(LitField::Str),
+            ];
+            let png_replacer = replacer("./a.png", "/static/a.1234.png");
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &png_replacer)]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert!(s.contains("/static/a.1234.png"));
+            assert!(!s.contains("./a.png"));
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn visitor_with_path() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "foo('bar'); ['bar'];".into());
+
+            let mut m = parse(&fm);
+
+            let call_path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::Call),
+                AstParentKind::CallExpr(CallExprField::Args(0)),
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+                AstParentKind::Expr(ExprField::Lit),
+                AstParentKind::Lit(LitField::Str),
+            ];
+            let array_path = vec![
+                AstParentKind::Module(ModuleField::Body(1)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::Array),
+                AstParentKind::ArrayLit(ArrayLitField::Elems(0)),
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+                AstParentKind::Expr(ExprField::Lit),
+                AstParentKind::Lit(LitField::Str),
+            ];
+            let replacer = CallArgReplacer(StrReplacer {
+                from: "bar",
+                to: "bar-success",
+            });
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&call_path, &replacer), (&array_path, &replacer)]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"foo("bar-success");["bar"];"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn unmatched_paths() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar');".into());
+
+            let mut m = parse(&fm);
+
+            let prefix = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::Paren),
+                AstParentKind::ParenExpr(ParenExprField::Expr),
+                AstParentKind::Expr(ExprField::Seq),
+            ];
+            let mut path = prefix.clone();
+            path.push(AstParentKind::SeqExpr(SeqExprField::Exprs(1)));
+            let mut stale_path = prefix;
+            stale_path.push(AstParentKind::SeqExpr(SeqExprField::Exprs(2)));
+            let bar_replacer = replacer("bar", "bar-success");
+
+            // Every visitor is reported, also when their paths are equal
+            let stale_copy = stale_path.clone();
+
+            let mut visitor = ApplyVisitors::new_with_tracking(
+                vec![
+                    (&path, &bar_replacer),
+                    (&stale_path, &bar_replacer),
+                    (&stale_copy, &bar_replacer),
+                ],
+                true,
+            );
+            m.visit_mut_with_path(&mut visitor, &mut Default::default());
+
+            assert_eq!(visitor.applied_count(), 1);
+            assert_eq!(
+                visitor.into_unmatched(),
+                vec![stale_path.clone(), stale_path.clone()]
+            );
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"("foo","bar-success");"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn kind_mismatch() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "f('foo', 'bar');".into());
+            let m = parse(&fm);
+            let paths = find_paths(&m, |_, _| true, |_, node| matches!(node, Node::Str(_)));
+            let path = &paths[1];
+            assert!(path.ends_with(&[
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+                AstParentKind::Expr(ExprField::Lit),
+                AstParentKind::Lit(LitField::Str),
+            ]));
+
+            // The argument at the same position is an array now, which
+            // contains the string
+            let fm = cm.new_source_file(FileName::Anon, "f('foo', ['bar']);".into());
+            let mut m = parse(&fm);
+            let bar_replacer = replacer("bar", "bar-success");
+            let mut visitor = ApplyVisitors::new_with_tracking(vec![(path, &bar_replacer)], true);
+            m.visit_mut_with_path(&mut visitor, &mut Default::default());
+
+            assert_eq!(visitor.applied_count(), 0);
+            assert_eq!(visitor.into_unmatched(), vec![path.clone()]);
+            assert_eq!(to_js(&m, &cm), r#"f("foo",["bar"]);"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn fallback_visitors() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar');".into());
+
+            let mut m = parse(&fm);
+
+            let path = |index| {
+                vec![
+                    AstParentKind::Module(ModuleField::Body(0)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                    AstParentKind::Stmt(StmtField::Expr),
+                    AstParentKind::ExprStmt(ExprStmtField::Expr),
+                    AstParentKind::Expr(ExprField::Paren),
+                    AstParentKind::ParenExpr(ParenExprField::Expr),
+                    AstParentKind::Expr(ExprField::Seq),
+                    AstParentKind::SeqExpr(SeqExprField::Exprs(index)),
+                ]
+            };
+            let foo_path = path(0);
+            let bar_path = path(1);
+            let optimized = ExactStrReplacerFactory {
+                from: "foo",
+                to: "optimized",
+            };
+            let generic = ExactStrReplacerFactory {
+                from: "bar",
+                to: "generic",
+            };
+            let fallback = ExactStrReplacerFactory {
+                from: "foo",
+                to: "fallback",
+            };
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![
+                    (&foo_path, &optimized),
+                    (&foo_path, &fallback),
+                    (&bar_path, &optimized),
+                    (&bar_path, &generic),
+                ]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"("optimized","generic");"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn deterministic_order() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar');".into());
+
+            let m = parse(&fm);
+
+            let expr_path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::Paren),
+                AstParentKind::ParenExpr(ParenExprField::Expr),
+                AstParentKind::Expr(ExprField::Seq),
+                AstParentKind::SeqExpr(SeqExprField::Exprs(1)),
+            ];
+            let mut lit_path = expr_path.clone();
+            lit_path.push(AstParentKind::Expr(ExprField::Lit));
+            let mut str_path = lit_path.clone();
+            str_path.push(AstParentKind::Lit(LitField::Str));
+
+            let expr_appender = StrAppender("-expr");
+            let lit_appender = StrAppender("-lit");
+            let str_appender = StrAppender("-str");
+
+            let registrations: [Vec<(&AstPath, &dyn VisitorFactory)>; 2] = [
+                vec![
+                    (&expr_path, &expr_appender),
+                    (&lit_path, &lit_appender),
+                    (&str_path, &str_appender),
+                ],
+                vec![
+                    (&str_path, &str_appender),
+                    (&expr_path, &expr_appender),
+                    (&lit_path, &lit_appender),
+                ],
+            ];
+            for visitors in registrations {
+                let mut m = m.clone();
+                m.visit_mut_with_path(&mut ApplyVisitors::new(visitors), &mut Default::default());
+
+                let s = to_js(&m, &cm);
+                assert_eq!(s, r#"("foo","bar-str-lit-expr");"#);
+            }
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn list_visitor() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "const a = 1, b = 2; foo('bar'); const c = 3, d = 4;".into(),
+            );
+
+            let mut m = parse(&fm);
+
+            let first_path = vec![AstParentKind::Module(ModuleField::Body(0))];
+            let last_path = vec![AstParentKind::Module(ModuleField::Body(2))];
+            let str_path = vec![
+                AstParentKind::Module(ModuleField::Body(1)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::Call),
+                AstParentKind::CallExpr(CallExprField::Args(0)),
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+            ];
+            let split = SplitVarDecl;
+            let bar_replacer = replacer("bar", "bar-success");
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![
+                    (&first_path, &split),
+                    (&str_path, &bar_replacer),
+                    (&last_path, &split),
+                ]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(
+                s,
+                r#"const a=1;const b=2;foo("bar-success");const c=3;const d=4;"#
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn traversal_order() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "outer(inner());".into());
+
+            let m = parse(&fm);
+
+            let outer_path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::Call),
+            ];
+            let mut inner_path = outer_path.clone();
+            inner_path.extend([
+                AstParentKind::CallExpr(CallExprField::Args(0)),
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+                AstParentKind::Expr(ExprField::Call),
+            ]);
+
+            for (order, expected) in [
+                (TraversalOrder::PreOrder, ["outer", "inner"]),
+                (TraversalOrder::PostOrder, ["inner", "outer"]),
+            ] {
+                let calls = Mutex::new(Vec::new());
+                let recorder = CallRecorder(&calls);
+
+                let mut m = m.clone();
+                m.visit_mut_with_path(
+                    &mut ApplyVisitors::new(vec![
+                        (&outer_path, &recorder),
+                        (&inner_path, &recorder),
+                    ])
+                    .with_order(order),
+                    &mut Default::default(),
+                );
+
+                assert_eq!(calls.into_inner().unwrap(), expected);
+            }
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn failing_visitor() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar');".into());
+
+            let mut m = parse(&fm);
+
+            let path = |index| {
+                vec![
+                    AstParentKind::Module(ModuleField::Body(0)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                    AstParentKind::Stmt(StmtField::Expr),
+                    AstParentKind::ExprStmt(ExprStmtField::Expr),
+                    AstParentKind::Expr(ExprField::Paren),
+                    AstParentKind::ParenExpr(ParenExprField::Expr),
+                    AstParentKind::Expr(ExprField::Seq),
+                    AstParentKind::SeqExpr(SeqExprField::Exprs(index)),
+                ]
+            };
+            let foo_path = path(0);
+            let bar_path = path(1);
+            let failing = FailingFactory;
+            let bar_replacer = replacer("bar", "bar-success");
+
+            let mut visitor =
+                ApplyVisitors::new(vec![(&foo_path, &failing), (&bar_path, &bar_replacer)]);
+            m.visit_mut_with_path(&mut visitor, &mut Default::default());
+
+            let err = visitor.take_error().unwrap_err();
+            assert_eq!(err.root_cause().to_string(), "unsupported syntax");
+            assert!(err.to_string().contains("Exprs(0)"));
+            assert!(visitor.take_error().is_ok());
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"("foo","bar");"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn member_expr_ident() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "a.b.c; b.b.b;".into());
+
+            let mut m = parse(&fm);
+
+            let path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::Member),
+                AstParentKind::MemberExpr(MemberExprField::Obj),
+                AstParentKind::Expr(ExprField::Member),
+                AstParentKind::MemberExpr(MemberExprField::Prop),
+                AstParentKind::MemberProp(MemberPropField::Ident),
+            ];
+            let renamer = IdentRenamer { from: "b", to: "x" };
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &renamer)]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, "a.x.c;b.b.b;");
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn export_specifier() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "const a = 1, c = 2, d = 3;\nexport { a as b, c };\nexport { d as default \
+                 };\nexport { e } from \"./x\";"
+                    .into(),
+            );
+
+            let mut m = parse(&fm);
+
+            let path = |item, specifier| {
+                vec![
+                    AstParentKind::Module(ModuleField::Body(item)),
+                    AstParentKind::ModuleItem(ModuleItemField::ModuleDecl),
+                    AstParentKind::ModuleDecl(ModuleDeclField::ExportNamed),
+                    AstParentKind::NamedExport(NamedExportField::Specifiers(specifier)),
+                ]
+            };
+            let renamer = |path: Vec<AstParentKind>, to: &'static str| {
+                crate::create_visitor!(exact path, visit_mut_export_specifier(
+                    specifier: &mut ExportSpecifier
+                ) {
+                    if let ExportSpecifier::Named(named) = specifier {
+                        named.exported =
+                            Some(ModuleExportName::Ident(Ident::new(to.into(), DUMMY_SP)));
+                    }
+                })
+            };
+            // Leaves `a as b` and `d as default` alone
+            let (c_path, c_renamer) = renamer(path(1, 1), "c2");
+            let (e_path, e_renamer) = renamer(path(3, 0), "f");
+
+            let mut visitor =
+                ApplyVisitors::new(vec![(&c_path, &*c_renamer), (&e_path, &*e_renamer)]);
+            m.visit_mut_with_path(&mut visitor, &mut Default::default());
+            assert_eq!(visitor.applied_count(), 2);
+
+            let s = to_js(&m, &cm);
+            assert_eq!(
+                s,
+                "const a=1,c=2,d=3;export{a as b,c as c2};export{d as default};export{e as \
+                 f}from\"./x\";"
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "visitor must be shallow")]
+    fn non_shallow_visitor() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "(foo(), bar());".into());
+
+            let mut m = parse(&fm);
+
+            // Points to the parenthesized expression, which contains two calls
+            let path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+            ];
+            let (path, visitor) = crate::create_visitor!(exact path, visit_mut_call_expr(
+                call: &mut CallExpr
+            ) {
+                call.args.clear();
+            });
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &*visitor)]),
+                &mut Default::default(),
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "visitor must be shallow")]
+    fn non_shallow_visitor_closest_parent() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "(foo(), bar());".into());
+
+            let mut m = parse(&fm);
+
+            // There's no call in the path, so the visitor is applied to the
+            // parenthesized expression as well
+            let path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+            ];
+            let (path, visitor) = crate::create_visitor!(path, visit_mut_call_expr(
+                call: &mut CallExpr
+            ) {
+                call.args.clear();
+            });
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &*visitor)]),
+                &mut Default::default(),
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn shallow_visitor_closest_parent() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "foo(bar(baz));".into());
+
+            let mut m = parse(&fm);
+
+            // Points to the argument, the visitor is applied to the outer call
+            // only and doesn't reach the nested one
+            let path = vec![
+                AstParentKind::Module(ModuleField::Body(0)),
+                AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                AstParentKind::Stmt(StmtField::Expr),
+                AstParentKind::ExprStmt(ExprStmtField::Expr),
+                AstParentKind::Expr(ExprField::Call),
+                AstParentKind::CallExpr(CallExprField::Args(0)),
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+            ];
+            let (path, visitor) = crate::create_visitor!(path, visit_mut_call_expr(
+                call: &mut CallExpr
+            ) {
+                call.args.clear();
+            });
+            assert_eq!(path.len(), 5);
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &*visitor)]),
+                &mut Default::default(),
+            );
+            assert_eq!(to_js(&m, &cm), "foo();");
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn path_builder() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar', ['baz']);".into());
+
+            let mut m = parse(&fm);
+
+            let seq = || {
+                AstPathBuilder::new(&m)
+                    .child_stmt(0)?
+                    .expr()?
+                    .paren()?
+                    .seq_expr(1)
+            };
+            let bar_path = seq().unwrap().lit().unwrap().str().unwrap().into_path();
+            assert_eq!(
+                bar_path,
+                vec![
+                    AstParentKind::Module(ModuleField::Body(0)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                    AstParentKind::Stmt(StmtField::Expr),
+                    AstParentKind::ExprStmt(ExprStmtField::Expr),
+                    AstParentKind::Expr(ExprField::Paren),
+                    AstParentKind::ParenExpr(ParenExprField::Expr),
+                    AstParentKind::Expr(ExprField::Seq),
+                    AstParentKind::SeqExpr(SeqExprField::Exprs(1)),
+                    AstParentKind::Expr(ExprField::Lit),
+                    AstParentKind::Lit(LitField::Str),
+                ]
+            );
+
+            let baz_path = AstPathBuilder::new(&m)
+                .child_stmt(0)
+                .and_then(|b| b.expr())
+                .and_then(|b| b.paren())
+                .and_then(|b| b.seq_expr(2))
+                .and_then(|b| b.array_elem(0))
+                .unwrap()
+                .into_path();
+
+            assert!(seq().unwrap().array_elem(0).is_err());
+            assert!(AstPathBuilder::new(&m).child_stmt(1).is_err());
+            assert!(AstPathBuilder::new(&m)
+                .child_stmt(0)
+                .and_then(|b| b.expr())
+                .and_then(|b| b.paren())
+                .and_then(|b| b.seq_expr(3))
+                .is_err());
+
+            let bar_replacer = replacer("bar", "bar-success");
+            let baz_replacer = replacer("baz", "baz-success");
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![
+                    (&bar_path, &bar_replacer),
+                    (&baz_path, &baz_replacer),
+                ]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"("foo","bar-success",["baz-success"]);"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn merged_visitor_sets() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar');".into());
+
+            let mut m = parse(&fm);
+
+            let path = |index| {
+                vec![
+                    AstParentKind::Module(ModuleField::Body(0)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                    AstParentKind::Stmt(StmtField::Expr),
+                    AstParentKind::ExprStmt(ExprStmtField::Expr),
+                    AstParentKind::Expr(ExprField::Paren),
+                    AstParentKind::ParenExpr(ParenExprField::Expr),
+                    AstParentKind::Expr(ExprField::Seq),
+                    AstParentKind::SeqExpr(SeqExprField::Exprs(index)),
+                ]
+            };
+            let foo_path = path(0);
+            let bar_path = path(1);
+            let first = StrAppender("-1");
+            let second = StrAppender("-2");
+            let third = StrAppender("-3");
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::merged(vec![
+                    vec![(&bar_path, &first), (&foo_path, &first)],
+                    vec![(&bar_path, &second)],
+                    vec![(&bar_path, &third), (&foo_path, &third)],
+                ]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"("foo-1-3","bar-1-2-3");"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn import_source() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "import x from './a'; export * from './a'; export { y } from './a'; foo('./a');"
+                    .into(),
+            );
+
+            let mut m = parse(&fm);
+
+            let rewrites = (0..3)
+                .map(|index| {
+                    rewrite_import_source(
+                        &[
+                            AstParentKind::Module(ModuleField::Body(index)),
+                            AstParentKind::ModuleItem(ModuleItemField::ModuleDecl),
+                        ],
+                        "./b".to_string(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(
+                    rewrites
+                        .iter()
+                        .map(|(path, visitor)| (path, &**visitor))
+                        .collect(),
+                ),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s.matches(r#""./b""#).count(), 3);
+            assert!(s.contains(r#"foo("./a")"#));
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn synthetic_spans() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "'foo';".into());
+
+            let mut m = parse(&fm);
+            let source_end = m.span.hi;
+
+            // Synthesized by an earlier transform
+            let injected = |value: &str| {
+                ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                    span: DUMMY_SP,
+                    expr: box Expr::Lit(Lit::Str(Str {
+                        span: DUMMY_SP,
+                        value: value.into(),
+                        raw: None,
+                    })),
+                }))
+            };
+            m.body.push(injected("bar"));
+            m.body.push(injected("baz"));
+
+            let mut program = Program::Module(m);
+            assign_synthetic_spans(&mut program);
+
+            let m = match program {
+                Program::Module(ref m) => m.clone(),
+                Program::Script(_) => unreachable!(),
+            };
+            let spans = m.body[1..]
+                .iter()
+                .map(|item| item.span())
+                .collect::<Vec<_>>();
+            assert!(spans.iter().all(|span| span.lo > source_end));
+            assert_ne!(spans[0], spans[1]);
+
+            let path = ast_path_from_spans(&program, &spans[1..]).unwrap();
+            assert_eq!(path, vec![AstParentKind::Module(ModuleField::Body(2))]);
+
+            let baz_replacer = replacer("baz", "baz-success");
+
+            let mut m = m;
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &baz_replacer)]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#""foo";"bar";"baz-success";"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn typed_visitors() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "foo('./a', 'b', ['./c']);".into());
+
+            let mut m = parse(&fm);
+
+            let is_relative = |n: &dyn std::any::Any, _: &[AstParentKind]| {
+                n.downcast_ref::<Str>()
+                    .map_or(false, |s| s.value.starts_with("./"))
+            };
+            let appender = StrAppender("?v=1");
+
+            m.visit_mut_with_path(
+                &mut ApplyTypedVisitors::new(vec![(&is_relative, &appender)]),
+                &mut Default::default(),
+            );
+
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"foo("./a?v=1","b",["./c?v=1"]);"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn keyed_visitors() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "t('a'); u('b'); t(u('c')); obj.t('d'); new t('e');".into(),
+            );
+
+            let mut m = parse(&fm);
+
+            let callee_name = |n: &dyn Any| {
+                let call = n.downcast_ref::<CallExpr>()?;
+                match &call.callee {
+                    Callee::Expr(box Expr::Ident(ident)) => Some(ident.sym.to_string()),
+                    _ => None,
+                }
+            };
+            let t = StrAppender("!");
+            let u = StrAppender("?");
+
+            m.visit_mut_with_path(
+                &mut ApplyKeyedVisitors::new(
+                    &callee_name,
+                    [
+                        ("t".to_string(), &t as &dyn VisitorFactory),
+                        ("u".to_string(), &u as &dyn VisitorFactory),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ),
+                &mut Default::default(),
+            );
+
+            // Nested calls are visited first
+            let s = to_js(&m, &cm);
+            assert_eq!(s, r#"t("a!");u("b?");t(u("c?!"));obj.t("d");new t("e");"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn parent_kind() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "foo(a, [b], { k: c }); new Bar(d); e;".into(),
+            );
+
+            let mut m = parse(&fm);
+
+            let idents = Mutex::new(Vec::new());
+            let record = |n: &dyn std::any::Any, ast_path: &[AstParentKind]| {
+                if let Some(ident) = n.downcast_ref::<Ident>() {
+                    idents
+                        .lock()
+                        .unwrap()
+                        .push((ident.sym.to_string(), ParentKind::from_path(ast_path)));
+                }
+                false
+            };
+            let noop = StrAppender("");
+
+            m.visit_mut_with_path(
+                &mut ApplyTypedVisitors::new(vec![(&record, &noop)]),
+                &mut Default::default(),
+            );
+
+            let idents = idents.into_inner().unwrap();
+            let kind_of = |name: &str| {
+                idents
+                    .iter()
+                    .find(|(sym, _)| sym == name)
+                    .map(|(_, kind)| *kind)
+                    .unwrap()
+            };
+            assert_eq!(kind_of("foo"), ParentKind::Callee);
+            assert_eq!(kind_of("a"), ParentKind::Argument(0));
+            assert_eq!(kind_of("b"), ParentKind::ArrayElement(0));
+            assert_eq!(kind_of("c"), ParentKind::ObjectValue);
+            assert_eq!(kind_of("Bar"), ParentKind::Callee);
+            assert_eq!(kind_of("d"), ParentKind::Argument(0));
+            assert_eq!(kind_of("e"), ParentKind::Other);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn parent_kind_of_strings() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "foo('a', ['b'], { k: 'c' }); new Bar('d'); 'e';".into(),
+            );
+            let m = parse(&fm);
+
+            // The string of a literal has the position of the literal
+            let kinds = |matcher: fn(&Node) -> bool| {
+                find_paths(&m, |_, _| true, |_, node| matcher(node))
+                    .iter()
+                    .map(|path| ParentKind::from_path(path))
+                    .collect::<Vec<_>>()
+            };
+            let expected = [
+                ParentKind::Argument(0),
+                ParentKind::ArrayElement(0),
+                ParentKind::ObjectValue,
+                ParentKind::Argument(0),
+                ParentKind::Other,
+            ];
+            assert_eq!(kinds(|node| matches!(node, Node::Str(_))), expected);
+            assert_eq!(kinds(|node| matches!(node, Node::Lit(_))), expected);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn record_only() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('foo', 'bar');".into());
+
+            let m = parse(&fm);
+
+            let path = |index| {
+                vec![
+                    AstParentKind::Module(ModuleField::Body(0)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                    AstParentKind::Stmt(StmtField::Expr),
+                    AstParentKind::ExprStmt(ExprStmtField::Expr),
+                    AstParentKind::Expr(ExprField::Paren),
+                    AstParentKind::ParenExpr(ParenExprField::Expr),
+                    AstParentKind::Expr(ExprField::Seq),
+                    AstParentKind::SeqExpr(SeqExprField::Exprs(index)),
+                ]
+            };
+            let foo_path = path(0);
+            let bar_path = path(1);
+            let stale_path = path(2);
+            let bar_replacer = replacer("bar", "bar-success");
+            let appender = StrAppender("-suffix");
+
+            let mut visited = m.clone();
+            let mut visitor = ApplyVisitors::new_record_only(vec![
+                (&bar_path, &bar_replacer),
+                (&foo_path, &appender),
+                (&stale_path, &appender),
+            ]);
+            visited.visit_mut_with_path(&mut visitor, &mut Default::default());
+
+            assert_eq!(to_js(&visited, &cm), to_js(&m, &cm));
+            assert!(!visitor.changed());
+            let planned = visitor.into_planned_edits();
+            assert_eq!(
+                planned.iter().map(|edit| &edit.path).collect::<Vec<_>>(),
+                vec![&foo_path, &bar_path]
+            );
+            assert!(planned[0].visitor_name.ends_with("StrAppender"));
+            assert!(planned[1].visitor_name.contains("StrReplacer"));
+            assert!(planned[0].span.lo < planned[1].span.lo);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn visitor_stats() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "foo('a', ['b', 'c']);\nbar('d');".into());
+            let m = parse(&fm);
+
+            let paths = find_paths(&m, |_, _| true, |_, node| matches!(node, Node::Str(_)));
+            let b_replacer = replacer("b", "b-success");
+            let appender = StrAppender("-suffix");
+            let mut visitors: Vec<(&AstPath, &dyn VisitorFactory)> = paths
+                .iter()
+                .map(|path| (path, &b_replacer as &dyn VisitorFactory))
+                .collect();
+            visitors.push((&paths[0], &appender));
+
+            let mut visited = m.clone();
+            let mut visitor = ApplyVisitors::new(visitors.clone()).with_stats();
+            visited.visit_mut_with_path(&mut visitor, &mut Default::default());
+            assert_eq!(
+                to_js(&visited, &cm),
+                r#"foo("a-suffix",["b-success","c"]);bar("d");"#
+            );
+            let stats = visitor.into_stats();
+            assert_eq!(stats.len(), 2);
+            assert_eq!(stats[b_replacer.name()].invocations, paths.len());
+            assert_eq!(stats[appender.name()].invocations, 1);
+
+            // Not measured by default
+            let mut visitor = ApplyVisitors::new(visitors);
+            m.clone()
+                .visit_mut_with_path(&mut visitor, &mut Default::default());
+            assert_eq!(visitor.applied_count(), paths.len() + 1);
+            assert!(visitor.into_stats().is_empty());
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn source_order() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "('a', ['b', 'c']);".into());
+
+            let m = parse(&fm);
+
+            let path = |index| {
+                vec![
+                    AstParentKind::Module(ModuleField::Body(0)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                    AstParentKind::Stmt(StmtField::Expr),
+                    AstParentKind::ExprStmt(ExprStmtField::Expr),
+                    AstParentKind::Expr(ExprField::Paren),
+                    AstParentKind::ParenExpr(ParenExprField::Expr),
+                    AstParentKind::Expr(ExprField::Seq),
+                    AstParentKind::SeqExpr(SeqExprField::Exprs(index)),
+                ]
+            };
+            let a_path = path(0);
+            let mut a_str_path = a_path.clone();
+            a_str_path.extend([
+                AstParentKind::Expr(ExprField::Lit),
+                AstParentKind::Lit(LitField::Str),
+            ]);
+            let mut array_path = path(1);
+            array_path.push(AstParentKind::Expr(ExprField::Array));
+            let mut c_path = array_path.clone();
+            c_path.extend([
+                AstParentKind::ArrayLit(ArrayLitField::Elems(1)),
+                AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr),
+            ]);
+            let sequenced = Sequenced(AtomicUsize::new(0));
+            // Registered out of source order
+            let visitors: Vec<(&AstPath, &dyn VisitorFactory)> = vec![
+                (&c_path, &sequenced),
+                (&array_path, &sequenced),
+                (&a_path, &sequenced),
+                // Starts at the same position as its parent
+                (&a_str_path, &sequenced),
+            ];
+            let expected =
+                |src: &str| to_js(&parse(&cm.new_source_file(FileName::Anon, src.into())), &cm);
+
+            let mut visited = m.clone();
+            apply_in_source_order(&mut visited, visitors.clone()).unwrap();
+            assert_eq!(
+                to_js(&visited, &cm),
+                expected("('a-0-1', ['b', 'c-3', 'new-2']);")
+            );
+
+            // The array is a parent of `'c'`, so it's visited after it by
+            // default
+            sequenced.0.store(0, Ordering::SeqCst);
+            let mut visited = m.clone();
+            visited.visit_mut_with_path(&mut ApplyVisitors::new(visitors), &mut Default::default());
+            assert_eq!(
+                to_js(&visited, &cm),
+                expected("('a-0-1', ['b', 'c-2', 'new-3']);")
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn destructured_bindings() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "const { a, b = 1, ...rest } = x;\nconst [first, , ...tail] = y;".into(),
+            );
+
+            let mut m = parse(&fm);
+
+            let path = |index, pat| {
+                vec![
+                    AstParentKind::Module(ModuleField::Body(index)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                    AstParentKind::Stmt(StmtField::Decl),
+                    AstParentKind::Decl(DeclField::Var),
+                    AstParentKind::VarDecl(VarDeclField::Decls(0)),
+                    AstParentKind::VarDeclarator(VarDeclaratorField::Name),
+                    pat,
+                ]
+            };
+            let prop_path = |index| {
+                let mut path = path(0, AstParentKind::Pat(PatField::Object));
+                path.push(AstParentKind::ObjectPat(ObjectPatField::Props(index)));
+                path
+            };
+            let elem_path = |index| {
+                let mut path = path(1, AstParentKind::Pat(PatField::Array));
+                path.push(AstParentKind::ArrayPat(ArrayPatField::Elems(index)));
+                path
+            };
+
+            // `b = 1` has a default value, which is kept
+            let (b_path, rename_b) = crate::create_visitor!(exact prop_path(1),
+                visit_mut_object_pat_prop(prop: &mut ObjectPatProp) {
+                if let ObjectPatProp::Assign(AssignPatProp { span, key, value }) = prop {
+                    let binding = Pat::Ident(Ident::new("renamed".into(), DUMMY_SP).into());
+                    let value = match value.take() {
+                        Some(right) => Pat::Assign(AssignPat {
+                            span: *span,
+                            left: box binding,
+                            right,
+                            type_ann: None,
+                        }),
+                        None => binding,
+                    };
+                    *prop = ObjectPatProp::KeyValue(KeyValuePatProp {
+                        key: PropName::Ident(key.clone()),
+                        value: box value,
+                    });
+                }
+            });
+            let (rest_path, rename_rest) = crate::create_visitor!(exact prop_path(2),
+                visit_mut_object_pat_prop(prop: &mut ObjectPatProp) {
+                if let ObjectPatProp::Rest(RestPat { arg: box Pat::Ident(binding), .. }) = prop {
+                    binding.id.sym = "others".into();
+                }
+            });
+            let hole_path = elem_path(1);
+            let tail_path = elem_path(2);
+            let second = ArrayPatBinding("second");
+            let remaining = ArrayPatBinding("remaining");
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![
+                    (&b_path, &*rename_b),
+                    (&rest_path, &*rename_rest),
+                    (&hole_path, &second),
+                    (&tail_path, &remaining),
+                ]),
+                &mut Default::default(),
+            );
+
+            let expected = parse(
+                &cm.new_source_file(
+                    FileName::Anon,
+                    "const { a, b: renamed = 1, ...others } = x;\nconst [first, second, \
+                     ...remaining] = y;"
+                        .into(),
+                ),
+            );
+            assert_eq!(to_js(&m, &cm), to_js(&expected, &cm));
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn codemod_matcher() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "foo('bar', ['bar']);".into());
+            let mut m = parse(&fm);
+
+            codemod(
+                &mut m,
+                |path, node| {
+                    matches!(node, Node::Lit(_))
+                        && matches!(
+                            ParentKind::from_path(path),
+                            ParentKind::Argument(_) | ParentKind::ArrayElement(_)
+                        )
+                },
+                StrAppender("-suffix"),
+            )
+            .unwrap();
+            assert_eq!(to_js(&m, &cm), r#"foo("bar-suffix",["bar-suffix"]);"#);
+
+            // No matches leave the module unchanged
+            codemod(
+                &mut m,
+                |_, node| matches!(node, Node::ClassMember(_)),
+                StrAppender("-suffix"),
+            )
+            .unwrap();
+            assert_eq!(to_js(&m, &cm), r#"foo("bar-suffix",["bar-suffix"]);"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn str_edits() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "f('foo-bar', 'foobar');".into());
+            let mut m = parse(&fm);
+            let paths = find_paths(&m, |_, _| true, |_, node| matches!(node, Node::Str(_)));
+
+            let mut edits = StrEdits::new();
+            // Swapped, applying one after the other would result in "foo-foo"
+            edits.replace(&paths[0], "foo", "bar");
+            edits.replace(&paths[0], "bar", "foo");
+            // Overlapping, the first replacement wins
+            edits.replace(&paths[1], "foo", "x");
+            edits.replace(&paths[1], "oba", "y");
+            edits.replace(&paths[1], "ar", "z");
+            let visitors = edits.into_visitors();
+            assert_eq!(visitors.len(), 2);
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(
+                    visitors
+                        .iter()
+                        .map(|(path, visitor)| (path, &**visitor))
+                        .collect(),
+                ),
+                &mut Default::default(),
+            );
+            assert_eq!(to_js(&m, &cm), r#"f("bar-foo","xbz");"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn find_paths_should_descend() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "function _private() { foo('a'); }\nfunction shared() { foo('b'); }\nfoo('c');"
+                    .into(),
+            );
+            let m = parse(&fm);
+            let is_str = |_: &AstPath, node: &Node| matches!(node, Node::Str(_));
+            let is_private_fn = |node: &Node| {
+                matches!(
+                    node,
+                    Node::Stmt(Stmt::Decl(Decl::Fn(FnDecl { ident, .. })))
+                        if ident.sym.starts_with('_')
+                )
+            };
+
+            let all = find_paths(&m, |_, _| true, is_str);
+            assert_eq!(all.len(), 3);
+            let pruned = find_paths(&m, |_, node| !is_private_fn(node), is_str);
+            assert_eq!(pruned, all[1..]);
+            assert!(pruned
+                .iter()
+                .all(|path| !matches!(path[0], AstParentKind::Module(ModuleField::Body(0)))));
+
+            let mut m = m;
+            codemod_pruned(
+                &mut m,
+                |_, node| !is_private_fn(node),
+                is_str,
+                StrAppender("-suffix"),
+            )
+            .unwrap();
+            assert_eq!(
+                to_js(&m, &cm),
+                r#"function _private(){foo("a")}function shared(){foo("b-suffix")}foo("c-suffix");"#
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn for_each_match_streams_paths() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "foo('a', ['b', { c: 'c' }]);\nfunction f() { return `d` + 'e'; }".into(),
+            );
+            let m = parse(&fm);
+            let is_lit = |_: &AstPath, node: &Node| matches!(node, Node::Lit(_));
+
+            let mut calls = 0;
+            let mut kinds = Vec::new();
+            let mut paths = Vec::new();
+            for_each_match(
+                &m,
+                |_, _| true,
+                is_lit,
+                |path| {
+                    calls += 1;
+                    // The path is the one of the matched node
+                    kinds.push(ParentKind::from_path(path));
+                    paths.push(path.clone());
+                },
+            );
+            assert_eq!(calls, 4);
+            assert_eq!(calls, find_paths(&m, |_, _| true, is_lit).len());
+            assert_eq!(
+                kinds,
+                [
+                    ParentKind::Argument(0),
+                    ParentKind::ArrayElement(0),
+                    ParentKind::ObjectValue,
+                    ParentKind::Other,
+                ]
+            );
+            // The path of a match doesn't contain segments of the nodes that
+            // were checked before it
+            let mut b_path = path_to_span(&m, span_of(&fm, "'b'")).unwrap();
+            b_path.push(AstParentKind::Expr(ExprField::Lit));
+            assert_eq!(paths[1], b_path);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    /// The span of the first occurrence of `text` in the source.
+    fn span_of(fm: &SourceFile, text: &str) -> Span {
+        let offset = fm.src.find(text).unwrap() as u32;
+        Span::new(
+            fm.start_pos + BytePos(offset),
+            fm.start_pos + BytePos(offset + text.len() as u32),
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn path_to_span_finds_node() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "foo('a');\nbar(['a', 'b']);".into());
+            let m = parse(&fm);
+
+            let path = path_to_span(&m, span_of(&fm, "'b'")).unwrap();
+            assert_eq!(ParentKind::from_path(&path), ParentKind::ArrayElement(1));
+            let b_replacer = replacer("b", "b-success");
+            let mut m = m;
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &b_replacer)]),
+                &mut Default::default(),
+            );
+            assert_eq!(to_js(&m, &cm), r#"foo("a");bar(["a","b-success"]);"#);
+
+            // The module item and its statement have the same span, the outer
+            // node wins
+            assert_eq!(
+                path_to_span(&m, span_of(&fm, "bar(['a', 'b']);")),
+                Some(vec![AstParentKind::Module(ModuleField::Body(1))])
+            );
+            // The expression and the call expression too
+            let path = path_to_span(&m, span_of(&fm, "bar(['a', 'b'])")).unwrap();
+            assert_eq!(
+                path.last(),
+                Some(&AstParentKind::ExprStmt(ExprStmtField::Expr))
+            );
+            assert_eq!(path_to_span(&m, span_of(&fm, "a');")), None);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn script() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "var a = 'a', b = 'b';\nif (a) foo('a');".into(),
+            );
+            let mut script = parse_file_as_script(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+            .unwrap();
+
+            codemod(
+                &mut script,
+                |_, node| matches!(node, Node::Str(_)),
+                replacer("a", "a-success"),
+            )
+            .unwrap();
+
+            // List visitors apply to the statements of a script too
+            let mut program = Program::Script(script);
+            let path = path_to_span(&program, span_of(&fm, "var a = 'a', b = 'b';")).unwrap();
+            assert_eq!(
+                path,
+                vec![
+                    AstParentKind::Program(ProgramField::Script),
+                    AstParentKind::Script(ScriptField::Body(0)),
+                ]
+            );
+            program.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &SplitVarDecl)]),
+                &mut Default::default(),
+            );
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            assert_eq!(
+                String::from_utf8(bytes).unwrap(),
+                r#"var a="a-success";var b="b";if(a)foo("a-success");"#
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    /// Replaces an expression with `baz` at a new position.
+    struct ExprReplacer(Span);
+
+    impl VisitorFactory for ExprReplacer {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box self
+        }
+    }
+
+    impl VisitMut for &'_ ExprReplacer {
+        fn visit_mut_expr(&mut self, expr: &mut Expr) {
+            *expr = Expr::Ident(Ident::new("baz".into(), self.0));
+        }
+    }
+
+    #[test]
+    fn relocate_comments() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon,
+                "// eslint-disable-next-line\nfoo(\"bar\");".into(),
+            );
+            let comments = SwcComments::default();
+            let mut m = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                Some(&comments),
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let old_lo = m.body[0].span().lo;
+            assert!(comments.has_leading(old_lo));
+
+            let new_span = Span::new(BytePos(1000), BytePos(1003), Default::default());
+            let path = AstPathBuilder::new(&m)
+                .child_stmt(0)
+                .and_then(|b| b.expr())
+                .unwrap()
+                .into_path();
+            let replacer = ExprReplacer(new_span);
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(vec![(&path, &replacer)]).with_comments(&comments),
+                &mut Default::default(),
+            );
+
+            assert_eq!(to_js(&m, &cm), "baz;");
+            assert!(!comments.has_leading(old_lo));
+            let leading = comments.get_leading(new_span.lo).unwrap();
+            assert_eq!(&*leading[0].text, " eslint-disable-next-line");
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    /// Uppercases strings, unless they are followed by a `/* keep */`
+    /// comment.
+    struct UppercaseUnlessKept;
+
+    impl VisitorFactory for UppercaseUnlessKept {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box NoopVisitor
+        }
+
+        fn try_create_path_visitor_with_comments<'a>(
+            &'a self,
+            _ast_path: &[AstParentKind],
+            comments: &'a (dyn Comments + Sync),
+        ) -> Result<Box<dyn PathVisitor + 'a>> {
+            Ok(box CommentAwareUppercaser {
+                comments,
+                outcome: VisitOutcome::Skipped,
+            })
+        }
+    }
+
+    struct CommentAwareUppercaser<'a> {
+        comments: &'a (dyn Comments + Sync),
+        outcome: VisitOutcome,
+    }
+
+    impl VisitMut for CommentAwareUppercaser<'_> {
+        fn visit_mut_str(&mut self, s: &mut Str) {
+            let keep = self
+                .comments
+                .get_trailing(s.span.hi)
+                .map_or(false, |trailing| {
+                    trailing.iter().any(|comment| comment.text.trim() == "keep")
+                });
+            if !keep {
+                s.value = s.value.to_uppercase().into();
+                s.raw = None;
+                self.outcome = VisitOutcome::Applied;
+            }
+        }
+    }
+
+    impl PathVisitor for CommentAwareUppercaser<'_> {
+        fn visitor(&mut self) -> &mut (dyn VisitMut + Send + Sync) {
+            self
+        }
+
+        fn outcome(&self) -> VisitOutcome {
+            self.outcome
+        }
+    }
+
+    #[test]
+    fn visitor_with_comments() {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "foo(\"a\" /* keep */, \"b\");".into());
+            let comments = SwcComments::default();
+            let mut m = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                Some(&comments),
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+
+            let paths = [0, 1].map(|index| {
+                AstPathBuilder::new(&m)
+                    .child_stmt(0)
+                    .and_then(|b| b.expr())
+                    .and_then(|b| b.call_arg(index))
+                    .and_then(|b| b.lit())
+                    .and_then(|b| b.str())
+                    .unwrap()
+                    .into_path()
+            });
+            let visitors = paths
+                .iter()
+                .map(|path| (path, &UppercaseUnlessKept as &dyn VisitorFactory))
+                .collect::<Vec<_>>();
+
+            // Without comments, the visitor is created by `create`
+            let mut unchanged = m.clone();
+            unchanged.visit_mut_with_path(
+                &mut ApplyVisitors::new(visitors.clone()),
+                &mut Default::default(),
+            );
+            assert_eq!(to_js(&unchanged, &cm), r#"foo("a","b");"#);
+
+            m.visit_mut_with_path(
+                &mut ApplyVisitors::new(visitors).with_comments(&comments),
+                &mut Default::default(),
+            );
+            assert_eq!(to_js(&m, &cm), r#"foo("a","B");"#);
+
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn max_depth() {
+        run_test(false, |cm, _handler| {
+            let depth = 30;
+            let src = format!("{}1{};", "[".repeat(depth), "]".repeat(depth));
+            let fm = cm.new_source_file(FileName::Anon, src);
+            let mut m = parse(&fm);
+
+            let mut builder = AstPathBuilder::new(&m).child_stmt(0).and_then(|b| b.expr());
+            for _ in 0..depth {
+                builder = builder.and_then(|b| b.array_elem(0));
+            }
+            let path = builder.unwrap().into_path();
+            let replacer = ExprReplacer(DUMMY_SP);
+
+            // The path is longer than the limit
+            let mut limited = m.clone();
+            let mut visitor =
+                ApplyVisitors::new(vec![(&path, &replacer)]).with_max_depth(path.len() / 2);
+            limited.visit_mut_with_path(&mut visitor, &mut Default::default());
+            let err = visitor.take_error().unwrap_err();
+            assert!(
+                err.to_string().contains("exceeds the maximum depth"),
+                "{err}"
+            );
+            assert!(!visitor.changed());
+            assert_eq!(to_js(&limited, &cm), to_js(&m, &cm));
+
+            // The default limit leaves room for the longest path
+            let mut visitor = ApplyVisitors::new(vec![(&path, &replacer)]);
+            m.visit_mut_with_path(&mut visitor, &mut Default::default());
+            visitor.take_error().unwrap();
+            assert_eq!(
+                to_js(&m, &cm),
+                format!("{}baz{};", "[".repeat(depth), "]".repeat(depth))
+            );
 
             Ok(())
         })
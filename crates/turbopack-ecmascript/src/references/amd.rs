@@ -11,8 +11,10 @@ use swc_core::{
     quote,
 };
 use turbo_tasks::{
-    debug::ValueDebugFormat, primitives::StringVc, trace::TraceRawVcs, TryJoinIterExt, Value,
-    ValueToString, ValueToStringVc,
+    debug::ValueDebugFormat,
+    primitives::{OptionStringVc, StringVc},
+    trace::TraceRawVcs,
+    TryJoinIterExt, Value, ValueToString, ValueToStringVc,
 };
 use turbopack_core::{
     chunk::{ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc},
@@ -85,6 +87,9 @@ pub enum AmdDefineFactoryType {
     Unknown,
     Function,
     Value,
+    /// The callback of an AMD `require(deps, callback)`, which is called with
+    /// the dependencies but doesn't export anything.
+    Callback,
 }
 
 #[turbo_tasks::value(shared)]
@@ -136,6 +141,7 @@ impl CodeGenerateable for AmdDefineWithDependenciesCodeGen {
                                 context,
                                 cjs_resolve(self.origin, *request),
                                 Value::new(Cjs),
+                                OptionStringVc::cell(None),
                             )
                             .await?,
                         )
@@ -169,7 +175,16 @@ impl CodeGenerateable for AmdDefineWithDependenciesCodeGen {
             // ```
             create_visitor!(exact path, visit_mut_call_expr(call_expr: &mut CallExpr) {
                 let CallExpr { args, callee, .. } = call_expr;
-                if let Some(factory) = take(args).pop().map(|e| e.expr) {
+                let factory = match factory_type {
+                    AmdDefineFactoryType::Callback => Some(
+                        take(args)
+                            .into_iter()
+                            .nth(1)
+                            .map_or_else(|| box quote!("() => {}" as Expr), |e| e.expr),
+                    ),
+                    _ => take(args).pop().map(|e| e.expr),
+                };
+                if let Some(factory) = factory {
                     let deps = resolved_elements.iter().map(|element| {
                         match element {
                             ResolvedElement::PatternMapping(pm) => {
@@ -235,6 +250,12 @@ impl CodeGenerateable for AmdDefineWithDependenciesCodeGen {
                                 spread: None
                             });
                         },
+                        AmdDefineFactoryType::Callback => {
+                            // Transforms `require([dep1, dep2], callback)` into
+                            // `callback(__turbopack_require__(dep1), __turbopack_require__(dep2))`
+                            *callee = Callee::Expr(factory);
+                            *args = deps;
+                        },
                         AmdDefineFactoryType::Value => {
                             // __turbopack_export_value__(...)
                             *callee = Callee::Expr(box quote!("__turbopack_export_value__" as Expr));
@@ -1,21 +1,34 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use swc_core::{
     common::DUMMY_SP,
     ecma::ast::{Callee, Expr, ExprOrSpread, Ident},
 };
-use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    debug::ValueDebugFormat,
+    primitives::{OptionStringVc, StringVc},
+    trace::TraceRawVcs,
+    Value, ValueToString, ValueToStringVc,
+};
 use turbopack_core::{
-    chunk::{ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc},
+    chunk::{
+        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingTypeOptionVc,
+    },
     reference::{AssetReference, AssetReferenceVc},
     resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
 };
 
 use super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::Cjs};
 use crate::{
+    analyzer::JsValue,
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
-    references::{util::throw_module_not_found_expr, AstPathVc},
+    references::{
+        util::{throw_module_not_found_expr, throw_unresolvable_request_expr},
+        AstPathVc,
+    },
     resolve::cjs_resolve,
+    utils::js_value_to_pattern,
 };
 
 #[turbo_tasks::value]
@@ -107,6 +120,7 @@ impl CodeGenerateable for CjsRequireAssetReference {
             context,
             cjs_resolve(self.origin, self.request),
             Value::new(Cjs),
+            OptionStringVc::cell(None),
         )
         .await?;
         let mut visitors = Vec::new();
@@ -184,7 +198,14 @@ impl ValueToString for CjsRequireResolveAssetReference {
 }
 
 #[turbo_tasks::value_impl]
-impl ChunkableAssetReference for CjsRequireResolveAssetReference {}
+impl ChunkableAssetReference for CjsRequireResolveAssetReference {
+    #[turbo_tasks::function]
+    fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
+        // Like in Node.js, the module is only resolved to its id, but not
+        // loaded, so it isn't placed into the chunk.
+        ChunkingTypeOptionVc::cell(None)
+    }
+}
 
 #[turbo_tasks::value_impl]
 impl CodeGenerateable for CjsRequireResolveAssetReference {
@@ -196,6 +217,7 @@ impl CodeGenerateable for CjsRequireResolveAssetReference {
             context,
             cjs_resolve(self.origin, self.request),
             Value::new(Cjs),
+            OptionStringVc::cell(None),
         )
         .await?;
         let mut visitors = Vec::new();
@@ -251,3 +273,93 @@ impl CodeGenerateable for CjsRequireCacheAccess {
         Ok(CodeGeneration { visitors }.into())
     }
 }
+
+/// Why the request of a `require()` call can't be determined at build time.
+#[derive(
+    ValueDebugFormat, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TraceRawVcs, Copy, Clone,
+)]
+pub enum UnresolvableRequire {
+    /// Nothing is known about the request, e.g. `require(name)`.
+    DynamicRequest,
+    /// The call doesn't have exactly one argument.
+    UnsupportedArguments,
+}
+
+impl UnresolvableRequire {
+    /// Classifies the linked arguments of a `require()` call. Returns `None`
+    /// when the request can be resolved, at least partially.
+    pub fn classify(args: &[JsValue]) -> Option<Self> {
+        match args {
+            [arg] => {
+                (!js_value_to_pattern(arg).has_constant_parts()).then_some(Self::DynamicRequest)
+            }
+            _ => Some(Self::UnsupportedArguments),
+        }
+    }
+
+    pub fn reason(&self) -> &'static str {
+        match self {
+            UnresolvableRequire::DynamicRequest => "the request is fully dynamic",
+            UnresolvableRequire::UnsupportedArguments => "require() expects a single argument",
+        }
+    }
+}
+
+/// Replaces a `require()` call whose request can't be determined at build
+/// time with an expression that throws a "Cannot find module" error
+/// explaining why.
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct CjsRequireUnresolvable {
+    pub kind: UnresolvableRequire,
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for CjsRequireUnresolvable {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, _context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let mut visitors = Vec::new();
+
+        let reason = self.kind.reason();
+        let path = &self.path.await?;
+        visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+            *expr = throw_unresolvable_request_expr(reason);
+        }));
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnresolvableRequire;
+    use crate::analyzer::JsValue;
+
+    #[test]
+    fn classify_unresolvable_require() {
+        let unknown = || JsValue::Unknown(None, "unknown variable");
+        assert_eq!(
+            UnresolvableRequire::classify(&[unknown()]),
+            Some(UnresolvableRequire::DynamicRequest)
+        );
+        assert_eq!(
+            UnresolvableRequire::classify(&[JsValue::concat(vec![unknown(), unknown()])]),
+            Some(UnresolvableRequire::DynamicRequest)
+        );
+        assert_eq!(
+            UnresolvableRequire::classify(&[]),
+            Some(UnresolvableRequire::UnsupportedArguments)
+        );
+        assert_eq!(
+            UnresolvableRequire::classify(&["./a".into(), "./b".into()]),
+            Some(UnresolvableRequire::UnsupportedArguments)
+        );
+        // Partially dynamic requests are resolved to all matching modules
+        assert_eq!(UnresolvableRequire::classify(&["./a".into()]), None);
+        assert_eq!(
+            UnresolvableRequire::classify(&[JsValue::concat(vec!["./locales/".into(), unknown()])]),
+            None
+        );
+    }
+}
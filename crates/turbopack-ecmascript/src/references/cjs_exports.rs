@@ -0,0 +1,284 @@
+use std::collections::BTreeSet;
+
+use swc_core::{
+    common::Mark,
+    ecma::ast::{
+        AssignExpr, AssignOp, CallExpr, Callee, Expr, ExprOrSpread, Ident, Lit, MemberExpr,
+        MemberProp, ModuleItem, Pat, PatOrExpr, Program, Stmt,
+    },
+};
+
+use super::cjs::CjsAssetReferenceVc;
+use crate::utils::unparen;
+
+/// The statically known exports of a CommonJS module. Only re-exports of other
+/// modules are detected, all other exports are only available at runtime.
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct CjsExports {
+    /// Modules whose exports are all re-exported, e.g. by
+    /// `module.exports = require("./x")`.
+    pub star_exports: Vec<CjsAssetReferenceVc>,
+    /// Exports that are re-exported from another module, e.g. by
+    /// `exports.foo = require("./x").foo`.
+    pub exports: BTreeSet<String>,
+}
+
+/// A re-export of another module in a CommonJS module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CjsReexport {
+    /// All exports of the required module are re-exported, e.g. by
+    /// `module.exports = require("./x")` or
+    /// `Object.assign(exports, require("./x"))`.
+    All { request: String },
+    /// A single export is re-exported from the required module, e.g. by
+    /// `exports.foo = require("./x").foo`.
+    Named { export: String, request: String },
+}
+
+/// Finds the re-exports in the top-level statements of a CommonJS module.
+/// Only `require` calls with a constant request are considered.
+pub fn find_cjs_reexports(program: &Program, unresolved_mark: Mark) -> Vec<CjsReexport> {
+    let stmts: Vec<&Stmt> = match program {
+        Program::Module(module) => module
+            .body
+            .iter()
+            .filter_map(|item| match item {
+                ModuleItem::Stmt(stmt) => Some(stmt),
+                ModuleItem::ModuleDecl(_) => None,
+            })
+            .collect(),
+        Program::Script(script) => script.body.iter().collect(),
+    };
+    let finder = ReexportFinder { unresolved_mark };
+    let mut reexports = Vec::new();
+    for stmt in stmts {
+        if let Stmt::Expr(expr) = stmt {
+            finder.expr(&expr.expr, &mut reexports);
+        }
+    }
+    reexports
+}
+
+struct ReexportFinder {
+    unresolved_mark: Mark,
+}
+
+impl ReexportFinder {
+    fn expr(&self, expr: &Expr, reexports: &mut Vec<CjsReexport>) {
+        match unparen(expr) {
+            Expr::Seq(seq) => {
+                for expr in seq.exprs.iter() {
+                    self.expr(expr, reexports);
+                }
+            }
+            Expr::Assign(assign) => self.assign(assign, reexports),
+            Expr::Call(call) => self.object_assign(call, reexports),
+            _ => {}
+        }
+    }
+
+    /// `module.exports = require("./x")` and `exports.foo = require("./x").foo`
+    fn assign(&self, assign: &AssignExpr, reexports: &mut Vec<CjsReexport>) {
+        if assign.op != AssignOp::Assign {
+            return;
+        }
+        let left = match &assign.left {
+            PatOrExpr::Expr(expr) => &**expr,
+            PatOrExpr::Pat(box Pat::Expr(expr)) => &**expr,
+            PatOrExpr::Pat(_) => return,
+        };
+        let Expr::Member(left) = unparen(left) else {
+            return;
+        };
+        if self.is_free(&left.obj, "module") && prop_name(&left.prop).as_deref() == Some("exports")
+        {
+            if let Some(request) = self.required_request(&assign.right) {
+                reexports.push(CjsReexport::All { request });
+            }
+        } else if self.is_exports_object(&left.obj) {
+            let Some(export) = prop_name(&left.prop) else {
+                return;
+            };
+            let right = match unparen(&assign.right) {
+                Expr::Member(MemberExpr { obj, .. }) => &**obj,
+                right => right,
+            };
+            if let Some(request) = self.required_request(right) {
+                reexports.push(CjsReexport::Named { export, request });
+            }
+        }
+    }
+
+    /// `Object.assign(exports, require("./x"), require("./y"))`
+    fn object_assign(&self, call: &CallExpr, reexports: &mut Vec<CjsReexport>) {
+        let Callee::Expr(box Expr::Member(callee)) = &call.callee else {
+            return;
+        };
+        if !self.is_free(&callee.obj, "Object")
+            || prop_name(&callee.prop).as_deref() != Some("assign")
+        {
+            return;
+        }
+        let Some((target, sources)) = call.args.split_first() else {
+            return;
+        };
+        if target.spread.is_some() || !self.is_exports_object(&target.expr) {
+            return;
+        }
+        for source in sources {
+            if source.spread.is_none() {
+                if let Some(request) = self.required_request(&source.expr) {
+                    reexports.push(CjsReexport::All { request });
+                }
+            }
+        }
+    }
+
+    /// `exports` or `module.exports`
+    fn is_exports_object(&self, expr: &Expr) -> bool {
+        match unparen(expr) {
+            Expr::Member(member) => {
+                self.is_free(&member.obj, "module")
+                    && prop_name(&member.prop).as_deref() == Some("exports")
+            }
+            expr => self.is_free(expr, "exports"),
+        }
+    }
+
+    /// The request of a `require` call with a constant request.
+    fn required_request(&self, expr: &Expr) -> Option<String> {
+        let Expr::Call(CallExpr {
+            callee: Callee::Expr(callee),
+            args,
+            ..
+        }) = unparen(expr)
+        else {
+            return None;
+        };
+        if !self.is_free(callee, "require") {
+            return None;
+        }
+        let [ExprOrSpread { spread: None, expr }] = &args[..] else {
+            return None;
+        };
+        match unparen(expr) {
+            Expr::Lit(Lit::Str(str)) => Some(str.value.to_string()),
+            Expr::Tpl(tpl) if tpl.exprs.is_empty() => {
+                let quasi = tpl.quasis.first()?;
+                Some(quasi.cooked.as_ref()?.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the expression is a reference to the global variable `name`.
+    fn is_free(&self, expr: &Expr, name: &str) -> bool {
+        matches!(
+            unparen(expr),
+            Expr::Ident(Ident { sym, span, .. })
+                if &**sym == name && span.ctxt.outer() == self.unresolved_mark
+        )
+    }
+}
+
+fn prop_name(prop: &MemberProp) -> Option<String> {
+    match prop {
+        MemberProp::Ident(ident) => Some(ident.sym.to_string()),
+        MemberProp::Computed(computed) => match unparen(&computed.expr) {
+            Expr::Lit(Lit::Str(str)) => Some(str.value.to_string()),
+            _ => None,
+        },
+        MemberProp::PrivateName(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::parse_file_as_script,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::{find_cjs_reexports, CjsReexport};
+
+    fn reexports(src: &str) -> Vec<CjsReexport> {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let script = parse_file_as_script(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Script(script);
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            Ok(find_cjs_reexports(&program, unresolved_mark))
+        })
+        .unwrap()
+    }
+
+    fn all(request: &str) -> CjsReexport {
+        CjsReexport::All {
+            request: request.to_string(),
+        }
+    }
+
+    fn named(export: &str, request: &str) -> CjsReexport {
+        CjsReexport::Named {
+            export: export.to_string(),
+            request: request.to_string(),
+        }
+    }
+
+    #[test]
+    fn full_reexport() {
+        assert_eq!(
+            reexports("module.exports = require(\"./a\");"),
+            vec![all("./a")]
+        );
+        assert_eq!(
+            reexports(
+                "Object.assign(exports, require(\"./a\"), { b: 1 \
+                 });\nObject.assign(module.exports, require(\"./c\"));"
+            ),
+            vec![all("./a"), all("./c")]
+        );
+    }
+
+    #[test]
+    fn single_property_reexport() {
+        assert_eq!(
+            reexports(
+                "exports.foo = require(\"./a\").foo;\nmodule.exports.bar = \
+                 require(\"./b\").baz;\nexports[\"qux\"] = require(\"./c\");"
+            ),
+            vec![
+                named("foo", "./a"),
+                named("bar", "./b"),
+                named("qux", "./c")
+            ]
+        );
+    }
+
+    #[test]
+    fn dynamic_require() {
+        assert_eq!(
+            reexports(
+                "module.exports = require(name);\nexports.foo = require(\"./\" + \
+                 name).foo;\nconst require = () => {};\nmodule.exports = require(\"./a\");"
+            ),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use std::mem::replace;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::{
+        ast::{
+            op, ArrowExpr, AssignExpr, AwaitExpr, BreakStmt, CallExpr, CondExpr, ContinueStmt,
+            EmptyStmt, Expr, FnDecl, Function, IfStmt, Invalid, LabeledStmt, NewExpr, OptChainExpr,
+            Stmt, TaggedTpl, UnaryExpr, UpdateExpr, VarDecl, VarDeclKind, YieldExpr,
+        },
+        atoms::JsWord,
+        visit::{
+            fields::{ExprField, LabeledStmtField, StmtField},
+            AstParentKind, Visit, VisitWith,
+        },
+    },
+};
+use turbo_tasks::{debug::ValueDebugFormat, trace::TraceRawVcs};
+use turbopack_core::chunk::ChunkingContextVc;
+
+use super::AstPathVc;
+use crate::{
+    analyzer::JsValue,
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+};
+
+/// The truthiness of a condition that is known at build time.
+#[derive(
+    ValueDebugFormat, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TraceRawVcs, Copy, Clone,
+)]
+pub enum ConstantConditionValue {
+    Truthy,
+    Falsy,
+}
+
+impl ConstantConditionValue {
+    /// The truthiness of a linked condition. A condition with alternatives,
+    /// e.g. `a || b`, is only known when all alternatives agree.
+    pub fn from_js_value(value: &JsValue) -> Option<Self> {
+        match value {
+            JsValue::Constant(constant) => Some(if constant.is_truthy() {
+                ConstantConditionValue::Truthy
+            } else {
+                ConstantConditionValue::Falsy
+            }),
+            JsValue::Alternatives(_, alternatives) => {
+                let (first, rest) = alternatives.split_first()?;
+                let value = Self::from_js_value(first)?;
+                rest.iter()
+                    .all(|alternative| Self::from_js_value(alternative) == Some(value))
+                    .then_some(value)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        matches!(self, ConstantConditionValue::Truthy)
+    }
+}
+
+/// Replaces an `if` statement or a conditional expression, whose condition is
+/// a constant, with the branch that is taken.
+///
+/// The node is kept when the condition has side effects, when the removed
+/// branch of an `if` statement declares bindings that are visible outside of
+/// it, i.e. `var`s and functions, or when the taken branch breaks out of a
+/// label of the `if` statement, whose target would be removed.
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct ConstantCondition {
+    pub value: ConstantConditionValue,
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ConstantCondition {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, _context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let mut visitors = Vec::new();
+
+        let value = self.value;
+        let mut path = self.path.await?.clone_value();
+        match path.pop() {
+            Some(AstParentKind::Stmt(StmtField::If)) => {
+                // The labels of the `if` statement are folded with it
+                while path.ends_with(&[
+                    AstParentKind::Stmt(StmtField::Labeled),
+                    AstParentKind::LabeledStmt(LabeledStmtField::Body),
+                ]) {
+                    path.truncate(path.len() - 2);
+                }
+                visitors.push(
+                    create_visitor!(exact path, visit_mut_stmt(stmt: &mut Stmt) {
+                        fold_if_stmt(stmt, value);
+                    }),
+                );
+            }
+            Some(AstParentKind::Expr(ExprField::Cond)) => {
+                visitors.push(
+                    create_visitor!(exact path, visit_mut_expr(expr: &mut Expr) {
+                        fold_cond_expr(expr, value);
+                    }),
+                );
+            }
+            _ => {}
+        }
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
+
+/// Folds the `if` statement `stmt`, which may be wrapped in labeled
+/// statements.
+fn fold_if_stmt(stmt: &mut Stmt, value: ConstantConditionValue) {
+    let mut labels = Vec::new();
+    let mut stmt = stmt;
+    while let Stmt::Labeled(LabeledStmt { label, body, .. }) = stmt {
+        labels.push(label.sym.clone());
+        stmt = &mut **body;
+    }
+    let Stmt::If(IfStmt { test, cons, alt, span }) = stmt else {
+        return;
+    };
+    let (taken, dead) = if value.is_truthy() {
+        (Some(&**cons), alt.as_deref())
+    } else {
+        (alt.as_deref(), Some(&**cons))
+    };
+    if has_side_effects(test)
+        || dead.map_or(false, has_hoisted_declarations)
+        || taken.map_or(false, |taken| jumps_to_labels(taken, &labels))
+    {
+        return;
+    }
+    let span = *span;
+    let empty = Stmt::Empty(EmptyStmt { span });
+    let Stmt::If(IfStmt { cons, alt, .. }) = replace(stmt, empty) else {
+        unreachable!();
+    };
+    // A labeled `if` statement keeps its label, which is valid for any
+    // statement, but isn't used by the taken branch
+    if value.is_truthy() {
+        *stmt = *cons;
+    } else if let Some(alt) = alt {
+        *stmt = *alt;
+    }
+}
+
+fn fold_cond_expr(expr: &mut Expr, value: ConstantConditionValue) {
+    let Expr::Cond(CondExpr { test, .. }) = expr else {
+        return;
+    };
+    if has_side_effects(test) {
+        return;
+    }
+    let invalid = Expr::Invalid(Invalid { span: DUMMY_SP });
+    let Expr::Cond(CondExpr { cons, alt, .. }) = replace(expr, invalid) else {
+        unreachable!();
+    };
+    *expr = if value.is_truthy() { *cons } else { *alt };
+}
+
+/// Whether evaluating the condition `expr` may have side effects. Property
+/// accesses like `process.env.NODE_ENV` are assumed to have none.
+fn has_side_effects(expr: &Expr) -> bool {
+    struct SideEffects(bool);
+
+    impl Visit for SideEffects {
+        fn visit_assign_expr(&mut self, _: &AssignExpr) {
+            self.0 = true;
+        }
+
+        fn visit_update_expr(&mut self, _: &UpdateExpr) {
+            self.0 = true;
+        }
+
+        fn visit_call_expr(&mut self, _: &CallExpr) {
+            self.0 = true;
+        }
+
+        fn visit_new_expr(&mut self, _: &NewExpr) {
+            self.0 = true;
+        }
+
+        fn visit_opt_chain_expr(&mut self, expr: &OptChainExpr) {
+            if expr.base.is_call() {
+                self.0 = true;
+            } else {
+                expr.visit_children_with(self);
+            }
+        }
+
+        fn visit_tagged_tpl(&mut self, _: &TaggedTpl) {
+            self.0 = true;
+        }
+
+        fn visit_await_expr(&mut self, _: &AwaitExpr) {
+            self.0 = true;
+        }
+
+        fn visit_yield_expr(&mut self, _: &YieldExpr) {
+            self.0 = true;
+        }
+
+        fn visit_unary_expr(&mut self, expr: &UnaryExpr) {
+            if expr.op == op!("delete") {
+                self.0 = true;
+            } else {
+                expr.visit_children_with(self);
+            }
+        }
+
+        // Creating a function doesn't call it
+        fn visit_function(&mut self, _: &Function) {}
+
+        fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+    }
+
+    let mut visitor = SideEffects(false);
+    expr.visit_with(&mut visitor);
+    visitor.0
+}
+
+/// Whether `stmt` declares `var`s or functions, which are hoisted out of the
+/// statement.
+fn has_hoisted_declarations(stmt: &Stmt) -> bool {
+    struct HoistedDeclarations(bool);
+
+    impl Visit for HoistedDeclarations {
+        fn visit_var_decl(&mut self, decl: &VarDecl) {
+            if decl.kind == VarDeclKind::Var {
+                self.0 = true;
+            }
+        }
+
+        fn visit_fn_decl(&mut self, _: &FnDecl) {
+            self.0 = true;
+        }
+
+        // Declarations in nested functions are scoped to them
+        fn visit_function(&mut self, _: &Function) {}
+
+        fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+    }
+
+    let mut visitor = HoistedDeclarations(false);
+    stmt.visit_with(&mut visitor);
+    visitor.0
+}
+
+/// Whether `stmt` contains a `break` or `continue` of one of `labels`, which
+/// are declared outside of it.
+fn jumps_to_labels(stmt: &Stmt, labels: &[JsWord]) -> bool {
+    struct LabelJumps<'a> {
+        labels: &'a [JsWord],
+        found: bool,
+    }
+
+    impl Visit for LabelJumps<'_> {
+        fn visit_break_stmt(&mut self, stmt: &BreakStmt) {
+            if let Some(label) = &stmt.label {
+                self.found |= self.labels.contains(&label.sym);
+            }
+        }
+
+        fn visit_continue_stmt(&mut self, stmt: &ContinueStmt) {
+            if let Some(label) = &stmt.label {
+                self.found |= self.labels.contains(&label.sym);
+            }
+        }
+
+        // Labels can't be used across function boundaries
+        fn visit_function(&mut self, _: &Function) {}
+
+        fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+    }
+
+    if labels.is_empty() {
+        return false;
+    }
+    let mut visitor = LabelJumps {
+        labels,
+        found: false,
+    };
+    stmt.visit_with(&mut visitor);
+    visitor.found
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName},
+        ecma::{
+            ast::{EsVersion, Expr, Stmt},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_script,
+            transforms::base::fixer::fixer,
+            visit::{VisitMut, VisitMutWith},
+        },
+        testing::run_test,
+    };
+
+    use super::{fold_cond_expr, fold_if_stmt, ConstantConditionValue};
+
+    /// Folds all `if` statements and conditional expressions of `src` with
+    /// the condition `value` and prints the result minified.
+    fn fold(src: &str, value: Option<ConstantConditionValue>) -> String {
+        struct Fold(ConstantConditionValue);
+
+        impl VisitMut for Fold {
+            fn visit_mut_stmt(&mut self, stmt: &mut Stmt) {
+                // Like the code generation, an `if` statement is folded with
+                // its labels
+                let mut body = &mut *stmt;
+                while let Stmt::Labeled(labeled) = body {
+                    body = &mut *labeled.body;
+                }
+                body.visit_mut_children_with(self);
+                fold_if_stmt(stmt, self.0);
+            }
+
+            fn visit_mut_expr(&mut self, expr: &mut Expr) {
+                expr.visit_mut_children_with(self);
+                fold_cond_expr(expr, self.0);
+            }
+        }
+
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let mut script = parse_file_as_script(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            if let Some(value) = value {
+                script.visit_mut_with(&mut Fold(value));
+            }
+            script.visit_mut_with(&mut fixer(None));
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm, "\n", &mut bytes, None),
+            };
+            emitter.emit_script(&script).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    fn assert_folded(src: &str, value: ConstantConditionValue, expected: &str) {
+        assert_eq!(fold(src, Some(value)), fold(expected, None));
+    }
+
+    #[test]
+    fn folded_if() {
+        let src = "if (process.env.NODE_ENV === 'production') { prod(); } else { dev(); }";
+        assert_folded(src, ConstantConditionValue::Truthy, "{ prod(); }");
+        assert_folded(src, ConstantConditionValue::Falsy, "{ dev(); }");
+        assert_folded("if (DEBUG) log();", ConstantConditionValue::Falsy, ";");
+        // The label of the `if` statement is kept for the taken branch
+        assert_folded(
+            "outer: if (FEATURE) { other(); } else { break outer; }",
+            ConstantConditionValue::Truthy,
+            "outer: { other(); }",
+        );
+    }
+
+    #[test]
+    fn labeled_jumps() {
+        // `break outer` would target the block that replaces the `if`
+        let src = "outer: if (FEATURE) { for (;;) { break outer; } } else { other(); }";
+        assert_folded(src, ConstantConditionValue::Truthy, src);
+        assert_folded(src, ConstantConditionValue::Falsy, "outer: { other(); }");
+        let src = "outer: inner: if (FEATURE) { while (a) { work(); break outer; } }";
+        assert_folded(src, ConstantConditionValue::Truthy, src);
+        // Jumps to an enclosing loop or to a label inside the taken branch
+        // don't depend on the `if` statement
+        assert_folded(
+            "outer: for (;;) { if (DEBUG) { log(); continue outer; } else { inner: for (;;) { \
+             break inner; } } }",
+            ConstantConditionValue::Falsy,
+            "outer: for (;;) { { inner: for (;;) { break inner; } } }",
+        );
+        assert_folded(
+            "outer: for (;;) { if (DEBUG) { log(); continue outer; } work(); }",
+            ConstantConditionValue::Truthy,
+            "outer: for (;;) { { log(); continue outer; } work(); }",
+        );
+    }
+
+    #[test]
+    fn kept_if() {
+        // The condition has side effects
+        let src = "if (check()) { a(); } else { b(); }";
+        assert_folded(src, ConstantConditionValue::Truthy, src);
+        // `x` would be undeclared without the removed branch
+        let src = "if (DEBUG) { var x = 1; } else { let y = 2; }";
+        assert_folded(src, ConstantConditionValue::Falsy, src);
+        assert_folded(src, ConstantConditionValue::Truthy, "{ var x = 1; }");
+    }
+
+    #[test]
+    fn folded_ternary() {
+        let src = "const mode = process.env.NODE_ENV === 'production' ? (a, b) : dev();";
+        assert_folded(src, ConstantConditionValue::Truthy, "const mode = (a, b);");
+        assert_folded(src, ConstantConditionValue::Falsy, "const mode = dev();");
+        let src = "x = i++ ? a : b;";
+        assert_folded(src, ConstantConditionValue::Truthy, src);
+    }
+}
@@ -0,0 +1,277 @@
+//! Detection of CSS-in-JS tagged templates, e.g. `` styled.div`color: red;` ``
+//! or `` css`color: red;` ``, whose static CSS can be extracted at build time
+//! for zero-runtime CSS-in-JS.
+
+use anyhow::Result;
+use swc_core::{
+    common::pass::AstNodePath,
+    ecma::{
+        ast::*,
+        visit::{AstParentKind, AstParentNodeRef, VisitAstPath, VisitWithPath},
+    },
+};
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::File;
+use turbopack_core::{
+    asset::AssetVc,
+    reference::{AssetReference, AssetReferenceVc},
+    resolve::{ResolveResult, ResolveResultVc},
+    virtual_asset::VirtualAssetVc,
+};
+
+use super::{as_parent_path, AstPathVc};
+use crate::{
+    analyzer::{graph::EvalContext, ConstantValue, JsValue},
+    utils::unparen,
+};
+
+/// A tagged template with CSS, see [find_css_templates].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssTemplate {
+    /// The path of the tagged template expression.
+    pub ast_path: Vec<AstParentKind>,
+    /// The CSS of the template. Interpolations of constant strings and
+    /// numbers are inlined, all others are replaced with
+    /// [interpolation_placeholder]s.
+    pub css: String,
+    /// The indices of the interpolations that are replaced with placeholders,
+    /// they have to be applied at runtime.
+    pub dynamic_interpolations: Vec<usize>,
+}
+
+/// The placeholder for the interpolation at `index` of a template, a CSS
+/// variable so the extracted CSS stays valid.
+pub fn interpolation_placeholder(index: usize) -> String {
+    format!("var(--css-in-js-{index})")
+}
+
+/// Finds the tagged templates of `program` whose tag is one of `tags`, either
+/// directly (`` css`...` ``), as the object of a member (`` styled.div`...` ``)
+/// or as a called function (`` styled(Button)`...` ``).
+///
+/// Only the names of the tags are compared, so an import from any package is
+/// considered.
+pub fn find_css_templates(
+    program: &Program,
+    tags: &[String],
+    eval_context: &EvalContext,
+) -> Vec<CssTemplate> {
+    if tags.is_empty() {
+        return Vec::new();
+    }
+    let mut visitor = CssTemplateVisitor {
+        tags,
+        eval_context,
+        templates: Vec::new(),
+    };
+    program.visit_with_path(&mut visitor, &mut Default::default());
+    visitor.templates
+}
+
+struct CssTemplateVisitor<'a> {
+    tags: &'a [String],
+    eval_context: &'a EvalContext,
+    templates: Vec<CssTemplate>,
+}
+
+impl CssTemplateVisitor<'_> {
+    fn is_css_tag(&self, tag: &Expr) -> bool {
+        let ident = match unparen(tag) {
+            Expr::Ident(ident)
+            | Expr::Member(MemberExpr {
+                obj: box Expr::Ident(ident),
+                prop: MemberProp::Ident(_),
+                ..
+            })
+            | Expr::Call(CallExpr {
+                callee: Callee::Expr(box Expr::Ident(ident)),
+                ..
+            }) => ident,
+            _ => return false,
+        };
+        self.tags.iter().any(|tag| *tag == *ident.sym)
+    }
+
+    fn css_template(&self, tpl: &Tpl, ast_path: Vec<AstParentKind>) -> CssTemplate {
+        let mut css = String::new();
+        let mut dynamic_interpolations = Vec::new();
+        for (index, quasi) in tpl.quasis.iter().enumerate() {
+            // The raw text keeps escapes like `content: "\2014"`
+            css.push_str(&quasi.raw);
+            let Some(expr) = tpl.exprs.get(index) else {
+                break;
+            };
+            match self.eval_context.eval(expr) {
+                JsValue::Constant(
+                    value @ (ConstantValue::StrWord(_)
+                    | ConstantValue::StrAtom(_)
+                    | ConstantValue::Num(_)),
+                ) => css.push_str(&value.to_js_string()),
+                _ => {
+                    css.push_str(&interpolation_placeholder(index));
+                    dynamic_interpolations.push(index);
+                }
+            }
+        }
+        CssTemplate {
+            ast_path,
+            css,
+            dynamic_interpolations,
+        }
+    }
+}
+
+impl VisitAstPath for CssTemplateVisitor<'_> {
+    fn visit_expr<'ast: 'r, 'r>(
+        &mut self,
+        expr: &'ast Expr,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        if let Expr::TaggedTpl(TaggedTpl { tag, tpl, .. }) = expr {
+            if self.is_css_tag(tag) {
+                let template = self.css_template(tpl, as_parent_path(ast_path));
+                self.templates.push(template);
+            }
+        }
+        expr.visit_children_with_path(self, ast_path);
+    }
+}
+
+/// A reference to the static CSS of a CSS-in-JS tagged template, which
+/// resolves to a CSS asset next to the module.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct CssInJsAssetReference {
+    pub source: AssetVc,
+    /// The index of the template in the module, which names the asset.
+    pub index: usize,
+    /// See [CssTemplate::css].
+    pub css: String,
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CssInJsAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc, index: usize, css: String, path: AstPathVc) -> Self {
+        Self::cell(CssInJsAssetReference {
+            source,
+            index,
+            css,
+            path,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for CssInJsAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        let path = self
+            .source
+            .path()
+            .join(&format!("css-in-js-{}.css", self.index));
+        let asset = VirtualAssetVc::new(path, File::from(self.css.clone()).into());
+        ResolveResult::Single(asset.into(), Vec::new()).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for CssInJsAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "css-in-js template {} of {}",
+            self.index,
+            self.source.path().to_string().await?,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::parse_file_as_module,
+            transforms::base::resolver,
+            visit::{fields::*, AstParentKind, VisitMutWith},
+        },
+        testing::run_test,
+    };
+
+    use super::{find_css_templates, CssTemplate};
+    use crate::analyzer::graph::EvalContext;
+
+    fn templates(src: &str) -> Vec<CssTemplate> {
+        let tags = ["styled".to_string(), "css".to_string()];
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Module(module);
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            let eval_context = EvalContext::new(&program, unresolved_mark);
+            Ok(find_css_templates(&program, &tags, &eval_context))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn styled_element() {
+        let found =
+            templates("const Title = styled.div`\n  color: red;\n  font-size: ${16}px;\n`;");
+        assert_eq!(
+            found,
+            vec![CssTemplate {
+                ast_path: vec![
+                    AstParentKind::Program(ProgramField::Module),
+                    AstParentKind::Module(ModuleField::Body(0)),
+                    AstParentKind::ModuleItem(ModuleItemField::Stmt),
+                    AstParentKind::Stmt(StmtField::Decl),
+                    AstParentKind::Decl(DeclField::Var),
+                    AstParentKind::VarDecl(VarDeclField::Decls(0)),
+                    AstParentKind::VarDeclarator(VarDeclaratorField::Init),
+                ],
+                css: "\n  color: red;\n  font-size: 16px;\n".to_string(),
+                dynamic_interpolations: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn dynamic_interpolations() {
+        let found = templates(
+            "const Button = styled(Base)`\n  color: ${color};\n  font-weight: ${\"bold\"};\n  \
+             background: ${(props) => props.bg};\n`;",
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].css,
+            "\n  color: var(--css-in-js-0);\n  font-weight: bold;\n  background: \
+             var(--css-in-js-2);\n"
+        );
+        assert_eq!(found[0].dynamic_interpolations, vec![0, 2]);
+    }
+
+    #[test]
+    fn other_tags() {
+        let found = templates(
+            "const a = css`margin: 0;`;\nconst b = html`<div></div>`;\nconst c = String.raw`a`;",
+        );
+        assert_eq!(
+            found.iter().map(|t| t.css.as_str()).collect::<Vec<_>>(),
+            vec!["margin: 0;"]
+        );
+    }
+}
@@ -5,13 +5,17 @@ use swc_core::{
     ecma::ast::{Expr, ExprStmt, Ident, Lit, Module, ModuleItem, Program, Script, Stmt},
     quote,
 };
-use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    Value, ValueToString, ValueToStringVc,
+};
 use turbopack_core::{
     asset::Asset,
     chunk::{
-        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
-        ChunkingTypeOptionVc, ModuleId,
+        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContextVc,
+        ChunkingType, ChunkingTypeOptionVc, ModuleId,
     },
+    issue::{analyze::AnalyzeIssue, IssueSeverity},
     reference::{AssetReference, AssetReferenceVc},
     resolve::{
         origin::ResolveOriginVc, parse::RequestVc, ResolveResult, ResolveResultVc, SpecialType,
@@ -20,11 +24,12 @@ use turbopack_core::{
 
 use crate::{
     analyzer::imports::ImportAnnotations,
-    chunk::EcmascriptChunkPlaceableVc,
+    chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor, magic_identifier,
     references::util::{request_to_string, throw_module_not_found_expr},
     resolve::esm_resolve,
+    EcmascriptModuleAssetVc,
 };
 
 #[turbo_tasks::value]
@@ -58,6 +63,9 @@ pub struct EsmAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub annotations: ImportAnnotations,
+    /// Whether the import declares bindings or re-exports, in contrast to
+    /// `import "./a"`, which is only evaluated for its side effects.
+    pub has_bindings: bool,
 }
 
 impl EsmAssetReference {
@@ -66,6 +74,9 @@ impl EsmAssetReference {
         if let Some(transition) = self.annotations.transition() {
             origin = origin.with_transition(transition);
         }
+        if let Some(module_type) = self.annotations.module_type() {
+            origin = origin.with_module_type(module_type);
+        }
         origin
     }
 }
@@ -73,7 +84,7 @@ impl EsmAssetReference {
 #[turbo_tasks::value_impl]
 impl EsmAssetReferenceVc {
     #[turbo_tasks::function]
-    pub(super) async fn get_referenced_asset(self) -> Result<ReferencedAssetVc> {
+    pub(crate) async fn get_referenced_asset(self) -> Result<ReferencedAssetVc> {
         let this = self.await?;
         let resolve_result = esm_resolve(this.get_origin(), this.request);
         match &*resolve_result.await? {
@@ -98,16 +109,123 @@ impl EsmAssetReferenceVc {
         Ok(ReferencedAssetVc::cell(ReferencedAsset::None))
     }
 
+    /// Whether the import is dropped: it has no bindings and the referenced
+    /// module has no side effects according to its package.json, so
+    /// evaluating the module has no observable effect. The module isn't
+    /// placed into the chunk for this import.
+    #[turbo_tasks::function]
+    pub(crate) async fn is_unused(self) -> Result<BoolVc> {
+        if self.await?.has_bindings {
+            return Ok(BoolVc::cell(false));
+        }
+        let ReferencedAsset::Some(asset) = &*self.get_referenced_asset().await? else {
+            return Ok(BoolVc::cell(false));
+        };
+        let Some(module) = EcmascriptModuleAssetVc::resolve_from(asset).await? else {
+            return Ok(BoolVc::cell(false));
+        };
+        Ok(BoolVc::cell(!*module.has_side_effects().await?))
+    }
+
+    /// Whether the import only has side effects and its bindings have no
+    /// value. That's the case when the module rule that matched the
+    /// referenced asset made it a chunkable asset without exports, e.g. a
+    /// global stylesheet, in contrast to a CSS module.
+    #[turbo_tasks::function]
+    pub(super) async fn is_side_effect_only(self) -> Result<BoolVc> {
+        let this = self.await?;
+        let assets = esm_resolve(this.get_origin(), this.request)
+            .primary_assets()
+            .await?;
+        if assets.is_empty() {
+            return Ok(BoolVc::cell(false));
+        }
+        for asset in assets.iter() {
+            if EcmascriptChunkPlaceableVc::resolve_from(asset)
+                .await?
+                .is_some()
+                || ChunkableAssetVc::resolve_from(asset).await?.is_none()
+            {
+                return Ok(BoolVc::cell(false));
+            }
+        }
+        Ok(BoolVc::cell(true))
+    }
+
+    /// Emits an error when `export` doesn't exist at runtime in the
+    /// referenced module. That's the case when it only exists in type space,
+    /// e.g. when it's a TypeScript interface, since those are erased, and for
+    /// named imports from a JSON module, which only has a default export. The
+    /// imported value would be `undefined`. Returns whether an error was
+    /// emitted.
+    #[turbo_tasks::function]
+    pub(super) async fn check_value_import(self, export: String) -> Result<BoolVc> {
+        let ReferencedAsset::Some(asset) = &*self.get_referenced_asset().await? else {
+            return Ok(BoolVc::cell(false));
+        };
+        let this = self.await?;
+        let exports = match &*asset.get_exports().await? {
+            EcmascriptExports::Value
+                if this.annotations.module_type() == Some("json") && export != "default" =>
+            {
+                AnalyzeIssue {
+                    code: None,
+                    category: StringVc::cell("analyze".to_string()),
+                    message: StringVc::cell(format!(
+                        "export {} doesn't exist in JSON module {}, it only has a default \
+                         export\nDid you want to import the default export and access the \
+                         property on it?",
+                        export,
+                        asset.path().to_string().await?
+                    )),
+                    path: this.origin.origin_path(),
+                    severity: IssueSeverity::Error.into(),
+                    source: None,
+                    title: StringVc::cell("named import from a JSON module".to_string()),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                return Ok(BoolVc::cell(true));
+            }
+            EcmascriptExports::EsmExports(exports) => exports.await?,
+            _ => return Ok(BoolVc::cell(false)),
+        };
+        if !exports.type_exports.contains(&export) {
+            return Ok(BoolVc::cell(false));
+        }
+        AnalyzeIssue {
+            code: None,
+            category: StringVc::cell("analyze".to_string()),
+            message: StringVc::cell(format!(
+                "export {} of module {} is a type and doesn't exist at runtime\nDid you want to \
+                 import it with `import type`?",
+                export,
+                asset.path().to_string().await?
+            )),
+            path: this.origin.origin_path(),
+            severity: IssueSeverity::Error.into(),
+            source: None,
+            title: StringVc::cell("type imported as a value".to_string()),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+        Ok(BoolVc::cell(true))
+    }
+
     #[turbo_tasks::function]
     pub fn new(
         origin: ResolveOriginVc,
         request: RequestVc,
         annotations: Value<ImportAnnotations>,
+        has_bindings: bool,
     ) -> Self {
         Self::cell(EsmAssetReference {
             origin,
             request,
             annotations: annotations.into_value(),
+            has_bindings,
         })
     }
 }
@@ -180,22 +298,28 @@ impl CodeGenerateable for EsmAssetReference {
         }
 
         // separate chunks can't be imported as the modules are not available
-        if !matches!(*chunking_type, None | Some(ChunkingType::Separate)) {
+        if !matches!(*chunking_type, None | Some(ChunkingType::Separate))
+            && !*self_vc.is_unused().await?
+        {
             let referenced_asset = self_vc.get_referenced_asset().await?;
             if let Some(ident) = referenced_asset.get_ident().await? {
                 match &*referenced_asset {
                     ReferencedAsset::Some(asset) => {
                         let id = asset.as_chunk_item(context).id().await?;
+                        let is_cjs =
+                            matches!(&*asset.get_exports().await?, EcmascriptExports::CommonJs(_));
+                        let is_async = if let Some(module) =
+                            EcmascriptModuleAssetVc::resolve_from(asset).await?
+                        {
+                            *module.is_async().await?
+                        } else {
+                            false
+                        };
                         visitors.push(create_visitor!(visit_mut_program(program: &mut Program) {
-                            let stmt = quote!(
-                                "var $name = __turbopack_import__($id);" as Stmt,
-                                name = Ident::new(ident.clone().into(), DUMMY_SP),
-                                id: Expr = Expr::Lit(match &*id {
-                                    ModuleId::String(s) => s.clone().into(),
-                                    ModuleId::Number(n) => (*n as f64).into(),
-                                })
-                            );
-                            insert_hoisted_stmt(program, stmt);
+                            insert_hoisted_stmt(program, import_stmt(&ident, &id, is_cjs));
+                            if is_async {
+                                insert_hoisted_stmt(program, await_module_stmt(&id));
+                            }
                         }));
                     }
                     ReferencedAsset::OriginalReferenceTypeExternal(request) => {
@@ -219,6 +343,44 @@ impl CodeGenerateable for EsmAssetReference {
     }
 }
 
+/// The statement that imports the module `id` as `name`. CommonJS modules
+/// (`is_cjs`) don't have a namespace object, so the runtime needs to create one
+/// with `module.exports` as default export. The `__esModule` flag is only known
+/// at runtime.
+fn import_stmt(name: &str, id: &ModuleId, is_cjs: bool) -> Stmt {
+    let name = Ident::new(name.into(), DUMMY_SP);
+    let id = Expr::Lit(match id {
+        ModuleId::String(s) => s.clone().into(),
+        ModuleId::Number(n) => (*n as f64).into(),
+    });
+    if is_cjs {
+        quote!(
+            "var $name = __turbopack_import__($id, true);" as Stmt,
+            name = name,
+            id: Expr = id
+        )
+    } else {
+        quote!(
+            "var $name = __turbopack_import__($id);" as Stmt,
+            name = name,
+            id: Expr = id
+        )
+    }
+}
+
+/// Waits for the evaluation of the async module `id` to finish. Async modules
+/// complete their exports only once `module.promise` resolves, so this makes
+/// the importing module async too (see `EcmascriptModuleAsset::is_async`).
+fn await_module_stmt(id: &ModuleId) -> Stmt {
+    quote!(
+        "await __turbopack_cache__[$id].promise;" as Stmt,
+        id: Expr = Expr::Lit(match id {
+            ModuleId::String(s) => s.clone().into(),
+            ModuleId::Number(n) => (*n as f64).into(),
+        })
+    )
+}
+
 lazy_static! {
     static ref ESM_HOISTING_LOCATION: &'static str = Box::leak(Box::new(magic_identifier::encode(
         "ecmascript hoisting location"
@@ -279,3 +441,58 @@ pub(crate) fn insert_hoisted_stmt(program: &mut Program, stmt: Stmt) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::DUMMY_SP,
+        ecma::{
+            ast::Script,
+            codegen::{text_writer::JsWriter, Emitter},
+        },
+        testing::run_test,
+    };
+    use turbopack_core::chunk::ModuleId;
+
+    use super::import_stmt;
+
+    fn emit(is_cjs: bool) -> String {
+        run_test(false, |cm, _handler| {
+            let script = Script {
+                span: DUMMY_SP,
+                body: vec![import_stmt(
+                    "mod",
+                    &ModuleId::String("./mod.js".to_string()),
+                    is_cjs,
+                )],
+                shebang: None,
+            };
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_script(&script).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn import_esm() {
+        assert_eq!(emit(false), r#"var mod=__turbopack_import__("./mod.js");"#);
+    }
+
+    #[test]
+    fn import_cjs() {
+        assert_eq!(
+            emit(true),
+            r#"var mod=__turbopack_import__("./mod.js",true);"#
+        );
+    }
+}
@@ -4,7 +4,7 @@ use swc_core::{
     ecma::{
         ast::{
             ComputedPropName, Expr, Ident, KeyValueProp, Lit, MemberExpr, MemberProp, Prop,
-            PropName, Str,
+            PropName, Str, UnaryExpr, UnaryOp,
         },
         visit::fields::{ExprField, PropField},
     },
@@ -75,15 +75,31 @@ impl CodeGenerateable for EsmBinding {
 
         let mut ast_path = this.ast_path.await?.clone_value();
         let imported_module = imported_module.await?.get_ident().await?;
+        let expr = if *this.reference.is_side_effect_only().await? {
+            // E.g. `import styles from "./global.css"`, there's nothing to bind
+            Some(Expr::Unary(UnaryExpr {
+                span: DUMMY_SP,
+                op: UnaryOp::Void,
+                arg: box Expr::Lit(Lit::Num(0.0.into())),
+            }))
+        } else {
+            if let Some(export) = &this.export {
+                // Only reports the error, the binding is `undefined` at runtime
+                this.reference.check_value_import(export.clone()).await?;
+            }
+            imported_module
+                .as_deref()
+                .map(|ident| make_expr(ident, this.export.as_deref()))
+        };
 
         loop {
             match ast_path.last() {
                 Some(swc_core::ecma::visit::AstParentKind::Expr(ExprField::Ident)) => {
                     ast_path.pop();
                     visitors.push(
-                        create_visitor!(exact ast_path, visit_mut_expr(expr: &mut Expr) {
-                            if let Some(ident) = imported_module.as_deref() {
-                              *expr = make_expr(ident, this.export.as_deref());
+                        create_visitor!(exact ast_path, visit_mut_expr(expr_ref: &mut Expr) {
+                            if let Some(expr) = &expr {
+                              *expr_ref = expr.clone();
                             }
                             // If there's no identifier for the imported module,
                             // resolution failed and will insert code that throws
@@ -98,8 +114,8 @@ impl CodeGenerateable for EsmBinding {
                         create_visitor!(ast_path, visit_mut_prop(prop: &mut Prop) {
                             if let Prop::Shorthand(ident) = prop {
                               // TODO: Merge with the above condition when https://rust-lang.github.io/rfcs/2497-if-let-chains.html lands.
-                              if let Some(imported_ident) = imported_module.as_deref() {
-                                *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value: box make_expr(imported_ident, this.export.as_deref())});
+                              if let Some(expr) = &expr {
+                                *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value: box expr.clone()});
                               }
                             }
                         }),
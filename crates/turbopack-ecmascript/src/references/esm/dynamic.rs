@@ -1,13 +1,18 @@
+use std::fmt::Write;
+
 use anyhow::Result;
 use swc_core::{
     ecma::ast::{Callee, ExprOrSpread},
     quote_expr,
 };
-use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringVc},
+    Value, ValueToString, ValueToStringVc,
+};
 use turbopack_core::{
     chunk::{
-        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
-        ChunkingTypeOptionVc,
+        ChunkLoadingHint, ChunkLoadingHintOptionVc, ChunkableAssetReference,
+        ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType, ChunkingTypeOptionVc,
     },
     reference::{AssetReference, AssetReferenceVc},
     resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
@@ -15,10 +20,11 @@ use turbopack_core::{
 
 use super::super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::EsmAsync};
 use crate::{
+    analyzer::imports::ImportAnnotations,
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
     references::AstPathVc,
-    resolve::esm_resolve,
+    resolve::{resolve_with_kind, ImportKind},
 };
 
 #[turbo_tasks::value]
@@ -27,16 +33,43 @@ pub struct EsmAsyncAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub path: AstPathVc,
+    /// Import attributes from the options argument, e.g. `import("./data.json",
+    /// { with: { type: "json" } })`
+    pub annotations: ImportAnnotations,
+    /// The name of the chunk from a `webpackChunkName` comment
+    pub chunk_name: OptionStringVc,
+    /// The hint from a `webpackPreload` or `webpackPrefetch` comment
+    pub loading_hint: ChunkLoadingHintOptionVc,
+}
+
+impl EsmAsyncAssetReference {
+    fn get_origin(&self) -> ResolveOriginVc {
+        let mut origin = self.origin;
+        if let Some(module_type) = self.annotations.module_type() {
+            origin = origin.with_module_type(module_type);
+        }
+        origin
+    }
 }
 
 #[turbo_tasks::value_impl]
 impl EsmAsyncAssetReferenceVc {
     #[turbo_tasks::function]
-    pub fn new(origin: ResolveOriginVc, request: RequestVc, path: AstPathVc) -> Self {
+    pub fn new(
+        origin: ResolveOriginVc,
+        request: RequestVc,
+        path: AstPathVc,
+        annotations: Value<ImportAnnotations>,
+        chunk_name: OptionStringVc,
+        loading_hint: ChunkLoadingHintOptionVc,
+    ) -> Self {
         Self::cell(EsmAsyncAssetReference {
             origin,
             request,
             path,
+            annotations: annotations.into_value(),
+            chunk_name,
+            loading_hint,
         })
     }
 }
@@ -45,7 +78,11 @@ impl EsmAsyncAssetReferenceVc {
 impl AssetReference for EsmAsyncAssetReference {
     #[turbo_tasks::function]
     fn resolve_reference(&self) -> ResolveResultVc {
-        esm_resolve(self.origin, self.request)
+        resolve_with_kind(
+            self.get_origin(),
+            self.request,
+            Value::new(ImportKind::DynamicImport),
+        )
     }
 }
 
@@ -53,10 +90,21 @@ impl AssetReference for EsmAsyncAssetReference {
 impl ValueToString for EsmAsyncAssetReference {
     #[turbo_tasks::function]
     async fn to_string(&self) -> Result<StringVc> {
-        Ok(StringVc::cell(format!(
-            "dynamic import {}",
-            self.request.to_string().await?,
-        )))
+        let request = self.request.to_string().await?;
+        let mut result = if self.annotations.is_empty() {
+            format!("dynamic import {request}")
+        } else {
+            format!("dynamic import {request} {}", self.annotations)
+        };
+        if let Some(chunk_name) = &*self.chunk_name.await? {
+            write!(result, " (chunk {chunk_name})")?;
+        }
+        match *self.loading_hint.await? {
+            Some(ChunkLoadingHint::Preload { order }) => write!(result, " (preload {order})")?,
+            Some(ChunkLoadingHint::Prefetch { order }) => write!(result, " (prefetch {order})")?,
+            None => {}
+        }
+        Ok(StringVc::cell(result))
     }
 }
 
@@ -66,6 +114,16 @@ impl ChunkableAssetReference for EsmAsyncAssetReference {
     fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
         ChunkingTypeOptionVc::cell(Some(ChunkingType::SeparateAsync))
     }
+
+    #[turbo_tasks::function]
+    fn chunk_name(&self) -> OptionStringVc {
+        self.chunk_name
+    }
+
+    #[turbo_tasks::function]
+    fn loading_hint(&self) -> ChunkLoadingHintOptionVc {
+        self.loading_hint
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -74,10 +132,15 @@ impl CodeGenerateable for EsmAsyncAssetReference {
     async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
         let pm = PatternMappingVc::resolve_request(
             self.request,
-            self.origin,
+            self.get_origin(),
             context,
-            esm_resolve(self.origin, self.request),
+            resolve_with_kind(
+                self.get_origin(),
+                self.request,
+                Value::new(ImportKind::DynamicImport),
+            ),
             Value::new(EsmAsync),
+            self.chunk_name,
         )
         .await?;
 
@@ -1,6 +1,6 @@
 use std::{
     borrow::Cow,
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
 };
 
 use anyhow::Result;
@@ -22,6 +22,7 @@ use turbopack_core::{
     asset::Asset,
     chunk::ChunkingContextVc,
     issue::{analyze::AnalyzeIssue, IssueSeverity},
+    reference::AssetReference,
 };
 
 use super::{base::ReferencedAsset, EsmAssetReferenceVc};
@@ -91,23 +92,43 @@ async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<S
             .cell()
             .as_issue()
             .emit(),
-            EcmascriptExports::CommonJs => AnalyzeIssue {
-                code: None,
-                category: StringVc::cell("analyze".to_string()),
-                message: StringVc::cell(format!(
-                    "export * used with module {} which is a CommonJS module with exports only \
-                     available at runtime\nList all export names manually (`export {{ a, b, c }} \
-                     from \"...\") or rewrite the module to ESM.`",
-                    asset.path().to_string().await?
-                )),
-                path: asset.path(),
-                severity: IssueSeverity::Warning.into(),
-                source: None,
-                title: StringVc::cell("unexpected export *".to_string()),
+            EcmascriptExports::CommonJs(exports) => {
+                let exports = exports.await?;
+                if exports.star_exports.is_empty() && exports.exports.is_empty() {
+                    AnalyzeIssue {
+                        code: None,
+                        category: StringVc::cell("analyze".to_string()),
+                        message: StringVc::cell(format!(
+                            "export * used with module {} which is a CommonJS module with exports \
+                             only available at runtime\nList all export names manually (`export \
+                             {{ a, b, c }} from \"...\") or rewrite the module to ESM.`",
+                            asset.path().to_string().await?
+                        )),
+                        path: asset.path(),
+                        severity: IssueSeverity::Warning.into(),
+                        source: None,
+                        title: StringVc::cell("unexpected export *".to_string()),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
+                // Re-exports like `module.exports = require("./x")` are followed
+                // like `export * from "./x"`
+                set.extend(exports.exports.iter().filter(|n| *n != "default").cloned());
+                for cjs_ref in exports.star_exports.iter() {
+                    let assets = cjs_ref.resolve_reference().primary_assets().await?;
+                    for asset in assets.iter() {
+                        if let Some(asset) = EcmascriptChunkPlaceableVc::resolve_from(asset).await?
+                        {
+                            if checked_assets.insert(asset) {
+                                queue.push((asset, asset.get_exports()));
+                            }
+                            break;
+                        }
+                    }
+                }
             }
-            .cell()
-            .as_issue()
-            .emit(),
         }
     }
     Ok(StringsVc::cell(set.into_iter().collect()))
@@ -116,8 +137,12 @@ async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<S
 #[turbo_tasks::value(shared)]
 #[derive(Hash, Debug)]
 pub struct EsmExports {
+    /// The exports in value space.
     pub exports: BTreeMap<String, EsmExport>,
     pub star_exports: Vec<EsmAssetReferenceVc>,
+    /// The exports that only exist in type space, e.g. TypeScript interfaces.
+    /// They are erased from the module, so importing them as a value fails.
+    pub type_exports: BTreeSet<String>,
 }
 
 #[turbo_tasks::value_impl]
@@ -5,6 +5,7 @@ pub(crate) mod export;
 pub(crate) mod meta;
 pub(crate) mod module_id;
 pub(crate) mod module_item;
+pub(crate) mod used_exports;
 
 pub use self::{
     base::{EsmAssetReference, EsmAssetReferenceVc},
@@ -13,4 +14,5 @@ pub use self::{
     export::{EsmExports, EsmExportsVc},
     meta::{ImportMetaBinding, ImportMetaBindingVc, ImportMetaRef, ImportMetaRefVc},
     module_item::{EsmModuleItem, EsmModuleItemVc},
+    used_exports::{used_exports, UsedExports, UsedExportsVc},
 };
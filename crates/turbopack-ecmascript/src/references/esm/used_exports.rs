@@ -0,0 +1,355 @@
+use std::collections::{BTreeSet, HashSet};
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use turbopack_core::{asset::Asset, reference::AssetReferenceVc};
+
+use super::{base::ReferencedAsset, export::EsmExport, EsmAssetReferenceVc, EsmBindingVc};
+use crate::{
+    chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
+    EcmascriptModuleAssetVc,
+};
+
+/// The exports of a module that are used by the other modules of a graph.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub enum UsedExports {
+    /// Exports might be accessed by name at runtime, e.g. through a namespace
+    /// object, `require` or a dynamic `import()`.
+    All,
+    None,
+    Set(BTreeSet<String>),
+}
+
+impl UsedExports {
+    pub fn contains(&self, name: &str) -> bool {
+        match self {
+            UsedExports::All => true,
+            UsedExports::None => false,
+            UsedExports::Set(names) => names.contains(name),
+        }
+    }
+
+    /// Adds `export` to the used exports. Returns true when they changed.
+    fn add(&mut self, export: &ExportUse) -> bool {
+        match (&mut *self, export) {
+            (UsedExports::All, _) => false,
+            (_, ExportUse::All) => {
+                *self = UsedExports::All;
+                true
+            }
+            (UsedExports::Set(names), ExportUse::Name(name)) => names.insert(name.clone()),
+            (UsedExports::None, ExportUse::Name(name)) => {
+                *self = UsedExports::Set(BTreeSet::from([name.clone()]));
+                true
+            }
+        }
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct UsedExportsMap(IndexMap<EcmascriptChunkPlaceableVc, UsedExports>);
+
+/// The exports of `module` that are used by the modules reachable from
+/// `entry`. Uses that are forwarded by re-exports, e.g.
+/// `export { a } from "./a"` or `export * from "./a"`, count as uses of the
+/// module that declares the export.
+///
+/// The exports of `entry` itself are all used. Modules that are not reachable
+/// from `entry` have no used exports.
+#[turbo_tasks::function]
+pub async fn used_exports(
+    entry: EcmascriptChunkPlaceableVc,
+    module: EcmascriptChunkPlaceableVc,
+) -> Result<UsedExportsVc> {
+    let module = module.resolve().await?;
+    let map = graph_used_exports(entry).await?;
+    Ok(map
+        .get(&module)
+        .cloned()
+        .unwrap_or(UsedExports::None)
+        .cell())
+}
+
+/// The used exports of all modules that are reachable from `entry`.
+#[turbo_tasks::function]
+async fn graph_used_exports(entry: EcmascriptChunkPlaceableVc) -> Result<UsedExportsMapVc> {
+    let mut modules = IndexSet::new();
+    modules.insert(entry.resolve().await?);
+    let mut usages = Vec::new();
+    // Modules are appended to `modules` while they are visited
+    while let Some(&module) = modules.get_index(usages.len()) {
+        let mut usage = ModuleUsage::default();
+        let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? else {
+            // The usage of other modules is unknown
+            for reference in module.references().await?.iter() {
+                for asset in referenced_placeables(*reference).await? {
+                    let (index, _) = modules.insert_full(asset);
+                    usage.uses.push((index, ExportUse::All));
+                }
+            }
+            usages.push(usage);
+            continue;
+        };
+
+        let analysis = ecmascript.analyze().await?;
+        for code_gen in analysis.code_generation.await?.iter() {
+            let Some(binding) = EsmBindingVc::resolve_from(*code_gen).await? else {
+                continue;
+            };
+            let binding = binding.await?;
+            if let ReferencedAsset::Some(asset) = &*binding.reference.get_referenced_asset().await?
+            {
+                let (index, _) = modules.insert_full(*asset);
+                usage.uses.push((
+                    index,
+                    // A namespace import that's not only used for member accesses
+                    binding
+                        .export
+                        .clone()
+                        .map_or(ExportUse::All, ExportUse::Name),
+                ));
+            }
+        }
+        for reference in analysis.references.await?.iter() {
+            // The uses of ESM imports are tracked by their bindings
+            if let Some(esm_reference) = EsmAssetReferenceVc::resolve_from(*reference).await? {
+                if let ReferencedAsset::Some(asset) = &*esm_reference.get_referenced_asset().await?
+                {
+                    modules.insert(*asset);
+                }
+                continue;
+            }
+            for asset in referenced_placeables(*reference).await? {
+                let (index, _) = modules.insert_full(asset);
+                usage.uses.push((index, ExportUse::All));
+            }
+        }
+        if let EcmascriptExports::EsmExports(exports) = &*analysis.exports.await? {
+            let exports = exports.await?;
+            for (name, export) in exports.exports.iter() {
+                usage.exports.insert(name.clone());
+                let (reference, export) = match export {
+                    EsmExport::ImportedBinding(reference, export) => {
+                        (reference, ExportUse::Name(export.clone()))
+                    }
+                    EsmExport::ImportedNamespace(reference) => (reference, ExportUse::All),
+                    EsmExport::LocalBinding(_) | EsmExport::Error => continue,
+                };
+                if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? {
+                    let (index, _) = modules.insert_full(*asset);
+                    usage.reexports.push((name.clone(), index, export));
+                }
+            }
+            for reference in exports.star_exports.iter() {
+                if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? {
+                    let (index, _) = modules.insert_full(*asset);
+                    usage.star_exports.push(index);
+                }
+            }
+        }
+        usages.push(usage);
+    }
+
+    let used = propagate_used_exports(&usages, 0);
+    Ok(UsedExportsMapVc::cell(
+        modules.into_iter().zip(used).collect(),
+    ))
+}
+
+async fn referenced_placeables(
+    reference: AssetReferenceVc,
+) -> Result<Vec<EcmascriptChunkPlaceableVc>> {
+    let mut placeables = Vec::new();
+    for asset in reference.resolve_reference().primary_assets().await?.iter() {
+        if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+            placeables.push(placeable);
+        }
+    }
+    Ok(placeables)
+}
+
+/// How a module uses the exports of another module.
+#[derive(Debug, Clone)]
+enum ExportUse {
+    Name(String),
+    All,
+}
+
+/// The uses of the exports of other modules by a module, which refers to the
+/// other modules by their index in the graph.
+#[derive(Default)]
+struct ModuleUsage {
+    /// The exports of other modules that the code of the module uses.
+    uses: Vec<(usize, ExportUse)>,
+    /// The names of the exports of the module, excluding star exports.
+    exports: HashSet<String>,
+    /// Exports of the module that forward an export of another module, e.g.
+    /// `export { b as a } from "./b"`.
+    reexports: Vec<(String, usize, ExportUse)>,
+    /// The modules of `export * from "..."`.
+    star_exports: Vec<usize>,
+}
+
+/// Computes the used exports of the modules of a graph, with all exports of
+/// the `entry` module being used. Uses are forwarded along re-exports until
+/// no used export is added.
+fn propagate_used_exports(modules: &[ModuleUsage], entry: usize) -> Vec<UsedExports> {
+    let mut used = vec![UsedExports::None; modules.len()];
+    used[entry] = UsedExports::All;
+    for usage in modules {
+        for (index, export) in usage.uses.iter() {
+            used[*index].add(export);
+        }
+    }
+
+    loop {
+        let mut changed = false;
+        for (index, usage) in modules.iter().enumerate() {
+            let mut forwarded = Vec::new();
+            for (name, target, export) in usage.reexports.iter() {
+                if used[index].contains(name) {
+                    forwarded.push((*target, export.clone()));
+                }
+            }
+            // Star exports provide the used exports that the module doesn't
+            // export itself. `default` is never re-exported by them.
+            let star_uses = match &used[index] {
+                UsedExports::All => vec![ExportUse::All],
+                UsedExports::None => Vec::new(),
+                UsedExports::Set(names) => names
+                    .iter()
+                    .filter(|name| *name != "default" && !usage.exports.contains(*name))
+                    .map(|name| ExportUse::Name(name.clone()))
+                    .collect(),
+            };
+            for target in usage.star_exports.iter() {
+                for export in star_uses.iter() {
+                    forwarded.push((*target, export.clone()));
+                }
+            }
+            for (target, export) in forwarded {
+                changed |= used[target].add(&export);
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    used
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+
+    use super::{propagate_used_exports, ExportUse, ModuleUsage, UsedExports};
+
+    fn name(name: &str) -> ExportUse {
+        ExportUse::Name(name.to_string())
+    }
+
+    fn set(names: &[&str]) -> UsedExports {
+        UsedExports::Set(
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<BTreeSet<_>>(),
+        )
+    }
+
+    fn exports(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn unused_named_export() {
+        // entry: import { used } from "./lib"; used();
+        // lib: export function used() {} export function unused() {}
+        let modules = [
+            ModuleUsage {
+                uses: vec![(1, name("used"))],
+                ..Default::default()
+            },
+            ModuleUsage {
+                exports: exports(&["used", "unused"]),
+                ..Default::default()
+            },
+        ];
+        let used = propagate_used_exports(&modules, 0);
+        assert_eq!(used[0], UsedExports::All);
+        assert_eq!(used[1], set(&["used"]));
+        assert!(used[1].contains("used"));
+        assert!(!used[1].contains("unused"));
+    }
+
+    #[test]
+    fn reexports() {
+        // entry: import { a, c } from "./barrel"; a(); c();
+        // barrel: export { x as a, y as b } from "./ab"; export * from "./c";
+        //         export * from "./d";
+        // ab: export const x = 1, y = 2;
+        // c: export const c = 3;
+        // d: import * as ns from "./e"; export { ns };
+        let modules = [
+            ModuleUsage {
+                uses: vec![(1, name("a")), (1, name("c"))],
+                ..Default::default()
+            },
+            ModuleUsage {
+                exports: exports(&["a", "b"]),
+                reexports: vec![
+                    ("a".to_string(), 2, name("x")),
+                    ("b".to_string(), 2, name("y")),
+                ],
+                star_exports: vec![3, 4],
+                ..Default::default()
+            },
+            ModuleUsage {
+                exports: exports(&["x", "y"]),
+                ..Default::default()
+            },
+            ModuleUsage {
+                exports: exports(&["c"]),
+                ..Default::default()
+            },
+            ModuleUsage {
+                exports: exports(&["ns"]),
+                reexports: vec![("ns".to_string(), 5, ExportUse::All)],
+                ..Default::default()
+            },
+            ModuleUsage::default(),
+        ];
+        let used = propagate_used_exports(&modules, 0);
+        assert_eq!(used[1], set(&["a", "c"]));
+        assert_eq!(used[2], set(&["x"]));
+        // Star exports can't tell which module provides `c`
+        assert_eq!(used[3], set(&["c"]));
+        assert_eq!(used[4], set(&["c"]));
+        assert_eq!(used[5], UsedExports::None);
+    }
+
+    #[test]
+    fn namespace_use() {
+        // entry: import * as ns from "./barrel"; use(ns);
+        // barrel: export { x as a } from "./lib";
+        let modules = [
+            ModuleUsage {
+                uses: vec![(1, ExportUse::All)],
+                ..Default::default()
+            },
+            ModuleUsage {
+                exports: exports(&["a"]),
+                reexports: vec![("a".to_string(), 2, name("x"))],
+                ..Default::default()
+            },
+            ModuleUsage {
+                exports: exports(&["x", "y"]),
+                ..Default::default()
+            },
+        ];
+        let used = propagate_used_exports(&modules, 0);
+        assert_eq!(used[1], UsedExports::All);
+        assert_eq!(used[2], set(&["x"]));
+    }
+}
@@ -1,14 +1,19 @@
 pub mod amd;
 pub mod cjs;
+pub mod cjs_exports;
+pub mod constant_condition;
+pub mod css_in_js;
 pub mod esm;
 pub mod node;
 pub mod pattern_mapping;
 pub mod raw;
+pub mod require_context;
 pub mod typescript;
 pub mod util;
+pub mod worker;
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     future::Future,
     mem::take,
     pin::Pin,
@@ -21,7 +26,7 @@ use regex::Regex;
 use swc_core::{
     common::{
         comments::CommentKind,
-        errors::{DiagnosticId, Handler, HANDLER},
+        errors::{DiagnosticBuilder, DiagnosticId, Handler, Level, HANDLER},
         pass::AstNodePath,
         Span, Spanned, GLOBALS,
     },
@@ -30,10 +35,11 @@ use swc_core::{
         visit::{AstParentKind, AstParentNodeRef, VisitAstPath, VisitWithPath},
     },
 };
-use turbo_tasks::{TryJoinIterExt, Value};
+use turbo_tasks::{primitives::OptionStringVc, TryJoinIterExt, Value};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::AssetVc,
+    chunk::ChunkLoadingHintOptionVc,
     environment::EnvironmentVc,
     reference::{AssetReferenceVc, AssetReferencesVc, SourceMapVc},
     resolve::{
@@ -42,6 +48,7 @@ use turbopack_core::{
     },
 };
 use turbopack_swc_utils::emitter::IssueEmitter;
+use url::Url;
 
 use self::{
     amd::{
@@ -49,26 +56,36 @@ use self::{
         AmdDefineWithDependenciesCodeGenVc,
     },
     cjs::CjsAssetReferenceVc,
+    cjs_exports::{find_cjs_reexports, CjsExports, CjsReexport},
+    css_in_js::{find_css_templates, CssInJsAssetReferenceVc},
     esm::{
         export::EsmExport, EsmAssetReferenceVc, EsmAsyncAssetReferenceVc, EsmExports,
         EsmModuleItemVc, ImportMetaBindingVc, ImportMetaRefVc,
     },
     node::{DirAssetReferenceVc, PackageJsonReferenceVc},
     raw::SourceAssetReferenceVc,
+    require_context::{
+        context_from_pattern, require_context_options, RequireContextAssetReferenceVc,
+        RequireContextKind,
+    },
     typescript::{
         TsConfigReferenceVc, TsReferencePathAssetReferenceVc, TsReferenceTypeAssetReferenceVc,
     },
+    worker::WorkerAssetReferenceVc,
 };
 use super::{
     analyzer::{
         builtin::replace_builtin,
+        dynamic_scope::{find_dynamic_code, DynamicCode, DynamicCodeKind},
         graph::{create_graph, Effect},
+        imports::ImportAnnotations,
         linker::{link, LinkCache},
-        well_known::replace_well_known,
+        well_known::{global_function, replace_well_known, ProcessEnvConfig},
         ConstantValue, FreeVarKind, JsValue, ObjectPart, WellKnownFunctionKind,
         WellKnownObjectKind,
     },
     errors,
+    magic_comments::{collect_magic_comments, MagicComments},
     parse::{parse, ParseResult},
     resolve::{apply_cjs_specific_options, cjs_resolve},
     special_cases::special_cases,
@@ -77,7 +94,7 @@ use super::{
         parse::{webpack_runtime, WebpackRuntime, WebpackRuntimeVc},
         WebpackChunkAssetReference, WebpackEntryAssetReference, WebpackRuntimeAssetReference,
     },
-    EcmascriptModuleAssetType,
+    EcmascriptModuleAssetType, EcmascriptOptionsVc,
 };
 use crate::{
     analyzer::{graph::EvalContext, imports::Reexport, ModuleValue},
@@ -87,7 +104,9 @@ use crate::{
     references::{
         cjs::{
             CjsRequireAssetReferenceVc, CjsRequireCacheAccess, CjsRequireResolveAssetReferenceVc,
+            CjsRequireUnresolvable, UnresolvableRequire,
         },
+        constant_condition::{ConstantCondition, ConstantConditionValue},
         esm::{module_id::EsmModuleIdAssetReferenceVc, EsmBindingVc, EsmExportsVc},
     },
     EcmascriptInputTransformsVc,
@@ -98,6 +117,9 @@ pub struct AnalyzeEcmascriptModuleResult {
     pub references: AssetReferencesVc,
     pub code_generation: CodeGenerateablesVc,
     pub exports: EcmascriptExportsVc,
+    /// The module uses a direct `eval`, so its bindings must not be renamed
+    /// and it must not be scope hoisted.
+    pub uses_dynamic_scope: bool,
 }
 
 /// A temporary analysis result builder to pass around, to be turned into an
@@ -106,6 +128,7 @@ pub(crate) struct AnalyzeEcmascriptModuleResultBuilder {
     references: Vec<AssetReferenceVc>,
     code_gens: Vec<CodeGenerateableVc>,
     exports: EcmascriptExports,
+    uses_dynamic_scope: bool,
 }
 
 impl AnalyzeEcmascriptModuleResultBuilder {
@@ -114,6 +137,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             references: Vec::new(),
             code_gens: Vec::new(),
             exports: EcmascriptExports::None,
+            uses_dynamic_scope: false,
         }
     }
 
@@ -138,12 +162,18 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.exports = exports;
     }
 
+    /// Marks the module as using a direct `eval`.
+    pub fn set_uses_dynamic_scope(&mut self, uses_dynamic_scope: bool) {
+        self.uses_dynamic_scope = uses_dynamic_scope;
+    }
+
     /// Builds the final analysis result.
     pub fn build(self) -> AnalyzeEcmascriptModuleResultVc {
         AnalyzeEcmascriptModuleResultVc::cell(AnalyzeEcmascriptModuleResult {
             references: AssetReferencesVc::cell(self.references),
             code_generation: CodeGenerateablesVc::cell(self.code_gens),
             exports: self.exports.into(),
+            uses_dynamic_scope: self.uses_dynamic_scope,
         })
     }
 }
@@ -166,9 +196,11 @@ pub(crate) async fn analyze_ecmascript_module(
     origin: ResolveOriginVc,
     ty: Value<EcmascriptModuleAssetType>,
     transforms: EcmascriptInputTransformsVc,
+    options: EcmascriptOptionsVc,
     environment: EnvironmentVc,
 ) -> Result<AnalyzeEcmascriptModuleResultVc> {
     let mut analysis = AnalyzeEcmascriptModuleResultBuilder::new();
+    let options = options.await?;
     let path = source.path();
 
     let is_typescript = match &*ty {
@@ -205,6 +237,7 @@ pub(crate) async fn analyze_ecmascript_module(
             eval_context,
             comments,
             source_map,
+            type_exports,
             ..
         } => {
             let mut import_references = Vec::new();
@@ -264,6 +297,18 @@ pub(crate) async fn analyze_ecmascript_module(
                 });
             });
 
+            if !options.css_in_js_tags.is_empty() {
+                let templates = find_css_templates(program, &options.css_in_js_tags, eval_context);
+                for (index, template) in templates.into_iter().enumerate() {
+                    analysis.add_reference(CssInJsAssetReferenceVc::new(
+                        source,
+                        index,
+                        template.css,
+                        AstPathVc::cell(template.ast_path),
+                    ));
+                }
+            }
+
             let handler = Handler::with_emitter(
                 true,
                 false,
@@ -284,11 +329,35 @@ pub(crate) async fn analyze_ecmascript_module(
                 GLOBALS.set(globals, || {
                     let var_graph = create_graph(program, eval_context);
 
-                    for (src, annotations) in eval_context.imports.references() {
+                    let dynamic_code = find_dynamic_code(program, eval_context.unresolved_mark);
+                    // Only a direct eval can access the bindings of the module
+                    let direct_evals = dynamic_code
+                        .iter()
+                        .filter(|code| code.kind == DynamicCodeKind::DirectEval)
+                        .collect::<Vec<_>>();
+                    for DynamicCode { kind, span } in direct_evals.iter() {
+                        DiagnosticBuilder::new(
+                            &handler,
+                            Level::Note,
+                            &format!(
+                                "{kind} evaluates code at runtime, so the module is not scope \
+                                 hoisted and its bindings are not renamed"
+                            ),
+                        )
+                        .set_span(*span)
+                        .code(DiagnosticId::Lint(
+                            errors::failed_to_analyse::ecmascript::DYNAMIC_CODE.to_string(),
+                        ))
+                        .emit();
+                    }
+                    analysis.set_uses_dynamic_scope(!direct_evals.is_empty());
+
+                    for (i, (src, annotations)) in eval_context.imports.references().enumerate() {
                         let r = EsmAssetReferenceVc::new(
                             origin,
                             RequestVc::parse(Value::new(src.to_string().into())),
                             Value::new(annotations.clone()),
+                            eval_context.imports.has_bindings(i),
                         );
                         import_references.push(r);
                         analysis.add_reference(r);
@@ -380,10 +449,17 @@ pub(crate) async fn analyze_ecmascript_module(
                 }
             }
 
-            let exports = if !esm_exports.is_empty() || !esm_star_exports.is_empty() {
+            // Interfaces can be merged with values of the same name
+            let mut type_exports = type_exports.clone();
+            type_exports.retain(|name| !esm_exports.contains_key(name));
+            let exports = if !esm_exports.is_empty()
+                || !esm_star_exports.is_empty()
+                || !type_exports.is_empty()
+            {
                 let esm_exports: EsmExportsVc = EsmExports {
                     exports: esm_exports,
                     star_exports: esm_star_exports,
+                    type_exports,
                 }
                 .into();
                 analysis.add_code_gen(esm_exports);
@@ -391,11 +467,37 @@ pub(crate) async fn analyze_ecmascript_module(
             } else if let Program::Module(_) = program {
                 EcmascriptExports::None
             } else {
-                EcmascriptExports::CommonJs
+                let reexports = GLOBALS.set(globals, || {
+                    find_cjs_reexports(program, eval_context.unresolved_mark)
+                });
+                let mut star_exports = Vec::new();
+                let mut exports = BTreeSet::new();
+                for reexport in reexports {
+                    match reexport {
+                        CjsReexport::All { request } => {
+                            star_exports.push(CjsAssetReferenceVc::new(
+                                origin,
+                                RequestVc::parse(Value::new(request.into())),
+                            ));
+                        }
+                        CjsReexport::Named { export, .. } => {
+                            exports.insert(export);
+                        }
+                    }
+                }
+                EcmascriptExports::CommonJs(
+                    CjsExports {
+                        star_exports,
+                        exports,
+                    }
+                    .cell(),
+                )
             };
 
             analysis.set_exports(exports);
 
+            let magic_comments = collect_magic_comments(program, comments);
+
             #[allow(clippy::too_many_arguments)]
             fn handle_call_boxed<
                 'a,
@@ -414,6 +516,7 @@ pub(crate) async fn analyze_ecmascript_module(
                 is_typescript: bool,
                 analysis: &'a mut AnalyzeEcmascriptModuleResultBuilder,
                 environment: EnvironmentVc,
+                magic_comments: &'a HashMap<Span, MagicComments>,
             ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
                 Box::pin(handle_call(
                     handler,
@@ -428,6 +531,7 @@ pub(crate) async fn analyze_ecmascript_module(
                     is_typescript,
                     analysis,
                     environment,
+                    magic_comments,
                 ))
             }
 
@@ -448,6 +552,7 @@ pub(crate) async fn analyze_ecmascript_module(
                 is_typescript: bool,
                 analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
                 environment: EnvironmentVc,
+                magic_comments: &HashMap<Span, MagicComments>,
             ) -> Result<()> {
                 fn explain_args(args: &[JsValue]) -> (String, String) {
                     JsValue::explain_args(args, 10, 2)
@@ -469,6 +574,7 @@ pub(crate) async fn analyze_ecmascript_module(
                                 is_typescript,
                                 analysis,
                                 environment,
+                                magic_comments,
                             )
                             .await?;
                         }
@@ -495,6 +601,7 @@ pub(crate) async fn analyze_ecmascript_module(
                                             is_typescript,
                                             analysis,
                                             environment,
+                                            magic_comments,
                                         )
                                         .await?;
                                     }
@@ -503,8 +610,12 @@ pub(crate) async fn analyze_ecmascript_module(
                         }
                     }
                     JsValue::WellKnownFunction(WellKnownFunctionKind::Import) => {
+                        let magic_comments = magic_comments.get(&span);
+                        if matches!(magic_comments, Some(m) if m.ignore) {
+                            return Ok(());
+                        }
                         let args = linked_args().await?;
-                        if args.len() == 1 {
+                        if args.len() == 1 || args.len() == 2 {
                             let pat = js_value_to_pattern(&args[0]);
                             if !pat.has_constant_parts() {
                                 let (args, hints) = explain_args(&args);
@@ -517,10 +628,30 @@ pub(crate) async fn analyze_ecmascript_module(
                                     ),
                                 )
                             }
+                            if let Some((dir, filter)) = context_from_pattern(&pat) {
+                                analysis.add_reference(RequireContextAssetReferenceVc::new(
+                                    origin,
+                                    dir,
+                                    true,
+                                    filter,
+                                    Value::new(RequireContextKind::Import),
+                                    AstPathVc::cell(ast_path.to_vec()),
+                                ));
+                                return Ok(());
+                            }
+                            let annotations = args
+                                .get(1)
+                                .map(ImportAnnotations::from_dynamic_import_options)
+                                .unwrap_or_default();
+                            let chunk_name = magic_comments.and_then(|m| m.chunk_name.clone());
+                            let loading_hint = magic_comments.and_then(|m| m.loading_hint);
                             analysis.add_reference(EsmAsyncAssetReferenceVc::new(
                                 origin,
                                 RequestVc::parse(Value::new(pat)),
                                 AstPathVc::cell(ast_path.to_vec()),
+                                Value::new(annotations),
+                                OptionStringVc::cell(chunk_name),
+                                ChunkLoadingHintOptionVc::cell(loading_hint),
                             ));
                             return Ok(());
                         }
@@ -535,21 +666,84 @@ pub(crate) async fn analyze_ecmascript_module(
                     }
                     JsValue::WellKnownFunction(WellKnownFunctionKind::Require) => {
                         let args = linked_args().await?;
-                        if args.len() == 1 {
-                            let pat = js_value_to_pattern(&args[0]);
-                            if !pat.has_constant_parts() {
-                                let (args, hints) = explain_args(&args);
+                        if let [JsValue::Array(_, deps), ..] = &args[..] {
+                            if args.len() <= 3 {
+                                analyze_amd_require(
+                                    analysis, origin, handler, span, ast_path, deps,
+                                );
+                                return Ok(());
+                            }
+                        }
+                        if let Some(unresolvable) = UnresolvableRequire::classify(&args) {
+                            // The call is replaced with a stub that throws a descriptive error,
+                            // instead of failing with an unknown module at runtime.
+                            let reason = unresolvable.reason();
+                            let (args, hints) = explain_args(&args);
+                            let message = format!(
+                                "require({args}) is not statically analyse-able, {reason}{hints}",
+                            );
+                            let code = errors::failed_to_analyse::ecmascript::REQUIRE.to_string();
+                            // Dependencies can't be fixed by the user and often only require
+                            // dynamically in code paths that aren't taken, so these stay
+                            // warnings.
+                            let in_node_modules = source
+                                .path()
+                                .await?
+                                .path
+                                .split('/')
+                                .any(|segment| segment == "node_modules");
+                            if in_node_modules {
                                 handler.span_warn_with_code(
                                     span,
-                                    &format!("require({args}) is very dynamic{hints}",),
-                                    DiagnosticId::Lint(
-                                        errors::failed_to_analyse::ecmascript::REQUIRE.to_string(),
-                                    ),
-                                )
+                                    &message,
+                                    DiagnosticId::Lint(code),
+                                );
+                            } else {
+                                handler.span_err_with_code(
+                                    span,
+                                    &message,
+                                    DiagnosticId::Error(code),
+                                );
                             }
-                            analysis.add_reference(CjsRequireAssetReferenceVc::new(
+                            analysis.add_code_gen(
+                                CjsRequireUnresolvable {
+                                    kind: unresolvable,
+                                    path: AstPathVc::cell(ast_path.to_vec()),
+                                }
+                                .cell(),
+                            );
+                            return Ok(());
+                        }
+                        let pat = js_value_to_pattern(&args[0]);
+                        if let Some((dir, filter)) = context_from_pattern(&pat) {
+                            // A partially known request, e.g. `require("./locales/" + lang)`,
+                            // can load any of the matching modules.
+                            analysis.add_reference(RequireContextAssetReferenceVc::new(
                                 origin,
-                                RequestVc::parse(Value::new(pat)),
+                                dir,
+                                true,
+                                filter,
+                                Value::new(RequireContextKind::Require),
+                                AstPathVc::cell(ast_path.to_vec()),
+                            ));
+                            return Ok(());
+                        }
+                        analysis.add_reference(CjsRequireAssetReferenceVc::new(
+                            origin,
+                            RequestVc::parse(Value::new(pat)),
+                            AstPathVc::cell(ast_path.to_vec()),
+                        ));
+                    }
+                    JsValue::WellKnownFunction(WellKnownFunctionKind::RequireContext) => {
+                        let args = linked_args().await?;
+                        if let Some((dir, include_subdirs, filter)) = require_context_options(&args)
+                        {
+                            analysis.add_reference(RequireContextAssetReferenceVc::new(
+                                origin,
+                                dir,
+                                include_subdirs,
+                                filter,
+                                Value::new(RequireContextKind::Context),
                                 AstPathVc::cell(ast_path.to_vec()),
                             ));
                             return Ok(());
@@ -557,9 +751,11 @@ pub(crate) async fn analyze_ecmascript_module(
                         let (args, hints) = explain_args(&args);
                         handler.span_warn_with_code(
                             span,
-                            &format!("require({args}) is not statically analyse-able{hints}",),
+                            &format!(
+                                "require.context({args}) is not statically analyse-able{hints}",
+                            ),
                             DiagnosticId::Error(
-                                errors::failed_to_analyse::ecmascript::REQUIRE.to_string(),
+                                errors::failed_to_analyse::ecmascript::REQUIRE_CONTEXT.to_string(),
                             ),
                         )
                     }
@@ -587,7 +783,11 @@ pub(crate) async fn analyze_ecmascript_module(
                                         errors::failed_to_analyse::ecmascript::REQUIRE_RESOLVE
                                             .to_string(),
                                     ),
-                                )
+                                );
+                                // Without any constant parts the request would match every
+                                // module, so the call is left to be resolved at runtime instead
+                                // of being rewritten.
+                                return Ok(());
                             }
                             analysis.add_reference(CjsRequireResolveAssetReferenceVc::new(
                                 origin,
@@ -1061,7 +1261,8 @@ pub(crate) async fn analyze_ecmascript_module(
             }
 
             let cache = Mutex::new(LinkCache::new());
-            let linker = |value| value_visitor(source, origin, value, environment);
+            let process_env = ProcessEnvConfig::from_options(&options);
+            let linker = |value| value_visitor(source, origin, value, environment, &process_env);
             let effects = take(&mut var_graph.effects);
             let link_value = |value| link(&var_graph, value, &linker, &cache);
             // There can be many references to import.meta, but only the first should hoist
@@ -1096,6 +1297,7 @@ pub(crate) async fn analyze_ecmascript_module(
                             is_typescript,
                             &mut analysis,
                             environment,
+                            &magic_comments,
                         )
                         .await?;
                     }
@@ -1127,6 +1329,7 @@ pub(crate) async fn analyze_ecmascript_module(
                             is_typescript,
                             &mut analysis,
                             environment,
+                            &magic_comments,
                         )
                         .await?;
                     }
@@ -1170,6 +1373,55 @@ pub(crate) async fn analyze_ecmascript_module(
 
                         analysis.add_code_gen(ImportMetaRefVc::new(AstPathVc::cell(ast_path)));
                     }
+                    Effect::Worker {
+                        func,
+                        input,
+                        options,
+                        ast_path,
+                        span: _,
+                        url_span,
+                    } => {
+                        if matches!(magic_comments.get(&url_span), Some(m) if m.ignore) {
+                            continue;
+                        }
+                        let shared = match link_value(func).await? {
+                            JsValue::WellKnownFunction(WellKnownFunctionKind::Worker) => false,
+                            JsValue::WellKnownFunction(WellKnownFunctionKind::SharedWorker) => true,
+                            _ => continue,
+                        };
+                        let input = link_value(input).await?;
+                        let options = link_value(options).await?;
+                        analysis.add_reference(WorkerAssetReferenceVc::new(
+                            origin,
+                            RequestVc::parse(Value::new(js_value_to_pattern(&input))),
+                            AstPathVc::cell(ast_path),
+                            shared,
+                            is_module_worker(&options),
+                        ));
+                    }
+                    Effect::Conditional {
+                        condition,
+                        ast_path,
+                        span: _,
+                    } => {
+                        // The linked values of reassigned bindings aren't reliable, e.g. `i++`
+                        // isn't part of them
+                        if !options.fold_constant_conditions
+                            || var_graph.depends_on_reassigned(&condition)
+                        {
+                            continue;
+                        }
+                        let condition = link_value(condition).await?;
+                        if let Some(value) = ConstantConditionValue::from_js_value(&condition) {
+                            analysis.add_code_gen(
+                                ConstantCondition {
+                                    value,
+                                    path: AstPathVc::cell(ast_path),
+                                }
+                                .cell(),
+                            );
+                        }
+                    }
                 }
             }
         }
@@ -1277,6 +1529,53 @@ fn analyze_amd_define_with_deps(
     id: Option<&str>,
     deps: &[JsValue],
 ) {
+    let requests = analyze_amd_dependencies(analysis, origin, handler, span, deps);
+
+    if id.is_some() {
+        handler.span_warn_with_code(
+            span,
+            "passing an ID to AMD define() is not yet fully supported",
+            DiagnosticId::Lint(errors::failed_to_analyse::ecmascript::AMD_DEFINE.to_string()),
+        );
+    }
+
+    analysis.add_code_gen(AmdDefineWithDependenciesCodeGenVc::new(
+        requests,
+        origin,
+        AstPathVc::cell(ast_path.to_vec()),
+        AmdDefineFactoryType::Function,
+    ));
+}
+
+/// Handles the AMD `require(deps, callback, errback)` form. The dependencies
+/// are loaded synchronously, so the errback is never called.
+fn analyze_amd_require(
+    analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
+    origin: ResolveOriginVc,
+    handler: &Handler,
+    span: Span,
+    ast_path: &[AstParentKind],
+    deps: &[JsValue],
+) {
+    let requests = analyze_amd_dependencies(analysis, origin, handler, span, deps);
+
+    analysis.add_code_gen(AmdDefineWithDependenciesCodeGenVc::new(
+        requests,
+        origin,
+        AstPathVc::cell(ast_path.to_vec()),
+        AmdDefineFactoryType::Callback,
+    ));
+}
+
+/// Adds references for the statically known dependencies of an AMD `define()`
+/// or `require()` call.
+fn analyze_amd_dependencies(
+    analysis: &mut AnalyzeEcmascriptModuleResultBuilder,
+    origin: ResolveOriginVc,
+    handler: &Handler,
+    span: Span,
+    deps: &[JsValue],
+) -> Vec<AmdDefineDependencyElement> {
     let mut requests = Vec::new();
     for dep in deps {
         if let Some(dep) = dep.as_str() {
@@ -1285,13 +1584,8 @@ fn analyze_amd_define_with_deps(
                     requests.push(AmdDefineDependencyElement::Exports);
                 }
                 "require" => {
-                    handler.span_warn_with_code(
-                        span,
-                        "using \"require\" as dependency in an AMD define() is not yet supported",
-                        DiagnosticId::Error(
-                            errors::failed_to_analyse::ecmascript::AMD_DEFINE.to_string(),
-                        ),
-                    );
+                    // Calls of the `require` parameter of the factory are analyzed like calls
+                    // of the free `require`.
                     requests.push(AmdDefineDependencyElement::Require);
                 }
                 "module" => {
@@ -1309,26 +1603,25 @@ fn analyze_amd_define_with_deps(
                 // TODO(alexkirsz) It'd be best to highlight the argument's span, but
                 // `JsValue`s do not keep a hold of their original span.
                 span,
-                "unsupported AMD define() dependency element form",
+                "unsupported AMD dependency element form",
                 DiagnosticId::Error(errors::failed_to_analyse::ecmascript::AMD_DEFINE.to_string()),
             );
         }
     }
+    requests
+}
 
-    if id.is_some() {
-        handler.span_warn_with_code(
-            span,
-            "passing an ID to AMD define() is not yet fully supported",
-            DiagnosticId::Lint(errors::failed_to_analyse::ecmascript::AMD_DEFINE.to_string()),
-        );
-    }
-
-    analysis.add_code_gen(AmdDefineWithDependenciesCodeGenVc::new(
-        requests,
-        origin,
-        AstPathVc::cell(ast_path.to_vec()),
-        AmdDefineFactoryType::Function,
-    ));
+/// Checks for `{ type: "module" }` in the options of `new Worker()`.
+fn is_module_worker(options: &JsValue) -> bool {
+    let JsValue::Object(_, parts) = options else {
+        return false;
+    };
+    parts.iter().any(|part| match part {
+        ObjectPart::KeyValue(key, value) => {
+            key.as_str() == Some("type") && value.as_str() == Some("module")
+        }
+        ObjectPart::Spread(_) => false,
+    })
 }
 
 /// Used to generate the "root" path to a __filename/__dirname/import.meta.url
@@ -1351,8 +1644,10 @@ async fn value_visitor(
     origin: ResolveOriginVc,
     v: JsValue,
     environment: EnvironmentVc,
+    process_env: &ProcessEnvConfig,
 ) -> Result<(JsValue, bool)> {
-    let (mut v, modified) = value_visitor_inner(source, origin, v, environment).await?;
+    let (mut v, modified) =
+        value_visitor_inner(source, origin, v, environment, process_env).await?;
     v.normalize_shallow();
     Ok((v, modified))
 }
@@ -1362,6 +1657,7 @@ async fn value_visitor_inner(
     origin: ResolveOriginVc,
     v: JsValue,
     environment: EnvironmentVc,
+    process_env: &ProcessEnvConfig,
 ) -> Result<(JsValue, bool)> {
     Ok((
         match v {
@@ -1421,6 +1717,32 @@ async fn value_visitor_inner(
             JsValue::FreeVar(FreeVarKind::Object) => {
                 JsValue::WellKnownObject(WellKnownObjectKind::GlobalObject)
             }
+            JsValue::FreeVar(FreeVarKind::Worker) => {
+                JsValue::WellKnownFunction(WellKnownFunctionKind::Worker)
+            }
+            JsValue::FreeVar(FreeVarKind::SharedWorker) => {
+                JsValue::WellKnownFunction(WellKnownFunctionKind::SharedWorker)
+            }
+            JsValue::FreeVar(FreeVarKind::ImportMeta) => {
+                JsValue::WellKnownObject(WellKnownObjectKind::ImportMeta)
+            }
+            JsValue::FreeVar(FreeVarKind::Url) => {
+                JsValue::WellKnownFunction(WellKnownFunctionKind::URLConstructor)
+            }
+            // The same URL as the `import.meta` binding at runtime
+            JsValue::Member(
+                _,
+                box JsValue::WellKnownObject(WellKnownObjectKind::ImportMeta),
+                box ref prop,
+            ) if prop.as_str() == Some("url") => match as_abs_path(source.path()).await?.as_str() {
+                Some(path) => Url::parse(&format!("file://{path}"))
+                    .map(JsValue::Url)
+                    .unwrap_or_else(|_| JsValue::Unknown(Some(Arc::new(v)), "url not parseable")),
+                None => JsValue::Unknown(Some(Arc::new(v)), "the module path is not known"),
+            },
+            JsValue::FreeVar(FreeVarKind::Other(ref name)) if global_function(name).is_some() => {
+                JsValue::WellKnownFunction(global_function(name).unwrap())
+            }
             JsValue::FreeVar(_) => JsValue::Unknown(Some(Arc::new(v)), "unknown global"),
             JsValue::Module(ModuleValue {
                 module: ref name, ..
@@ -1475,7 +1797,7 @@ async fn value_visitor_inner(
                 "cross function analyzing is not yet supported",
             ),
             _ => {
-                let (mut v, mut modified) = replace_well_known(v, environment).await?;
+                let (mut v, mut modified) = replace_well_known(v, environment, process_env).await?;
                 modified = replace_builtin(&mut v) || modified;
                 return Ok((v, modified));
             }
@@ -1580,7 +1902,7 @@ fn as_parent_path(ast_path: &AstNodePath<AstParentNodeRef<'_>>) -> Vec<AstParent
     ast_path.iter().map(|n| n.kind()).collect()
 }
 
-fn for_each_ident_in_decl(decl: &Decl, f: &mut impl FnMut(String)) {
+pub(crate) fn for_each_ident_in_decl(decl: &Decl, f: &mut impl FnMut(String)) {
     match decl {
         Decl::Class(ClassDecl { ident, .. }) | Decl::Fn(FnDecl { ident, .. }) => {
             f(ident.sym.to_string());
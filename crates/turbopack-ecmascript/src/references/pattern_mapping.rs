@@ -4,7 +4,11 @@ use swc_core::{
     ecma::ast::{Expr, Lit},
     quote,
 };
-use turbo_tasks::{debug::ValueDebug, primitives::StringVc, Value, ValueToString};
+use turbo_tasks::{
+    debug::ValueDebug,
+    primitives::{OptionStringVc, StringVc},
+    Value, ValueToString,
+};
 use turbopack_core::{
     chunk::{ChunkableAssetVc, ChunkingContextVc, FromChunkableAsset, ModuleId},
     issue::{code_gen::CodeGenerationIssue, IssueSeverity},
@@ -111,6 +115,7 @@ impl PatternMappingVc {
         context: ChunkingContextVc,
         resolve_result: ResolveResultVc,
         resolve_type: Value<ResolveType>,
+        chunk_name: OptionStringVc,
     ) -> Result<PatternMappingVc> {
         let result = resolve_result.await?;
         let asset = match &*result {
@@ -161,7 +166,7 @@ impl PatternMappingVc {
         if let Some(chunkable) = ChunkableAssetVc::resolve_from(asset).await? {
             if *resolve_type == ResolveType::EsmAsync {
                 if let Some((loader, _)) =
-                    EcmascriptChunkItemVc::from_async_asset(context, chunkable).await?
+                    EcmascriptChunkItemVc::from_async_asset(context, chunkable, chunk_name).await?
                 {
                     return Ok(PatternMappingVc::cell(PatternMapping::Single(
                         loader.id().await?.clone_value(),
@@ -0,0 +1,634 @@
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::ast::{
+        CallExpr, Callee, Expr, ExprOrSpread, Ident, KeyValueProp, ObjectLit, Prop, PropName,
+        PropOrSpread,
+    },
+    quote,
+};
+use turbo_tasks::{
+    debug::ValueDebugFormat,
+    primitives::{OptionStringVc, StringVc},
+    trace::TraceRawVcs,
+    Value, ValueToString, ValueToStringVc,
+};
+use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemPathVc};
+use turbopack_core::{
+    chunk::{ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc},
+    reference::{AssetReference, AssetReferenceVc},
+    resolve::{
+        origin::ResolveOriginVc, parse::RequestVc, pattern::Pattern, ResolveResult, ResolveResultVc,
+    },
+};
+
+use super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::Cjs};
+use crate::{
+    analyzer::{ConstantValue, JsValue},
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    helper::{EcmascriptHelperAssetVc, HelperDefinition},
+    references::AstPathVc,
+    resolve::{cjs_resolve, esm_resolve},
+    utils::module_id_to_lit,
+};
+
+/// How the modules of a [RequireContextAssetReference] are loaded.
+#[derive(
+    ValueDebugFormat, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, TraceRawVcs, Copy, Clone,
+)]
+pub enum RequireContextKind {
+    /// `require.context("./locales", true, /\.json$/)`, which evaluates to a
+    /// function that requires a module by its key.
+    Context,
+    /// `require("./locales/" + lang + ".json")`
+    Require,
+    /// `import("./locales/" + lang + ".json")`
+    Import,
+}
+
+/// The keys of a context, mapped to the requests of the modules they load.
+#[turbo_tasks::value(transparent)]
+pub struct RequireContextMap(IndexMap<String, String>);
+
+/// A reference to all modules in a directory that match a filter, like a
+/// webpack "context". The call is rewritten to look up the module by its key
+/// at runtime.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct RequireContextAssetReference {
+    pub origin: ResolveOriginVc,
+    /// The directory relative to the origin, ending with a `/`, e.g.
+    /// `./locales/`.
+    pub dir: String,
+    /// Whether modules in subdirectories of `dir` are included.
+    pub include_subdirs: bool,
+    /// A regex the keys of the modules have to match.
+    pub filter: String,
+    pub kind: RequireContextKind,
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl RequireContextAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        origin: ResolveOriginVc,
+        dir: String,
+        include_subdirs: bool,
+        filter: String,
+        kind: Value<RequireContextKind>,
+        path: AstPathVc,
+    ) -> Self {
+        Self::cell(RequireContextAssetReference {
+            origin,
+            dir,
+            include_subdirs,
+            filter,
+            kind: kind.into_value(),
+            path,
+        })
+    }
+
+    /// The keys of the context, mapped to the requests of the modules.
+    #[turbo_tasks::function]
+    async fn map(self) -> Result<RequireContextMapVc> {
+        let this = self.await?;
+        let dir = this
+            .origin
+            .origin_path()
+            .parent()
+            .try_join(&this.dir)
+            .await?;
+        let Some(dir) = *dir else {
+            return Ok(RequireContextMapVc::cell(IndexMap::new()));
+        };
+        let files = list_files(dir, this.include_subdirs).await?;
+        let filter = Regex::new(&this.filter)?;
+        Ok(RequireContextMapVc::cell(context_map(
+            &files,
+            &this.dir,
+            this.kind,
+            this.include_subdirs,
+            &filter,
+        )))
+    }
+}
+
+impl RequireContextAssetReference {
+    fn resolve(&self, request: &str) -> (RequestVc, ResolveResultVc) {
+        let request = RequestVc::parse(Value::new(Pattern::Constant(request.to_string())));
+        let result = match self.kind {
+            RequireContextKind::Context | RequireContextKind::Require => {
+                cjs_resolve(self.origin, request)
+            }
+            RequireContextKind::Import => esm_resolve(self.origin, request),
+        };
+        (request, result)
+    }
+
+    /// The module of [context_fn], which is shared by all contexts of a
+    /// chunk.
+    fn helper(&self) -> Option<EcmascriptHelperAssetVc> {
+        let definition = HelperDefinition::new("require_context", &context_fn())?;
+        Some(EcmascriptHelperAssetVc::from_definition(
+            self.origin.origin_path().root(),
+            &definition,
+        ))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for RequireContextAssetReference {
+    #[turbo_tasks::function]
+    async fn resolve_reference(self_vc: RequireContextAssetReferenceVc) -> Result<ResolveResultVc> {
+        let this = self_vc.await?;
+        let map = self_vc.map().await?;
+        let mut results = map
+            .values()
+            .collect::<IndexSet<_>>()
+            .into_iter()
+            .map(|request| this.resolve(request).1)
+            .collect::<Vec<_>>();
+        if let Some(helper) = this.helper() {
+            results.push(ResolveResult::Single(helper.into(), Vec::new()).cell());
+        }
+        Ok(ResolveResultVc::alternatives(results))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for RequireContextAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "require.context {} {} {}",
+            self.dir,
+            if self.include_subdirs {
+                "(recursive)"
+            } else {
+                "(non-recursive)"
+            },
+            self.filter,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for RequireContextAssetReference {}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for RequireContextAssetReference {
+    #[turbo_tasks::function]
+    async fn code_generation(
+        self_vc: RequireContextAssetReferenceVc,
+        context: ChunkingContextVc,
+    ) -> Result<CodeGenerationVc> {
+        let this = self_vc.await?;
+        let map = self_vc.map().await?;
+
+        let mut props = Vec::new();
+        for (key, request) in map.iter() {
+            let (request, result) = this.resolve(request);
+            let pm = PatternMappingVc::resolve_request(
+                request,
+                this.origin,
+                context,
+                result,
+                Value::new(Cjs),
+                OptionStringVc::cell(None),
+            )
+            .await?;
+            // Modules that can't be resolved are left out, requiring them throws at
+            // runtime.
+            if let PatternMapping::Single(id) = &*pm {
+                props.push(PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp {
+                    key: PropName::Str(key.as_str().into()),
+                    value: box module_id_to_lit(id),
+                })));
+            }
+        }
+        let map = Expr::Object(ObjectLit {
+            span: DUMMY_SP,
+            props,
+        });
+        let create_context = match this.helper() {
+            Some(helper) => helper.expr(context).await?,
+            None => context_fn(),
+        };
+
+        let mut visitors = Vec::new();
+        let path = &this.path.await?;
+        match this.kind {
+            RequireContextKind::Context => {
+                visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+                    *expr = context_expr(
+                        create_context.clone(),
+                        map.clone(),
+                        "__turbopack_require__",
+                    );
+                }));
+            }
+            RequireContextKind::Require => {
+                visitors.push(
+                    create_visitor!(exact path, visit_mut_call_expr(call_expr: &mut CallExpr) {
+                        call_expr.callee = Callee::Expr(box context_expr(
+                            create_context.clone(),
+                            map.clone(),
+                            "__turbopack_require__",
+                        ));
+                    }),
+                );
+            }
+            RequireContextKind::Import => {
+                visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+                    if let Expr::Call(call_expr) = expr {
+                        let key = match std::mem::take(&mut call_expr.args).into_iter().next() {
+                            Some(ExprOrSpread { expr, spread: None }) => *expr,
+                            _ => quote!("undefined" as Expr),
+                        };
+                        *expr = quote!(
+                            "Promise.resolve($key).then($context)" as Expr,
+                            key: Expr = key,
+                            context: Expr = context_expr(
+                                create_context.clone(),
+                                map.clone(),
+                                "__turbopack_import__",
+                            )
+                        );
+                    }
+                }));
+            }
+        }
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
+
+/// A function that loads the module of a key from a `map` with `load`, like
+/// the function that webpack's `require.context` returns. It also has
+/// `keys()` and `resolve(key)` methods.
+fn context_fn() -> Expr {
+    quote!(
+        "(function (map, load) {
+            function resolve(key) {
+                if (!Object.prototype.hasOwnProperty.call(map, key)) {
+                    const e = new Error(\"Cannot find module '\" + key + \"'\");
+                    e.code = 'MODULE_NOT_FOUND';
+                    throw e;
+                }
+                return map[key];
+            }
+            function context(key) {
+                return load(resolve(key));
+            }
+            context.keys = () => Object.keys(map);
+            context.resolve = resolve;
+            return context;
+        })" as Expr
+    )
+}
+
+/// Calls the function that creates a context, see [context_fn].
+fn context_expr(context_fn: Expr, map: Expr, load: &str) -> Expr {
+    quote!(
+        "$context_fn($map, $load)" as Expr,
+        context_fn: Expr = context_fn,
+        map: Expr = map,
+        load = Ident::new(load.into(), DUMMY_SP)
+    )
+}
+
+/// The paths of the files in `dir`, relative to it. Subdirectories are only
+/// read with `include_subdirs`.
+async fn list_files(dir: FileSystemPathVc, include_subdirs: bool) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut queue = vec![(dir, String::new())];
+    while let Some((dir, prefix)) = queue.pop() {
+        let DirectoryContent::Entries(entries) = &*dir.read_dir().await? else {
+            continue;
+        };
+        for (name, entry) in entries.iter() {
+            match entry {
+                DirectoryEntry::File(_) => files.push(format!("{prefix}{name}")),
+                DirectoryEntry::Directory(path) if include_subdirs => {
+                    queue.push((*path, format!("{prefix}{name}/")))
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Maps the keys of the `files` in `dir` to their requests. Keys are relative
+/// to `dir` for [RequireContextKind::Context], and the full request
+/// otherwise, since that's what the key is compared to at runtime.
+///
+/// Like in webpack, a file can also be loaded without its extension. Files are
+/// included when any of their keys matches `filter`.
+fn context_map(
+    files: &[String],
+    dir: &str,
+    kind: RequireContextKind,
+    include_subdirs: bool,
+    filter: &Regex,
+) -> IndexMap<String, String> {
+    let key_prefix = match kind {
+        RequireContextKind::Context => "./",
+        RequireContextKind::Require | RequireContextKind::Import => dir,
+    };
+    let mut map = IndexMap::new();
+    for file in files {
+        if !include_subdirs && file.contains('/') {
+            continue;
+        }
+        let key = format!("{key_prefix}{file}");
+        let file_name_start = key.rfind('/').map_or(0, |i| i + 1);
+        let stem = match key[file_name_start..].rfind('.') {
+            Some(0) | None => None,
+            Some(i) => Some(key[..file_name_start + i].to_string()),
+        };
+        let keys = [Some(key), stem].into_iter().flatten().collect::<Vec<_>>();
+        if keys.iter().any(|key| filter.is_match(key)) {
+            for key in keys {
+                map.entry(key).or_insert_with(|| format!("{dir}{file}"));
+            }
+        }
+    }
+    map.sort_keys();
+    map
+}
+
+/// The directory and filter of a context for a partially known request, e.g.
+/// `"./locales/" + lang + ".json"`. The directory is the constant prefix up to
+/// the last `/`. Returns `None` when the request doesn't start with a relative
+/// directory or has alternatives.
+pub fn context_from_pattern(pattern: &Pattern) -> Option<(String, String)> {
+    let Pattern::Concatenation(parts) = pattern else {
+        return None;
+    };
+    let Some(Pattern::Constant(first)) = parts.first() else {
+        return None;
+    };
+    if !first.starts_with("./") && !first.starts_with("../") {
+        return None;
+    }
+    let dir = &first[..first.rfind('/')? + 1];
+    let mut filter = "^".to_string();
+    for part in parts {
+        match part {
+            Pattern::Constant(s) => filter.push_str(&regex::escape(s)),
+            Pattern::Dynamic => filter.push_str(".*"),
+            Pattern::Alternatives(_) | Pattern::Concatenation(_) => return None,
+        }
+    }
+    filter.push('$');
+    Some((dir.to_string(), filter))
+}
+
+/// The directory, `useSubdirectories` flag and filter of the linked arguments
+/// of `require.context(directory, useSubdirectories = true, regExp =
+/// /^\.\/.*$/)`. Returns `None` when they aren't known at build time.
+pub fn require_context_options(args: &[JsValue]) -> Option<(String, bool, String)> {
+    let dir = args.first()?.as_str()?;
+    if dir != "." && dir != ".." && !dir.starts_with("./") && !dir.starts_with("../") {
+        return None;
+    }
+    let dir = if dir.ends_with('/') {
+        dir.to_string()
+    } else {
+        format!("{dir}/")
+    };
+    let include_subdirs = match args.get(1) {
+        None | Some(JsValue::Constant(ConstantValue::True)) => true,
+        Some(JsValue::Constant(ConstantValue::False)) => false,
+        _ => return None,
+    };
+    let filter = match args.get(2) {
+        None => r"^\./.*$".to_string(),
+        Some(JsValue::Constant(ConstantValue::Regex(exp, flags))) => regex_from_js(exp, flags),
+        _ => return None,
+    };
+    // The `mode` argument, only synchronous contexts are supported
+    match args.get(3) {
+        None => {}
+        Some(mode) if mode.as_str() == Some("sync") => {}
+        _ => return None,
+    }
+    Regex::new(&filter).ok()?;
+    Some((dir, include_subdirs, filter))
+}
+
+/// Converts a JavaScript regex literal into the Rust regex syntax. Only the
+/// `i`, `m` and `s` flags affect matching a single key.
+fn regex_from_js(exp: &str, flags: &str) -> String {
+    let mut regex = String::new();
+    let flags = flags
+        .chars()
+        .filter(|flag| matches!(*flag, 'i' | 'm' | 's'))
+        .collect::<String>();
+    if !flags.is_empty() {
+        regex.push_str(&format!("(?{flags})"));
+    }
+    // `\/` is a common escape in JavaScript, but not allowed by the regex crate
+    let mut chars = exp.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            regex.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('/') => regex.push('/'),
+            Some(escaped) => {
+                regex.push('\\');
+                regex.push(escaped);
+            }
+            None => regex.push('\\'),
+        }
+    }
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use regex::Regex;
+    use turbopack_core::resolve::pattern::Pattern;
+
+    use super::{context_from_pattern, context_map, require_context_options, RequireContextKind};
+    use crate::analyzer::{ConstantValue, JsValue};
+
+    /// The files of the fixture directory, relative to it.
+    fn fixture_files() -> Vec<String> {
+        fn walk(dir: &Path, prefix: &str, files: &mut Vec<String>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let entry = entry.unwrap();
+                let name = entry.file_name().into_string().unwrap();
+                if entry.file_type().unwrap().is_dir() {
+                    walk(&entry.path(), &format!("{prefix}{name}/"), files);
+                } else {
+                    files.push(format!("{prefix}{name}"));
+                }
+            }
+        }
+        let mut files = Vec::new();
+        walk(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/require_context/locales"),
+            "",
+            &mut files,
+        );
+        files
+    }
+
+    fn keys(
+        dir: &str,
+        kind: RequireContextKind,
+        include_subdirs: bool,
+        filter: &str,
+    ) -> Vec<(String, String)> {
+        context_map(
+            &fixture_files(),
+            dir,
+            kind,
+            include_subdirs,
+            &Regex::new(filter).unwrap(),
+        )
+        .into_iter()
+        .collect()
+    }
+
+    fn pair(key: &str, request: &str) -> (String, String) {
+        (key.to_string(), request.to_string())
+    }
+
+    #[test]
+    fn concatenated_request() {
+        let pattern = Pattern::Concatenation(vec![
+            Pattern::Constant("./locales/".to_string()),
+            Pattern::Dynamic,
+            Pattern::Constant(".json".to_string()),
+        ]);
+        let (dir, filter) = context_from_pattern(&pattern).unwrap();
+        assert_eq!(dir, "./locales/");
+        assert_eq!(filter, r"^\./locales/.*\.json$");
+        assert_eq!(
+            keys(&dir, RequireContextKind::Require, true, &filter),
+            vec![
+                pair("./locales/de", "./locales/de.json"),
+                pair("./locales/de.json", "./locales/de.json"),
+                pair("./locales/en", "./locales/en.json"),
+                pair("./locales/en.json", "./locales/en.json"),
+                pair("./locales/extra/fr", "./locales/extra/fr.json"),
+                pair("./locales/extra/fr.json", "./locales/extra/fr.json"),
+            ]
+        );
+
+        // Not a relative directory
+        let pattern = Pattern::Concatenation(vec![
+            Pattern::Constant("locales/".to_string()),
+            Pattern::Dynamic,
+        ]);
+        assert_eq!(context_from_pattern(&pattern), None);
+    }
+
+    #[test]
+    fn extensionless_request() {
+        let pattern = Pattern::Concatenation(vec![
+            Pattern::Constant("./locales/e".to_string()),
+            Pattern::Dynamic,
+        ]);
+        let (dir, filter) = context_from_pattern(&pattern).unwrap();
+        assert_eq!(
+            keys(&dir, RequireContextKind::Import, true, &filter),
+            vec![
+                pair("./locales/en", "./locales/en.json"),
+                pair("./locales/en.json", "./locales/en.json"),
+                pair("./locales/extra/fr", "./locales/extra/fr.json"),
+                pair("./locales/extra/fr.json", "./locales/extra/fr.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn require_context() {
+        let args = [
+            "./locales".into(),
+            JsValue::Constant(ConstantValue::False),
+            JsValue::Constant(ConstantValue::Regex(r"\.json$".into(), "".into())),
+        ];
+        let (dir, include_subdirs, filter) = require_context_options(&args).unwrap();
+        assert_eq!(dir, "./locales/");
+        assert!(!include_subdirs);
+        assert_eq!(
+            keys(&dir, RequireContextKind::Context, include_subdirs, &filter),
+            vec![
+                pair("./de", "./locales/de.json"),
+                pair("./de.json", "./locales/de.json"),
+                pair("./en", "./locales/en.json"),
+                pair("./en.json", "./locales/en.json"),
+            ]
+        );
+
+        // Recursive with the default filter
+        let (dir, include_subdirs, filter) =
+            require_context_options(&["./locales".into()]).unwrap();
+        assert!(include_subdirs);
+        assert_eq!(
+            keys(&dir, RequireContextKind::Context, include_subdirs, &filter)
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>(),
+            vec![
+                "./README",
+                "./README.md",
+                "./de",
+                "./de.json",
+                "./en",
+                "./en.json",
+                "./extra/fr",
+                "./extra/fr.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn require_context_options_from_js() {
+        let regex = |exp: &str, flags: &str| {
+            JsValue::Constant(ConstantValue::Regex(exp.into(), flags.into()))
+        };
+        assert_eq!(
+            require_context_options(&[
+                "../locales/".into(),
+                JsValue::Constant(ConstantValue::True),
+                regex(r"^\.\/[a-z]+\.JSON$", "ig"),
+            ]),
+            Some((
+                "../locales/".to_string(),
+                true,
+                r"(?i)^\./[a-z]+\.JSON$".to_string()
+            ))
+        );
+        // Not a relative directory
+        assert_eq!(require_context_options(&["locales".into()]), None);
+        // Unknown flag
+        assert_eq!(
+            require_context_options(&["./locales".into(), JsValue::Unknown(None, "")]),
+            None
+        );
+        // Lazy contexts are not supported
+        assert_eq!(
+            require_context_options(&[
+                "./locales".into(),
+                JsValue::Constant(ConstantValue::True),
+                regex(r"\.json$", ""),
+                "lazy".into(),
+            ]),
+            None
+        );
+    }
+}
@@ -6,7 +6,18 @@ use turbopack_core::resolve::parse::RequestVc;
 /// Creates a IIFE expression that throws a "Cannot find module" error for the
 /// given request string
 pub fn throw_module_not_found_expr(request: &str) -> Expr {
-    let message = format!("Cannot find module '{request}'");
+    throw_module_not_found_error_expr(format!("Cannot find module '{request}'"))
+}
+
+/// Creates a IIFE expression that throws a "Cannot find module" error for a
+/// request that can't be determined at build time. `reason` explains why.
+pub fn throw_unresolvable_request_expr(reason: &str) -> Expr {
+    throw_module_not_found_error_expr(format!(
+        "Cannot find module, the request can't be determined at build time: {reason}"
+    ))
+}
+
+fn throw_module_not_found_error_expr(message: String) -> Expr {
     quote!(
         "(() => { const e = new Error($message); e.code = 'MODULE_NOT_FOUND'; throw e; })()"
             as Expr,
@@ -24,3 +35,59 @@ pub async fn request_to_string(request: RequestVc) -> Result<StringVc> {
             .unwrap_or_else(|| "unknown".into()),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::DUMMY_SP,
+        ecma::{
+            ast::{Expr, ExprStmt, Module, ModuleItem, Stmt},
+            codegen::{text_writer::JsWriter, Emitter},
+        },
+        testing::run_test,
+    };
+
+    use super::throw_unresolvable_request_expr;
+
+    fn to_js(expr: Expr) -> String {
+        run_test(false, |cm, _handler| {
+            let module = Module {
+                span: DUMMY_SP,
+                body: vec![ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                    span: DUMMY_SP,
+                    expr: box expr,
+                }))],
+                shebang: None,
+            };
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_module(&module).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn unresolvable_request_stub() {
+        let code = to_js(throw_unresolvable_request_expr(
+            "the request is fully dynamic",
+        ));
+        assert!(
+            code.contains(
+                "Cannot find module, the request can't be determined at build time: the request \
+                 is fully dynamic"
+            ),
+            "{code}"
+        );
+        assert!(code.contains("MODULE_NOT_FOUND"), "{code}");
+        assert!(code.contains("throw e"), "{code}");
+    }
+}
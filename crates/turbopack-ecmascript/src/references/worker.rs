@@ -0,0 +1,302 @@
+use anyhow::Result;
+use swc_core::ecma::ast::{Expr, ExprOrSpread, NewExpr};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetContentVc},
+    chunk::{
+        ChunkVc, ChunkableAsset, ChunkableAssetReference, ChunkableAssetReferenceVc,
+        ChunkingContextVc, ChunkingType, ChunkingTypeOptionVc,
+    },
+    reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
+    resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
+};
+
+use crate::{
+    chunk::{output_format::OutputFormat, EcmascriptChunkPlaceableVc, EcmascriptChunkVc},
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    references::AstPathVc,
+    resolve::esm_resolve,
+};
+
+/// A reference to the entry of a web worker, created from
+/// `new Worker(new URL("./worker.js", import.meta.url))` or the same with
+/// `SharedWorker`.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct WorkerAssetReference {
+    pub origin: ResolveOriginVc,
+    pub request: RequestVc,
+    pub path: AstPathVc,
+    pub shared: bool,
+    /// True when the worker is created with `{ type: "module" }`, which
+    /// requires the worker entry to be output as ESM.
+    pub module: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl WorkerAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        origin: ResolveOriginVc,
+        request: RequestVc,
+        path: AstPathVc,
+        shared: bool,
+        module: bool,
+    ) -> Self {
+        Self::cell(WorkerAssetReference {
+            origin,
+            request,
+            path,
+            shared,
+            module,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for WorkerAssetReference {
+    /// Resolves to the [WorkerEntryAsset] of the worker, so the entry is
+    /// chunked into a chunk of its own.
+    #[turbo_tasks::function]
+    async fn resolve_reference(&self) -> Result<ResolveResultVc> {
+        let module = self.module;
+        Ok(esm_resolve(self.origin, self.request)
+            .await?
+            .map(
+                |asset| async move {
+                    Ok(
+                        match EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+                            Some(placeable) => WorkerEntryAssetVc::new(placeable, module).into(),
+                            None => asset,
+                        },
+                    )
+                },
+                |reference| async move { Ok(reference) },
+            )
+            .await?
+            .cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for WorkerAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "new {}{} {}",
+            if self.module { "module " } else { "" },
+            if self.shared {
+                "SharedWorker"
+            } else {
+                "Worker"
+            },
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for WorkerAssetReference {
+    #[turbo_tasks::function]
+    fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
+        // The worker is loaded by the browser from its own entry, not by the
+        // referencing chunk group.
+        ChunkingTypeOptionVc::cell(Some(ChunkingType::Separate))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for WorkerAssetReference {
+    /// Replaces `new URL(...)` with the URL of the chunk of the worker entry.
+    #[turbo_tasks::function]
+    async fn code_generation(
+        self_vc: WorkerAssetReferenceVc,
+        context: ChunkingContextVc,
+    ) -> Result<CodeGenerationVc> {
+        let this = self_vc.await?;
+        let mut visitors = Vec::new();
+
+        let assets = self_vc.resolve_reference().primary_assets().await?;
+        let entry = match assets.first() {
+            Some(asset) => WorkerEntryAssetVc::resolve_from(asset).await?,
+            None => None,
+        };
+        if let Some(entry) = entry {
+            let chunk_path = &*entry.as_chunkable_asset().as_chunk(context).path().await?;
+            if let Some(path) = context.output_root().await?.get_path_to(chunk_path) {
+                let url = format!("/{path}");
+                let path = &this.path.await?;
+                visitors.push(
+                    create_visitor!(exact path, visit_mut_new_expr(new_expr: &mut NewExpr) {
+                        rewrite_worker_url(new_expr, &url);
+                    }),
+                );
+            }
+        }
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
+
+/// Replaces the `new URL(...)` argument of `new Worker(new URL(...))` with
+/// `url`.
+fn rewrite_worker_url(new_expr: &mut NewExpr, url: &str) {
+    if let Some(ExprOrSpread { spread: None, expr }) =
+        new_expr.args.as_mut().and_then(|args| args.first_mut())
+    {
+        *expr = box Expr::Lit(url.into());
+    }
+}
+
+/// The format of the chunk of a worker entry. Module workers load the chunk
+/// as an ES module, which imports the other chunks of its chunk group. Classic
+/// workers can't load other chunks, so the chunk is a standalone script.
+fn worker_output_format(module: bool) -> OutputFormat {
+    if module {
+        OutputFormat::Esm
+    } else {
+        OutputFormat::Iife { global_name: None }
+    }
+}
+
+/// The entry of a web worker, which is chunked into an evaluated chunk of its
+/// own that the browser loads when the worker is created.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct WorkerEntryAsset {
+    asset: EcmascriptChunkPlaceableVc,
+    /// See [WorkerAssetReference::module].
+    module: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl WorkerEntryAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(asset: EcmascriptChunkPlaceableVc, module: bool) -> Self {
+        Self::cell(WorkerEntryAsset { asset, module })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for WorkerEntryAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.asset.path()
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.asset.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.asset.references()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for WorkerEntryAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(&self, context: ChunkingContextVc) -> ChunkVc {
+        EcmascriptChunkVc::new_evaluate_with_format(
+            context,
+            self.asset,
+            None,
+            Value::new(worker_output_format(self.module)),
+        )
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName},
+        ecma::{
+            ast::{EsVersion, NewExpr},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            visit::{VisitMut, VisitMutWith},
+        },
+        testing::run_test,
+    };
+
+    use super::{rewrite_worker_url, worker_output_format};
+    use crate::chunk::output_format::OutputFormat;
+
+    /// Rewrites the worker URL of all `new` expressions of `src` to `url`, if
+    /// any, and prints the result minified.
+    fn rewrite(src: &str, url: Option<&str>) -> String {
+        struct Rewrite<'a>(&'a str);
+
+        impl VisitMut for Rewrite<'_> {
+            fn visit_mut_new_expr(&mut self, new_expr: &mut NewExpr) {
+                rewrite_worker_url(new_expr, self.0);
+            }
+        }
+
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let mut module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            if let Some(url) = url {
+                module.visit_mut_with(&mut Rewrite(url));
+            }
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm, "\n", &mut bytes, None),
+            };
+            emitter.emit_module(&module).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    fn assert_rewritten(src: &str, expected: &str) {
+        assert_eq!(
+            rewrite(src, Some("/_chunks/worker.js")),
+            rewrite(expected, None)
+        );
+    }
+
+    #[test]
+    fn worker_url() {
+        assert_rewritten(
+            r#"new Worker(new URL("./worker.js", import.meta.url));"#,
+            r#"new Worker("/_chunks/worker.js");"#,
+        );
+    }
+
+    #[test]
+    fn module_worker_url() {
+        assert_rewritten(
+            r#"new SharedWorker(new URL("./worker.js", import.meta.url), { type: "module" });"#,
+            r#"new SharedWorker("/_chunks/worker.js", { type: "module" });"#,
+        );
+    }
+
+    #[test]
+    fn output_format() {
+        assert!(matches!(worker_output_format(true), OutputFormat::Esm));
+        assert!(matches!(
+            worker_output_format(false),
+            OutputFormat::Iife { global_name: None }
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 pub mod node_native_binding;
 
 use anyhow::Result;
+use turbo_tasks::Value;
 use turbopack_core::resolve::{
     handle_resolve_error,
     options::{ConditionValue, ResolveIntoPackage, ResolveOptions, ResolveOptionsVc},
@@ -9,29 +10,51 @@ use turbopack_core::resolve::{
     ResolveResultVc,
 };
 
-#[turbo_tasks::function]
-pub async fn apply_esm_specific_options(options: ResolveOptionsVc) -> Result<ResolveOptionsVc> {
-    let mut options: ResolveOptions = options.await?.clone_value();
-    for item in options.into_package.iter_mut() {
-        match item {
-            ResolveIntoPackage::ExportsField { conditions, .. } => {
-                conditions.insert("import".to_string(), ConditionValue::Set);
-                conditions.insert("require".to_string(), ConditionValue::Unset);
-            }
-            ResolveIntoPackage::MainField(_) | ResolveIntoPackage::Default(_) => {}
+/// How a module is referenced, which selects the conditions of the `exports`
+/// field of a package that match.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum ImportKind {
+    /// `import ... from "..."` and `export ... from "..."`.
+    Import,
+    /// `import("...")`.
+    DynamicImport,
+    /// `require("...")` and similar CommonJS references.
+    Require,
+}
+
+impl ImportKind {
+    /// The values of the `import` and `require` conditions. Nested condition
+    /// objects and the `default` fallback are evaluated with them too.
+    pub fn conditions(&self) -> [(&'static str, ConditionValue); 2] {
+        let is_require = matches!(self, ImportKind::Require);
+        [
+            ("import", ConditionValue::from(!is_require)),
+            ("require", ConditionValue::from(is_require)),
+        ]
+    }
+
+    fn request_type(&self) -> &'static str {
+        match self {
+            ImportKind::Import => "esm request",
+            ImportKind::DynamicImport => "dynamic import request",
+            ImportKind::Require => "commonjs request",
         }
     }
-    Ok(options.into())
 }
 
 #[turbo_tasks::function]
-pub async fn apply_cjs_specific_options(options: ResolveOptionsVc) -> Result<ResolveOptionsVc> {
+pub async fn apply_import_kind_options(
+    options: ResolveOptionsVc,
+    kind: Value<ImportKind>,
+) -> Result<ResolveOptionsVc> {
     let mut options: ResolveOptions = options.await?.clone_value();
     for item in options.into_package.iter_mut() {
         match item {
             ResolveIntoPackage::ExportsField { conditions, .. } => {
-                conditions.insert("import".to_string(), ConditionValue::Unset);
-                conditions.insert("require".to_string(), ConditionValue::Set);
+                for (condition, value) in kind.conditions() {
+                    conditions.insert(condition.to_string(), value);
+                }
             }
             ResolveIntoPackage::MainField(_) | ResolveIntoPackage::Default(_) => {}
         }
@@ -40,15 +63,34 @@ pub async fn apply_cjs_specific_options(options: ResolveOptionsVc) -> Result<Res
 }
 
 #[turbo_tasks::function]
-pub async fn esm_resolve(origin: ResolveOriginVc, request: RequestVc) -> Result<ResolveResultVc> {
-    let options = apply_esm_specific_options(origin.resolve_options());
-    specific_resolve(origin, request, options, "esm request").await
+pub fn apply_esm_specific_options(options: ResolveOptionsVc) -> ResolveOptionsVc {
+    apply_import_kind_options(options, Value::new(ImportKind::Import))
+}
+
+#[turbo_tasks::function]
+pub fn apply_cjs_specific_options(options: ResolveOptionsVc) -> ResolveOptionsVc {
+    apply_import_kind_options(options, Value::new(ImportKind::Require))
 }
 
+/// Resolves `request` with the `exports` conditions that match `kind`.
 #[turbo_tasks::function]
-pub async fn cjs_resolve(origin: ResolveOriginVc, request: RequestVc) -> Result<ResolveResultVc> {
-    let options = apply_cjs_specific_options(origin.resolve_options());
-    specific_resolve(origin, request, options, "commonjs request").await
+pub async fn resolve_with_kind(
+    origin: ResolveOriginVc,
+    request: RequestVc,
+    kind: Value<ImportKind>,
+) -> Result<ResolveResultVc> {
+    let options = apply_import_kind_options(origin.resolve_options(), kind);
+    specific_resolve(origin, request, options, kind.request_type()).await
+}
+
+#[turbo_tasks::function]
+pub fn esm_resolve(origin: ResolveOriginVc, request: RequestVc) -> ResolveResultVc {
+    resolve_with_kind(origin, request, Value::new(ImportKind::Import))
+}
+
+#[turbo_tasks::function]
+pub fn cjs_resolve(origin: ResolveOriginVc, request: RequestVc) -> ResolveResultVc {
+    resolve_with_kind(origin, request, Value::new(ImportKind::Require))
 }
 
 async fn specific_resolve(
@@ -61,3 +103,78 @@ async fn specific_resolve(
 
     handle_resolve_error(result, request_type, origin, request, options).await
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+    use turbopack_core::resolve::{exports::ExportsField, options::ConditionValue};
+
+    use super::ImportKind;
+
+    /// The paths that `.` of `exports` resolves to when imported with `kind`.
+    fn resolve(exports: serde_json::Value, kind: ImportKind) -> Vec<String> {
+        let exports = ExportsField::try_from(&exports).unwrap();
+        let mut conditions: BTreeMap<String, ConditionValue> = [("node", ConditionValue::Set)]
+            .into_iter()
+            .chain(kind.conditions())
+            .map(|(condition, value)| (condition.to_string(), value))
+            .collect();
+        conditions.insert("browser".to_string(), ConditionValue::Unset);
+        exports
+            .results(".", &conditions, &ConditionValue::Unset)
+            .unwrap()
+    }
+
+    #[test]
+    fn dual_package() {
+        let exports = json!({
+            "import": "./index.mjs",
+            "require": "./index.cjs",
+        });
+        assert_eq!(
+            resolve(exports.clone(), ImportKind::Import),
+            ["./index.mjs"]
+        );
+        assert_eq!(
+            resolve(exports.clone(), ImportKind::DynamicImport),
+            ["./index.mjs"]
+        );
+        assert_eq!(resolve(exports, ImportKind::Require), ["./index.cjs"]);
+    }
+
+    #[test]
+    fn default_fallback() {
+        let exports = json!({
+            ".": {
+                "import": "./index.mjs",
+                "default": "./index.js",
+            }
+        });
+        assert_eq!(
+            resolve(exports.clone(), ImportKind::Import),
+            ["./index.mjs"]
+        );
+        assert_eq!(resolve(exports, ImportKind::Require), ["./index.js"]);
+    }
+
+    #[test]
+    fn nested_conditions() {
+        let exports = json!({
+            ".": {
+                "browser": "./browser.js",
+                "node": {
+                    "import": "./node.mjs",
+                    "require": {
+                        "production": "./node.prod.cjs",
+                        "default": "./node.cjs",
+                    },
+                },
+                "default": "./index.js",
+            }
+        });
+        assert_eq!(resolve(exports.clone(), ImportKind::Import), ["./node.mjs"]);
+        assert_eq!(resolve(exports, ImportKind::Require), ["./node.cjs"]);
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+use turbo_tasks::primitives::BoolVc;
+use turbo_tasks_fs::{glob::Glob, FileJsonContent, FileSystemPathVc};
+use turbopack_core::resolve::{find_context_file, FindContextFileResult};
+
+/// Determines whether the module at `path` has side effects according to the
+/// `sideEffects` field of the closest package.json. Modules without such a
+/// field are assumed to have side effects.
+#[turbo_tasks::function]
+pub async fn module_has_side_effects(path: FileSystemPathVc) -> Result<BoolVc> {
+    let FindContextFileResult::Found(package_json, _) =
+        &*find_context_file(path.parent(), "package.json").await?
+    else {
+        return Ok(BoolVc::cell(true));
+    };
+    let FileJsonContent::Content(package) = &*package_json.read_json().await? else {
+        return Ok(BoolVc::cell(true));
+    };
+    let package_path = package_json.parent().await?;
+    let path = path.await?;
+    let Some(rel_path) = package_path.get_path_to(&path) else {
+        return Ok(BoolVc::cell(true));
+    };
+    Ok(BoolVc::cell(
+        side_effects_field_matches(&package["sideEffects"], rel_path).unwrap_or(true),
+    ))
+}
+
+/// Evaluates the `sideEffects` field of a package.json for the module at
+/// `rel_path`, which is relative to the package root.
+///
+/// The field can be a boolean or an array of globs. Globs without a `/` match
+/// the file name in any directory, like `*.css`. Globs starting with `!`
+/// exclude previously matched modules. The last matching glob wins, modules
+/// that aren't matched by any glob have no side effects.
+///
+/// Returns `None` when the field is missing or invalid.
+pub fn side_effects_field_matches(field: &JsonValue, rel_path: &str) -> Option<bool> {
+    match field {
+        JsonValue::Bool(value) => Some(*value),
+        JsonValue::Array(globs) => {
+            let mut result = false;
+            for glob in globs {
+                let glob = glob.as_str()?;
+                let (negated, glob) = match glob.strip_prefix('!') {
+                    Some(glob) => (true, glob),
+                    None => (false, glob),
+                };
+                let glob = glob.strip_prefix("./").unwrap_or(glob);
+                let glob = if glob.contains('/') {
+                    Glob::parse(glob)
+                } else {
+                    Glob::parse(&format!("**/{glob}"))
+                };
+                if glob.ok()?.execute(rel_path) {
+                    result = !negated;
+                }
+            }
+            Some(result)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::side_effects_field_matches;
+
+    #[test]
+    fn boolean() {
+        assert_eq!(
+            side_effects_field_matches(&json!(false), "index.js"),
+            Some(false)
+        );
+        assert_eq!(
+            side_effects_field_matches(&json!(true), "index.js"),
+            Some(true)
+        );
+        assert_eq!(side_effects_field_matches(&json!(null), "index.js"), None);
+        assert_eq!(side_effects_field_matches(&json!("*.js"), "index.js"), None);
+    }
+
+    #[test]
+    fn globs() {
+        let field = json!(["*.css", "./src/polyfill.js"]);
+        assert_eq!(side_effects_field_matches(&field, "style.css"), Some(true));
+        assert_eq!(
+            side_effects_field_matches(&field, "dist/theme/style.css"),
+            Some(true)
+        );
+        assert_eq!(
+            side_effects_field_matches(&field, "src/polyfill.js"),
+            Some(true)
+        );
+        assert_eq!(side_effects_field_matches(&field, "index.js"), Some(false));
+        assert_eq!(
+            side_effects_field_matches(&field, "src/index.js"),
+            Some(false)
+        );
+        assert_eq!(
+            side_effects_field_matches(&field, "lib/polyfill.js"),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn negated_globs() {
+        let field = json!(["src/**/*", "!src/**/*.js", "src/setup.js"]);
+        assert_eq!(
+            side_effects_field_matches(&field, "src/style.css"),
+            Some(true)
+        );
+        assert_eq!(
+            side_effects_field_matches(&field, "src/utils/index.js"),
+            Some(false)
+        );
+        assert_eq!(
+            side_effects_field_matches(&field, "src/setup.js"),
+            Some(true)
+        );
+        assert_eq!(side_effects_field_matches(&field, "index.js"), Some(false));
+    }
+}
@@ -0,0 +1,101 @@
+//! Detection of modules with top-level await.
+//!
+//! The evaluation of such a module only completes asynchronously, so it's
+//! emitted as an async module, see `asyncModule` in the runtime.
+
+use swc_core::ecma::{
+    ast::{
+        ArrowExpr, AwaitExpr, Constructor, ForOfStmt, Function, GetterProp, Program, SetterProp,
+    },
+    visit::{Visit, VisitWith},
+};
+
+/// Whether `program` uses `await` outside of functions, i.e. an `await`
+/// expression or a `for await` loop. Only modules can have top-level await.
+pub fn has_top_level_await(program: &Program) -> bool {
+    let Program::Module(module) = program else {
+        return false;
+    };
+    let mut visitor = TopLevelAwait(false);
+    module.visit_with(&mut visitor);
+    visitor.0
+}
+
+struct TopLevelAwait(bool);
+
+impl Visit for TopLevelAwait {
+    fn visit_await_expr(&mut self, _: &AwaitExpr) {
+        self.0 = true;
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        if stmt.is_await {
+            self.0 = true;
+        } else {
+            stmt.visit_children_with(self);
+        }
+    }
+
+    // An `await` in a function belongs to the function
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_constructor(&mut self, _: &Constructor) {}
+
+    fn visit_getter_prop(&mut self, _: &GetterProp) {}
+
+    fn visit_setter_prop(&mut self, _: &SetterProp) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName},
+        ecma::{ast::EsVersion, parser::parse_file_as_program},
+        testing::run_test,
+    };
+
+    use super::has_top_level_await;
+
+    fn is_async(src: &str) -> bool {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let program = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            Ok(has_top_level_await(&program))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn top_level_await() {
+        assert!(is_async(
+            "import { load } from './db';\nexport const db = await load();"
+        ));
+        assert!(is_async("if (x) { const { a } = await import('./a'); }"));
+        assert!(is_async(
+            "export const items = [];\nfor await (const item of stream()) items.push(item);"
+        ));
+    }
+
+    #[test]
+    fn nested_await() {
+        assert!(!is_async(
+            "export async function load() { return await fetch('/'); }"
+        ));
+        assert!(!is_async(
+            "export const load = async () => { for await (const x of y) {} };"
+        ));
+        assert!(!is_async(
+            "export default { async get() { await x; } };\nclass A { async m() { await y; } }"
+        ));
+        assert!(!is_async("export const x = 1;"));
+    }
+}
@@ -0,0 +1,451 @@
+use std::{collections::HashSet, mem::replace};
+
+use swc_core::{
+    common::{Mark, Spanned, DUMMY_SP},
+    ecma::{
+        ast::*,
+        atoms::JsWord,
+        visit::{Visit, VisitWith},
+    },
+};
+
+use crate::utils::unparen;
+
+/// Converts a CommonJS module whose exports are statically known to ESM, so
+/// its exports can be tree shaken like the exports of an ESM module:
+///
+/// ```js
+/// exports.a = 1;         var a = 1;
+/// exports.b = f();  ->   var b = f();
+///                        export { a, b };
+///                        export default { a, b };
+/// ```
+///
+/// A single `module.exports = { a: 1, b }` with an object literal is
+/// converted the same way. The default export is the object that was
+/// `module.exports` before, which default imports of the module receive.
+///
+/// ESM is always strict mode code, so only modules with a `"use strict"`
+/// directive are converted, as sloppy mode code (e.g. `with` or assignments to
+/// undeclared variables) could behave differently.
+///
+/// The module is left unchanged when its exports can't be determined
+/// statically, i.e. when `exports` or `module` is used in any other way (e.g.
+/// read, passed to a function or assigned in a nested statement), when an
+/// export is assigned more than once, or when the module uses top level
+/// `this` or ESM syntax. Returns whether the module was converted.
+///
+/// Note: This requires running `resolver` with `unresolved_mark` **before**
+/// running this.
+pub fn cjs_to_esm(program: &mut Program, unresolved_mark: Mark) -> bool {
+    let stmts = match &*program {
+        Program::Module(module) => {
+            let mut stmts = Vec::with_capacity(module.body.len());
+            for item in module.body.iter() {
+                match item {
+                    ModuleItem::Stmt(stmt) => stmts.push(stmt),
+                    ModuleItem::ModuleDecl(_) => return false,
+                }
+            }
+            stmts
+        }
+        Program::Script(script) => script.body.iter().collect(),
+    };
+    if !has_use_strict(&stmts) {
+        return false;
+    }
+
+    let mut usage = CjsUsage {
+        unresolved_mark,
+        in_function: false,
+        dynamic: false,
+    };
+    let mut assigned = Vec::new();
+    let mut has_module_exports = false;
+    for (index, stmt) in stmts.iter().enumerate() {
+        match export_assignment(stmt, unresolved_mark) {
+            Some(ExportAssignment::Export(name, value)) => {
+                value.visit_with(&mut usage);
+                assigned.push((index, vec![(name, value)]));
+            }
+            Some(ExportAssignment::ModuleExports(props)) => {
+                for (_, value) in props.iter() {
+                    value.visit_with(&mut usage);
+                }
+                has_module_exports = true;
+                assigned.push((index, props));
+            }
+            None => stmt.visit_with(&mut usage),
+        }
+    }
+    // `module.exports = {}` replaces all exports assigned before
+    if usage.dynamic || assigned.is_empty() || (has_module_exports && assigned.len() > 1) {
+        return false;
+    }
+    let mut exported = HashSet::new();
+    for (name, _) in assigned.iter().flat_map(|(_, exports)| exports) {
+        if !exported.insert(name.clone()) || matches!(&**name, "default" | "__esModule") {
+            return false;
+        }
+    }
+
+    // The exports are declared as variables, which must not shadow other
+    // bindings
+    let mut used = UsedNames(HashSet::new());
+    program.visit_with(&mut used);
+    let mut used = used.0;
+    let mut local_name = |name: &JsWord| {
+        let mut local = name.clone();
+        let mut suffix = 1;
+        while used.contains(&local) {
+            local = format!("{name}_{suffix}").into();
+            suffix += 1;
+        }
+        used.insert(local.clone());
+        Ident::new(local, DUMMY_SP)
+    };
+
+    let mut declarations = assigned.into_iter().peekable();
+    let mut exports = Vec::new();
+    let mut body = Vec::with_capacity(stmts.len() + 2);
+    for (index, stmt) in stmts.into_iter().enumerate() {
+        let Some((_, assigned)) = declarations.next_if(|(assigned, _)| *assigned == index) else {
+            body.push(ModuleItem::Stmt(stmt.clone()));
+            continue;
+        };
+        let mut decls = Vec::with_capacity(assigned.len());
+        for (name, value) in assigned {
+            let local = local_name(&name);
+            decls.push(VarDeclarator {
+                span: DUMMY_SP,
+                name: Pat::Ident(local.clone().into()),
+                init: Some(value),
+                definite: false,
+            });
+            exports.push((name, local));
+        }
+        body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Var(box VarDecl {
+            span: stmt.span(),
+            kind: VarDeclKind::Var,
+            declare: false,
+            decls,
+        }))));
+    }
+
+    body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(
+        NamedExport {
+            span: DUMMY_SP,
+            specifiers: exports
+                .iter()
+                .map(|(name, local)| {
+                    ExportSpecifier::Named(ExportNamedSpecifier {
+                        span: DUMMY_SP,
+                        orig: ModuleExportName::Ident(local.clone()),
+                        exported: (*name != local.sym)
+                            .then(|| ModuleExportName::Ident(Ident::new(name.clone(), DUMMY_SP))),
+                        is_type_only: false,
+                    })
+                })
+                .collect(),
+            src: None,
+            type_only: false,
+            asserts: None,
+        },
+    )));
+    body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
+        ExportDefaultExpr {
+            span: DUMMY_SP,
+            expr: box Expr::Object(ObjectLit {
+                span: DUMMY_SP,
+                props: exports
+                    .into_iter()
+                    .map(|(name, local)| {
+                        box if name == local.sym {
+                            Prop::Shorthand(local)
+                        } else {
+                            Prop::KeyValue(KeyValueProp {
+                                key: PropName::Ident(Ident::new(name, DUMMY_SP)),
+                                value: box Expr::Ident(local),
+                            })
+                        }
+                    })
+                    .map(PropOrSpread::Prop)
+                    .collect(),
+            }),
+        },
+    )));
+
+    let (span, shebang) = match program {
+        Program::Module(module) => (module.span, module.shebang.take()),
+        Program::Script(script) => (script.span, script.shebang.take()),
+    };
+    *program = Program::Module(Module {
+        span,
+        body,
+        shebang,
+    });
+    true
+}
+
+/// Whether the directive prologue of the module contains `"use strict"`.
+fn has_use_strict(stmts: &[&Stmt]) -> bool {
+    stmts
+        .iter()
+        .map_while(|stmt| match stmt {
+            Stmt::Expr(ExprStmt {
+                expr: box Expr::Lit(Lit::Str(Str { value, .. })),
+                ..
+            }) => Some(value),
+            _ => None,
+        })
+        .any(|directive| &**directive == "use strict")
+}
+
+enum ExportAssignment {
+    /// `exports.a = value` or `module.exports.a = value`
+    Export(JsWord, Box<Expr>),
+    /// `module.exports = { a: value, b }`
+    ModuleExports(Vec<(JsWord, Box<Expr>)>),
+}
+
+fn export_assignment(stmt: &Stmt, unresolved_mark: Mark) -> Option<ExportAssignment> {
+    let Stmt::Expr(ExprStmt { expr, .. }) = stmt else {
+        return None;
+    };
+    let Expr::Assign(AssignExpr {
+        op: AssignOp::Assign,
+        left: PatOrExpr::Expr(left) | PatOrExpr::Pat(box Pat::Expr(left)),
+        right,
+        ..
+    }) = unparen(expr) else {
+        return None;
+    };
+    let Expr::Member(MemberExpr { obj, prop, .. }) = unparen(left) else {
+        return None;
+    };
+    let is_free = |expr: &Expr, name: &str| {
+        matches!(
+            unparen(expr),
+            Expr::Ident(Ident { sym, span, .. })
+                if &**sym == name && span.ctxt.outer() == unresolved_mark
+        )
+    };
+    let is_exports = |expr: &Expr| {
+        is_free(expr, "exports")
+            || matches!(
+                unparen(expr),
+                Expr::Member(MemberExpr {
+                    obj,
+                    prop: MemberProp::Ident(Ident { sym, .. }),
+                    ..
+                }) if &**sym == "exports" && is_free(obj, "module")
+            )
+    };
+
+    if is_exports(obj) {
+        return Some(ExportAssignment::Export(prop_name(prop)?, right.clone()));
+    }
+    if !is_free(obj, "module")
+        || !matches!(prop, MemberProp::Ident(Ident { sym, .. }) if &**sym == "exports")
+    {
+        return None;
+    }
+    let Expr::Object(ObjectLit { props, .. }) = unparen(right) else {
+        return None;
+    };
+    props
+        .iter()
+        .map(|prop| match prop {
+            PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp { key, value })) => {
+                let name = match key {
+                    PropName::Ident(Ident { sym, .. }) => sym.clone(),
+                    PropName::Str(Str { value, .. }) => value.clone(),
+                    _ => return None,
+                };
+                (Ident::verify_symbol(&name).is_ok() && &*name != "__proto__")
+                    .then(|| (name, value.clone()))
+            }
+            PropOrSpread::Prop(box Prop::Shorthand(ident)) => {
+                Some((ident.sym.clone(), box Expr::Ident(ident.clone())))
+            }
+            // Spreads, methods, getters and setters
+            _ => None,
+        })
+        .collect::<Option<_>>()
+        .map(ExportAssignment::ModuleExports)
+}
+
+/// The name of the export that `exports.a` or `exports["a"]` assigns to, if
+/// it can be declared as a variable.
+fn prop_name(prop: &MemberProp) -> Option<JsWord> {
+    let name = match prop {
+        MemberProp::Ident(Ident { sym, .. }) => sym,
+        MemberProp::Computed(ComputedPropName {
+            expr: box Expr::Lit(Lit::Str(Str { value, .. })),
+            ..
+        }) => value,
+        _ => return None,
+    };
+    Ident::verify_symbol(name).is_ok().then(|| name.clone())
+}
+
+/// Finds uses of `exports` and `module` and top level `this`, which are
+/// `exports` too, that prevent the conversion.
+struct CjsUsage {
+    unresolved_mark: Mark,
+    in_function: bool,
+    dynamic: bool,
+}
+
+impl Visit for CjsUsage {
+    fn visit_ident(&mut self, ident: &Ident) {
+        if ident.span.ctxt.outer() == self.unresolved_mark
+            && matches!(&*ident.sym, "exports" | "module")
+        {
+            self.dynamic = true;
+        }
+    }
+
+    fn visit_this_expr(&mut self, _: &ThisExpr) {
+        if !self.in_function {
+            self.dynamic = true;
+        }
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        let in_function = replace(&mut self.in_function, true);
+        function.visit_children_with(self);
+        self.in_function = in_function;
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        let in_function = replace(&mut self.in_function, true);
+        class.visit_children_with(self);
+        self.in_function = in_function;
+    }
+}
+
+/// Collects the names of all identifiers, including properties.
+struct UsedNames(HashSet<JsWord>);
+
+impl Visit for UsedNames {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.0.insert(ident.sym.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_script,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::cjs_to_esm;
+
+    /// Returns whether the module was converted and the minified output.
+    fn transform(src: &str) -> (bool, String) {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let script = parse_file_as_script(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Script(script);
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            let converted = cjs_to_esm(&mut program, unresolved_mark);
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok((converted, String::from_utf8(bytes).unwrap()))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn named_exports() {
+        assert_eq!(
+            transform("\"use strict\";\nexports.a = 1;\nexports.b = 2;"),
+            (
+                true,
+                "\"use strict\";var a=1;var b=2;export{a,b};export default{a,b};".to_string()
+            )
+        );
+        // A local binding with the name of an export
+        assert_eq!(
+            transform("\"use strict\";\nconst a = require(\"./a\");\nmodule.exports.a = a + 1;"),
+            (
+                true,
+                "\"use strict\";const a=require(\"./a\");var a_1=a+1;export{a_1 as a};export \
+                 default{a:a_1};"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn module_exports_object() {
+        assert_eq!(
+            transform("\"use strict\";\nfunction b() {}\nmodule.exports = { a: 1, b };"),
+            (
+                true,
+                "\"use strict\";function b(){}var a=1,b_1=b;export{a,b_1 as b};export \
+                 default{a,b:b_1};"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn sloppy_mode() {
+        for src in [
+            "exports.a = 1;",
+            "with (obj) {\n  exports.a = b;\n}",
+            "exports.a = 1;\n\"use strict\";",
+        ] {
+            let (converted, output) = transform(src);
+            assert!(!converted, "{src} was converted to {output}");
+        }
+    }
+
+    #[test]
+    fn dynamic_exports() {
+        for src in [
+            "exports.a = 1;\nexports[name] = 2;",
+            "exports.a = 1;\nconsole.log(exports.a);",
+            "exports.a = 1;\nexports.a = 2;",
+            "if (a) {\n  exports.a = 1;\n}",
+            "module.exports = function () {};",
+            "exports.a = 1;\nmodule.exports = { b: 2 };",
+            "Object.defineProperty(exports, \"__esModule\", { value: true });\nexports.a = 1;",
+            "this.a = 1;",
+        ] {
+            let src = format!("\"use strict\";\n{src}");
+            let (converted, output) = transform(&src);
+            assert!(!converted, "{src} was converted to {output}");
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::{HashMap, HashSet};
+
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::{
+        ast::*,
+        atoms::JsWord,
+        visit::{Visit, VisitMut, VisitMutWith, VisitWith},
+    },
+};
+
+/// Inlines the members of `const enum` declarations at their use sites, e.g.
+/// `Direction.Up` becomes `0`.
+///
+/// Members are evaluated like TypeScript does: members without an initializer
+/// continue the numeric sequence of the previous member, initializers can be
+/// numeric or string literals and arithmetic expressions of those and of
+/// previous members. Enums with a member that can't be evaluated at compile
+/// time are left as regular enums.
+///
+/// Declarations of enums that are fully inlined are removed. Exported enums
+/// and enums that are used in another way than a member access are kept as
+/// regular enums, since other modules can't inline them.
+///
+/// Note: This requires running `resolver` **before** running this and has to
+/// run before the `strip` transform.
+pub fn inline_const_enums(program: &mut Program) {
+    let mut collector = ConstEnumCollector::default();
+    program.visit_with(&mut collector);
+    if collector.enums.is_empty() && collector.non_inlinable.is_empty() {
+        return;
+    }
+
+    let mut inliner = ConstEnumInliner {
+        enums: collector.enums,
+        remaining: HashSet::new(),
+    };
+    program.visit_mut_with(&mut inliner);
+
+    program.visit_mut_with(&mut ConstEnumRemover {
+        inlined: inliner
+            .enums
+            .into_keys()
+            .filter(|id| !inliner.remaining.contains(id))
+            .collect(),
+    });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EnumValue {
+    Number(f64),
+    String(JsWord),
+}
+
+impl EnumValue {
+    fn into_expr(self) -> Expr {
+        match self {
+            EnumValue::Number(value) if value.is_sign_negative() && value != 0.0 => {
+                Expr::Unary(UnaryExpr {
+                    span: DUMMY_SP,
+                    op: UnaryOp::Minus,
+                    arg: box EnumValue::Number(-value).into_expr(),
+                })
+            }
+            EnumValue::Number(value) => Expr::Lit(Lit::Num(Number {
+                span: DUMMY_SP,
+                value,
+                raw: None,
+            })),
+            EnumValue::String(value) => Expr::Lit(Lit::Str(Str {
+                span: DUMMY_SP,
+                value,
+                raw: None,
+            })),
+        }
+    }
+}
+
+type EnumMembers = HashMap<JsWord, EnumValue>;
+
+#[derive(Default)]
+struct ConstEnumCollector {
+    enums: HashMap<Id, EnumMembers>,
+    non_inlinable: HashSet<Id>,
+}
+
+impl Visit for ConstEnumCollector {
+    fn visit_ts_enum_decl(&mut self, decl: &TsEnumDecl) {
+        if !decl.is_const || decl.declare {
+            return;
+        }
+        let id = decl.id.to_id();
+        let mut members = EnumMembers::new();
+        let mut next = Some(0.0);
+        for member in decl.members.iter() {
+            let name = match &member.id {
+                TsEnumMemberId::Ident(ident) => ident.sym.clone(),
+                TsEnumMemberId::Str(str) => str.value.clone(),
+            };
+            let value = match &member.init {
+                Some(init) => self.eval(init, &id, &members),
+                None => next.map(EnumValue::Number),
+            };
+            let Some(value) = value else {
+                self.non_inlinable.insert(id);
+                return;
+            };
+            next = match value {
+                EnumValue::Number(value) => Some(value + 1.0),
+                EnumValue::String(_) => None,
+            };
+            members.insert(name, value);
+        }
+        self.enums.insert(id, members);
+    }
+}
+
+impl ConstEnumCollector {
+    /// Evaluates a member initializer. Returns `None` for values that are not
+    /// known at compile time.
+    fn eval(&self, expr: &Expr, id: &Id, members: &EnumMembers) -> Option<EnumValue> {
+        let value = match expr {
+            Expr::Lit(Lit::Num(num)) => EnumValue::Number(num.value),
+            Expr::Lit(Lit::Str(str)) => EnumValue::String(str.value.clone()),
+            Expr::Tpl(Tpl { exprs, quasis, .. }) if exprs.is_empty() => {
+                EnumValue::String(quasis.first()?.cooked.as_deref()?.into())
+            }
+            Expr::Paren(ParenExpr { expr, .. }) => return self.eval(expr, id, members),
+            Expr::Ident(ident) => members.get(&ident.sym)?.clone(),
+            Expr::Member(member) => {
+                let Expr::Ident(obj) = &*member.obj else {
+                    return None;
+                };
+                let obj = obj.to_id();
+                let members = if &obj == id {
+                    members
+                } else {
+                    self.enums.get(&obj)?
+                };
+                members.get(&member_name(&member.prop)?)?.clone()
+            }
+            Expr::Unary(UnaryExpr { op, arg, .. }) => {
+                let EnumValue::Number(value) = self.eval(arg, id, members)? else {
+                    return None;
+                };
+                EnumValue::Number(match op {
+                    UnaryOp::Minus => -value,
+                    UnaryOp::Plus => value,
+                    UnaryOp::Tilde => !to_int32(value) as f64,
+                    _ => return None,
+                })
+            }
+            Expr::Bin(BinExpr {
+                op, left, right, ..
+            }) => {
+                let left = self.eval(left, id, members)?;
+                let right = self.eval(right, id, members)?;
+                match (left, right) {
+                    (EnumValue::Number(left), EnumValue::Number(right)) => {
+                        EnumValue::Number(eval_numeric_bin(*op, left, right)?)
+                    }
+                    (left, right) if *op == BinaryOp::Add => {
+                        EnumValue::String(format!("{}{}", to_str(&left), to_str(&right)).into())
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        match value {
+            EnumValue::Number(value) if !value.is_finite() => None,
+            value => Some(value),
+        }
+    }
+}
+
+fn eval_numeric_bin(op: BinaryOp, left: f64, right: f64) -> Option<f64> {
+    Some(match op {
+        BinaryOp::Add => left + right,
+        BinaryOp::Sub => left - right,
+        BinaryOp::Mul => left * right,
+        BinaryOp::Div => left / right,
+        BinaryOp::Mod => left % right,
+        BinaryOp::Exp => left.powf(right),
+        BinaryOp::BitOr => (to_int32(left) | to_int32(right)) as f64,
+        BinaryOp::BitAnd => (to_int32(left) & to_int32(right)) as f64,
+        BinaryOp::BitXor => (to_int32(left) ^ to_int32(right)) as f64,
+        BinaryOp::LShift => to_int32(left).wrapping_shl(to_int32(right) as u32) as f64,
+        BinaryOp::RShift => to_int32(left).wrapping_shr(to_int32(right) as u32) as f64,
+        BinaryOp::ZeroFillRShift => {
+            (to_int32(left) as u32).wrapping_shr(to_int32(right) as u32) as f64
+        }
+        _ => return None,
+    })
+}
+
+/// The ToInt32 conversion of JavaScript.
+fn to_int32(value: f64) -> i32 {
+    if !value.is_finite() {
+        return 0;
+    }
+    value.trunc().rem_euclid(4294967296.0) as u32 as i32
+}
+
+fn to_str(value: &EnumValue) -> String {
+    match value {
+        EnumValue::Number(value) => value.to_string(),
+        EnumValue::String(value) => value.to_string(),
+    }
+}
+
+fn member_name(prop: &MemberProp) -> Option<JsWord> {
+    match prop {
+        MemberProp::Ident(ident) => Some(ident.sym.clone()),
+        MemberProp::Computed(ComputedPropName {
+            expr: box Expr::Lit(Lit::Str(str)),
+            ..
+        }) => Some(str.value.clone()),
+        _ => None,
+    }
+}
+
+/// Replaces member accesses of const enums with their values and collects the
+/// enums that are still referenced afterwards.
+struct ConstEnumInliner {
+    enums: HashMap<Id, EnumMembers>,
+    remaining: HashSet<Id>,
+}
+
+impl VisitMut for ConstEnumInliner {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Expr::Member(MemberExpr {
+            obj: box Expr::Ident(obj),
+            prop,
+            ..
+        }) = expr
+        {
+            let value = self
+                .enums
+                .get(&obj.to_id())
+                .zip(member_name(prop))
+                .and_then(|(members, name)| members.get(&name));
+            if let Some(value) = value {
+                *expr = value.clone().into_expr();
+                return;
+            }
+        }
+        expr.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_ident(&mut self, ident: &mut Ident) {
+        let id = ident.to_id();
+        if self.enums.contains_key(&id) {
+            self.remaining.insert(id);
+        }
+    }
+
+    fn visit_mut_ts_enum_decl(&mut self, decl: &mut TsEnumDecl) {
+        // The name of the declaration is not a use of the enum.
+        decl.members.visit_mut_with(self);
+    }
+
+    fn visit_mut_ts_type(&mut self, _: &mut TsType) {
+        // Uses in types are removed by the `strip` transform.
+    }
+
+    fn visit_mut_export_decl(&mut self, export: &mut ExportDecl) {
+        if let Decl::TsEnum(decl) = &export.decl {
+            self.remaining.insert(decl.id.to_id());
+        }
+        export.visit_mut_children_with(self);
+    }
+}
+
+/// Removes the declarations of inlined const enums and turns the remaining
+/// const enums into regular enums, so the `strip` transform keeps them.
+struct ConstEnumRemover {
+    inlined: HashSet<Id>,
+}
+
+impl ConstEnumRemover {
+    fn is_inlined(&self, item: &Stmt) -> bool {
+        matches!(item, Stmt::Decl(Decl::TsEnum(decl)) if self.inlined.contains(&decl.id.to_id()))
+    }
+}
+
+impl VisitMut for ConstEnumRemover {
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.retain(|item| !matches!(item, ModuleItem::Stmt(stmt) if self.is_inlined(stmt)));
+        items.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.retain(|stmt| !self.is_inlined(stmt));
+        stmts.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_ts_enum_decl(&mut self, decl: &mut TsEnumDecl) {
+        if !decl.declare {
+            decl.is_const = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::{parse_file_as_module, Syntax, TsConfig},
+            transforms::{base::resolver, typescript::strip},
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::inline_const_enums;
+
+    fn transform(src: &str) -> String {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let module = parse_file_as_module(
+                &fm,
+                Syntax::Typescript(TsConfig::default()),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Module(module);
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, true));
+            inline_const_enums(&mut program);
+            program.visit_mut_with(&mut strip(top_level_mark));
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn numeric_const_enum() {
+        let output = transform(
+            "const enum Direction { Up, Down, Left = 10, Right }\nconsole.log(Direction.Up, \
+             Direction.Down, Direction[\"Left\"], Direction.Right);",
+        );
+        assert_eq!(output, "console.log(0,1,10,11);");
+    }
+
+    #[test]
+    fn string_const_enum() {
+        let output = transform(
+            "const enum Color { Red = \"RED\", Green = `GREEN` }\nconsole.log(Color.Red, \
+             Color.Green);",
+        );
+        assert_eq!(output, "console.log(\"RED\",\"GREEN\");");
+    }
+
+    #[test]
+    fn mixed_const_enum() {
+        let output = transform(
+            "const enum Flags { None = 0, A = 1 << 0, B = 1 << 1, AB = A | B, Neg = -1, Name = \
+             \"flags\", Full = Name + \"-\" + AB }\nconsole.log(Flags.AB, Flags.Neg, Flags.Full);",
+        );
+        assert_eq!(output, "console.log(3,-1,\"flags-3\");");
+    }
+
+    #[test]
+    fn computed_const_enum() {
+        // Members that can't be evaluated keep the enum at runtime
+        let output = transform(
+            "declare function f(): number;\nconst enum E { A = f(), B }\nconsole.log(E.A);",
+        );
+        assert!(output.contains("E.A"), "{output}");
+        assert!(output.contains("var E"), "{output}");
+    }
+
+    #[test]
+    fn exported_const_enum() {
+        let output = transform("export const enum E { A }\nconsole.log(E.A);");
+        assert!(output.contains("console.log(0)"), "{output}");
+        assert!(output.contains("export var E"), "{output}");
+    }
+}
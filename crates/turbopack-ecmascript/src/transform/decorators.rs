@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use swc_core::{
+    common::util::take::Take,
+    ecma::{
+        ast::{Module, Program},
+        transforms::proposal::decorators::{decorators, Config},
+        visit::FoldWith,
+    },
+};
+use turbo_tasks::{debug::ValueDebugFormat, trace::TraceRawVcs};
+
+/// The decorators proposal that decorators are parsed and lowered with.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    TraceRawVcs,
+    Serialize,
+    Deserialize,
+    ValueDebugFormat,
+)]
+pub enum DecoratorsKind {
+    /// TypeScript's `experimentalDecorators`. Decorators are placed before
+    /// `export`, e.g. `@dec export class A {}`.
+    Legacy,
+    /// The TC39 proposal. Decorators are placed after `export`, e.g.
+    /// `export @dec class A {}`.
+    Ecma,
+}
+
+/// Lowers the decorators of classes, class members and parameters.
+///
+/// Decorator expressions are evaluated in source order and applied in reverse
+/// order. With `emit_metadata`, `design:type`, `design:paramtypes` and
+/// `design:returntype` metadata is emitted like TypeScript's
+/// `emitDecoratorMetadata`, which is only supported for legacy decorators.
+///
+/// Has to run before the `strip` transform, since the metadata is derived
+/// from the type annotations.
+pub fn lower_decorators(program: &mut Program, kind: DecoratorsKind, emit_metadata: bool) {
+    let p = std::mem::replace(program, Program::Module(Module::dummy()));
+    *program = p.fold_with(&mut decorators(Config {
+        legacy: kind == DecoratorsKind::Legacy,
+        emit_metadata: kind == DecoratorsKind::Legacy && emit_metadata,
+        ..Default::default()
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::{parse_file_as_module, EsConfig, Syntax, TsConfig},
+            transforms::{
+                base::{
+                    helpers::{inject_helpers, Helpers, HELPERS},
+                    resolver,
+                },
+                typescript::strip,
+            },
+            visit::{FoldWith, VisitMutWith},
+        },
+        testing::run_test,
+    };
+
+    use super::{lower_decorators, DecoratorsKind};
+
+    fn transform(src: &str, kind: DecoratorsKind) -> String {
+        let syntax = match kind {
+            DecoratorsKind::Legacy => Syntax::Typescript(TsConfig {
+                decorators: true,
+                ..Default::default()
+            }),
+            DecoratorsKind::Ecma => Syntax::Es(EsConfig {
+                decorators: true,
+                decorators_before_export: false,
+                ..Default::default()
+            }),
+        };
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let module = parse_file_as_module(&fm, syntax, EsVersion::latest(), None, &mut vec![])
+                .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Module(module);
+
+            HELPERS.set(&Helpers::new(false), || {
+                let unresolved_mark = Mark::new();
+                let top_level_mark = Mark::new();
+                program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, true));
+                lower_decorators(&mut program, kind, true);
+                program.visit_mut_with(&mut strip(top_level_mark));
+                program = program.clone().fold_with(&mut inject_helpers());
+            });
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn legacy_method_decorator_with_metadata() {
+        let code = transform(
+            "class Service {\n  @first()\n  @second()\n  greet(name: string): string {\n    \
+             return name;\n  }\n}",
+            DecoratorsKind::Legacy,
+        );
+        assert!(!code.contains('@'));
+        // Decorators are evaluated in source order
+        let first = code.find("first()").unwrap();
+        let second = code.find("second()").unwrap();
+        assert!(first < second);
+        assert!(code.contains(r#""design:type",Function"#));
+        assert!(code.contains(r#""design:paramtypes",[String]"#));
+        assert!(code.contains(r#""design:returntype",String"#));
+    }
+
+    #[test]
+    fn ecma_class_decorator() {
+        let code = transform(
+            "export @sealed class Point {\n  x = 1;\n}",
+            DecoratorsKind::Ecma,
+        );
+        assert!(!code.contains('@'));
+        assert!(code.contains("sealed"));
+        assert!(!code.contains("design:"));
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use swc_core::{
+    common::{Mark, DUMMY_SP},
+    ecma::{
+        ast::*,
+        visit::{VisitMut, VisitMutWith},
+    },
+};
+
+use crate::analyzer::{ConstantNumber, ConstantValue, JsValue, ObjectPart};
+
+/// Replaces references to global variables, like `__DEV__`, and member
+/// chains on them, like `process.env.NODE_ENV`, with the values of `defines`.
+///
+/// Only free references are replaced. A local binding with the same name, e.g.
+/// a function parameter named `API_URL`, shadows the definition. Assignment
+/// targets are never replaced. Values that can't be written as a literal
+/// (constants and arrays and objects of them) are ignored.
+///
+/// Note: This requires running `resolver` with `unresolved_mark` **before**
+/// running this.
+pub fn inline_defines(
+    program: &mut Program,
+    defines: &HashMap<String, JsValue>,
+    unresolved_mark: Mark,
+) {
+    let defines = defines
+        .iter()
+        .filter_map(|(name, value)| Some((name.as_str(), value_to_expr(value)?)))
+        .collect::<HashMap<_, _>>();
+    if defines.is_empty() {
+        return;
+    }
+    program.visit_mut_with(&mut DefineReplacer {
+        defines,
+        unresolved_mark,
+    });
+}
+
+struct DefineReplacer<'a> {
+    defines: HashMap<&'a str, Expr>,
+    unresolved_mark: Mark,
+}
+
+impl DefineReplacer<'_> {
+    fn is_free(&self, ident: &Ident) -> bool {
+        ident.span.ctxt.outer() == self.unresolved_mark
+    }
+
+    /// The dotted name of a free identifier or a member chain on it.
+    fn free_name(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Ident(ident) if self.is_free(ident) => Some(ident.sym.to_string()),
+            Expr::Member(MemberExpr {
+                obj,
+                prop: MemberProp::Ident(prop),
+                ..
+            }) => {
+                let mut name = self.free_name(obj)?;
+                name.push('.');
+                name.push_str(&prop.sym);
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl VisitMut for DefineReplacer<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Some(value) = self
+            .free_name(expr)
+            .and_then(|name| self.defines.get(name.as_str()))
+        {
+            *expr = value.clone();
+            return;
+        }
+        expr.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            if self.is_free(ident) {
+                if let Some(value) = self.defines.get(&*ident.sym) {
+                    let key = PropName::Ident(ident.clone());
+                    *prop = Prop::KeyValue(KeyValueProp {
+                        key,
+                        value: box value.clone(),
+                    });
+                    return;
+                }
+            }
+        }
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_assign_expr(&mut self, assign: &mut AssignExpr) {
+        match &mut assign.left {
+            PatOrExpr::Expr(expr) | PatOrExpr::Pat(box Pat::Expr(expr)) => {
+                expr.visit_mut_children_with(self)
+            }
+            PatOrExpr::Pat(pat) => pat.visit_mut_with(self),
+        }
+        assign.right.visit_mut_with(self);
+    }
+
+    fn visit_mut_update_expr(&mut self, update: &mut UpdateExpr) {
+        update.arg.visit_mut_children_with(self);
+    }
+}
+
+fn value_to_expr(value: &JsValue) -> Option<Expr> {
+    Some(match value {
+        JsValue::Constant(value) => constant_to_expr(value),
+        JsValue::Array(_, items) => Expr::Array(ArrayLit {
+            span: DUMMY_SP,
+            elems: items
+                .iter()
+                .map(|item| {
+                    Some(Some(ExprOrSpread {
+                        spread: None,
+                        expr: box value_to_expr(item)?,
+                    }))
+                })
+                .collect::<Option<_>>()?,
+        }),
+        JsValue::Object(_, parts) => Expr::Object(ObjectLit {
+            span: DUMMY_SP,
+            props: parts
+                .iter()
+                .map(|part| {
+                    Some(match part {
+                        ObjectPart::KeyValue(JsValue::Constant(key), value) => {
+                            PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp {
+                                key: PropName::Str(Str {
+                                    span: DUMMY_SP,
+                                    value: key.to_js_string().into(),
+                                    raw: None,
+                                }),
+                                value: box value_to_expr(value)?,
+                            }))
+                        }
+                        ObjectPart::KeyValue(..) => return None,
+                        ObjectPart::Spread(value) => PropOrSpread::Spread(SpreadElement {
+                            dot3_token: DUMMY_SP,
+                            expr: box value_to_expr(value)?,
+                        }),
+                    })
+                })
+                .collect::<Option<_>>()?,
+        }),
+        _ => return None,
+    })
+}
+
+fn constant_to_expr(value: &ConstantValue) -> Expr {
+    match value {
+        ConstantValue::Undefined => Expr::Unary(UnaryExpr {
+            span: DUMMY_SP,
+            op: UnaryOp::Void,
+            arg: box Expr::Lit(Lit::Num(0.0.into())),
+        }),
+        ConstantValue::StrWord(value) => Expr::Lit(Lit::Str(Str {
+            span: DUMMY_SP,
+            value: value.clone(),
+            raw: None,
+        })),
+        ConstantValue::StrAtom(value) => Expr::Lit(Lit::Str(Str {
+            span: DUMMY_SP,
+            value: (&**value).into(),
+            raw: None,
+        })),
+        ConstantValue::Num(ConstantNumber(value)) if value.is_sign_negative() && *value != 0.0 => {
+            Expr::Unary(UnaryExpr {
+                span: DUMMY_SP,
+                op: UnaryOp::Minus,
+                arg: box Expr::Lit(Lit::Num((-value).into())),
+            })
+        }
+        ConstantValue::Num(ConstantNumber(value)) => Expr::Lit(Lit::Num((*value).into())),
+        ConstantValue::True => Expr::Lit(Lit::Bool(Bool {
+            span: DUMMY_SP,
+            value: true,
+        })),
+        ConstantValue::False => Expr::Lit(Lit::Bool(Bool {
+            span: DUMMY_SP,
+            value: false,
+        })),
+        ConstantValue::Null => Expr::Lit(Lit::Null(Null { span: DUMMY_SP })),
+        ConstantValue::BigInt(value) => Expr::Lit(Lit::BigInt(BigInt {
+            span: DUMMY_SP,
+            value: box value.clone(),
+            raw: None,
+        })),
+        ConstantValue::Regex(exp, flags) => Expr::Lit(Lit::Regex(Regex {
+            span: DUMMY_SP,
+            exp: exp.clone(),
+            flags: flags.clone(),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::inline_defines;
+    use crate::analyzer::{ConstantValue, JsValue};
+
+    fn transform(src: &str) -> String {
+        let defines = HashMap::from([
+            (
+                "__DEV__".to_string(),
+                JsValue::Constant(ConstantValue::False),
+            ),
+            ("API_URL".to_string(), "https://example.com".into()),
+            ("process.env.NODE_ENV".to_string(), "production".into()),
+        ]);
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Module(module);
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            inline_defines(&mut program, &defines, unresolved_mark);
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn free_identifier() {
+        assert_eq!(
+            transform("if (__DEV__) fetch(API_URL, { API_URL });"),
+            r#"if(false)fetch("https://example.com",{API_URL:"https://example.com"});"#
+        );
+        assert_eq!(
+            transform("console.log(process.env.NODE_ENV, process.env.OTHER);"),
+            r#"console.log("production",process.env.OTHER);"#
+        );
+    }
+
+    #[test]
+    fn shadowed_identifier() {
+        assert_eq!(
+            transform("function get(API_URL) { return fetch(API_URL); }\nfetch(API_URL);"),
+            r#"function get(API_URL){return fetch(API_URL)}fetch("https://example.com");"#
+        );
+        assert_eq!(
+            transform("const process = {};\nconsole.log(process.env.NODE_ENV);"),
+            "const process={};console.log(process.env.NODE_ENV);"
+        );
+    }
+
+    #[test]
+    fn assignment_target() {
+        assert_eq!(
+            transform("__DEV__ = true;\nprocess.env.NODE_ENV = __DEV__;"),
+            "__DEV__=true;process.env.NODE_ENV=false;"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use swc_core::{
+    common::{Mark, DUMMY_SP},
+    ecma::ast::*,
+    quote,
+};
+
+/// Preserves the `name` of top level function and class declarations, which
+/// changes when their bindings are renamed, e.g. by scope hoisting. Some
+/// libraries, like dependency injection frameworks and serializers, rely on
+/// it at runtime.
+///
+/// The name is pinned with `Object.defineProperty(MyClass, "name", ...)`.
+/// Function declarations are hoisted, so their names are pinned at the start
+/// of the module. Classes are pinned right after their declaration.
+///
+/// Only named declarations at the top level are affected. Nested bindings and
+/// anonymous default exports are never renamed.
+///
+/// Note: This requires running `resolver` with `unresolved_mark` **before**
+/// running this.
+pub fn keep_names(program: &mut Program, unresolved_mark: Mark) {
+    match program {
+        Program::Module(module) => {
+            let items = std::mem::take(&mut module.body);
+            let imports = items
+                .iter()
+                .take_while(|item| matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+                .count();
+            let mut functions = Vec::new();
+            let mut body = Vec::with_capacity(items.len());
+            for item in items {
+                let declared = match &item {
+                    ModuleItem::Stmt(Stmt::Decl(decl))
+                    | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => {
+                        declared_name(decl)
+                    }
+                    ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                        decl,
+                        ..
+                    })) => default_declared_name(decl),
+                    _ => None,
+                };
+                let pin = declared.map(|(ident, kind)| (pin_name(ident, unresolved_mark), kind));
+                body.push(item);
+                match pin {
+                    Some((pin, DeclKind::Function)) => functions.push(ModuleItem::Stmt(pin)),
+                    Some((pin, DeclKind::Class)) => body.push(ModuleItem::Stmt(pin)),
+                    None => {}
+                }
+            }
+            body.splice(imports..imports, functions);
+            module.body = body;
+        }
+        Program::Script(script) => {
+            let stmts = std::mem::take(&mut script.body);
+            let mut functions = Vec::new();
+            let mut body = Vec::with_capacity(stmts.len());
+            for stmt in stmts {
+                let pin = match &stmt {
+                    Stmt::Decl(decl) => declared_name(decl)
+                        .map(|(ident, kind)| (pin_name(ident, unresolved_mark), kind)),
+                    _ => None,
+                };
+                body.push(stmt);
+                match pin {
+                    Some((pin, DeclKind::Function)) => functions.push(pin),
+                    Some((pin, DeclKind::Class)) => body.push(pin),
+                    None => {}
+                }
+            }
+            body.splice(0..0, functions);
+            script.body = body;
+        }
+    }
+}
+
+enum DeclKind {
+    Function,
+    Class,
+}
+
+fn declared_name(decl: &Decl) -> Option<(&Ident, DeclKind)> {
+    match decl {
+        // Overload signatures and ambient declarations have no runtime value
+        Decl::Fn(FnDecl {
+            ident,
+            declare: false,
+            function: box Function { body: Some(_), .. },
+        }) => Some((ident, DeclKind::Function)),
+        Decl::Class(ClassDecl {
+            ident,
+            declare: false,
+            ..
+        }) => Some((ident, DeclKind::Class)),
+        _ => None,
+    }
+}
+
+fn default_declared_name(decl: &DefaultDecl) -> Option<(&Ident, DeclKind)> {
+    match decl {
+        DefaultDecl::Fn(FnExpr {
+            ident: Some(ident),
+            function: box Function { body: Some(_), .. },
+        }) => Some((ident, DeclKind::Function)),
+        DefaultDecl::Class(ClassExpr {
+            ident: Some(ident), ..
+        }) => Some((ident, DeclKind::Class)),
+        _ => None,
+    }
+}
+
+/// `Object.defineProperty(ident, "name", { value: "ident", configurable: true
+/// });`, which references the binding of `ident`, so it's renamed together
+/// with it.
+fn pin_name(ident: &Ident, unresolved_mark: Mark) -> Stmt {
+    quote!(
+        "$object.defineProperty($target, \"name\", { value: $name, configurable: true });" as Stmt,
+        object = Ident::new("Object".into(), DUMMY_SP.apply_mark(unresolved_mark)),
+        target = ident.clone(),
+        name: Expr = Expr::Lit(Lit::Str(ident.sym.clone().into()))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark, SourceMap},
+        ecma::{
+            ast::{EsVersion, Module, Program},
+            atoms::JsWord,
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::{parse_file_as_module, Syntax, TsConfig},
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::keep_names;
+    use crate::chunk::scope_hoisting::{concatenate_modules, HoistedModule};
+
+    fn parse(cm: &Arc<SourceMap>, src: &str, syntax: Syntax) -> HoistedModule {
+        let fm = cm.new_source_file(FileName::Anon, src.into());
+        let module = parse_file_as_module(&fm, syntax, EsVersion::latest(), None, &mut vec![])
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+            .unwrap();
+        let mut program = Program::Module(module);
+
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+        keep_names(&mut program, unresolved_mark);
+
+        let Program::Module(module) = program else {
+            unreachable!();
+        };
+        HoistedModule {
+            module,
+            unresolved_mark,
+            top_level_mark,
+            imports: HashMap::new(),
+        }
+    }
+
+    fn emit(cm: &Arc<SourceMap>, module: &Module) -> String {
+        let mut bytes = Vec::new();
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+        };
+        emitter.emit_module(module).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    fn transform(src: &str) -> String {
+        run_test(false, |cm, _handler| {
+            Ok(emit(&cm, &parse(&cm, src, Default::default()).module))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn pinned_names() {
+        assert_eq!(
+            transform(
+                "import a from \"./a\";\nexport class MyClass {}\nf(MyClass);\nfunction f() {}"
+            ),
+            "import a from\"./a\";Object.defineProperty(f,\"name\",{value:\"f\",configurable:\
+             true});export class \
+             MyClass{}Object.defineProperty(MyClass,\"name\",{value:\"MyClass\",configurable:\
+             true});f(MyClass);function f(){}"
+        );
+        assert_eq!(
+            transform("export default class Service {}"),
+            "export default class \
+             Service{}Object.defineProperty(Service,\"name\",{value:\"Service\",configurable:\
+             true});"
+        );
+    }
+
+    #[test]
+    fn kept_declarations() {
+        // Anonymous, nested or without a runtime value
+        assert_eq!(
+            transform("export default function () {\n  function g() {}\n}"),
+            "export default function(){function g(){}}"
+        );
+        run_test(false, |cm, _handler| {
+            let module = parse(
+                &cm,
+                "declare class A {}\nexport function f(a: string): void;",
+                Syntax::Typescript(TsConfig::default()),
+            )
+            .module;
+            assert_eq!(module.body.len(), 2);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn renamed_by_scope_hoisting() {
+        run_test(false, |cm, _handler| {
+            let dep = parse(&cm, "export class MyClass {}", Default::default());
+            let mut index = parse(
+                &cm,
+                "import { MyClass as Dep } from \"./dep\";\nclass MyClass {}\nexport const names \
+                 = [Dep.name, MyClass.name];",
+                Default::default(),
+            );
+            index.imports.insert(JsWord::from("./dep"), 0);
+
+            let hoisted = concatenate_modules(vec![dep, index]).unwrap();
+            // `MyClass_0.name === "MyClass"`
+            assert_eq!(
+                emit(&cm, &hoisted),
+                "class MyClass_0{}Object.defineProperty(MyClass_0,\"name\",{value:\"MyClass\",\
+                 configurable:true});class \
+                 MyClass{}Object.defineProperty(MyClass,\"name\",{value:\"MyClass\",configurable:\
+                 true});export const names=[MyClass_0.name,MyClass.name];"
+            );
+
+            Ok(())
+        })
+        .unwrap();
+    }
+}
@@ -1,4 +1,14 @@
+mod cjs_to_esm;
+mod const_enum;
+mod decorators;
+mod define;
+mod keep_names;
+mod plugin;
+mod propagate_constants;
+mod react_refresh;
 mod server_to_client_proxy;
+mod strip_console;
+mod type_imports;
 
 use std::{path::Path, sync::Arc};
 
@@ -16,10 +26,26 @@ use swc_core::{
         visit::{FoldWith, VisitMutWith},
     },
 };
-use turbo_tasks::primitives::StringVc;
+use turbo_tasks::primitives::{StringVc, StringsVc};
 use turbopack_core::environment::EnvironmentVc;
 
-use self::server_to_client_proxy::{create_proxy_module, is_client_module};
+pub(crate) use self::plugin::apply_plugins;
+use self::{
+    cjs_to_esm::cjs_to_esm,
+    const_enum::inline_const_enums,
+    decorators::lower_decorators,
+    keep_names::keep_names,
+    propagate_constants::propagate_constants,
+    react_refresh::react_refresh_options,
+    server_to_client_proxy::{create_proxy_module, is_client_module},
+    strip_console::strip_console,
+    type_imports::{elide_type_only_imports, restore_side_effect_imports},
+};
+pub use self::{
+    decorators::DecoratorsKind,
+    define::inline_defines,
+    plugin::{TransformPlugin, TransformPluginStage, TransformPluginVc},
+};
 mod next_ssg;
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -27,22 +53,50 @@ mod next_ssg;
 pub enum EcmascriptInputTransform {
     ClientDirective(StringVc),
     CommonJs,
+    /// Converts CommonJS modules with statically known exports to ESM, so
+    /// their exports can be tree shaken.
+    CommonJsToEsm,
     Custom,
+    /// Lowers decorators. Has to be placed before the TypeScript transform.
+    Decorators {
+        kind: DecoratorsKind,
+        /// Emits type metadata for legacy decorators, like TypeScript's
+        /// `emitDecoratorMetadata`.
+        #[serde(default)]
+        emit_decorators_metadata: bool,
+    },
     Emotion,
+    /// Preserves the `name` of top level functions and classes when their
+    /// bindings are renamed, e.g. by scope hoisting.
+    KeepNames,
     /// This enables the Next SSG transform, which will eliminate
     /// `getStaticProps`/`getServerSideProps`/etc. exports from the output, as
     /// well as any imports that are only used by those exports.
     ///
     /// It also provides diagnostics for improper use of `getServerSideProps`.
     NextJs,
+    /// A custom pass, see [TransformPlugin].
+    Plugin(TransformPluginVc),
     PresetEnv(EnvironmentVc),
+    /// Replaces references to top level `const` bindings that are
+    /// initialized with a literal with the literal, so conditions using them
+    /// can be evaluated.
+    PropagateConstants,
     React {
         #[serde(default)]
         refresh: bool,
     },
+    /// Removes calls to the given methods of `console`, e.g. `log` and
+    /// `debug`.
+    StripConsole(StringsVc),
     StyledComponents,
     StyledJsx,
-    TypeScript,
+    TypeScript {
+        /// Keeps the imported modules of imports that are elided, since they
+        /// might have side effects, like TypeScript's `isolatedModules`.
+        #[serde(default)]
+        isolated_modules: bool,
+    },
 }
 
 #[turbo_tasks::value(transparent, serialization = "auto_for_input")]
@@ -90,9 +144,7 @@ impl EcmascriptInputTransform {
                         runtime: Some(swc_core::ecma::transforms::react::Runtime::Automatic),
                         development: Some(true),
                         refresh: if refresh {
-                            Some(swc_core::ecma::transforms::react::RefreshOptions {
-                                ..Default::default()
-                            })
+                            Some(react_refresh_options())
                         } else {
                             None
                         },
@@ -115,6 +167,15 @@ impl EcmascriptInputTransform {
                     Some(comments.clone()),
                 ));
             }
+            EcmascriptInputTransform::CommonJsToEsm => {
+                cjs_to_esm(program, unresolved_mark);
+            }
+            EcmascriptInputTransform::Decorators {
+                kind,
+                emit_decorators_metadata,
+            } => {
+                lower_decorators(program, kind, emit_decorators_metadata);
+            }
             EcmascriptInputTransform::Emotion => {
                 let p = std::mem::replace(program, Program::Module(Module::dummy()));
                 *program = p.fold_with(&mut swc_emotion::emotion(
@@ -124,6 +185,9 @@ impl EcmascriptInputTransform {
                     comments.clone(),
                 ))
             }
+            EcmascriptInputTransform::KeepNames => {
+                keep_names(program, unresolved_mark);
+            }
             EcmascriptInputTransform::PresetEnv(env) => {
                 let versions = env.runtime_versions().await?;
                 let config = swc_core::ecma::preset_env::Config {
@@ -145,6 +209,12 @@ impl EcmascriptInputTransform {
                     inject_helpers()
                 ));
             }
+            EcmascriptInputTransform::PropagateConstants => {
+                propagate_constants(program, top_level_mark);
+            }
+            EcmascriptInputTransform::StripConsole(methods) => {
+                strip_console(program, &methods.await?, unresolved_mark);
+            }
             EcmascriptInputTransform::StyledComponents => {
                 program.visit_mut_with(&mut styled_components::styled_components(
                     FileName::Anon,
@@ -161,9 +231,12 @@ impl EcmascriptInputTransform {
                     FileName::Anon,
                 ));
             }
-            EcmascriptInputTransform::TypeScript => {
+            EcmascriptInputTransform::TypeScript { isolated_modules } => {
                 use swc_core::ecma::transforms::typescript::strip;
+                inline_const_enums(program);
+                let imports = elide_type_only_imports(program, isolated_modules);
                 program.visit_mut_with(&mut strip(top_level_mark));
+                restore_side_effect_imports(program, imports);
             }
             EcmascriptInputTransform::ClientDirective(transition_name) => {
                 let transition_name = &*transition_name.await?;
@@ -180,6 +253,13 @@ impl EcmascriptInputTransform {
 
                 *program = module_program.fold_with(&mut next_ssg(eliminated_packages));
             }
+            EcmascriptInputTransform::Plugin(plugin) => {
+                apply_plugins(
+                    [&*plugin.await?],
+                    TransformPluginStage::BeforeReferences,
+                    program,
+                );
+            }
             EcmascriptInputTransform::Custom => todo!(),
         }
         Ok(())
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use swc_core::ecma::{ast::Program, visit::VisitMutWith};
+use turbo_tasks::{debug::ValueDebugFormat, trace::TraceRawVcs};
+
+use crate::code_gen::VisitorFactory;
+
+/// The point in the pipeline at which a [TransformPlugin] is applied.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    TraceRawVcs,
+    Serialize,
+    Deserialize,
+    ValueDebugFormat,
+)]
+pub enum TransformPluginStage {
+    /// After the other input transforms, before the module is analyzed. Changes
+    /// affect the references of the module.
+    BeforeReferences,
+    /// During code generation, after the references have been rewritten.
+    AfterReferences,
+    /// During code generation, right before the module is emitted. This is
+    /// after the `fixer` pass, so the plugin needs to add the parentheses
+    /// required by its changes itself.
+    BeforeEmit,
+}
+
+/// A custom pass that is applied to the module at `stage`. It's registered as
+/// [crate::EcmascriptInputTransform::Plugin].
+///
+/// Plugins of the same stage are applied in the order of the transforms list.
+/// A single visitor is created for the whole program per application.
+#[turbo_tasks::value(shared, serialization = "none", eq = "manual", cell = "new")]
+pub struct TransformPlugin {
+    pub stage: TransformPluginStage,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub visitor: Box<dyn VisitorFactory>,
+}
+
+/// Applies the `plugins` registered for `stage` to the program, in order.
+pub(crate) fn apply_plugins<'a>(
+    plugins: impl IntoIterator<Item = &'a TransformPlugin>,
+    stage: TransformPluginStage,
+    program: &mut Program,
+) {
+    for plugin in plugins {
+        if plugin.stage == stage {
+            program.visit_mut_with(&mut plugin.visitor.create());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName},
+        ecma::{
+            ast::{EsVersion, Program, Str},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            visit::VisitMut,
+        },
+        testing::run_test,
+    };
+
+    use super::{apply_plugins, TransformPlugin, TransformPluginStage};
+    use crate::code_gen::VisitorFactory;
+
+    /// Appends a suffix to all string literals.
+    struct StrRenamer(&'static str);
+
+    impl VisitorFactory for StrRenamer {
+        fn create<'a>(&'a self) -> Box<dyn VisitMut + Send + Sync + 'a> {
+            box self
+        }
+    }
+
+    impl VisitMut for &'_ StrRenamer {
+        fn visit_mut_str(&mut self, s: &mut Str) {
+            s.value = format!("{}{}", s.value, self.0).into();
+            s.raw = None;
+        }
+    }
+
+    fn plugin(stage: TransformPluginStage, suffix: &'static str) -> TransformPlugin {
+        TransformPlugin {
+            stage,
+            visitor: box StrRenamer(suffix),
+        }
+    }
+
+    #[test]
+    fn plugin_stage() {
+        let plugins = [
+            plugin(TransformPluginStage::BeforeEmit, "-emit1"),
+            plugin(TransformPluginStage::AfterReferences, "-references"),
+            plugin(TransformPluginStage::BeforeEmit, "-emit2"),
+        ];
+        let code = run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, "foo(\"a\", [\"b\"]);".into());
+            let module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Module(module);
+
+            apply_plugins(&plugins, TransformPluginStage::BeforeEmit, &mut program);
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap();
+        assert_eq!(code, r#"foo("a-emit1-emit2",["b-emit1-emit2"]);"#);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use swc_core::{
+    common::{BytePos, Mark, Span},
+    ecma::{
+        ast::*,
+        visit::{Visit, VisitMut, VisitMutWith, VisitWith},
+    },
+};
+
+/// Replaces references to top level `const` bindings that are initialized with
+/// a literal, e.g. `const mode = "production"`, with the literal. This allows
+/// the analyzer to evaluate conditions like `if (env === mode)`.
+///
+/// Bindings that are assigned to anywhere, which throws, are kept. References
+/// before the declaration are kept too, since the binding isn't initialized
+/// yet, even when they are in a function that is called later. The
+/// declarations are kept, they may be exported.
+///
+/// Note: This requires running `resolver` with `top_level_mark` **before**
+/// running this.
+pub fn propagate_constants(program: &mut Program, top_level_mark: Mark) {
+    let mut constants = top_level_constants(program, top_level_mark);
+    if constants.is_empty() {
+        return;
+    }
+    let mut bindings = BindingCounter(HashMap::new());
+    program.visit_with(&mut bindings);
+    // The declaration is the only binding of a constant
+    constants.retain(|id, _| bindings.0.get(id) == Some(&1));
+    program.visit_mut_with(&mut ConstantPropagator { constants });
+}
+
+/// A constant and the end of its declaration.
+type Constant = (Lit, BytePos);
+
+fn top_level_constants(program: &Program, top_level_mark: Mark) -> HashMap<Id, Constant> {
+    let decls: Box<dyn Iterator<Item = &VarDecl>> = match program {
+        Program::Module(module) => box module.body.iter().filter_map(|item| match item {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Var(decl)))
+            | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                decl: Decl::Var(decl),
+                ..
+            })) => Some(&**decl),
+            _ => None,
+        }),
+        Program::Script(script) => box script.body.iter().filter_map(|stmt| match stmt {
+            Stmt::Decl(Decl::Var(decl)) => Some(&**decl),
+            _ => None,
+        }),
+    };
+    let mut constants = HashMap::new();
+    for decl in decls.filter(|decl| decl.kind == VarDeclKind::Const) {
+        for declarator in decl.decls.iter() {
+            let VarDeclarator {
+                name: Pat::Ident(BindingIdent { id, .. }),
+                init: Some(box Expr::Lit(lit)),
+                span,
+                ..
+            } = declarator else {
+                continue;
+            };
+            if id.span.ctxt.outer() != top_level_mark {
+                continue;
+            }
+            if let Lit::Str(_) | Lit::Num(_) | Lit::Bool(_) | Lit::Null(_) | Lit::BigInt(_) = lit {
+                constants.insert(id.to_id(), (lit.clone(), span.hi));
+            }
+        }
+    }
+    constants
+}
+
+/// Counts the declarations of and assignments to each binding.
+struct BindingCounter(HashMap<Id, usize>);
+
+impl BindingCounter {
+    fn add(&mut self, ident: &Ident) {
+        *self.0.entry(ident.to_id()).or_default() += 1;
+    }
+}
+
+impl Visit for BindingCounter {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.add(&ident.id);
+    }
+
+    fn visit_pat_or_expr(&mut self, target: &PatOrExpr) {
+        if let PatOrExpr::Expr(box Expr::Ident(ident)) = target {
+            self.add(ident);
+        }
+        target.visit_children_with(self);
+    }
+
+    fn visit_update_expr(&mut self, expr: &UpdateExpr) {
+        if let Expr::Ident(ident) = &*expr.arg {
+            self.add(ident);
+        }
+        expr.visit_children_with(self);
+    }
+}
+
+struct ConstantPropagator {
+    constants: HashMap<Id, Constant>,
+}
+
+impl ConstantPropagator {
+    /// The value of `ident` when it references an initialized constant.
+    fn value(&self, ident: &Ident) -> Option<Lit> {
+        let (lit, declared) = self.constants.get(&ident.to_id())?;
+        (ident.span.lo >= *declared).then(|| with_span(lit, ident.span))
+    }
+}
+
+impl VisitMut for ConstantPropagator {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Expr::Ident(ident) = expr {
+            if let Some(lit) = self.value(ident) {
+                *expr = Expr::Lit(lit);
+            }
+            return;
+        }
+        expr.visit_mut_children_with(self);
+    }
+
+    // `{ mode }`
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            if let Some(lit) = self.value(ident) {
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(ident.clone()),
+                    value: box Expr::Lit(lit),
+                });
+            }
+            return;
+        }
+        prop.visit_mut_children_with(self);
+    }
+}
+
+/// `lit` at the position of the reference it replaces.
+fn with_span(lit: &Lit, span: Span) -> Lit {
+    let mut lit = lit.clone();
+    match &mut lit {
+        Lit::Str(Str { span: s, .. })
+        | Lit::Num(Number { span: s, .. })
+        | Lit::Bool(Bool { span: s, .. })
+        | Lit::Null(Null { span: s })
+        | Lit::BigInt(BigInt { span: s, .. })
+        | Lit::Regex(Regex { span: s, .. })
+        | Lit::JSXText(JSXText { span: s, .. }) => *s = span,
+    }
+    lit
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program, Stmt},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::propagate_constants;
+    use crate::{
+        analyzer::graph::EvalContext, references::constant_condition::ConstantConditionValue,
+    };
+
+    /// Parses `src` and passes it to `f`, after propagating constants when
+    /// `propagate` is set. Returns the minified output and the value of `f`.
+    fn transform<T>(
+        src: &str,
+        propagate: bool,
+        f: impl FnOnce(&Program, &EvalContext) -> T,
+    ) -> (String, T) {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Module(module);
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            if propagate {
+                propagate_constants(&mut program, top_level_mark);
+            }
+            let value = f(&program, &EvalContext::new(&program, unresolved_mark));
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok((String::from_utf8(bytes).unwrap(), value))
+        })
+        .unwrap()
+    }
+
+    fn propagated(src: &str) -> String {
+        transform(src, true, |_, _| ()).0
+    }
+
+    #[test]
+    fn propagated_constants() {
+        assert_eq!(
+            propagated("const mode = \"production\";\nif (env === mode) f(mode);"),
+            r#"const mode="production";if(env==="production")f("production");"#
+        );
+        assert_eq!(
+            propagated("const n = 1;\nfunction g() {\n  return { n };\n}"),
+            "const n=1;function g(){return{n:1}}"
+        );
+        // Shadowed bindings are different bindings
+        assert_eq!(
+            propagated("export const a = true;\nfunction f(a) {\n  return a;\n}\nf(a);"),
+            "export const a=true;function f(a){return a}f(true);"
+        );
+    }
+
+    #[test]
+    fn kept_bindings() {
+        // Not a literal, not a `const`, or not at the top level
+        let src = "const a = b;\nlet c = 1;\n{\n  const d = 1;\n  f(a, c, d);\n}";
+        assert_eq!(propagated(src), "const a=b;let c=1;{const d=1;f(a,c,d)}");
+        // Assigned to, which throws
+        assert_eq!(
+            propagated("const a = 1;\nf(a);\na++;"),
+            "const a=1;f(a);a++;"
+        );
+        // Referenced before the declaration
+        assert_eq!(
+            propagated("function f() {\n  return a;\n}\nconst a = 1;\nf(a);"),
+            "function f(){return a}const a=1;f(1);"
+        );
+    }
+
+    #[test]
+    fn folded_condition() {
+        let src = "const DEBUG = false;\nif (DEBUG) {\n  log();\n}";
+        let condition = |program: &Program, eval_context: &EvalContext| {
+            let Program::Module(module) = program else {
+                unreachable!();
+            };
+            let Some(Stmt::If(stmt)) = module.body[1].as_stmt() else {
+                unreachable!();
+            };
+            ConstantConditionValue::from_js_value(&eval_context.eval(&stmt.test))
+        };
+        // The analyzer doesn't evaluate the binding on its own
+        assert_eq!(transform(src, false, condition).1, None);
+        assert_eq!(
+            transform(src, true, condition).1,
+            Some(ConstantConditionValue::Falsy)
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use swc_core::ecma::transforms::react::RefreshOptions;
+
+/// The React Refresh options for the `react` transform.
+///
+/// Components are registered with `$RefreshReg$` and hooks get a
+/// `$RefreshSig$` signature in every module, not only in modules that look
+/// like refresh boundaries: Components wrapped in `memo` or `forwardRef` are
+/// easily missed by a static check, and the signatures of hooks in modules
+/// that only export hooks are needed to refresh the components calling them.
+/// Whether a module is a boundary is decided by the runtime from its actual
+/// exports, see `registerExportsAndSetupBoundaryForReactRefresh`.
+pub fn react_refresh_options() -> RefreshOptions {
+    RefreshOptions::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use swc_core::{
+        base::SwcComments,
+        common::{errors::HANDLER, FileName, Mark, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::{parse_file_as_module, EsConfig, Syntax},
+            transforms::{base::resolver, react::react},
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::react_refresh_options;
+
+    /// Returns the program, its comments and the top level mark.
+    fn parse(cm: &Arc<SourceMap>, src: &str) -> (Program, SwcComments, Mark) {
+        let fm = cm.new_source_file(FileName::Anon, src.into());
+        let comments = SwcComments::default();
+        let module = parse_file_as_module(
+            &fm,
+            Syntax::Es(EsConfig {
+                jsx: true,
+                ..Default::default()
+            }),
+            EsVersion::latest(),
+            Some(&comments),
+            &mut vec![],
+        )
+        .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+        .unwrap();
+        let mut program = Program::Module(module);
+
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+        (program, comments, top_level_mark)
+    }
+
+    fn transform(src: &str) -> String {
+        run_test(false, |cm, _handler| {
+            let (mut program, comments, top_level_mark) = parse(&cm, src);
+            program.visit_mut_with(&mut react(
+                cm.clone(),
+                Some(comments),
+                swc_core::ecma::transforms::react::Options {
+                    development: Some(true),
+                    refresh: Some(react_refresh_options()),
+                    ..Default::default()
+                },
+                top_level_mark,
+            ));
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn component_module() {
+        let src = "import { useState } from \"react\";\nexport function Counter() {\n  const \
+                   [count] = useState(0);\n  return <div>{count}</div>;\n}\nconst Title = () => \
+                   <h1 />;\nexport default Title;";
+        let code = transform(src);
+        assert!(code.contains("$RefreshSig$()"));
+        assert!(code.contains("$RefreshReg$("));
+        assert!(code.contains("\"Counter\")"));
+        assert!(code.contains("\"Title\")"));
+    }
+
+    #[test]
+    fn mixed_module() {
+        // Not a refresh boundary, but the component is still registered
+        let src = "export const answer = 42;\nexport function Answer() {\n  return \
+                   <div>{answer}</div>;\n}";
+        let code = transform(src);
+        assert!(code.contains("$RefreshReg$("));
+        assert!(code.contains("\"Answer\")"));
+    }
+
+    #[test]
+    fn memo_wrapped_component() {
+        let src = "import { memo } from \"react\";\nconst Counter = memo(() => <div />);\nexport \
+                   default Counter;";
+        let code = transform(src);
+        assert!(code.contains("$RefreshReg$("));
+        assert!(code.contains("\"Counter\")"));
+    }
+
+    #[test]
+    fn hook_only_module() {
+        let src = "import { useState } from \"react\";\nexport function useCounter() {\n  const \
+                   [count] = useState(0);\n  return count;\n}";
+        let code = transform(src);
+        // The signature of the hook is needed to refresh the components that call it
+        assert!(code.contains("$RefreshSig$()"));
+        assert!(!code.contains("$RefreshReg$("));
+    }
+}
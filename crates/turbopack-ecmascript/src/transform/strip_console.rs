@@ -0,0 +1,184 @@
+use swc_core::{
+    common::{Mark, DUMMY_SP},
+    ecma::{
+        ast::*,
+        visit::{VisitMut, VisitMutWith},
+    },
+};
+
+use crate::utils::unparen;
+
+/// Removes calls to the `methods` of `console`, e.g. `console.log(...)`.
+///
+/// Call statements are dropped. Calls whose value is used, e.g.
+/// `const x = console.log(...)`, are replaced with `undefined`. The arguments
+/// of removed calls are not evaluated.
+///
+/// Only calls on the free `console` are removed. A local binding named
+/// `console` shadows it.
+///
+/// Note: This requires running `resolver` with `unresolved_mark` **before**
+/// running this.
+pub fn strip_console(program: &mut Program, methods: &[String], unresolved_mark: Mark) {
+    if methods.is_empty() {
+        return;
+    }
+    program.visit_mut_with(&mut ConsoleStripper {
+        methods,
+        unresolved_mark,
+    });
+}
+
+struct ConsoleStripper<'a> {
+    methods: &'a [String],
+    unresolved_mark: Mark,
+}
+
+impl ConsoleStripper<'_> {
+    /// `console.log(...)` or `console["log"](...)`
+    fn is_console_call(&self, expr: &Expr) -> bool {
+        let Expr::Call(CallExpr {
+            callee: Callee::Expr(callee),
+            ..
+        }) = unparen(expr) else {
+            return false;
+        };
+        let Expr::Member(MemberExpr { obj, prop, .. }) = unparen(callee) else {
+            return false;
+        };
+        let method = match prop {
+            MemberProp::Ident(prop) => &prop.sym,
+            MemberProp::Computed(ComputedPropName {
+                expr: box Expr::Lit(Lit::Str(prop)),
+                ..
+            }) => &prop.value,
+            _ => return false,
+        };
+        matches!(
+            unparen(obj),
+            Expr::Ident(Ident { sym, span, .. })
+                if &**sym == "console" && span.ctxt.outer() == self.unresolved_mark
+        ) && self.methods.iter().any(|m| m == &**method)
+    }
+
+    fn is_console_stmt(&self, stmt: &Stmt) -> bool {
+        matches!(stmt, Stmt::Expr(ExprStmt { expr, .. }) if self.is_console_call(expr))
+    }
+}
+
+impl VisitMut for ConsoleStripper<'_> {
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.retain(|item| !matches!(item, ModuleItem::Stmt(stmt) if self.is_console_stmt(stmt)));
+        items.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.retain(|stmt| !self.is_console_stmt(stmt));
+        stmts.visit_mut_children_with(self);
+    }
+
+    // Statements that are not part of a list, e.g. `if (a) console.log(a);`
+    fn visit_mut_stmt(&mut self, stmt: &mut Stmt) {
+        if self.is_console_stmt(stmt) {
+            *stmt = Stmt::Empty(EmptyStmt { span: DUMMY_SP });
+            return;
+        }
+        stmt.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if self.is_console_call(expr) {
+            *expr = Expr::Unary(UnaryExpr {
+                span: DUMMY_SP,
+                op: UnaryOp::Void,
+                arg: box Expr::Lit(Lit::Num(0.0.into())),
+            });
+            return;
+        }
+        expr.visit_mut_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::strip_console;
+
+    fn transform(src: &str) -> String {
+        let methods = ["log".to_string(), "debug".to_string()];
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Module(module);
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            strip_console(&mut program, &methods, unresolved_mark);
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn standalone_calls() {
+        assert_eq!(
+            transform("console.log(\"a\");\nconsole.error(\"b\");\nconsole[\"debug\"](c());"),
+            r#"console.error("b");"#
+        );
+        assert_eq!(
+            transform("function f(a) {\n  console.debug(a);\n  if (a) console.log(a);\n}"),
+            "function f(a){if(a);}"
+        );
+    }
+
+    #[test]
+    fn value_is_used() {
+        assert_eq!(
+            transform("const x = console.log(\"a\");\nfoo(a && console.debug(a));"),
+            "const x=void 0;foo(a&&void 0);"
+        );
+        assert_eq!(
+            transform("const f = () => console.log(\"a\");"),
+            "const f=()=>void 0;"
+        );
+    }
+
+    #[test]
+    fn shadowed_console() {
+        assert_eq!(
+            transform("function f(console) {\n  console.log(\"a\");\n}\nconsole.log(\"b\");"),
+            r#"function f(console){console.log("a")}"#
+        );
+    }
+}
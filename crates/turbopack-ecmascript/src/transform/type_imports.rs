@@ -0,0 +1,166 @@
+use swc_core::{
+    common::{Span, Spanned},
+    ecma::ast::*,
+};
+
+/// Removes the imports that are provably type-only, i.e. `import type`
+/// declarations and `type` specifiers like `A` in `import { type A, b }`.
+///
+/// Imports that are only used as types can't be distinguished from value
+/// imports without the imported module, so the `strip` transform removes all
+/// unused import declarations. With `isolated_modules` the imported module
+/// must still be evaluated for its side effects, so the declarations that
+/// are not type-only are returned to restore them with
+/// [restore_side_effect_imports] after `strip` was applied.
+///
+/// Has to run before the `strip` transform.
+pub fn elide_type_only_imports(program: &mut Program, isolated_modules: bool) -> Vec<ImportDecl> {
+    let Program::Module(module) = program else {
+        return Vec::new();
+    };
+    let mut value_imports = Vec::new();
+    module.body.retain_mut(|item| {
+        let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item else {
+            return true;
+        };
+        if import.type_only {
+            return false;
+        }
+        let is_side_effect_import = import.specifiers.is_empty();
+        import.specifiers.retain(|specifier| {
+            !matches!(
+                specifier,
+                ImportSpecifier::Named(ImportNamedSpecifier {
+                    is_type_only: true,
+                    ..
+                })
+            )
+        });
+        if import.specifiers.is_empty() && !is_side_effect_import && !isolated_modules {
+            return false;
+        }
+        if isolated_modules {
+            value_imports.push(import.clone());
+        }
+        true
+    });
+    value_imports
+}
+
+/// Adds side-effect imports like `import "./x"` for the `imports` that were
+/// removed by the `strip` transform, at their original position.
+pub fn restore_side_effect_imports(program: &mut Program, imports: Vec<ImportDecl>) {
+    let Program::Module(module) = program else {
+        return;
+    };
+    let remaining = module
+        .body
+        .iter()
+        .filter_map(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => Some(import.span),
+            _ => None,
+        })
+        .collect::<Vec<Span>>();
+    for import in imports {
+        if remaining.contains(&import.span) {
+            continue;
+        }
+        let index = module
+            .body
+            .partition_point(|item| item.span().lo <= import.span.lo);
+        module.body.insert(
+            index,
+            ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                specifiers: Vec::new(),
+                ..import
+            })),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::{EsVersion, Program},
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::{parse_file_as_module, Syntax, TsConfig},
+            transforms::{base::resolver, typescript::strip},
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::{elide_type_only_imports, restore_side_effect_imports};
+
+    fn transform(src: &str, isolated_modules: bool) -> String {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let module = parse_file_as_module(
+                &fm,
+                Syntax::Typescript(TsConfig::default()),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            let mut program = Program::Module(module);
+
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, true));
+            let imports = elide_type_only_imports(&mut program, isolated_modules);
+            program.visit_mut_with(&mut strip(top_level_mark));
+            restore_side_effect_imports(&mut program, imports);
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+            };
+            emitter.emit_program(&program).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn type_only_import() {
+        let src = "import type { A } from \"./a\";\nimport { type B } from \"./b\";\nlet x: A | B;";
+        assert_eq!(transform(src, false), "let x;");
+        assert_eq!(transform(src, true), "import\"./b\";let x;");
+    }
+
+    #[test]
+    fn side_effect_import() {
+        let src = "import \"./polyfill\";\nconsole.log(1);";
+        assert_eq!(
+            transform(src, false),
+            "import\"./polyfill\";console.log(1);"
+        );
+        assert_eq!(transform(src, true), "import\"./polyfill\";console.log(1);");
+    }
+
+    #[test]
+    fn value_import() {
+        let src = "import { a } from \"./a\";\nconsole.log(a);";
+        assert_eq!(transform(src, true), "import{a}from\"./a\";console.log(a);");
+    }
+
+    #[test]
+    fn mixed_import() {
+        let used = "import { type A, b } from \"./x\";\nlet x: A = b;";
+        assert_eq!(transform(used, true), "import{b}from\"./x\";let x=b;");
+
+        // `b` is only used as a type, but the module must still be evaluated
+        let unused = "import { type A, b } from \"./x\";\nlet x: A | typeof b;";
+        assert_eq!(transform(unused, false), "let x;");
+        assert_eq!(transform(unused, true), "import\"./x\";let x;");
+    }
+}
@@ -0,0 +1,689 @@
+//! Statement level tree shaking.
+//!
+//! Top level statements are classified as pure when evaluating them can't
+//! have observable effects: function and class declarations and variables that
+//! are initialized with literals or other pure expressions. Unused exports that
+//! are declared by pure statements can be removed, even when other statements
+//! of the module have side effects.
+//!
+//! When [turbopack_core::chunk::ChunkingContext::is_tree_shaking_enabled],
+//! [shake_chunk_content] removes the unused exports of the modules of a chunk.
+
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use swc_core::{
+    common::{
+        comments::SingleThreadedComments, FileName, Globals, Mark, SourceMap, SyntaxContext,
+        GLOBALS,
+    },
+    ecma::{
+        ast::{
+            op, Class, ClassDecl, ClassMember, Decl, DefaultDecl, EsVersion, ExportDecl,
+            ExportDefaultDecl, ExportDefaultExpr, ExportNamedSpecifier, ExportSpecifier, Expr,
+            FnDecl, Id, Ident, MemberProp, Module, ModuleDecl, ModuleExportName, ModuleItem,
+            NamedExport, Pat, Program, Prop, PropName, PropOrSpread, Stmt, SuperProp, VarDecl,
+        },
+        atoms::JsWord,
+        parser::{parse_file_as_module, EsConfig, Syntax},
+        transforms::base::resolver,
+        visit::{Visit, VisitMutWith, VisitWith},
+    },
+};
+use turbo_tasks::Value;
+use turbo_tasks_fs::File;
+use turbopack_core::{asset::Asset, chunk::ChunkingContextVc, virtual_asset::VirtualAssetVc};
+
+use crate::{
+    analyzer::dynamic_scope::uses_dynamic_scope,
+    chunk::{
+        scope_hoisting::to_code, EcmascriptChunkContentResult, EcmascriptChunkContentResultVc,
+        EcmascriptChunkItems, EcmascriptChunkItemsVc, EcmascriptChunkPlaceablesVc,
+    },
+    parse::{parse, ParseResult},
+    references::esm::{used_exports, UsedExports},
+    utils::unparen,
+    EcmascriptInputTransformsVc, EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+    ModuleChunkItemVc,
+};
+
+/// Returns true when evaluating the top level `item` has no side effects.
+///
+/// Function and class declarations and variables with pure initializers are
+/// pure. Calls, member accesses (which could invoke getters), assignments and
+/// references to globals are considered to have side effects. Imports and
+/// re-exports are never pure, since they evaluate other modules.
+pub fn is_pure_module_item(item: &ModuleItem, unresolved_mark: Mark) -> bool {
+    let purity = Purity {
+        unresolved: SyntaxContext::empty().apply_mark(unresolved_mark),
+    };
+    match item {
+        ModuleItem::Stmt(stmt) => purity.is_pure_stmt(stmt),
+        ModuleItem::ModuleDecl(decl) => match decl {
+            ModuleDecl::ExportDecl(ExportDecl { decl, .. }) => purity.is_pure_decl(decl),
+            ModuleDecl::ExportDefaultDecl(ExportDefaultDecl { decl, .. }) => match decl {
+                DefaultDecl::Class(class) => purity.is_pure_class(&class.class),
+                DefaultDecl::Fn(_) => true,
+                DefaultDecl::TsInterfaceDecl(_) => true,
+            },
+            ModuleDecl::ExportDefaultExpr(ExportDefaultExpr { expr, .. }) => {
+                purity.is_pure_expr(expr)
+            }
+            ModuleDecl::ExportNamed(NamedExport { src, .. }) => src.is_none(),
+            _ => false,
+        },
+    }
+}
+
+struct Purity {
+    unresolved: SyntaxContext,
+}
+
+impl Purity {
+    fn is_pure_stmt(&self, stmt: &Stmt) -> bool {
+        match stmt {
+            Stmt::Empty(_) => true,
+            Stmt::Decl(decl) => self.is_pure_decl(decl),
+            _ => false,
+        }
+    }
+
+    fn is_pure_decl(&self, decl: &Decl) -> bool {
+        match decl {
+            Decl::Fn(_) => true,
+            Decl::Class(ClassDecl { class, .. }) => self.is_pure_class(class),
+            Decl::Var(box VarDecl { decls, .. }) => decls.iter().all(|decl| {
+                // Destructuring could invoke getters or iterators
+                matches!(decl.name, Pat::Ident(_))
+                    && decl
+                        .init
+                        .as_deref()
+                        .map_or(true, |init| self.is_pure_expr(init))
+            }),
+            _ => false,
+        }
+    }
+
+    fn is_pure_class(&self, class: &Class) -> bool {
+        class
+            .super_class
+            .as_deref()
+            .map_or(true, |super_class| self.is_pure_expr(super_class))
+            && class.body.iter().all(|member| match member {
+                ClassMember::Constructor(_)
+                | ClassMember::PrivateMethod(_)
+                | ClassMember::TsIndexSignature(_)
+                | ClassMember::Empty(_) => true,
+                ClassMember::Method(method) => self.is_pure_prop_name(&method.key),
+                ClassMember::ClassProp(prop) => {
+                    self.is_pure_prop_name(&prop.key)
+                        && (!prop.is_static
+                            || prop
+                                .value
+                                .as_deref()
+                                .map_or(true, |value| self.is_pure_expr(value)))
+                }
+                ClassMember::PrivateProp(prop) => {
+                    !prop.is_static
+                        || prop
+                            .value
+                            .as_deref()
+                            .map_or(true, |value| self.is_pure_expr(value))
+                }
+                ClassMember::StaticBlock(_) => false,
+            })
+    }
+
+    fn is_pure_prop_name(&self, name: &PropName) -> bool {
+        match name {
+            PropName::Computed(computed) => self.is_pure_expr(&computed.expr),
+            _ => true,
+        }
+    }
+
+    fn is_pure_expr(&self, expr: &Expr) -> bool {
+        match unparen(expr) {
+            Expr::Lit(_) | Expr::Fn(_) | Expr::Arrow(_) => true,
+            Expr::Ident(ident) => {
+                ident.span.ctxt != self.unresolved
+                    || matches!(&*ident.sym, "undefined" | "NaN" | "Infinity")
+            }
+            Expr::Class(class) => self.is_pure_class(&class.class),
+            Expr::Tpl(tpl) => tpl.exprs.iter().all(|expr| self.is_pure_expr(expr)),
+            Expr::Array(array) => array.elems.iter().flatten().all(|elem| {
+                // Spreading invokes the iterator of the value
+                elem.spread.is_none() && self.is_pure_expr(&elem.expr)
+            }),
+            Expr::Object(object) => object.props.iter().all(|prop| match prop {
+                PropOrSpread::Spread(_) => false,
+                PropOrSpread::Prop(box prop) => match prop {
+                    Prop::Shorthand(ident) => self.is_pure_expr(&Expr::Ident(ident.clone())),
+                    Prop::KeyValue(kv) => {
+                        self.is_pure_prop_name(&kv.key) && self.is_pure_expr(&kv.value)
+                    }
+                    Prop::Getter(getter) => self.is_pure_prop_name(&getter.key),
+                    Prop::Setter(setter) => self.is_pure_prop_name(&setter.key),
+                    Prop::Method(method) => self.is_pure_prop_name(&method.key),
+                    Prop::Assign(_) => false,
+                },
+            }),
+            Expr::Unary(unary) => unary.op != op!("delete") && self.is_pure_expr(&unary.arg),
+            // `in` and `instanceof` throw for non-object operands
+            Expr::Bin(bin) => {
+                bin.op != op!("in")
+                    && bin.op != op!("instanceof")
+                    && self.is_pure_expr(&bin.left)
+                    && self.is_pure_expr(&bin.right)
+            }
+            Expr::Cond(cond) => {
+                self.is_pure_expr(&cond.test)
+                    && self.is_pure_expr(&cond.cons)
+                    && self.is_pure_expr(&cond.alt)
+            }
+            Expr::Seq(seq) => seq.exprs.iter().all(|expr| self.is_pure_expr(expr)),
+            _ => false,
+        }
+    }
+}
+
+/// Removes the pure statements of the module that only declare exports which
+/// are not in `used_exports` and aren't referenced by any retained statement.
+/// Statements with side effects are always retained, as are the declarations
+/// they reference.
+pub fn remove_unused_exports(
+    module: &mut Module,
+    unresolved_mark: Mark,
+    top_level_mark: Mark,
+    used_exports: &HashSet<JsWord>,
+) {
+    let top_level = SyntaxContext::empty().apply_mark(top_level_mark);
+
+    let items = module
+        .body
+        .iter()
+        .map(|item| ItemInfo {
+            pure: is_pure_module_item(item, unresolved_mark),
+            declared: declared_bindings(item),
+            exported: exported_names(item),
+            references: references(item, top_level),
+        })
+        .collect::<Vec<_>>();
+
+    let mut retained = items
+        .iter()
+        .map(|item| !item.pure || item.exported.iter().any(|name| used_exports.contains(name)))
+        .collect::<Vec<_>>();
+
+    // Retain the declarations referenced by retained statements until no
+    // further statement is added.
+    let mut referenced = HashSet::new();
+    loop {
+        let mut changed = false;
+        for (item, &retained) in items.iter().zip(retained.iter()) {
+            if retained {
+                referenced.extend(item.references.iter().cloned());
+            }
+        }
+        for (item, retained) in items.iter().zip(retained.iter_mut()) {
+            if !*retained && item.declared.iter().any(|id| referenced.contains(id)) {
+                *retained = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let body = std::mem::take(&mut module.body);
+    module.body = body
+        .into_iter()
+        .zip(items.iter().zip(retained))
+        .filter_map(|(item, (info, retained))| {
+            if !retained {
+                return None;
+            }
+            match item {
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. }))
+                    if info.pure
+                        && !info.exported.iter().any(|name| used_exports.contains(name)) =>
+                {
+                    // Only retained because it's referenced by other statements
+                    Some(ModuleItem::Stmt(Stmt::Decl(decl)))
+                }
+                ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+                    span,
+                    specifiers,
+                    src: None,
+                    type_only,
+                    asserts,
+                })) => {
+                    let specifiers = specifiers
+                        .into_iter()
+                        .filter(|specifier| {
+                            export_specifier_name(specifier)
+                                .map_or(true, |name| used_exports.contains(&name))
+                        })
+                        .collect::<Vec<_>>();
+                    (!specifiers.is_empty()).then(|| {
+                        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+                            span,
+                            specifiers,
+                            src: None,
+                            type_only,
+                            asserts,
+                        }))
+                    })
+                }
+                item => Some(item),
+            }
+        })
+        .collect();
+}
+
+struct ItemInfo {
+    pure: bool,
+    declared: Vec<Id>,
+    exported: Vec<JsWord>,
+    references: HashSet<Id>,
+}
+
+fn declared_bindings(item: &ModuleItem) -> Vec<Id> {
+    let decl = match item {
+        ModuleItem::Stmt(Stmt::Decl(decl))
+        | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => decl,
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+            decl, ..
+        })) => {
+            return match decl {
+                DefaultDecl::Class(class) => class.ident.iter().map(Ident::to_id).collect(),
+                DefaultDecl::Fn(function) => function.ident.iter().map(Ident::to_id).collect(),
+                DefaultDecl::TsInterfaceDecl(_) => Vec::new(),
+            };
+        }
+        _ => return Vec::new(),
+    };
+    match decl {
+        Decl::Class(ClassDecl { ident, .. }) | Decl::Fn(FnDecl { ident, .. }) => {
+            vec![ident.to_id()]
+        }
+        Decl::Var(var) => var
+            .decls
+            .iter()
+            .filter_map(|decl| decl.name.as_ident().map(|ident| ident.id.to_id()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn exported_names_of_decl(decl: &Decl) -> Vec<JsWord> {
+    match decl {
+        Decl::Class(ClassDecl { ident, .. }) | Decl::Fn(FnDecl { ident, .. }) => {
+            vec![ident.sym.clone()]
+        }
+        Decl::Var(var) => var
+            .decls
+            .iter()
+            .filter_map(|decl| decl.name.as_ident().map(|ident| ident.id.sym.clone()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn export_specifier_name(specifier: &ExportSpecifier) -> Option<JsWord> {
+    let ExportSpecifier::Named(ExportNamedSpecifier { orig, exported, .. }) = specifier else {
+        return None;
+    };
+    match exported.as_ref().unwrap_or(orig) {
+        ModuleExportName::Ident(ident) => Some(ident.sym.clone()),
+        ModuleExportName::Str(s) => Some(s.value.clone()),
+    }
+}
+
+fn exported_names(item: &ModuleItem) -> Vec<JsWord> {
+    match item {
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => {
+            exported_names_of_decl(decl)
+        }
+        ModuleItem::ModuleDecl(
+            ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_),
+        ) => vec!["default".into()],
+        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport { specifiers, .. })) => {
+            specifiers
+                .iter()
+                .filter_map(export_specifier_name)
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Collects the references to top level bindings in `item`.
+fn references(item: &ModuleItem, top_level: SyntaxContext) -> HashSet<Id> {
+    struct References {
+        top_level: SyntaxContext,
+        ids: HashSet<Id>,
+    }
+
+    impl Visit for References {
+        fn visit_ident(&mut self, ident: &Ident) {
+            if ident.span.ctxt == self.top_level {
+                self.ids.insert(ident.to_id());
+            }
+        }
+
+        fn visit_member_prop(&mut self, prop: &MemberProp) {
+            if let MemberProp::Computed(computed) = prop {
+                computed.visit_with(self);
+            }
+        }
+
+        fn visit_super_prop(&mut self, prop: &SuperProp) {
+            if let SuperProp::Computed(computed) = prop {
+                computed.visit_with(self);
+            }
+        }
+
+        fn visit_prop_name(&mut self, name: &PropName) {
+            if let PropName::Computed(computed) = name {
+                computed.visit_with(self);
+            }
+        }
+    }
+
+    let mut visitor = References {
+        top_level,
+        ids: HashSet::new(),
+    };
+    item.visit_with(&mut visitor);
+    visitor.ids
+}
+
+/// Removes the unused exports of the ESM modules of the chunk `content`, see
+/// [remove_unused_exports], when
+/// [turbopack_core::chunk::ChunkingContext::is_tree_shaking_enabled]. Exports
+/// are unused when no module that is reachable from the `entries` of the chunk
+/// uses them, see [used_exports]. The shaken module keeps the path and the
+/// module id of the original module.
+///
+/// As the module id stays the same, chunks of different entries that contain
+/// the same module must not be loaded together.
+#[turbo_tasks::function]
+pub(crate) async fn shake_chunk_content(
+    context: ChunkingContextVc,
+    entries: EcmascriptChunkPlaceablesVc,
+    content: EcmascriptChunkContentResultVc,
+) -> Result<EcmascriptChunkContentResultVc> {
+    if !*context.is_tree_shaking_enabled().await? {
+        return Ok(content);
+    }
+    let this = content.await?;
+    let entries = entries.await?;
+    let mut chunk_items = Vec::new();
+    for chunk in this.chunk_items.await?.iter() {
+        chunk_items.extend(chunk.await?.iter().copied());
+    }
+
+    let mut changed = false;
+    'items: for chunk_item in chunk_items.iter_mut() {
+        let Some(module_chunk_item) = ModuleChunkItemVc::resolve_from(*chunk_item).await? else {
+            continue;
+        };
+        let module = module_chunk_item.await?.module;
+        let mut used = HashSet::new();
+        for entry in entries.iter() {
+            match &*used_exports(*entry, module.as_ecmascript_chunk_placeable()).await? {
+                UsedExports::All => continue 'items,
+                UsedExports::None => {}
+                UsedExports::Set(names) => {
+                    used.extend(names.iter().map(|name| JsWord::from(name.as_str())))
+                }
+            }
+        }
+        if let Some(shaken) = shaken_module(module, &used).await? {
+            *chunk_item = shaken
+                .as_ecmascript_chunk_placeable()
+                .as_chunk_item(context);
+            changed = true;
+        }
+    }
+    if !changed {
+        return Ok(content);
+    }
+
+    Ok(EcmascriptChunkContentResult {
+        chunk_items: EcmascriptChunkItemsVc::cell(EcmascriptChunkItems::make_chunks(&chunk_items)),
+        chunks: this.chunks.clone(),
+        async_chunk_groups: this.async_chunk_groups.clone(),
+        loading_hints: this.loading_hints.clone(),
+        external_asset_references: this.external_asset_references.clone(),
+    }
+    .cell())
+}
+
+/// A new module at the path of `module` without the exports that are not in
+/// `used_exports`. The exports are removed after the transforms of the module
+/// have been applied, so the new module has no transforms. Returns `None` when
+/// nothing can be removed.
+async fn shaken_module(
+    module: EcmascriptModuleAssetVc,
+    used_exports: &HashSet<JsWord>,
+) -> Result<Option<EcmascriptModuleAssetVc>> {
+    let this = module.await?;
+    let parsed = parse(this.source, Value::new(this.ty), this.transforms).await?;
+    let ParseResult::Ok {
+        program,
+        comments,
+        source_map,
+        eval_context,
+        degraded: false,
+        ..
+    } = &*parsed else {
+        return Ok(None);
+    };
+    if !eval_context.is_esm() {
+        return Ok(None);
+    }
+    let Some(code) = shake_code(to_code(source_map, program, comments)?, used_exports)? else {
+        return Ok(None);
+    };
+
+    let source = VirtualAssetVc::new(this.source.path(), File::from(code).into());
+    Ok(Some(EcmascriptModuleAssetVc::new(
+        source.into(),
+        this.context,
+        Value::new(EcmascriptModuleAssetType::Ecmascript),
+        EcmascriptInputTransformsVc::cell(Vec::new()),
+        this.options,
+        this.environment,
+    )))
+}
+
+/// Removes the exports that are not in `used_exports` from the ES module
+/// `source`. Returns `None` when nothing was removed or when the module uses
+/// `eval`, which could refer to any of its bindings.
+fn shake_code(source: String, used_exports: &HashSet<JsWord>) -> Result<Option<String>> {
+    GLOBALS.set(&Globals::new(), || {
+        let cm = Arc::new(SourceMap::default());
+        let comments = SingleThreadedComments::default();
+        let fm = cm.new_source_file(FileName::Anon, source);
+        let mut errors = Vec::new();
+        let Ok(mut module) = parse_file_as_module(
+            &fm,
+            Syntax::Es(EsConfig {
+                jsx: true,
+                ..Default::default()
+            }),
+            EsVersion::latest(),
+            Some(&comments),
+            &mut errors,
+        ) else {
+            return Ok(None);
+        };
+        if !errors.is_empty() {
+            return Ok(None);
+        }
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+        if uses_dynamic_scope(&module, unresolved_mark) {
+            return Ok(None);
+        }
+        remove_unused_exports(&mut module, unresolved_mark, top_level_mark, used_exports);
+        let code = to_code(&cm, &Program::Module(module), &comments)?;
+        Ok((code != *fm.src).then_some(code))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark, SourceMap},
+        ecma::{
+            ast::{EsVersion, Module},
+            atoms::JsWord,
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::{is_pure_module_item, remove_unused_exports, shake_code};
+
+    fn parse(cm: &Arc<SourceMap>, src: &str) -> (Module, Mark, Mark) {
+        let fm = cm.new_source_file(FileName::Anon, src.into());
+        let mut module = parse_file_as_module(
+            &fm,
+            Default::default(),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+        .unwrap();
+
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        (module, unresolved_mark, top_level_mark)
+    }
+
+    fn emit(cm: &Arc<SourceMap>, module: &Module) -> String {
+        let mut bytes = Vec::new();
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm.clone(), "\n", &mut bytes, None),
+        };
+        emitter.emit_module(module).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    fn shake(src: &str, used_exports: &[&str]) -> String {
+        let mut result = String::new();
+        run_test(false, |cm, _handler| {
+            let (mut module, unresolved_mark, top_level_mark) = parse(&cm, src);
+            let used_exports = used_exports
+                .iter()
+                .map(|&name| JsWord::from(name))
+                .collect::<HashSet<_>>();
+            remove_unused_exports(&mut module, unresolved_mark, top_level_mark, &used_exports);
+            result = emit(&cm, &module);
+            Ok(())
+        })
+        .unwrap();
+        result
+    }
+
+    #[test]
+    fn classify_statements() {
+        run_test(false, |cm, _handler| {
+            let (module, unresolved_mark, _) = parse(
+                &cm,
+                r#"
+                function a() { sideEffect(); }
+                class B extends Object.prototype.constructor {}
+                class C { static x = 1; y = sideEffect(); }
+                const d = 1, e = "e", f = [d, { e }];
+                const g = unknown();
+                globalThis.h = 1;
+                const i = window;
+                export const j = () => sideEffect();
+                export default class { static [sideEffect()] = 1 }
+                "#,
+            );
+            let purity = module
+                .body
+                .iter()
+                .map(|item| is_pure_module_item(item, unresolved_mark))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                purity,
+                vec![true, false, true, true, false, false, false, true, false]
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn unused_pure_export() {
+        assert_eq!(
+            shake(
+                "export const unused = 1; console.log(\"side effect\"); export const used = 2;",
+                &["used"]
+            ),
+            "console.log(\"side effect\");export const used=2;"
+        );
+    }
+
+    #[test]
+    fn referenced_declarations() {
+        assert_eq!(
+            shake(
+                "const helper = 1; export function unused() {} export const used = unused; \
+                 sideEffect(helper); export { helper as other };",
+                &["used"]
+            ),
+            "const helper=1;function unused(){}export const used=unused;sideEffect(helper);"
+        );
+    }
+
+    #[test]
+    fn shake_module_code() {
+        let used = HashSet::from([JsWord::from("a")]);
+        let code = shake_code(
+            "export const a = 1;\nexport const b = 2;\nsideEffect();\n".to_string(),
+            &used,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(code.contains("export const a = 1;"), "{code}");
+        assert!(!code.contains("b = 2"), "{code}");
+        assert!(code.contains("sideEffect();"), "{code}");
+
+        // Nothing to remove
+        assert_eq!(
+            shake_code("export const a = 1;\n".to_string(), &used).unwrap(),
+            None
+        );
+        // eval could refer to b
+        assert_eq!(
+            shake_code(
+                "export const a = 1;\nexport const b = 2;\neval(\"b\");\n".to_string(),
+                &used
+            )
+            .unwrap(),
+            None
+        );
+    }
+}
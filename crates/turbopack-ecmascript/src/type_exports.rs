@@ -0,0 +1,182 @@
+//! Detection of the exports of a TypeScript module that only exist in type
+//! space, like interfaces and type aliases.
+//!
+//! They are removed together with all other types before the module is
+//! analyzed, so they have to be collected from the untransformed program.
+//! Importing one of them as a value is an error, see
+//! `EsmAssetReferenceVc::check_value_import`.
+
+use std::collections::{BTreeSet, HashSet};
+
+use swc_core::ecma::{ast::*, atoms::JsWord};
+
+use crate::references::for_each_ident_in_decl;
+
+/// The names of the exports of `program` that are declared as types, e.g.
+/// `export interface Props {}`, `export type { Props }` or `export { Props }`
+/// of a local type alias. Names that are declared as values too, like an
+/// interface merged with a class, are included, the caller has to remove
+/// them.
+pub fn type_exports(program: &Program) -> BTreeSet<String> {
+    let Program::Module(module) = program else {
+        return BTreeSet::new();
+    };
+    let mut local_types = HashSet::new();
+    let mut local_values = HashSet::new();
+    for item in module.body.iter() {
+        let decl = match item {
+            ModuleItem::Stmt(Stmt::Decl(decl))
+            | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => decl,
+            _ => continue,
+        };
+        match decl {
+            Decl::TsInterface(decl) => {
+                local_types.insert(decl.id.sym.clone());
+            }
+            Decl::TsTypeAlias(decl) => {
+                local_types.insert(decl.id.sym.clone());
+            }
+            Decl::TsEnum(box TsEnumDecl { id, .. })
+            | Decl::TsModule(box TsModuleDecl {
+                id: TsModuleName::Ident(id),
+                ..
+            }) => {
+                local_values.insert(id.sym.clone());
+            }
+            Decl::Class(_) | Decl::Fn(_) | Decl::Var(_) => {
+                for_each_ident_in_decl(decl, &mut |name| {
+                    local_values.insert(name.into());
+                });
+            }
+            Decl::TsModule(_) => {}
+        }
+    }
+    let is_type = |name: &JsWord| local_types.contains(name) && !local_values.contains(name);
+
+    let mut exports = BTreeSet::new();
+    for item in module.body.iter() {
+        let ModuleItem::ModuleDecl(decl) = item else {
+            continue;
+        };
+        match decl {
+            ModuleDecl::ExportDecl(ExportDecl {
+                decl: Decl::TsInterface(box TsInterfaceDecl { id, .. }),
+                ..
+            })
+            | ModuleDecl::ExportDecl(ExportDecl {
+                decl: Decl::TsTypeAlias(box TsTypeAliasDecl { id, .. }),
+                ..
+            }) => {
+                exports.insert(id.sym.to_string());
+            }
+            ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                decl: DefaultDecl::TsInterfaceDecl(_),
+                ..
+            }) => {
+                exports.insert("default".to_string());
+            }
+            ModuleDecl::ExportNamed(export) => {
+                for specifier in export.specifiers.iter() {
+                    let ExportSpecifier::Named(ExportNamedSpecifier {
+                        orig,
+                        exported,
+                        is_type_only,
+                        ..
+                    }) = specifier else {
+                        continue;
+                    };
+                    // The types of another module are unknown
+                    let is_local_type = export.src.is_none()
+                        && matches!(orig, ModuleExportName::Ident(ident) if is_type(&ident.sym));
+                    let is_type = export.type_only || *is_type_only || is_local_type;
+                    if is_type {
+                        exports.insert(export_name(exported.as_ref().unwrap_or(orig)));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    exports
+}
+
+fn export_name(name: &ModuleExportName) -> String {
+    match name {
+        ModuleExportName::Ident(ident) => ident.sym.to_string(),
+        ModuleExportName::Str(str) => str.value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName},
+        ecma::{
+            ast::EsVersion,
+            parser::{parse_file_as_program, Syntax, TsConfig},
+        },
+        testing::run_test,
+    };
+
+    use super::type_exports;
+
+    fn exports(src: &str) -> Vec<String> {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.into());
+            let program = parse_file_as_program(
+                &fm,
+                Syntax::Typescript(TsConfig::default()),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))?;
+            Ok(type_exports(&program).into_iter().collect())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn declared_types() {
+        assert_eq!(
+            exports(
+                "export interface Props {}\nexport type Size = number;\nexport const size = \
+                 1;\nexport default interface Options {}"
+            ),
+            vec!["Props", "Size", "default"]
+        );
+    }
+
+    #[test]
+    fn named_types() {
+        assert_eq!(
+            exports(
+                "interface Props {}\ntype Size = number;\nconst size = 1;\nexport { Props, Size \
+                 as Dimension, size };"
+            ),
+            vec!["Dimension", "Props"]
+        );
+        assert_eq!(
+            exports("import { A, B } from './a';\nexport type { A };\nexport { type B };"),
+            vec!["A", "B"]
+        );
+        assert_eq!(
+            exports("export type { A } from './a';\nexport { B } from './b';"),
+            vec!["A"]
+        );
+    }
+
+    #[test]
+    fn merged_declarations() {
+        // A class and an interface with the same name are a value
+        assert_eq!(
+            exports("interface Base {}\nclass Base {}\nexport { Base };"),
+            Vec::<String>::new()
+        );
+        // The caller removes it, since it's a value export too
+        assert_eq!(
+            exports("export interface Base {}\nexport class Base {}"),
+            vec!["Base"]
+        );
+    }
+}
@@ -2,3 +2,6 @@ const a = ``;
 const b = `hello`;
 const c = `--service=${"0.14.12"}`;
 const d = `${"--service="}${"0.14.12"}`;
+const e = `${"./locales"}/en.js`;
+const f = `./locales/${"en-US"}`;
+const g = `./locales/${1}.js`;
@@ -0,0 +1,8 @@
+const object = { a: { b: "optional-value" } };
+
+const a1 = object?.a.b;
+const a2 = object?.a?.b;
+const a3 = object?.["a"].b;
+
+const b1 = null?.a.b;
+const b2 = undefined?.a;
@@ -21,6 +21,7 @@ use turbopack_core::{
 use turbopack_ecmascript::chunk::{
     EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
     EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc, EcmascriptChunkVc,
+    EcmascriptExports, EcmascriptExportsVc,
 };
 
 #[turbo_tasks::value]
@@ -75,6 +76,13 @@ impl EcmascriptChunkPlaceable for JsonModuleAsset {
         })
         .into()
     }
+
+    /// The parsed JSON value is the default export. There are no named
+    /// exports.
+    #[turbo_tasks::function]
+    fn get_exports(&self) -> EcmascriptExportsVc {
+        EcmascriptExports::Value.cell()
+    }
 }
 
 #[turbo_tasks::value]
@@ -10,6 +10,8 @@
 
 #![feature(min_specialization)]
 
+pub mod webassembly;
+
 use anyhow::{anyhow, Result};
 use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::{FileContent, FileSystemPathVc};
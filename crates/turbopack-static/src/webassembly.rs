@@ -0,0 +1,221 @@
+//! WebAssembly support for turbopack.
+//!
+//! `.wasm` files are emitted like static assets. When imported from ES
+//! modules, they produce a module that exports a function which fetches and
+//! compiles or instantiates the WebAssembly module, e.g.
+//!
+//! ```js
+//! import init from "./module.wasm";
+//! const instance = await init({ env: { log } });
+//! ```
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
+    context::AssetContextVc,
+    reference::{AssetReferencesVc, SingleAssetReferenceVc},
+};
+use turbopack_ecmascript::{
+    chunk::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+    },
+    utils::stringify_str,
+};
+
+use crate::{StaticAsset, StaticAssetVc};
+
+/// The default export of a WebAssembly module.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Hash, Debug, Copy, Clone)]
+pub enum WebAssemblyVariant {
+    /// A function that compiles the WebAssembly module and returns a
+    /// `WebAssembly.Module`, which can be instantiated by the importer. Used
+    /// for imports with a `type: "webassembly"` attribute.
+    Compile,
+    /// A function that takes the imports object and returns the
+    /// `WebAssembly.Instance`. Used for imports of `.wasm` files.
+    Instantiate,
+}
+
+#[turbo_tasks::value]
+#[derive(Clone)]
+pub struct WebAssemblyModuleAsset {
+    pub source: AssetVc,
+    pub context: AssetContextVc,
+    pub variant: WebAssemblyVariant,
+}
+
+#[turbo_tasks::value_impl]
+impl WebAssemblyModuleAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        source: AssetVc,
+        context: AssetContextVc,
+        variant: Value<WebAssemblyVariant>,
+    ) -> Self {
+        Self::cell(WebAssemblyModuleAsset {
+            source,
+            context,
+            variant: variant.into_value(),
+        })
+    }
+
+    #[turbo_tasks::function]
+    async fn wasm_asset(
+        self_vc: WebAssemblyModuleAssetVc,
+        context: ChunkingContextVc,
+    ) -> Result<StaticAssetVc> {
+        Ok(StaticAssetVc::cell(StaticAsset {
+            context,
+            source: self_vc.await?.source,
+        }))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for WebAssemblyModuleAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for WebAssemblyModuleAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(self_vc: WebAssemblyModuleAssetVc, context: ChunkingContextVc) -> ChunkVc {
+        EcmascriptChunkVc::new(context, self_vc.as_ecmascript_chunk_placeable()).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkPlaceable for WebAssemblyModuleAsset {
+    #[turbo_tasks::function]
+    fn as_chunk_item(
+        self_vc: WebAssemblyModuleAssetVc,
+        context: ChunkingContextVc,
+    ) -> EcmascriptChunkItemVc {
+        WebAssemblyChunkItemVc::cell(WebAssemblyChunkItem {
+            module: self_vc,
+            context,
+            wasm_asset: self_vc.wasm_asset(context),
+        })
+        .into()
+    }
+
+    #[turbo_tasks::function]
+    fn get_exports(&self) -> EcmascriptExportsVc {
+        EcmascriptExports::Value.into()
+    }
+}
+
+#[turbo_tasks::value]
+struct WebAssemblyChunkItem {
+    module: WebAssemblyModuleAssetVc,
+    context: ChunkingContextVc,
+    wasm_asset: StaticAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for WebAssemblyChunkItem {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        let module = self.module.await?;
+        // The same file is a different module for each variant
+        let kind = match module.variant {
+            WebAssemblyVariant::Compile => "wasm module",
+            WebAssemblyVariant::Instantiate => "wasm",
+        };
+        Ok(StringVc::cell(format!(
+            "{} ({kind})",
+            module.source.path().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkItem for WebAssemblyChunkItem {
+    #[turbo_tasks::function]
+    async fn references(&self) -> Result<AssetReferencesVc> {
+        Ok(AssetReferencesVc::cell(vec![SingleAssetReferenceVc::new(
+            self.wasm_asset.into(),
+            StringVc::cell(format!("wasm(url) {}", self.wasm_asset.path().await?)),
+        )
+        .into()]))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkItem for WebAssemblyChunkItem {
+    #[turbo_tasks::function]
+    fn chunking_context(&self) -> ChunkingContextVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let url = format!("/{}", &*self.wasm_asset.path().await?);
+        Ok(EcmascriptChunkItemContent {
+            inner_code: loader_code(self.module.await?.variant, &url).into(),
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+/// The code of the module that exports the loader function for the
+/// WebAssembly module at `url`.
+fn loader_code(variant: WebAssemblyVariant, url: &str) -> String {
+    let fetch = format!(
+        "fetch({url}).then((res) => res.arrayBuffer())",
+        url = stringify_str(url)
+    );
+    match variant {
+        WebAssemblyVariant::Compile => format!(
+            "__turbopack_export_value__(() => {fetch}.then((bytes) => \
+             WebAssembly.compile(bytes)));"
+        ),
+        WebAssemblyVariant::Instantiate => format!(
+            "__turbopack_export_value__((imports = {{}}) => {fetch}.then((bytes) => \
+             WebAssembly.instantiate(bytes, imports)).then(({{ instance }}) => instance));"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{loader_code, WebAssemblyVariant};
+
+    #[test]
+    fn compile_loader() {
+        let code = loader_code(WebAssemblyVariant::Compile, "/static/abc.wasm");
+        assert_eq!(
+            code,
+            "__turbopack_export_value__(() => fetch(\"/static/abc.wasm\").then((res) => \
+             res.arrayBuffer()).then((bytes) => WebAssembly.compile(bytes)));"
+        );
+    }
+
+    #[test]
+    fn instantiate_loader() {
+        let code = loader_code(WebAssemblyVariant::Instantiate, "/static/abc.wasm");
+        assert!(code.starts_with("__turbopack_export_value__((imports = {}) => "));
+        assert!(code.contains("WebAssembly.instantiate(bytes, imports)"));
+        assert!(code.ends_with(".then(({ instance }) => instance));"));
+    }
+}
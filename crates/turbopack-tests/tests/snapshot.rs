@@ -20,7 +20,10 @@ use turbo_tasks_fs::{
 use turbo_tasks_hash::encode_hex;
 use turbo_tasks_memory::MemoryBackend;
 use turbopack::{
-    ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc},
+    ecmascript::{
+        chunk::{output_format::OutputFormat, EcmascriptChunkPlaceablesVc, EcmascriptChunkVc},
+        EcmascriptModuleAssetVc,
+    },
     module_options::ModuleOptionsContext,
     resolve_options_context::ResolveOptionsContext,
     transition::TransitionsByNameVc,
@@ -64,6 +67,17 @@ struct SnapshotOptions {
     browserslist: String,
     #[serde(default = "default_entry")]
     entry: String,
+    #[serde(default)]
+    format: SnapshotFormat,
+}
+
+/// The format of the evaluated chunk of the entry.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SnapshotFormat {
+    #[default]
+    Runtime,
+    Esm,
 }
 
 impl Default for SnapshotOptions {
@@ -71,6 +85,7 @@ impl Default for SnapshotOptions {
         SnapshotOptions {
             browserslist: default_browserslist(),
             entry: default_entry(),
+            format: SnapshotFormat::default(),
         }
     }
 }
@@ -187,6 +202,12 @@ async fn run_test(resource: String) -> Result<FileSystemPathVc> {
         .copied()
         .collect();
 
+    let output_format = match options.format {
+        SnapshotFormat::Runtime => None,
+        SnapshotFormat::Esm => Some(OutputFormat::Esm),
+    };
+    let output_format = &output_format;
+
     let modules = entry_paths
         .into_iter()
         .map(SourceAssetVc::new)
@@ -196,7 +217,16 @@ async fn run_test(resource: String) -> Result<FileSystemPathVc> {
         .map(|module| async move {
             if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
                 // TODO: Load runtime entries from snapshots
-                Ok(ecmascript.as_evaluated_chunk(chunking_context, runtime_entries))
+                Ok(match output_format {
+                    Some(output_format) => EcmascriptChunkVc::new_evaluate_with_format(
+                        chunking_context,
+                        ecmascript.into(),
+                        runtime_entries,
+                        Value::new(output_format.clone()),
+                    )
+                    .into(),
+                    None => ecmascript.as_evaluated_chunk(chunking_context, runtime_entries),
+                })
             } else if let Some(chunkable) = ChunkableAssetVc::resolve_from(module).await? {
                 Ok(chunkable.as_chunk(chunking_context))
             } else {
@@ -0,0 +1,11 @@
+(self.TURBOPACK = self.TURBOPACK || []).push(["output/79fb1_turbopack-tests_tests_snapshot_basic_esm_format_input_import.js_manifest-chunk.js", {
+
+"[project]/crates/turbopack-tests/tests/snapshot/basic/esm_format/input/import.js/manifest-chunk.js": (({ r: __turbopack_require__, x: __turbopack_external_require__, i: __turbopack_import__, s: __turbopack_esm__, v: __turbopack_export_value__, c: __turbopack_cache__, l: __turbopack_load__, p: process, g: global, __dirname }) => (() => {
+
+const chunks = [
+    "output/crates_turbopack-tests_tests_snapshot_basic_esm_format_input_import.js",
+];
+
+__turbopack_export_value__(Promise.all(chunks.map(__turbopack_load__)));
+})()),
+}]);
\ No newline at end of file
@@ -0,0 +1,16 @@
+(self.TURBOPACK = self.TURBOPACK || []).push(["output/crates_turbopack-tests_tests_snapshot_basic_esm_format_input_import.js", {
+
+"[project]/crates/turbopack-tests/tests/snapshot/basic/esm_format/input/import.js (ecmascript)": (({ r: __turbopack_require__, x: __turbopack_external_require__, i: __turbopack_import__, s: __turbopack_esm__, v: __turbopack_export_value__, c: __turbopack_cache__, l: __turbopack_load__, p: process, g: global, __dirname }) => (() => {
+
+__turbopack_esm__({
+    "foo": ()=>foo
+});
+function foo(value) {
+    console.assert(value);
+}
+
+})()),
+}]);
+
+
+//# sourceMappingURL=crates_turbopack-tests_tests_snapshot_basic_esm_format_input_import.js.map
\ No newline at end of file
@@ -55,7 +55,7 @@ pub mod transition;
 pub use turbopack_css as css;
 pub use turbopack_ecmascript as ecmascript;
 use turbopack_json::JsonModuleAssetVc;
-use turbopack_static::StaticModuleAssetVc;
+use turbopack_static::{webassembly::WebAssemblyModuleAssetVc, StaticModuleAssetVc};
 
 use self::{
     resolve_options_context::ResolveOptionsContextVc,
@@ -164,14 +164,26 @@ async fn module(source: AssetVc, context: ModuleAssetContextVc) -> Result<AssetV
     let path = source.path();
     let options = ModuleOptionsVc::new(path.parent(), context.module_options_context());
 
-    let current_module_type = get_module_type(path, options).await?;
+    Ok(module_with_type(
+        source,
+        context,
+        get_module_type(path, options),
+    ))
+}
 
-    Ok(match &*current_module_type {
+#[turbo_tasks::function]
+async fn module_with_type(
+    source: AssetVc,
+    context: ModuleAssetContextVc,
+    module_type: ModuleTypeVc,
+) -> Result<AssetVc> {
+    Ok(match &*module_type.await? {
         ModuleType::Ecmascript(transforms) => EcmascriptModuleAssetVc::new(
             source,
             context.into(),
             Value::new(EcmascriptModuleAssetType::Ecmascript),
             *transforms,
+            context.module_options_context().ecmascript_options(),
             context.environment(),
         )
         .into(),
@@ -180,6 +192,7 @@ async fn module(source: AssetVc, context: ModuleAssetContextVc) -> Result<AssetV
             context.with_typescript_resolving_enabled().into(),
             Value::new(EcmascriptModuleAssetType::Typescript),
             *transforms,
+            context.module_options_context().ecmascript_options(),
             context.environment(),
         )
         .into(),
@@ -188,6 +201,7 @@ async fn module(source: AssetVc, context: ModuleAssetContextVc) -> Result<AssetV
             context.with_typescript_resolving_enabled().into(),
             Value::new(EcmascriptModuleAssetType::TypescriptDeclaration),
             *transforms,
+            context.module_options_context().ecmascript_options(),
             context.environment(),
         )
         .into(),
@@ -200,6 +214,9 @@ async fn module(source: AssetVc, context: ModuleAssetContextVc) -> Result<AssetV
             ModuleCssModuleAssetVc::new(source, context.into(), *transforms).into()
         }
         ModuleType::Static => StaticModuleAssetVc::new(source, context.into()).into(),
+        ModuleType::WebAssembly(variant) => {
+            WebAssemblyModuleAssetVc::new(source, context.into(), Value::new(*variant)).into()
+        }
         ModuleType::Custom(_) => todo!(),
     })
 }
@@ -211,6 +228,9 @@ pub struct ModuleAssetContext {
     module_options_context: ModuleOptionsContextVc,
     resolve_options_context: ResolveOptionsContextVc,
     transition: Option<TransitionVc>,
+    /// Overrides the module type of processed assets, e.g. for an import
+    /// with a `type: "json"` attribute.
+    module_type: Option<ModuleTypeVc>,
 }
 
 #[turbo_tasks::value_impl]
@@ -228,6 +248,7 @@ impl ModuleAssetContextVc {
             module_options_context,
             resolve_options_context,
             transition: None,
+            module_type: None,
         })
     }
 
@@ -245,6 +266,7 @@ impl ModuleAssetContextVc {
             module_options_context,
             resolve_options_context,
             transition: Some(transition),
+            module_type: None,
         })
     }
 
@@ -280,6 +302,16 @@ impl ModuleAssetContextVc {
     }
 }
 
+impl ModuleAssetContext {
+    fn module(&self, source: AssetVc, context: ModuleAssetContextVc) -> AssetVc {
+        if let Some(module_type) = self.module_type {
+            module_with_type(source, context, module_type)
+        } else {
+            module(source, context)
+        }
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl AssetContext for ModuleAssetContext {
     #[turbo_tasks::function]
@@ -349,7 +381,7 @@ impl AssetContext for ModuleAssetContext {
                 module_options_context,
                 resolve_options_context,
             );
-            let m = module(asset, context);
+            let m = this.module(asset, context);
             Ok(transition.process_module(m, context))
         } else {
             let context = ModuleAssetContextVc::new(
@@ -358,7 +390,7 @@ impl AssetContext for ModuleAssetContext {
                 this.module_options_context,
                 this.resolve_options_context,
             );
-            Ok(module(asset, context))
+            Ok(this.module(asset, context))
         }
     }
 
@@ -386,6 +418,27 @@ impl AssetContext for ModuleAssetContext {
             },
         )
     }
+
+    #[turbo_tasks::function]
+    async fn with_module_type(
+        self_vc: ModuleAssetContextVc,
+        module_type: &str,
+    ) -> Result<AssetContextVc> {
+        let Some(module_type) = ModuleType::from_import_type(module_type) else {
+            return Ok(self_vc.into());
+        };
+        let this = self_vc.await?;
+        Ok(ModuleAssetContext {
+            transitions: this.transitions,
+            environment: this.environment,
+            module_options_context: this.module_options_context,
+            resolve_options_context: this.resolve_options_context,
+            transition: this.transition,
+            module_type: Some(module_type.cell()),
+        }
+        .cell()
+        .into())
+    }
 }
 
 #[turbo_tasks::function]
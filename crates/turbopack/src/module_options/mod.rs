@@ -2,6 +2,7 @@ use anyhow::Result;
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_css::{CssInputTransform, CssInputTransformsVc};
 use turbopack_ecmascript::{EcmascriptInputTransform, EcmascriptInputTransformsVc};
+use turbopack_static::webassembly::WebAssemblyVariant;
 
 pub mod module_options_context;
 pub mod module_rule;
@@ -27,12 +28,22 @@ impl ModuleOptionsVc {
             enable_styled_jsx,
             enable_styled_components,
             enable_typescript_transform,
+            enable_typescript_isolated_modules,
+            enable_decorators,
+            enable_decorators_metadata,
             preset_env_versions,
             ref custom_ecmascript_app_transforms,
             ref custom_ecmascript_transforms,
             ref custom_rules,
             ..
         } = *context.await?;
+        // Decorators need to be lowered before the TypeScript transform strips the
+        // types they emit metadata for.
+        let decorators_transform =
+            enable_decorators.map(|kind| EcmascriptInputTransform::Decorators {
+                kind,
+                emit_decorators_metadata: enable_decorators_metadata,
+            });
         let mut transforms = custom_ecmascript_app_transforms.clone();
         transforms.extend(custom_ecmascript_transforms.iter().cloned());
 
@@ -55,18 +66,27 @@ impl ModuleOptionsVc {
             transforms.push(EcmascriptInputTransform::PresetEnv(env));
         }
 
-        let app_transforms = EcmascriptInputTransformsVc::cell(transforms);
+        let app_transforms = EcmascriptInputTransformsVc::cell(
+            decorators_transform
+                .iter()
+                .cloned()
+                .chain(transforms.iter().cloned())
+                .collect(),
+        );
         let vendor_transforms =
             EcmascriptInputTransformsVc::cell(custom_ecmascript_transforms.clone());
         let (ts_app_transforms, ts_transforms) = if enable_typescript_transform {
-            let mut base_transforms = vec![EcmascriptInputTransform::TypeScript];
+            let mut base_transforms = vec![EcmascriptInputTransform::TypeScript {
+                isolated_modules: enable_typescript_isolated_modules,
+            }];
             base_transforms.extend(custom_ecmascript_transforms.iter().cloned());
             (
                 EcmascriptInputTransformsVc::cell(
-                    base_transforms
+                    decorators_transform
                         .iter()
                         .cloned()
-                        .chain(app_transforms.await?.iter().cloned())
+                        .chain(base_transforms.iter().cloned())
+                        .chain(transforms.iter().cloned())
                         .collect(),
                 ),
                 EcmascriptInputTransformsVc::cell(base_transforms),
@@ -181,6 +201,12 @@ impl ModuleOptionsVc {
                 ]),
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Static)],
             ),
+            ModuleRule::new(
+                ModuleRuleCondition::ResourcePathEndsWith(".wasm".to_string()),
+                vec![ModuleRuleEffect::ModuleType(ModuleType::WebAssembly(
+                    WebAssemblyVariant::Instantiate,
+                ))],
+            ),
             ModuleRule::new(
                 ModuleRuleCondition::ResourcePathHasNoExtension,
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
@@ -1,5 +1,10 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
 use turbopack_core::environment::EnvironmentVc;
-use turbopack_ecmascript::EcmascriptInputTransform;
+use turbopack_ecmascript::{
+    DecoratorsKind, EcmascriptInputTransform, EcmascriptOptions, EcmascriptOptionsVc,
+};
 
 use super::ModuleRule;
 
@@ -11,7 +16,26 @@ pub struct ModuleOptionsContext {
     pub enable_styled_components: bool,
     pub enable_styled_jsx: bool,
     pub enable_typescript_transform: bool,
+    /// Keep imports that are elided by the TypeScript transform as
+    /// side-effect imports, like TypeScript's `isolatedModules` option.
+    pub enable_typescript_isolated_modules: bool,
+    /// Lowers decorators of the given proposal.
+    pub enable_decorators: Option<DecoratorsKind>,
+    /// Emits type metadata for legacy decorators, like TypeScript's
+    /// `emitDecoratorMetadata`.
+    pub enable_decorators_metadata: bool,
     pub preset_env_versions: Option<EnvironmentVc>,
+    /// Removes the branch of `if` statements and conditional expressions
+    /// that isn't taken when their condition is known at build time.
+    pub enable_constant_condition_folding: bool,
+    /// Values of `process.env` variables that are inlined when they are
+    /// accessed with a static key, e.g. `process.env.NODE_ENV`.
+    pub process_env: BTreeMap<String, String>,
+    /// Like [ModuleOptionsContext::process_env], for `import.meta.env`.
+    pub import_meta_env: BTreeMap<String, String>,
+    /// Tag names of CSS-in-JS tagged templates whose static CSS is extracted
+    /// at build time, e.g. `styled` and `css`.
+    pub css_in_js_tags: Vec<String>,
     pub custom_ecmascript_app_transforms: Vec<EcmascriptInputTransform>,
     pub custom_ecmascript_transforms: Vec<EcmascriptInputTransform>,
     /// Custom rules to be applied after all default rules.
@@ -25,6 +49,19 @@ impl ModuleOptionsContextVc {
     pub fn default() -> Self {
         Self::cell(Default::default())
     }
+
+    /// The options for the analysis of ecmascript modules.
+    #[turbo_tasks::function]
+    pub async fn ecmascript_options(self) -> Result<EcmascriptOptionsVc> {
+        let this = self.await?;
+        Ok(EcmascriptOptions {
+            fold_constant_conditions: this.enable_constant_condition_folding,
+            process_env: this.process_env.clone(),
+            import_meta_env: this.import_meta_env.clone(),
+            css_in_js_tags: this.css_in_js_tags.clone(),
+        }
+        .cell())
+    }
 }
 
 impl Default for ModuleOptionsContextVc {
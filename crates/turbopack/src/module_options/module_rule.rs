@@ -6,6 +6,7 @@ use turbo_tasks::{primitives::Regex, trace::TraceRawVcs};
 use turbo_tasks_fs::FileSystemPathReadRef;
 use turbopack_css::CssInputTransformsVc;
 use turbopack_ecmascript::EcmascriptInputTransformsVc;
+use turbopack_static::webassembly::WebAssemblyVariant;
 
 #[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq)]
 pub struct ModuleRule {
@@ -102,10 +103,23 @@ pub enum ModuleType {
     Css(CssInputTransformsVc),
     CssModule(CssInputTransformsVc),
     Static,
+    WebAssembly(WebAssemblyVariant),
     // TODO allow custom function when we support function pointers
     Custom(u8),
 }
 
+impl ModuleType {
+    /// The module type that is requested by the `type` attribute of an
+    /// import, e.g. `import data from "./data" with { type: "json" }`.
+    pub fn from_import_type(module_type: &str) -> Option<ModuleType> {
+        match module_type {
+            "json" => Some(ModuleType::Json),
+            "webassembly" => Some(ModuleType::WebAssembly(WebAssemblyVariant::Compile)),
+            _ => None,
+        }
+    }
+}
+
 impl ModuleRuleEffect {
     pub fn key(&self) -> ModuleRuleEffectKey {
         match self {
@@ -124,3 +138,21 @@ pub enum ModuleRuleEffectKey {
     AddEcmascriptTransforms,
     Custom,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ModuleType, WebAssemblyVariant};
+
+    #[test]
+    fn module_type_from_import_type() {
+        assert!(matches!(
+            ModuleType::from_import_type("json"),
+            Some(ModuleType::Json)
+        ));
+        assert!(matches!(
+            ModuleType::from_import_type("webassembly"),
+            Some(ModuleType::WebAssembly(WebAssemblyVariant::Compile))
+        ));
+        assert!(ModuleType::from_import_type("css").is_none());
+    }
+}
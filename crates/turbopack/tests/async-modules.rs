@@ -0,0 +1,48 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use anyhow::{Context, Result};
+use helpers::{context, root, run};
+use turbopack::ecmascript::EcmascriptModuleAssetVc;
+use turbopack_core::{context::AssetContext, source_asset::SourceAssetVc};
+
+/// Whether the modules in `tests/async-modules` are async modules. `c.js`
+/// uses top-level await and is imported by `b.js`, which is in an import
+/// cycle with `a.js`. `d.js` and `e.js` are in an import cycle without any
+/// async module.
+async fn async_modules(names: &'static [&'static str]) -> Result<Vec<bool>> {
+    let mut result = Vec::new();
+    for name in names {
+        let input = root().join(&format!("tests/async-modules/{name}"));
+        let module = context().process(SourceAssetVc::new(input).into());
+        let module = EcmascriptModuleAssetVc::resolve_from(module)
+            .await?
+            .context("module must be an ecmascript module")?;
+        result.push(*module.is_async().await?);
+    }
+    Ok(result)
+}
+
+#[test]
+fn async_import_cycles() {
+    const NAMES: &[&str] = &[
+        "index.js", "a.js", "b.js", "c.js", "d.js", "e.js", "plain.js",
+    ];
+    assert_eq!(
+        NAMES
+            .iter()
+            .copied()
+            .zip(run(async_modules(NAMES)))
+            .collect::<Vec<_>>(),
+        vec![
+            ("index.js", true),
+            ("a.js", true),
+            ("b.js", true),
+            ("c.js", true),
+            ("d.js", false),
+            ("e.js", false),
+            ("plain.js", false),
+        ]
+    );
+}
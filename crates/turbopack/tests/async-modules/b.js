@@ -0,0 +1,4 @@
+import { a } from "./a.js";
+import { c } from "./c.js";
+
+export const b = () => a + c;
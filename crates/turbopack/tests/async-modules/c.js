@@ -0,0 +1 @@
+export const c = await Promise.resolve(1);
@@ -0,0 +1,58 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use anyhow::Result;
+use helpers::{entry_chunk, run};
+use turbopack_core::asset::Asset;
+
+/// Builds the chunk graph of `tests/chunk-ids/index.js` and returns the paths
+/// of the chunk of the entry and of the chunks it references.
+async fn chunk_paths(content_hashing: bool) -> Result<Vec<String>> {
+    let chunk = entry_chunk("chunk-ids", |builder| {
+        if content_hashing {
+            builder.content_hashing()
+        } else {
+            builder
+        }
+    })
+    .await?;
+
+    let mut paths = vec![chunk.path().to_string().await?.to_string()];
+    for reference in chunk.references().await?.iter() {
+        for asset in reference.resolve_reference().primary_assets().await?.iter() {
+            paths.push(asset.path().to_string().await?.to_string());
+        }
+    }
+    Ok(paths)
+}
+
+#[test]
+fn deterministic_chunk_ids() {
+    // Every build uses its own turbo tasks instance, so nothing is cached
+    // between them
+    let builds = [run(chunk_paths(true)), run(chunk_paths(true))];
+    assert!(
+        builds[0].len() > 1,
+        "the entry chunk must reference other chunks"
+    );
+    assert_eq!(builds[0], builds[1]);
+    // The hash of the sources is part of the path
+    assert!(
+        !builds[0][0].ends_with("/tests_chunk-ids_index.js"),
+        "{}",
+        builds[0][0]
+    );
+}
+
+#[test]
+fn dev_chunk_paths() {
+    let paths = run(chunk_paths(false));
+    // Without content hashing the path of a single entry chunk only depends
+    // on the entry, so it stays the same across edits
+    assert!(
+        paths[0].ends_with("/tests_chunk-ids_index.js"),
+        "{}",
+        paths[0]
+    );
+}
@@ -0,0 +1,4 @@
+export const b = "b";
+
+// b.js and index.js load each other's chunks
+import("./index.js");
@@ -0,0 +1,36 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use anyhow::Result;
+use helpers::{entry_chunk, root, run};
+use turbopack_core::chunk::{
+    dev::{DevChunkingContextVc, DEFAULT_CHUNK_SIZE_BUDGET},
+    ChunkGroupVc,
+};
+
+/// Builds the chunk group of `tests/chunk-size-budget/index.js`, which imports
+/// three small modules, and returns the number of chunks in it.
+async fn chunk_count(budget: Option<usize>) -> Result<usize> {
+    let chunk = entry_chunk("chunk-size-budget", |b| b.chunk_size_budget(budget)).await?;
+    Ok(ChunkGroupVc::from_chunk(chunk).chunks().await?.len())
+}
+
+#[test]
+fn split_by_size() {
+    // The entry and its imports together exceed the budget, so every import
+    // is placed into a chunk of its own
+    assert_eq!(run(chunk_count(Some(120))), 4);
+    assert_eq!(run(chunk_count(Some(DEFAULT_CHUNK_SIZE_BUDGET))), 1);
+    assert_eq!(run(chunk_count(None)), 1);
+}
+
+#[test]
+fn dev_default_budget() {
+    let budget = run(async {
+        let root = root();
+        let context = DevChunkingContextVc::builder(root, root, root, root).build();
+        Ok(*context.chunk_size_budget().await?)
+    });
+    assert_eq!(budget, Some(DEFAULT_CHUNK_SIZE_BUDGET));
+}
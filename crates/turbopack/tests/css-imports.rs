@@ -0,0 +1,18 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use helpers::{chunk_code, entry_chunk, run};
+
+#[test]
+fn css_import_bindings() {
+    // `tests/css-imports/index.js` imports a CSS module and a global
+    // stylesheet
+    let code = run(async { chunk_code(entry_chunk("css-imports", |b| b).await?).await });
+    // The CSS module is imported for its class names
+    assert!(code.contains("button.module.css"), "{code}");
+    assert!(code.contains("[\"default\"].button"), "{code}");
+    // The global stylesheet isn't imported by the code, it has no value
+    assert!(!code.contains("global.css"), "{code}");
+    assert!(code.contains(", void 0)"), "{code}");
+}
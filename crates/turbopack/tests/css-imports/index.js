@@ -0,0 +1,4 @@
+import styles from "./button.module.css";
+import global from "./global.css";
+
+console.log(styles.button, global);
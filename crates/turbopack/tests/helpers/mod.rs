@@ -1,6 +1,106 @@
-use std::{collections::VecDeque, fmt::Write as _};
+// Every test crate only uses some of the helpers
+#![allow(dead_code)]
 
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    future::Future,
+};
+
+use anyhow::{bail, Context, Result};
 use difference::{Changeset, Difference};
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystemPathVc, FileSystemVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    register, resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    chunk::{
+        dev::{DevChunkingContextBuilder, DevChunkingContextVc},
+        ChunkVc, ChunkableAssetVc,
+    },
+    context::AssetContext,
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment},
+    source_asset::SourceAssetVc,
+};
+
+/// Runs `future` with a new turbo tasks instance, so nothing is cached between
+/// runs.
+pub fn run<T>(future: impl Future<Output = Result<T>> + Send + 'static) -> T
+where
+    T: Send + 'static,
+{
+    let r = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    r.block_on(async {
+        register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        tt.run_once(future).await.unwrap()
+    })
+}
+
+/// The root of the package, which the paths of the test inputs are relative
+/// to.
+pub fn root() -> FileSystemPathVc {
+    let package_root = env!("CARGO_MANIFEST_DIR").to_string();
+    let fs: FileSystemVc = DiskFileSystemVc::new("workspace".to_string(), package_root).into();
+    fs.root()
+}
+
+/// The context that processes the test inputs, for Node.js.
+pub fn context() -> ModuleAssetContextVc {
+    ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::NodeJsLambda(
+                NodeJsEnvironment::default().into(),
+            )),
+            Value::new(EnvironmentIntention::ServerRendering),
+        ),
+        Default::default(),
+        ResolveOptionsContext::default().cell(),
+    )
+}
+
+/// Builds the chunk of `tests/{dir}/index.js`, with a chunking context that
+/// emits to `tests/{dir}/out` and is configured by `configure`.
+pub async fn entry_chunk(
+    dir: &str,
+    configure: impl FnOnce(DevChunkingContextBuilder) -> DevChunkingContextBuilder,
+) -> Result<ChunkVc> {
+    let root = root();
+    let input = root.join(&format!("tests/{dir}/index.js"));
+    let output = root.join(&format!("tests/{dir}/out"));
+
+    let module = context().process(SourceAssetVc::new(input).into());
+    let chunking_context = configure(DevChunkingContextVc::builder(
+        root,
+        output,
+        output.join("chunks"),
+        output.join("assets"),
+    ))
+    .build();
+    Ok(ChunkableAssetVc::resolve_from(module)
+        .await?
+        .context("entry must be chunkable")?
+        .as_chunk(chunking_context))
+}
+
+/// The code of `chunk`.
+pub async fn chunk_code(chunk: ChunkVc) -> Result<String> {
+    let AssetContent::File(file) = &*chunk.content().await? else {
+        bail!("chunk content must be a file");
+    };
+    let FileContent::Content(file) = &*file.await? else {
+        bail!("chunk content must exist");
+    };
+    Ok(file.content().to_str()?.into_owned())
+}
 
 pub fn print_changeset(changeset: &Changeset) -> String {
     assert!(changeset.split == "\n");
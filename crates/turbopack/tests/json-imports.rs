@@ -0,0 +1,34 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use anyhow::Result;
+use helpers::{chunk_code, entry_chunk, run};
+use turbo_tasks::TryJoinIterExt;
+use turbopack_core::{asset::Asset, issue::IssueVc};
+
+/// Builds the ecmascript chunk of `tests/json-imports/index.js`, which imports
+/// a JSON module with the `json` type attribute, and returns its code and the
+/// titles of the issues emitted while generating it.
+async fn chunk_code_and_issues() -> Result<(String, Vec<String>)> {
+    let chunk = entry_chunk("json-imports", |b| b).await?;
+    let issues = IssueVc::peek_issues_with_path(chunk.content())
+        .await?
+        .strongly_consistent()
+        .await?;
+    let titles = issues
+        .iter()
+        .map(|issue| async move { Ok(issue.title().await?.clone_value()) })
+        .try_join()
+        .await?;
+    Ok((chunk_code(chunk).await?, titles))
+}
+
+#[test]
+fn json_import_bindings() {
+    let (code, titles) = run(chunk_code_and_issues());
+    // The assertion form resolves to the JSON module
+    assert!(code.contains("data.json (json)"), "{code}");
+    // It only has a default export, a named import is an error
+    assert_eq!(titles, vec!["named import from a JSON module".to_string()]);
+}
@@ -0,0 +1,4 @@
+import data from "./data.json" assert { type: "json" };
+import { name } from "./data.json" assert { type: "json" };
+
+console.log(data.name, name);
@@ -0,0 +1,35 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use anyhow::Result;
+use helpers::{entry_chunk, run};
+use turbopack_core::{
+    asset::Asset,
+    chunk::{ChunkGroupReferenceVc, ChunkLoadingHint},
+};
+
+/// Builds the chunk of `tests/loading-hints/index.js` and returns the loading
+/// hints of the chunk groups it references, in the order of the imports.
+async fn chunk_group_hints() -> Result<Vec<Option<ChunkLoadingHint>>> {
+    let chunk = entry_chunk("loading-hints", |b| b).await?;
+    let mut hints = Vec::new();
+    for reference in chunk.references().await?.iter() {
+        if let Some(reference) = ChunkGroupReferenceVc::resolve_from(reference).await? {
+            hints.push(*reference.loading_hint().await?);
+        }
+    }
+    Ok(hints)
+}
+
+#[test]
+fn magic_comment_hints_on_chunk_group_references() {
+    assert_eq!(
+        run(chunk_group_hints()),
+        vec![
+            Some(ChunkLoadingHint::Prefetch { order: 0 }),
+            Some(ChunkLoadingHint::Preload { order: 2 }),
+            None,
+        ]
+    );
+}
@@ -0,0 +1,3 @@
+import(/* webpackPrefetch: true */ "./a.js");
+import(/* webpackPreload: 2 */ "./b.js");
+import("./c.js");
@@ -0,0 +1,19 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use helpers::{chunk_code, entry_chunk, run};
+
+#[test]
+fn require_resolve_yields_module_id() {
+    // `tests/require-resolve/index.js` resolves `x.js` with `require.resolve`
+    let code = run(async { chunk_code(entry_chunk("require-resolve", |b| b).await?).await });
+    // The call is replaced with the id of the module
+    assert!(!code.contains("require.resolve"), "{code}");
+    assert!(
+        code.contains("console.log(\"[workspace]/tests/require-resolve/x.js (ecmascript)\")"),
+        "{code}"
+    );
+    // ...which isn't placed into the chunk
+    assert!(!code.contains("evaluated x"), "{code}");
+}
@@ -0,0 +1 @@
+console.log(require.resolve("./x"));
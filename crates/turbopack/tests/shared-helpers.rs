@@ -0,0 +1,17 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use helpers::{chunk_code, entry_chunk, run};
+
+#[test]
+fn one_helper_definition_per_chunk() {
+    // The modules `a.js` and `b.js` of `tests/shared-helpers/index.js` both use
+    // the `require.context` helper
+    let code = run(async { chunk_code(entry_chunk("shared-helpers", |b| b).await?).await });
+    // Both modules create a context
+    assert!(code.contains("a/x.js"), "{code}");
+    assert!(code.contains("b/y.js"), "{code}");
+    // ...with the same helper, which is defined once
+    assert_eq!(code.matches("Object.keys(map)").count(), 1, "{code}");
+}
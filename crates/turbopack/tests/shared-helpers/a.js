@@ -0,0 +1 @@
+export default require.context("./a", false, /\.js$/);
@@ -0,0 +1 @@
+export default require.context("./b", false, /\.js$/);
@@ -0,0 +1,61 @@
+#![feature(min_specialization)]
+
+mod helpers;
+
+use anyhow::Result;
+use helpers::{chunk_code, context, entry_chunk, root, run};
+use turbopack_core::{asset::Asset, context::AssetContext, source_asset::SourceAssetVc};
+use turbopack_static::webassembly::{WebAssemblyModuleAssetVc, WebAssemblyVariant};
+
+/// Returns the variants of the WebAssembly modules among the module processed
+/// from `path` and the modules it imports.
+async fn wasm_variants(path: &'static str) -> Result<Vec<WebAssemblyVariant>> {
+    let module = context().process(SourceAssetVc::new(root().join(path)).into());
+
+    let mut variants = Vec::new();
+    if let Some(wasm) = WebAssemblyModuleAssetVc::resolve_from(module).await? {
+        variants.push(wasm.await?.variant);
+    }
+    for reference in module.references().await?.iter() {
+        for asset in reference.resolve_reference().primary_assets().await?.iter() {
+            if let Some(wasm) = WebAssemblyModuleAssetVc::resolve_from(asset).await? {
+                variants.push(wasm.await?.variant);
+            }
+        }
+    }
+    variants.sort();
+    Ok(variants)
+}
+
+#[test]
+fn wasm_module_rule() {
+    // `.wasm` files are instantiated by the module rule
+    assert_eq!(
+        run(wasm_variants("tests/wasm-imports/add.wasm")),
+        vec![WebAssemblyVariant::Instantiate]
+    );
+    // The `webassembly` type attribute only compiles them
+    assert_eq!(
+        run(wasm_variants("tests/wasm-imports/index.js")),
+        vec![WebAssemblyVariant::Compile, WebAssemblyVariant::Instantiate]
+    );
+}
+
+#[test]
+fn wasm_default_export() {
+    // `tests/wasm-imports/index.js` imports a `.wasm` file with and without the
+    // `webassembly` type attribute
+    let code = run(async { chunk_code(entry_chunk("wasm-imports", |b| b).await?).await });
+    // Each variant is its own module exporting the loader as default export
+    assert!(code.contains("add.wasm (wasm)"), "{code}");
+    assert!(code.contains("add.wasm (wasm module)"), "{code}");
+    assert!(
+        code.contains("__turbopack_export_value__((imports = {}) => fetch("),
+        "{code}"
+    );
+    assert!(
+        code.contains("__turbopack_export_value__(() => fetch("),
+        "{code}"
+    );
+    assert!(code.contains("[\"default\"]"), "{code}");
+}
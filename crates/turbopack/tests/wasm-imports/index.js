@@ -0,0 +1,4 @@
+import init from "./add.wasm";
+import compile from "./add.wasm" assert { type: "webassembly" };
+
+console.log(init, compile);